@@ -0,0 +1,46 @@
+// src/ansi.rs
+// Terminal escape sequences in program output.
+//
+// Shared by the shell and the GUI terminal, which declare it as a module of
+// their own: the pager and `--selftest` read output without colours, the
+// GUI shows it as plain text.
+
+/// `s` without its escape sequences: CSI (colours, cursor movement), OSC
+/// (window titles, ended by BEL or ESC \) and two-character ESC X.
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) { break; }
+                }
+            }
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' { break; }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') { chars.next(); break; }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_are_removed() {
+        assert_eq!(strip_ansi("\x1b[1;32mok\x1b[0m done"), "ok done");
+        assert_eq!(strip_ansi("\x1b]0;title\x07a\x1b]2;t\x1b\\b"), "ab");
+        assert_eq!(strip_ansi("\x1b7x\x1b8"), "x");
+    }
+}
@@ -0,0 +1,136 @@
+// src/completion/help_flags.rs
+// Options of external commands, read from what `CMD --help` prints. Most
+// tools list theirs one to a line, the flags and then the help after a
+// gap:
+//
+//   -a, --all                  do not ignore entries starting with .
+//       --color[=WHEN]         color the output WHEN
+//
+// (or, clap-style, with the help indented on the line below). Each command
+// is asked once per session; one that doesn't answer within HELP_TIMEOUT is
+// killed and taken to have no options. It gets no stdin, so it can't wait
+// for input.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const HELP_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// A command's flags, each with its help.
+type Flags = Vec<(String, String)>;
+
+/// Options parsed from each command's --help, by where it lives on PATH.
+static CACHE: Mutex<Option<HashMap<PathBuf, Flags>>> = Mutex::new(None);
+
+/// The options of the external command `command` that `partial` could be,
+/// with their help: `--` names, and single letters once just `-` is typed.
+pub fn complete_help_flags(command: &str, partial: &str, matching: super::Matching) -> Flags {
+    let Some(path) = crate::executor::builtin::find_in_path(command).into_iter().next() else { return vec![] };
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    let options = cache.get_or_insert_with(HashMap::new)
+        .entry(path.clone())
+        .or_insert_with(|| parse_help(&help_text(&path)));
+    options.iter()
+        .filter(|(flag, _)| matching.matches(partial, flag) && (flag.starts_with("--") || partial == "-"))
+        .cloned()
+        .collect()
+}
+
+/// What `program --help` prints, on stdout or stderr; empty if it doesn't
+/// finish in time.
+fn help_text(program: &std::path::Path) -> String {
+    let child = Command::new(program).arg("--help")
+        .stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped())
+        .spawn();
+    let Ok(mut child) = child else { return String::new() };
+    let stdout = collect(child.stdout.take());
+    let stderr = collect(child.stderr.take());
+
+    let deadline = Instant::now() + HELP_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => break,
+            Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+            _ => { child.kill().ok(); child.wait().ok(); return String::new(); }
+        }
+    }
+    let mut text = stdout.join().unwrap_or_default();
+    if text.is_empty() { text = stderr.join().unwrap_or_default(); }
+    String::from_utf8_lossy(&text).into_owned()
+}
+
+/// Read `stream` to the end on a thread of its own, so a long help text
+/// can't fill the pipe while the child is being waited for.
+fn collect<R: Read + Send + 'static>(stream: Option<R>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut text = Vec::new();
+        if let Some(mut stream) = stream { stream.read_to_end(&mut text).ok(); }
+        text
+    })
+}
+
+/// The flags in a help text, each with its one-line description.
+fn parse_help(text: &str) -> Flags {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut found = Flags::new();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if !trimmed.starts_with('-') || trimmed.starts_with("---") { continue; }
+        // Flags, then a gap of two spaces, a tab or " : ", then the help
+        let gap = [trimmed.find("  "), trimmed.find('\t'), trimmed.find(" : ")].into_iter().flatten().min();
+        let (spec, help) = match gap {
+            Some(at) => (&trimmed[..at], trimmed[at..].trim_start_matches([' ', '\t', ':']).trim_end()),
+            None => (trimmed, ""),
+        };
+        let help = if !help.is_empty() { help.to_string() } else {
+            // clap puts the help on the next line, further in
+            lines.get(i + 1)
+                .filter(|next| indent(next) > indent(line) && !next.trim_start().starts_with('-'))
+                .map_or(String::new(), |next| next.trim().to_string())
+        };
+        for part in spec.split([',', ' ', '|']).filter(|p| p.starts_with('-')) {
+            let flag = part.split(['=', '[', '<']).next().unwrap_or(part).trim_end_matches(['.', ':', ';']);
+            let name = flag.trim_start_matches('-');
+            let dashes = flag.len() - name.len();
+            let valid = (1..=2).contains(&dashes)
+                && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if valid && !found.iter().any(|(f, _)| f == flag) {
+                found.push((flag.to_string(), help.clone()));
+            }
+        }
+    }
+    found
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gnu_and_clap_help() {
+        let text = "Usage: ls [OPTION]... [FILE]...\n\
+                    \x20 -a, --all                  do not ignore entries starting with .\n\
+                    \x20     --color[=WHEN]         color the output WHEN\n\
+                    \x20 -w, --width=COLS           set output width to COLS\n\
+                    \x20 -h, --help\n\
+                    \x20         Print help\n\
+                    -W arg : warning control\n\
+                    see --- below\n";
+        let flags = parse_help(text);
+        let names: Vec<&str> = flags.iter().map(|(f, _)| f.as_str()).collect();
+        assert_eq!(names, ["-a", "--all", "--color", "-w", "--width", "-h", "--help", "-W"]);
+        assert_eq!(flags[1].1, "do not ignore entries starting with .");
+        assert_eq!(flags[6].1, "Print help");
+        assert_eq!(flags[7].1, "warning control");
+    }
+}
@@ -0,0 +1,97 @@
+// src/completion/matching.rs
+// How the word typed picks its completions. By default a candidate has to
+// start with it. With `set -o fuzzycomplete` a candidate only has to hold
+// the word's letters in order, so `gcm` finds `git-commit-msg`, and the
+// matches are ranked: names starting with the word first, shortest first,
+// then names with the word somewhere inside, then scattered letters,
+// where letters that begin a part of the name (after `-`, `_`, `.` or a
+// change to capitals) count for more and gaps count against. Fuzzy
+// matching ignores case unless the word has capitals in it.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Matching {
+    #[default]
+    Prefix,
+    Fuzzy,
+}
+
+/// How well a candidate matched: a tier, then a score within it; the
+/// higher the better.
+pub type Score = (u8, i64);
+
+impl Matching {
+    pub fn matches(self, word: &str, candidate: &str) -> bool {
+        self.score(word, candidate).is_some()
+    }
+
+    /// How well `candidate` matches `word`, or None if it doesn't.
+    pub fn score(self, word: &str, candidate: &str) -> Option<Score> {
+        let length = candidate.chars().count() as i64;
+        if candidate.starts_with(word) { return Some((3, -length)); }
+        if self == Matching::Prefix { return None; }
+
+        let ignore_case = !word.chars().any(char::is_uppercase);
+        let fold = |s: &str| if ignore_case { s.to_lowercase() } else { s.to_string() };
+        let (word, folded) = (fold(word), fold(candidate));
+        if folded.starts_with(&word) { return Some((2, -length)); }
+        if let Some(at) = folded.find(&word) { return Some((1, -(at as i64) - length)); }
+        let word: Vec<char> = word.chars().collect();
+        let folded: Vec<char> = folded.chars().collect();
+        let original: Vec<char> = candidate.chars().collect();
+        if folded.len() != original.len() { return None; }
+        subsequence(&word, &folded, &original).map(|score| (0, score - length / 4))
+    }
+}
+
+/// Score `word`'s letters found in order in `candidate` (`original` being
+/// it before case folding), taking a letter that starts a part of the
+/// name over an earlier one that doesn't whenever the rest still fits.
+fn subsequence(word: &[char], candidate: &[char], original: &[char]) -> Option<i64> {
+    let fits = |from: usize, rest: &[char]| {
+        let mut rest = rest.iter().peekable();
+        for c in &candidate[from..] {
+            if rest.peek() == Some(&c) { rest.next(); }
+        }
+        rest.peek().is_none()
+    };
+    let (mut score, mut at, mut last) = (0i64, 0usize, None::<usize>);
+    for (n, c) in word.iter().enumerate() {
+        let next = (at..candidate.len()).find(|&i| candidate[i] == *c)?;
+        let start = (next..candidate.len())
+            .find(|&i| candidate[i] == *c && starts_part(original, i) && fits(i + 1, &word[n + 1..]));
+        let i = start.unwrap_or(next);
+        if starts_part(original, i) { score += 10; }
+        score -= match last {
+            Some(l) if l + 1 == i => -5,
+            Some(l) => (i - l - 1) as i64,
+            None => i as i64,
+        };
+        last = Some(i);
+        at = i + 1;
+    }
+    Some(score)
+}
+
+fn starts_part(name: &[char], i: usize) -> bool {
+    i == 0
+        || matches!(name[i - 1], '-' | '_' | '.' | '/' | ' ')
+        || (name[i].is_uppercase() && name[i - 1].is_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_ranking() {
+        let fuzzy = Matching::Fuzzy;
+        assert!(Matching::Prefix.score("gcm", "git-commit-msg").is_none());
+        assert!(fuzzy.matches("gcm", "git-commit-msg"));
+        assert!(!fuzzy.matches("gcmz", "git-commit-msg"));
+        assert!(!fuzzy.matches("GCM", "git-commit-msg"));
+
+        let mut names = vec!["xgcmx", "git-commit-msg", "gcm-tool", "Gcm", "gxcxm"];
+        names.sort_by_key(|n| std::cmp::Reverse(fuzzy.score("gcm", n)));
+        assert_eq!(names, ["gcm-tool", "Gcm", "xgcmx", "git-commit-msg", "gxcxm"]);
+    }
+}
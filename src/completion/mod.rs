@@ -1,10 +1,53 @@
 // src/completion/mod.rs
 // Tab completion engine - completes file paths and command names
 
-use std::path::PathBuf;
+mod help_flags;
+mod matching;
+mod path_cache;
+mod processes;
+mod programmable;
+
+pub use matching::Matching;
+pub use path_cache::{forget as forget_commands, prewarm as prewarm_commands};
+pub use programmable::{complete_with, CompletionSpec};
+
+use std::path::{Path, PathBuf};
+
+use crate::shell::Shell;
+
+/// One completion, and what it is, for the menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub value: String,
+    pub kind: Kind,
+    /// A few words on it: a file's size, where a command lives
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Directory,
+    File,
+    Executable,
+    /// A program on PATH
+    Command,
+    Builtin,
+    Alias,
+    Function,
+    Flag,
+    Variable,
+    /// Anything else, such as a word from a `complete` spec
+    Word,
+}
+
+impl Candidate {
+    pub fn new(value: impl Into<String>, kind: Kind, description: Option<String>) -> Candidate {
+        Candidate { value: value.into(), kind, description }
+    }
+}
 
 /// Given a partial word, return a list of completions
-pub fn complete(partial: &str, is_first_word: bool) -> Vec<String> {
+pub fn complete(partial: &str, is_first_word: bool, matching: Matching) -> Vec<Candidate> {
     if partial.is_empty() {
         return vec![];
     }
@@ -15,25 +58,188 @@ pub fn complete(partial: &str, is_first_word: bool) -> Vec<String> {
         || partial.starts_with('~');
 
     if looks_like_path || !is_first_word {
-        complete_path(partial)
+        complete_path(partial, matching)
     } else {
-        let mut results = complete_commands(partial);
-        results.extend(complete_path(partial));
-        results.dedup();
+        let mut results = complete_commands(partial, matching);
+        results.extend(complete_path(partial, matching));
+        results.dedup_by(|a, b| a.value == b.value);
         results
     }
 }
 
+/// The shell's own names for a command word: aliases, functions and
+/// builtins, each described.
+pub fn complete_names(shell: Option<&Shell>, partial: &str, matching: Matching) -> Vec<Candidate> {
+    let mut results = Vec::new();
+    if let Some(shell) = shell {
+        let mut aliases: Vec<_> = shell.aliases.iter().filter(|(name, _)| matching.matches(partial, name)).collect();
+        aliases.sort();
+        results.extend(aliases.into_iter().map(|(name, value)| {
+            Candidate::new(name.as_str(), Kind::Alias, Some(format!("alias for {}", value)))
+        }));
+        let mut functions: Vec<&String> = shell.functions.keys().filter(|name| matching.matches(partial, name)).collect();
+        functions.sort();
+        results.extend(functions.into_iter().map(|name| Candidate::new(name.as_str(), Kind::Function, Some("function".to_string()))));
+    }
+    results.extend(builtin_names().iter()
+        .filter(|name| matching.matches(partial, name))
+        .map(|name| Candidate::new(*name, Kind::Builtin, Some("builtin".to_string()))));
+    results
+}
+
+/// What a command's arguments are, for the commands where it isn't just
+/// any path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    Directories,
+    /// Files, with directories kept so they can still be gone into
+    Files,
+    Aliases,
+    Variables,
+    /// The shell's jobs, as `%N`
+    Jobs,
+    /// Jobs, then processes by PID
+    Processes,
+    /// Packages in the registry, for `install`
+    Packages,
+    /// Packages installed, for `uninstall`
+    InstalledPackages,
+    /// `pkg`'s subcommands, then the packages each takes
+    Pkg,
+}
+
+const CONTEXTS: &[(&str, Context)] = &[
+    ("cd", Context::Directories),
+    ("pushd", Context::Directories),
+    ("rmdir", Context::Directories),
+    ("source", Context::Files),
+    (".", Context::Files),
+    ("unalias", Context::Aliases),
+    ("unset", Context::Variables),
+    ("export", Context::Variables),
+    ("fg", Context::Jobs),
+    ("bg", Context::Jobs),
+    ("kill", Context::Processes),
+    ("install", Context::Packages),
+    ("uninstall", Context::InstalledPackages),
+    ("pkg", Context::Pkg),
+];
+
+/// What the arguments of `command` are, if it's in the table.
+pub fn context(command: &str) -> Option<Context> {
+    CONTEXTS.iter().find(|(name, _)| *name == command).map(|(_, context)| *context)
+}
+
+/// Complete an argument `partial` of a command whose arguments are
+/// `context`, after the arguments `args`. Aliases and variables come with
+/// their values, jobs with their command lines, processes with their names
+/// and packages with their versions.
+pub fn complete_in_context(shell: Option<&Shell>, context: Context, args: &[&str], partial: &str, matching: Matching) -> Vec<Candidate> {
+    use crate::executor::builtin::pkg;
+    let packages = |list: Vec<(String, String)>| -> Vec<Candidate> {
+        list.into_iter()
+            .filter(|(name, _)| matching.matches(partial, name))
+            .map(|(name, about)| Candidate::new(name, Kind::Word, Some(about)))
+            .collect()
+    };
+    match context {
+        Context::Directories => {
+            let mut found = complete_path(partial, matching);
+            found.retain(|c| c.kind == Kind::Directory);
+            found
+        }
+        Context::Files => complete_path(partial, matching),
+        Context::Aliases => {
+            let Some(shell) = shell else { return vec![] };
+            let mut aliases: Vec<_> = shell.aliases.iter().filter(|(name, _)| matching.matches(partial, name)).collect();
+            aliases.sort();
+            aliases.into_iter()
+                .map(|(name, value)| Candidate::new(name.as_str(), Kind::Alias, Some(format!("alias for {}", value))))
+                .collect()
+        }
+        Context::Variables => {
+            // `export NAME=value` has nothing left to complete once the `=` is in
+            let Some(shell) = shell.filter(|_| !partial.contains('=')) else { return vec![] };
+            let mut vars: Vec<_> = shell.env.iter().filter(|(name, _)| matching.matches(partial, name)).collect();
+            vars.sort();
+            vars.into_iter()
+                .map(|(name, value)| Candidate::new(name.as_str(), Kind::Variable, Some(value.clone())))
+                .collect()
+        }
+        Context::Jobs => shell.map_or(vec![], |shell| processes::complete_jobs(shell, partial, matching)),
+        Context::Processes => shell.map_or(vec![], |shell| processes::complete_processes(shell, partial, matching)),
+        Context::Packages => packages(pkg::available_packages()),
+        Context::InstalledPackages => packages(pkg::installed_packages()),
+        Context::Pkg => match args.first().copied() {
+            None => pkg::SUBCOMMANDS.iter()
+                .filter(|(name, _, _)| matching.matches(partial, name))
+                .map(|(name, _, about)| Candidate::new(*name, Kind::Word, Some(about.to_string())))
+                .collect(),
+            Some("install") => packages(pkg::available_packages()),
+            Some("uninstall" | "upgrade") => packages(pkg::installed_packages()),
+            Some(_) => vec![],
+        },
+    }
+}
+
+/// Where a `$NAME` or `${NAME` being typed starts in `partial`, if the
+/// word ends in one.
+pub fn variable_start(partial: &str) -> Option<usize> {
+    let at = partial.rfind('$')?;
+    let name = &partial[at + 1..];
+    let name = name.strip_prefix('{').unwrap_or(name);
+    name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_').then_some(at)
+}
+
+/// Variables for a `$NAME` or `${NAME` word, the second kind closed with
+/// `}`: the shell's own, then the special ones once just `$` is typed.
+/// Each comes with its value.
+pub fn complete_variables(shell: &Shell, word: &str, matching: Matching) -> Vec<Candidate> {
+    let (open, close, typed) = match word.strip_prefix("${") {
+        Some(typed) => ("${", "}", typed),
+        None => ("$", "", word.strip_prefix('$').unwrap_or(word)),
+    };
+    let mut vars: Vec<(&String, &String)> = shell.env.iter().filter(|(name, _)| matching.matches(typed, name)).collect();
+    vars.sort();
+    let mut results: Vec<Candidate> = vars.into_iter()
+        .map(|(name, value)| Candidate::new(format!("{}{}{}", open, name, close), Kind::Variable, Some(value.clone())))
+        .collect();
+
+    let pipe_status: Vec<String> = shell.pipe_status.iter().map(|c| c.to_string()).collect();
+    let specials = [
+        ("?", format!("exit status of the last command: {}", shell.last_exit_code)),
+        ("!", format!("pid of the last background job: {}", shell.last_bg_pid.map_or(String::new(), |p| p.to_string()))),
+        ("$", format!("pid of the shell: {}", std::process::id())),
+        ("#", format!("number of arguments: {}", shell.positional_args().len())),
+        ("@", "the arguments, one word each".to_string()),
+        ("PIPESTATUS", format!("exit status of each stage of the last pipeline: {}", pipe_status.join(" "))),
+        ("CMD_DURATION", format!("how long the last command line took in ms: {}",
+            shell.last_duration.map_or(String::new(), |d| d.as_millis().to_string()))),
+    ];
+    let index = |name: &str| if name == "PIPESTATUS" { "[@]" } else { "" };
+    for (name, about) in specials {
+        let named = name.starts_with(|c: char| c.is_ascii_alphabetic());
+        if (typed.is_empty() || named) && matching.matches(typed, name) {
+            // ${PIPESTATUS} alone would be just the first stage
+            let value = if close.is_empty() { format!("{}{}", open, name) } else { format!("{}{}{}{}", open, name, index(name), close) };
+            results.push(Candidate::new(value, Kind::Variable, Some(about)));
+        }
+    }
+    results
+}
+
 /// Complete file and directory names
-pub fn complete_path(partial: &str) -> Vec<String> {
+pub fn complete_path(partial: &str, matching: Matching) -> Vec<Candidate> {
     let expanded = if partial.starts_with('~') {
         let home = dirs::home_dir()
-            .map(|h| h.display().to_string())
+            .map(|h| crate::paths::display(&h))
             .unwrap_or_else(|| "~".to_string());
         partial.replacen('~', &home, 1)
     } else {
         partial.to_string()
     };
+    // `dir\fi` on Windows is completed as `dir/fi`
+    let expanded = crate::paths::display(std::path::Path::new(&expanded));
 
     let (dir, prefix) = if expanded.contains('/') {
         let p = std::path::Path::new(&expanded);
@@ -46,82 +252,101 @@ pub fn complete_path(partial: &str) -> Vec<String> {
         (PathBuf::from("."), expanded.clone())
     };
 
-    let mut matches = Vec::new();
-
     let read_dir = match std::fs::read_dir(&dir) {
         Ok(rd) => rd,
-        Err(_) => return matches,
+        Err(_) => return Vec::new(),
     };
 
-    for entry in read_dir.flatten() {
-        let name = entry.file_name().to_string_lossy().to_string();
-        if name.starts_with(&prefix) {
-            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
-
-            let completion = if expanded.contains('/') {
-                let base = dir.display().to_string();
-                let sep = if base.ends_with('/') { "" } else { "/" };
-                let trail = if is_dir { "/" } else { "" };
-                let full = format!("{}{}{}{}", base, sep, name, trail);
-                if partial.starts_with('~') {
-                    let home = dirs::home_dir()
-                        .map(|h| h.display().to_string())
-                        .unwrap_or_default();
-                    full.replacen(&home, "~", 1)
-                } else {
-                    full
-                }
+    let entries: Vec<(String, bool)> = read_dir.flatten()
+        .map(|entry| (entry.file_name().to_string_lossy().to_string(), entry.file_type().map(|t| t.is_dir()).unwrap_or(false)))
+        .filter(|(name, _)| matching.matches(&prefix, name))
+        .collect();
+    // What each is, a stat apiece, batched for a directory on a network share
+    let described = crate::executor::builtin::stat_batch(&entries, |(name, is_dir)| describe_file(&dir.join(name), *is_dir));
+
+    let mut matches = Vec::new();
+    for ((name, is_dir), (kind, description)) in entries.into_iter().zip(described) {
+        let completion = if expanded.contains('/') {
+            let base = crate::paths::display(&dir);
+            let sep = if base.ends_with('/') { "" } else { "/" };
+            let trail = if is_dir { "/" } else { "" };
+            let full = format!("{}{}{}{}", base, sep, name, trail);
+            if partial.starts_with('~') {
+                let home = dirs::home_dir()
+                    .map(|h| crate::paths::display(&h))
+                    .unwrap_or_default();
+                full.replacen(&home, "~", 1)
             } else {
-                if is_dir { format!("{}/", name) } else { name }
-            };
+                full
+            }
+        } else {
+            if is_dir { format!("{}/", name) } else { name }
+        };
 
-            matches.push(completion);
-        }
+        matches.push(Candidate::new(completion, kind, description));
     }
 
-    matches.sort();
+    matches.sort_by(|a, b| a.value.cmp(&b.value));
     matches
 }
 
-/// Complete command names from PATH
-pub fn complete_commands(partial: &str) -> Vec<String> {
-    let path_var = std::env::var("PATH").unwrap_or_default();
-    let mut commands = Vec::new();
-
-    for dir in path_var.split(':') {
-        let Ok(entries) = std::fs::read_dir(dir) else { continue };
-        for entry in entries.flatten() {
-            let name = entry.file_name().to_string_lossy().to_string();
-            if name.starts_with(partial) {
-                #[cfg(unix)]
-                {
-                    use std::os::unix::fs::PermissionsExt;
-                    if let Ok(meta) = entry.metadata() {
-                        if meta.permissions().mode() & 0o111 != 0 {
-                            commands.push(name);
-                        }
-                    }
-                }
-                #[cfg(windows)]
-                {
-                    commands.push(name);
-                }
-            }
-        }
+/// Whether `path` is a directory, a program or another file, and its size.
+fn describe_file(path: &Path, is_dir: bool) -> (Kind, Option<String>) {
+    use crate::executor::builtin::{format_size_as, is_executable, SizeUnits};
+    if is_dir { return (Kind::Directory, Some("directory".to_string())); }
+    let Ok(meta) = path.metadata().or_else(|_| path.symlink_metadata()) else { return (Kind::File, None) };
+    if meta.is_dir() { return (Kind::Directory, Some("directory".to_string())); }
+    let size = format_size_as(meta.len(), SizeUnits::Binary, "");
+    if is_executable(path) {
+        (Kind::Executable, Some(format!("executable, {}", size)))
+    } else {
+        (Kind::File, Some(size))
     }
+}
 
-    commands.sort();
-    commands.dedup();
-    commands
+/// Complete command names from PATH, each described by where it lives.
+/// PATH is read in the background; see path_cache.rs.
+pub fn complete_commands(partial: &str, matching: Matching) -> Vec<Candidate> {
+    path_cache::commands().iter()
+        .filter(|(name, _)| matching.matches(partial, name))
+        .map(|(name, dir)| Candidate::new(name.as_str(), Kind::Command, Some(crate::paths::display(&dir.join(name)))))
+        .collect()
+}
+
+/// Complete an option of a builtin that declares them, or of an external
+/// command from its --help: `--` names, and single letters too once just
+/// `-` has been typed. Each comes with its help line.
+pub fn complete_options(command: &str, partial: &str, matching: Matching) -> Vec<(String, String)> {
+    let Some(options) = crate::executor::builtin::options(command) else {
+        if crate::executor::is_builtin_cmd(command) { return vec![]; }
+        return help_flags::complete_help_flags(command, partial, matching);
+    };
+    let mut results = Vec::new();
+    for opt in options.opts {
+        if !opt.long.is_empty() {
+            results.push((format!("--{}", opt.long), opt.help.to_string()));
+        }
+        if partial == "-" {
+            results.extend(opt.short.chars().map(|c| (format!("-{}", c), opt.help.to_string())));
+        }
+    }
+    results.push(("--help".to_string(), "show help".to_string()));
+    results.retain(|(name, _)| matching.matches(partial, name));
+    results
 }
 
 /// Shell builtin names for completion
 pub fn builtin_names() -> &'static [&'static str] {
     &[
-        "cd", "pwd", "echo", "export", "unset", "alias", "unalias",
-        "history", "source", "help", "jobs", "fg", "bg", "kill",
+        "cd", "pwd", "echo", "export", "declare", "readonly", "unset", "alias", "unalias",
+        "history", "fc", "source", "help", "jobs", "fg", "bg", "disown", "kill",
         "clear", "cls", "exit", "quit", "ls", "true", "false",
         "test", "functions", "sleep", "touch", "mkdir", 
-        "rm", "cp", "mv", "cat",
+        "rm", "cp", "mv", "cat", "stats", "schedule", "printf",
+        "type", "command", "builtin", "shift", "getopts", "quote",
+        "umask", "ulimit", "hash", "complete", "guard", "wrap", "explain", "exec", "cleanup", "hook", "sandbox",
+        "sed", "cut", "tr", "du", "basename", "dirname", "realpath",
+        "stat", "file", "yes", "expr", "sha256sum", "md5sum", "fetch", "serve", "ping", "port",
+        "whoami", "hostname", "uname", "uptime", "open", "rename", "trash", "chown",
     ]
 }
\ No newline at end of file
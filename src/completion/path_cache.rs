@@ -0,0 +1,121 @@
+// src/completion/path_cache.rs
+// The programs on PATH, for completing a command name. Reading every PATH
+// directory and checking each file is executable is slow with a long PATH
+// or a directory on a network share, so it's done on a thread of its own:
+// the line editor asks for a scan at each prompt (see prewarm), and Tab
+// uses the last one finished. A scan is redone once it's RESCAN old, so
+// programs installed since show up, and a change to $PATH throws the old
+// one away. Only when there's nothing to show yet does Tab wait, and then
+// no longer than WAIT.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+const RESCAN: Duration = Duration::from_secs(10);
+const WAIT: Duration = Duration::from_secs(2);
+
+/// Each program's name and the directory it's run from, by name.
+pub type Commands = Arc<Vec<(String, PathBuf)>>;
+
+#[derive(Default)]
+struct Cache {
+    /// The $PATH `commands` was scanned from, or is being
+    path: String,
+    commands: Option<Commands>,
+    scanned: Option<Instant>,
+    /// The scan under way, by number; one abandoned finds it changed
+    scanning: Option<u64>,
+    scans: u64,
+}
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+static SCANNED: Condvar = Condvar::new();
+
+/// Start scanning the current $PATH in the background, unless the last
+/// scan of it is still fresh or one is already under way.
+pub fn prewarm() {
+    let path = std::env::var("PATH").unwrap_or_default();
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    refresh(cache.get_or_insert_with(Cache::default), &path);
+}
+
+/// The programs on the current $PATH.
+pub fn commands() -> Commands {
+    let path = std::env::var("PATH").unwrap_or_default();
+    let mut guard = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    refresh(guard.get_or_insert_with(Cache::default), &path);
+    let deadline = Instant::now() + WAIT;
+    loop {
+        let cache = guard.get_or_insert_with(Cache::default);
+        if let Some(commands) = &cache.commands { return Arc::clone(commands); }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if cache.scanning.is_none() || left.is_zero() { return Commands::default(); }
+        guard = SCANNED.wait_timeout(guard, left).unwrap_or_else(|e| e.into_inner()).0;
+    }
+}
+
+/// Forget the last scan (`hash -r`), so the next one is done afresh.
+pub fn forget() {
+    if let Some(cache) = CACHE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        cache.commands = None;
+        cache.scanned = None;
+        cache.scanning = None;
+    }
+}
+
+fn refresh(cache: &mut Cache, path: &str) {
+    if cache.path != path {
+        cache.path = path.to_string();
+        cache.commands = None;
+        cache.scanned = None;
+        cache.scanning = None;
+    }
+    let fresh = cache.scanned.is_some_and(|at| at.elapsed() < RESCAN);
+    if fresh || cache.scanning.is_some() { return; }
+
+    cache.scans += 1;
+    let number = cache.scans;
+    cache.scanning = Some(number);
+    let path = path.to_string();
+    std::thread::spawn(move || {
+        let commands = Arc::new(scan(&path));
+        let mut guard = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        let cache = guard.get_or_insert_with(Cache::default);
+        // Dropped if $PATH changed or `hash -r` was run meanwhile
+        if cache.scanning == Some(number) {
+            cache.scanning = None;
+            cache.commands = Some(commands);
+            cache.scanned = Some(Instant::now());
+        }
+        SCANNED.notify_all();
+    });
+}
+
+/// Every executable in the directories of `path`; where a name is in more
+/// than one, the first on PATH, as that's the one that runs.
+fn scan(path: &str) -> Vec<(String, PathBuf)> {
+    let sep = if cfg!(windows) { ';' } else { ':' };
+    let mut commands: Vec<(String, PathBuf)> = Vec::new();
+    for dir in path.split(sep) {
+        let Ok(entries) = std::fs::read_dir(dir) else { continue };
+        let names: Vec<String> = entries.flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        let dir = PathBuf::from(dir);
+        #[cfg(unix)]
+        let names: Vec<String> = {
+            use std::os::unix::fs::PermissionsExt;
+            // A stat per program, batched for a PATH entry on a network share
+            let executable = crate::executor::builtin::stat_batch(&names, |name| {
+                dir.join(name).symlink_metadata().is_ok_and(|m| m.permissions().mode() & 0o111 != 0)
+            });
+            names.into_iter().zip(executable).filter(|(_, x)| *x).map(|(name, _)| name).collect()
+        };
+        commands.extend(names.into_iter().map(|name| (name, dir.clone())));
+    }
+    // A stable sort keeps PATH order among equal names
+    commands.sort_by(|a, b| a.0.cmp(&b.0));
+    commands.dedup_by(|a, b| a.0 == b.0);
+    commands
+}
@@ -0,0 +1,83 @@
+// src/completion/processes.rs
+// Arguments of fg, bg and kill: the shell's jobs as `%N`, described by
+// their command line and state, and for kill the user's running processes
+// too, by PID with the program's name beside it. A process can be found
+// by its name as well as its PID, so `kill fire<TAB>` offers Firefox's.
+// Processes come from /proc on Linux, `ps` on other Unixes and `tasklist`
+// on Windows.
+
+use crate::shell::Shell;
+use super::{Candidate, Kind, Matching};
+
+/// The jobs `partial` could be, as `%N`.
+pub fn complete_jobs(shell: &Shell, partial: &str, matching: Matching) -> Vec<Candidate> {
+    let mut jobs: Vec<_> = shell.jobs.values().collect();
+    jobs.sort_by_key(|job| job.id);
+    jobs.into_iter()
+        .map(|job| (format!("%{}", job.id), job))
+        .filter(|(spec, job)| {
+            matching.matches(partial, spec) || matching.matches(partial.trim_start_matches('%'), &job.command)
+        })
+        .map(|(spec, job)| Candidate::new(spec, Kind::Word, Some(format!("{} ({})", job.command, job.status))))
+        .collect()
+}
+
+/// The jobs, then the running processes, `partial` could be.
+pub fn complete_processes(shell: &Shell, partial: &str, matching: Matching) -> Vec<Candidate> {
+    let mut results = complete_jobs(shell, partial, matching);
+    if partial.starts_with('%') { return results; }
+    let own = std::process::id();
+    results.extend(processes().into_iter()
+        .filter(|(pid, name)| *pid != own && (matching.matches(partial, &pid.to_string()) || matching.matches(partial, name)))
+        .map(|(pid, name)| Candidate::new(pid.to_string(), Kind::Word, Some(name))));
+    results
+}
+
+/// The current user's processes, by PID, each with its program's name.
+#[cfg(target_os = "linux")]
+fn processes() -> Vec<(u32, String)> {
+    use std::os::unix::fs::MetadataExt;
+    let Ok(entries) = std::fs::read_dir("/proc") else { return vec![] };
+    let uid = unsafe { libc::getuid() };
+    let mut found: Vec<(u32, String)> = entries.flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            // Only those kill could signal
+            if entry.metadata().ok()?.uid() != uid { return None; }
+            let name = std::fs::read_to_string(entry.path().join("comm")).ok()?;
+            Some((pid, name.trim_end().to_string()))
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn processes() -> Vec<(u32, String)> {
+    let Ok(output) = std::process::Command::new("ps").args(["-x", "-o", "pid=", "-o", "comm="]).output() else { return vec![] };
+    let mut found: Vec<(u32, String)> = String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| {
+            let (pid, command) = line.trim_start().split_once(' ')?;
+            // macOS gives the program's whole path
+            let name = command.trim().rsplit('/').next().unwrap_or(command);
+            Some((pid.parse().ok()?, name.to_string()))
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+#[cfg(windows)]
+fn processes() -> Vec<(u32, String)> {
+    let Ok(output) = std::process::Command::new("tasklist").args(["/fo", "csv", "/nh"]).output() else { return vec![] };
+    let mut found: Vec<(u32, String)> = String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| {
+            // "name.exe","1234","Console","1","10,000 K"
+            let mut fields = line.split("\",\"").map(|f| f.trim_matches('"'));
+            let name = fields.next()?.to_string();
+            Some((fields.next()?.parse().ok()?, name))
+        })
+        .collect();
+    found.sort();
+    found
+}
@@ -0,0 +1,81 @@
+// src/completion/programmable.rs
+// Completions registered with the `complete` builtin. A command's spec
+// gives the candidates for its arguments as any mix of a word list (-W),
+// the files matching a glob (-G; directories are kept so they can still be
+// gone into), and the lines a shell function prints (-F). The function is
+// called as `FUNC COMMAND WORD PREVIOUS`, with the word being completed
+// and the one before it, on a copy of the shell so it can't change
+// anything. Every candidate has to match the word typed, as any other
+// would (see matching.rs).
+//
+// When a spec comes up empty, completion falls back to paths as usual.
+
+use std::collections::HashSet;
+use std::io::Read;
+
+use crate::shell::Shell;
+use super::{Candidate, Kind};
+
+/// What `complete` registered for one command.
+#[derive(Debug, Clone, Default)]
+pub struct CompletionSpec {
+    pub words: Vec<String>,
+    pub glob: Option<String>,
+    pub function: Option<String>,
+}
+
+impl CompletionSpec {
+    /// The `complete` command that registers this spec for `name`.
+    pub fn command_line(&self, name: &str) -> String {
+        use crate::executor::builtin::shell_quote;
+        let mut line = String::from("complete");
+        if !self.words.is_empty() { line.push_str(&format!(" -W {}", shell_quote(&self.words.join(" ")))); }
+        if let Some(glob) = &self.glob { line.push_str(&format!(" -G {}", shell_quote(glob))); }
+        if let Some(function) = &self.function { line.push_str(&format!(" -F {}", function)); }
+        format!("{} {}", line, shell_quote(name))
+    }
+}
+
+/// The candidates `spec` gives for `word`, an argument of `command` that
+/// follows `previous`, in the order the spec lists them.
+pub fn complete_with(shell: &Shell, spec: &CompletionSpec, command: &str, word: &str, previous: &str) -> Vec<Candidate> {
+    let matching = shell.matching();
+    let mut results: Vec<Candidate> = spec.words.iter()
+        .filter(|w| matching.matches(word, w))
+        .map(|w| Candidate::new(w.as_str(), Kind::Word, None))
+        .collect();
+
+    if let Some(pattern) = spec.glob.as_deref().and_then(|g| glob::Pattern::new(g).ok()) {
+        results.extend(super::complete_path(word, matching).into_iter().filter(|path| {
+            let name = path.value.trim_end_matches('/').rsplit('/').next().unwrap_or(&path.value);
+            path.kind == Kind::Directory || pattern.matches(name)
+        }));
+    }
+
+    if let Some(function) = spec.function.as_deref().filter(|f| shell.functions.contains_key(*f)) {
+        let args = [command.to_string(), word.to_string(), previous.to_string()];
+        let output = function_output(shell, function, &args);
+        results.extend(output.lines()
+            .filter(|l| !l.is_empty() && matching.matches(word, l))
+            .map(|l| Candidate::new(l, Kind::Word, None)));
+    }
+
+    let mut seen = HashSet::new();
+    results.retain(|r| seen.insert(r.value.clone()));
+    results
+}
+
+/// What the shell function `name` prints when called with `args`.
+fn function_output(shell: &Shell, name: &str, args: &[String]) -> String {
+    use crate::executor::builtin::io;
+    let Ok((mut reader, writer)) = io::pipe() else { return String::new() };
+    // Read as it's written, so a long list can't fill the pipe and stall
+    let collector = std::thread::spawn(move || {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).ok();
+        text
+    });
+    let mut shell = shell.clone();
+    io::with_streams(None, Some(writer), None, || crate::executor::call_function(&mut shell, name, args)).ok();
+    collector.join().unwrap_or_default()
+}
@@ -0,0 +1,274 @@
+// src/executor/builtin/checksum.rs
+// sha256sum / md5sum [-c] [FILE]... — print a digest of each file in the
+// usual `HASH  NAME` form, or with -c read such lines back and check each
+// file still matches (a download against the checksum list published next
+// to it, say).
+//
+// Both digests are small enough to carry here rather than pull in a crate
+// for each; files are hashed as they are read, so size doesn't matter.
+
+use std::io::Read;
+
+use crate::shell::Shell;
+use super::util::{Opt, Options};
+
+pub const SHA256SUM: Options = Options {
+    name: "sha256sum",
+    operands: "[FILE]...",
+    about: "Print or check SHA-256 checksums (of standard input when FILE is - or missing).",
+    opts: CHECK_OPTS,
+    numeric: None,
+};
+
+pub const MD5SUM: Options = Options {
+    name: "md5sum",
+    operands: "[FILE]...",
+    about: "Print or check MD5 checksums (of standard input when FILE is - or missing).",
+    opts: CHECK_OPTS,
+    numeric: None,
+};
+
+const CHECK_OPTS: &[Opt] = &[
+    Opt::new("c", "check", "read HASH  NAME lines from the FILEs and check each"),
+    Opt::new("", "quiet", "with -c, only report files that fail"),
+];
+
+#[derive(Clone, Copy)]
+pub enum Algorithm { Sha256, Md5 }
+
+pub fn builtin_checksum(shell: &Shell, algorithm: Algorithm, args: &[String]) -> i32 {
+    let options = match algorithm { Algorithm::Sha256 => &SHA256SUM, Algorithm::Md5 => &MD5SUM };
+    let p = match options.parse(args) { Ok(p) => p, Err(code) => return code };
+    let (verify, quiet) = (p.has("check"), p.has("quiet"));
+    let mut files = p.operands;
+    if files.is_empty() { files.push("-"); }
+
+    if verify {
+        return check(shell, algorithm, options.name, &files, quiet);
+    }
+    let mut code = 0;
+    for file in files {
+        match hash_file(shell, algorithm, file) {
+            Ok(hex) => outln!("{}  {}", hex, file),
+            Err(e) => { errln!("{}: {}: {}", options.name, file, e); code = 1; }
+        }
+    }
+    code
+}
+
+fn hash_file(shell: &Shell, algorithm: Algorithm, file: &str) -> std::io::Result<String> {
+    let mut input: Box<dyn Read> = if file == "-" {
+        Box::new(super::io::stdin())
+    } else {
+        Box::new(std::fs::File::open(shell.cwd.join(file))?)
+    };
+    let mut digest = Digest::new(algorithm);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        match input.read(&mut buf)? {
+            0 => break,
+            n => digest.update(&buf[..n]),
+        }
+    }
+    Ok(digest.finish().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// `-c`: check every `HASH  NAME` line of the checksum files. The status is
+/// 1 if any file doesn't match or can't be read.
+fn check(shell: &Shell, algorithm: Algorithm, name: &str, lists: &[&str], quiet: bool) -> i32 {
+    let hex_len = match algorithm { Algorithm::Sha256 => 64, Algorithm::Md5 => 32 };
+    let (mut failed, mut unreadable, mut malformed) = (0, 0, 0);
+    for list in lists {
+        let text = match super::io::read_to_string(&list_path(shell, list)) {
+            Ok(text) => text,
+            Err(e) => { errln!("{}: {}: {}", name, list, e); unreadable += 1; continue; }
+        };
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            // `HASH  NAME`, or `HASH *NAME` for a file hashed in binary mode
+            let entry = line.split_once(' ').map(|(hash, rest)| (hash, rest.strip_prefix([' ', '*']).unwrap_or(rest)));
+            let Some((expected, file)) = entry.filter(|(hash, file)| {
+                hash.len() == hex_len && hash.chars().all(|c| c.is_ascii_hexdigit()) && !file.is_empty()
+            }) else {
+                malformed += 1;
+                continue;
+            };
+            match hash_file(shell, algorithm, file) {
+                Ok(actual) if actual.eq_ignore_ascii_case(expected) => {
+                    if !quiet { outln!("{}: OK", file); }
+                }
+                Ok(_) => { outln!("{}: FAILED", file); failed += 1; }
+                Err(e) => { outln!("{}: FAILED open or read ({})", file, e); unreadable += 1; }
+            }
+        }
+    }
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    if malformed > 0 {
+        errln!("{}: WARNING: {} line{} improperly formatted", name, malformed, plural(malformed));
+    }
+    if unreadable > 0 {
+        errln!("{}: WARNING: {} listed file{} could not be read", name, unreadable, plural(unreadable));
+    }
+    if failed > 0 {
+        errln!("{}: WARNING: {} computed checksum{} did NOT match", name, failed, plural(failed));
+    }
+    if failed > 0 || unreadable > 0 { 1 } else { 0 }
+}
+
+/// A checksum list's path: `-` for stdin, otherwise relative to the shell.
+fn list_path(shell: &Shell, list: &str) -> String {
+    if list == "-" { list.to_string() } else { shell.cwd.join(list).to_string_lossy().to_string() }
+}
+
+// ── Digests ───────────────────────────────────────────────────────────────────
+
+/// SHA-256 or MD5, fed a piece at a time. Both work on 64-byte blocks,
+/// padded at the end the same way apart from the byte order of the length.
+struct Digest {
+    algorithm: Algorithm,
+    state: [u32; 8],
+    pending: Vec<u8>,
+    len: u64,
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const MD5_SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+impl Digest {
+    fn new(algorithm: Algorithm) -> Digest {
+        let state = match algorithm {
+            Algorithm::Sha256 => [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+            ],
+            Algorithm::Md5 => [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0, 0, 0, 0],
+        };
+        Digest { algorithm, state, pending: Vec::with_capacity(64), len: 0 }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+        if !self.pending.is_empty() {
+            let take = (64 - self.pending.len()).min(data.len());
+            self.pending.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.pending.len() < 64 { return; }
+            let block = std::mem::take(&mut self.pending);
+            self.block(&block);
+        }
+        let mut blocks = data.chunks_exact(64);
+        for block in &mut blocks { self.block(block); }
+        self.pending.extend_from_slice(blocks.remainder());
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let bits = self.len.wrapping_mul(8);
+        let mut tail = std::mem::take(&mut self.pending);
+        tail.push(0x80);
+        while tail.len() % 64 != 56 { tail.push(0); }
+        match self.algorithm {
+            Algorithm::Sha256 => tail.extend_from_slice(&bits.to_be_bytes()),
+            Algorithm::Md5 => tail.extend_from_slice(&bits.to_le_bytes()),
+        }
+        for block in tail.chunks_exact(64) { self.block(block); }
+        match self.algorithm {
+            Algorithm::Sha256 => self.state.iter().flat_map(|w| w.to_be_bytes()).collect(),
+            Algorithm::Md5 => self.state[..4].iter().flat_map(|w| w.to_le_bytes()).collect(),
+        }
+    }
+
+    fn block(&mut self, block: &[u8]) {
+        match self.algorithm {
+            Algorithm::Sha256 => self.sha256_block(block),
+            Algorithm::Md5 => self.md5_block(block),
+        }
+    }
+
+    fn sha256_block(&mut self, block: &[u8]) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g; g = f; f = e;
+            e = d.wrapping_add(t1);
+            d = c; c = b; b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (s, v) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+
+    fn md5_block(&mut self, block: &[u8]) {
+        let mut m = [0u32; 16];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            m[i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        let [mut a, mut b, mut c, mut d, ..] = self.state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            // The constants are the first 32 bits of |sin(i + 1)|
+            let k = ((i as f64 + 1.0).sin().abs() * 4_294_967_296.0) as u32;
+            let f = f.wrapping_add(a).wrapping_add(k).wrapping_add(m[g]);
+            a = d; d = c; c = b;
+            b = b.wrapping_add(f.rotate_left(MD5_SHIFTS[i / 16 * 4 + i % 4]));
+        }
+        for (s, v) in self.state.iter_mut().zip([a, b, c, d]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(algorithm: Algorithm, pieces: &[&[u8]]) -> String {
+        let mut digest = Digest::new(algorithm);
+        for piece in pieces { digest.update(piece); }
+        digest.finish().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn sha256_vectors() {
+        assert_eq!(hex(Algorithm::Sha256, &[b""]), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(hex(Algorithm::Sha256, &[b"abc"]), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        let long = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+        assert_eq!(hex(Algorithm::Sha256, &[&long[..10], &long[10..]]),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1");
+    }
+
+    #[test]
+    fn md5_vectors() {
+        assert_eq!(hex(Algorithm::Md5, &[b""]), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(Algorithm::Md5, &[b"abc"]), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(hex(Algorithm::Md5, &[b"The quick brown fox ", b"jumps over the lazy dog"]),
+            "9e107d9d372bb6826bd81d3542a419d6");
+    }
+}
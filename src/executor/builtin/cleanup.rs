@@ -0,0 +1,144 @@
+// src/executor/builtin/cleanup.rs
+// cleanup [-n] [-y] [-t] [DIR] — find the build output and caches under a
+// project that can be rebuilt (target/, node_modules/, __pycache__ ...),
+// show what each takes up, and remove them once confirmed.
+//
+// A generic name like `build` or `target` only counts next to a file that
+// shows which tool made it, so a hand-made directory of that name is left
+// alone. The search doesn't follow symlinks or look inside .git.
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+use crate::shell::Shell;
+use super::util::{disk_usage, NumberFormat, Opt, Options};
+
+pub const CLEANUP: Options = Options {
+    name: "cleanup",
+    operands: "[DIR]",
+    about: "Report rebuildable build output and caches under DIR (default: .) \
+            and remove them after asking.",
+    opts: &[
+        Opt::new("n", "dry-run", "only report, remove nothing"),
+        Opt::new("y", "yes", "remove without asking"),
+        Opt::new("t", "trash", "empty the trash as well"),
+    ],
+    numeric: None,
+};
+
+/// A kind of directory that can be rebuilt: its name, the files one of
+/// which must sit beside it (none: the name is enough), and what it is.
+struct Hog {
+    name: &'static str,
+    markers: &'static [&'static str],
+    what: &'static str,
+}
+
+const HOGS: &[Hog] = &[
+    Hog { name: "target",        markers: &["Cargo.toml", "pom.xml"], what: "build output" },
+    Hog { name: "node_modules",  markers: &[], what: "npm packages" },
+    Hog { name: "__pycache__",   markers: &[], what: "Python bytecode" },
+    Hog { name: ".pytest_cache", markers: &[], what: "pytest cache" },
+    Hog { name: ".mypy_cache",   markers: &[], what: "mypy cache" },
+    Hog { name: ".gradle",       markers: &[], what: "Gradle cache" },
+    Hog { name: ".next",         markers: &["package.json"], what: "Next.js build" },
+    Hog { name: "build",         markers: &["CMakeLists.txt", "setup.py", "pyproject.toml", "build.gradle", "package.json"], what: "build output" },
+    Hog { name: "dist",          markers: &["setup.py", "pyproject.toml", "package.json"], what: "packaged output" },
+];
+
+pub fn builtin_cleanup(shell: &Shell, args: &[String]) -> i32 {
+    let p = match CLEANUP.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.len() > 1 { errln!("usage: cleanup [-n] [-y] [-t] [DIR]"); return 1; }
+    let root = shell.cwd.join(p.operands.first().copied().unwrap_or("."));
+    if !root.is_dir() {
+        errln!("cleanup: {}: not a directory", paths::display(&root));
+        return 1;
+    }
+
+    let mut found = Vec::new();
+    find_hogs(&root, &mut found);
+    let mut targets: Vec<(PathBuf, u64, &str)> = found.into_iter()
+        .map(|(path, what)| { let size = disk_usage(&path); (path, size, what) })
+        .collect();
+    targets.sort_by_key(|t| std::cmp::Reverse(t.1));
+
+    // The trash is only ever reported unless -t asks for it to go too
+    let trash = super::trash::trash_dir().filter(|t| t.is_dir()).map(|t| { let size = disk_usage(&t); (t, size) });
+    let numbers = NumberFormat::from_config(shell);
+
+    for (path, size, what) in &targets {
+        let shown = path.strip_prefix(&root).unwrap_or(path);
+        outln!("{:>8}  {}  ({})", numbers.size(*size), paths::display(shown), what);
+    }
+    let mut total: u64 = targets.iter().map(|t| t.1).sum();
+    match &trash {
+        Some((_, size)) if *size > 0 && p.has("trash") => {
+            outln!("{:>8}  trash", numbers.size(*size));
+            total += size;
+        }
+        Some((_, size)) if *size > 0 => {
+            errln!("cleanup: the trash holds {} more (-t to empty it too)", numbers.size(*size));
+        }
+        _ => {}
+    }
+    let empty_trash = p.has("trash") && trash.as_ref().is_some_and(|t| t.1 > 0);
+    if targets.is_empty() && !empty_trash {
+        outln!("Nothing to clean up.");
+        return 0;
+    }
+    let count = targets.len() + empty_trash as usize;
+    outln!("{:>8}  total in {} {}", numbers.size(total), count,
+        if count == 1 { "directory" } else { "directories" });
+
+    if p.has("dry-run") || !(p.has("yes") || confirm(count)) { return 0; }
+
+    let mut code = 0;
+    for (path, _, _) in &targets {
+        if let Err(e) = std::fs::remove_dir_all(path) {
+            errln!("cleanup: {}: {}", paths::display(path), e);
+            code = 1;
+        }
+    }
+    if let Some((trash, _)) = trash.filter(|_| empty_trash) {
+        if let Err(e) = super::trash::empty() {
+            errln!("cleanup: {}: {}", paths::display(&trash), e);
+            code = 1;
+        }
+    }
+    code
+}
+
+/// Collect the rebuildable directories under `dir`, without looking
+/// inside the ones found.
+fn find_hogs(dir: &Path, found: &mut Vec<(PathBuf, &'static str)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        // A link's own type, so links are never followed
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) { continue; }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == ".git" { continue; }
+
+        let hog = HOGS.iter().find(|h| {
+            h.name == name && (h.markers.is_empty() || h.markers.iter().any(|m| dir.join(m).exists()))
+        });
+        match hog {
+            Some(hog) => found.push((entry.path(), hog.what)),
+            None      => find_hogs(&entry.path(), found),
+        }
+    }
+}
+
+/// Ask before removing anything. Without a terminal the answer is no.
+fn confirm(count: usize) -> bool {
+    if !std::io::stdin().is_terminal() {
+        errln!("cleanup: nothing removed (no terminal to confirm on; use -y)");
+        return false;
+    }
+    crossterm::terminal::disable_raw_mode().ok();
+    err!("Remove {}? [y/N] ", if count == 1 { "it" } else { "them" });
+    std::io::stderr().flush().ok();
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer).ok();
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
@@ -0,0 +1,82 @@
+// src/executor/builtin/complete.rs
+// complete — say how a command's arguments are tab-completed:
+//
+//   complete -W 'start stop status' svc    from a list of words
+//   complete -G '*.tar.gz' untar           files matching a glob
+//   complete -F _hosts ssh                 lines printed by a function,
+//                                          called as FUNC CMD WORD PREVIOUS
+//   complete [-p] [CMD...]                 show specs, as complete commands
+//   complete -r [CMD...]                   remove them (all with no CMD)
+//
+// The specs live on the shell; see completion/programmable.rs for how the
+// line editor uses them.
+
+use crate::completion::CompletionSpec;
+use crate::shell::Shell;
+use super::util::{Opt, Options};
+
+pub const COMPLETE: Options = Options {
+    name: "complete",
+    operands: "[COMMAND]...",
+    about: "Set how the arguments of each COMMAND are completed, or show or remove what has been set.",
+    opts: &[
+        Opt::new("W", "words", "complete from the words in WORDS").takes("WORDS"),
+        Opt::new("G", "glob", "complete files whose names match GLOB").takes("GLOB"),
+        Opt::new("F", "function", "complete from the lines FUNCTION prints").takes("FUNCTION"),
+        Opt::new("p", "print", "show the completions set, as complete commands"),
+        Opt::new("r", "remove", "forget the completions for each COMMAND, or all"),
+    ],
+    numeric: None,
+};
+
+pub fn builtin_complete(shell: &mut Shell, args: &[String]) -> i32 {
+    let p = match COMPLETE.parse(args) { Ok(p) => p, Err(code) => return code };
+
+    if p.has("remove") {
+        if p.operands.is_empty() { shell.completions.clear(); return 0; }
+        let mut code = 0;
+        for name in &p.operands {
+            if shell.completions.remove(*name).is_none() {
+                errln!("complete: {}: no completion specification", name);
+                code = 1;
+            }
+        }
+        return code;
+    }
+
+    let words = p.value("words").map(|w| w.split_whitespace().map(String::from).collect()).unwrap_or_default();
+    let spec = CompletionSpec {
+        words,
+        glob: p.value("glob").map(String::from),
+        function: p.value("function").map(String::from),
+    };
+    if spec.words.is_empty() && spec.glob.is_none() && spec.function.is_none() {
+        return print_specs(shell, &p.operands);
+    }
+    if p.operands.is_empty() { errln!("usage: complete [-W WORDS] [-G GLOB] [-F FUNCTION] COMMAND..."); return 2; }
+    if let Some(glob) = &spec.glob {
+        if let Err(e) = glob::Pattern::new(glob) { errln!("complete: bad glob '{}': {}", glob, e); return 2; }
+    }
+    for name in &p.operands {
+        shell.completions.insert(name.to_string(), spec.clone());
+    }
+    0
+}
+
+/// `complete -p`: the specs for `names`, or all of them by name.
+fn print_specs(shell: &Shell, names: &[&str]) -> i32 {
+    let mut code = 0;
+    if names.is_empty() {
+        let mut all: Vec<_> = shell.completions.iter().collect();
+        all.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, spec) in all { outln!("{}", spec.command_line(name)); }
+        return code;
+    }
+    for name in names {
+        match shell.completions.get(*name) {
+            Some(spec) => outln!("{}", spec.command_line(name)),
+            None => { errln!("complete: {}: no completion specification", name); code = 1; }
+        }
+    }
+    code
+}
@@ -1,16 +1,36 @@
 // src/executor/builtin/core.rs
 use std::path::PathBuf;
-use crate::shell::Shell;
+use crate::paths;
+use crate::shell::{is_valid_name, CommandWrapper, GuardAction, GuardRule, HashedCommand, Shell, HOOK_EVENTS};
+use super::{pager, printf};
+use super::util::{find_in_path, is_executable, local_datetime, new_temp_file, operands, Arg, Opt, Options};
+
+pub const HISTORY: Options = Options {
+    name: "history",
+    operands: "[N | PATTERN]",
+    about: "Show the command history: all of it, the last N entries, or the\n\
+            entries matching PATTERN (a glob if it has * ? or [, else any\n\
+            text in the line; case doesn't matter).",
+    opts: &[
+        Opt::new("n", "lines", "show only the last N entries").takes("N"),
+        Opt::new("t", "time", "show when each entry was run"),
+        Opt::new("c", "clear", "clear the history"),
+        Opt::new("d", "delete", "delete entry N (-1 is the last)").takes("N"),
+        Opt::new("", "forget", "remove entries matching PAT, from `stats` too").takes("PAT"),
+    ],
+    numeric: None,
+};
 
 pub fn builtin_cd(shell: &mut Shell, args: &[String]) -> i32 {
-    let target: PathBuf = match args.get(1).map(|s| s.as_str()) {
+    let operand = operands(args).first();
+    let target: PathBuf = match operand.map(|s| s.as_str()) {
         None | Some("~") => match dirs::home_dir() {
             Some(h) => h,
-            None => { eprintln!("cd: cannot find home directory"); return 1; }
+            None => { errln!("cd: cannot find home directory"); return 1; }
         },
         Some("-") => match &shell.prev_dir {
             Some(p) => p.clone(),
-            None => { eprintln!("cd: no previous directory"); return 1; }
+            None => { errln!("cd: no previous directory"); return 1; }
         },
         Some(path) => {
             if path.starts_with("~/") || path.starts_with("~\\") {
@@ -23,17 +43,22 @@ pub fn builtin_cd(shell: &mut Shell, args: &[String]) -> i32 {
 
     let target = match target.canonicalize() {
         Ok(p) => p,
-        Err(e) => { eprintln!("cd: {}: {}", args.get(1).unwrap_or(&String::new()), e); return 1; }
+        Err(e) => { errln!("cd: {}: {}", operand.unwrap_or(&String::new()), e); return 1; }
     };
 
     match std::env::set_current_dir(&target) {
-        Ok(_) => { shell.prev_dir = Some(shell.cwd.clone()); shell.cwd = target; 0 }
-        Err(e) => { eprintln!("cd: {e}"); 1 }
+        Ok(_) => {
+            shell.prev_dir = Some(shell.cwd.clone());
+            shell.cwd = target;
+            shell.run_hooks("chpwd", &[]);
+            0
+        }
+        Err(e) => { errln!("cd: {e}"); 1 }
     }
 }
 
 pub fn builtin_pwd(shell: &Shell) -> i32 {
-    println!("{}", shell.cwd.display());
+    outln!("{}", paths::display(&shell.cwd));
     0
 }
 
@@ -42,47 +67,217 @@ pub fn builtin_echo(args: &[String]) -> i32 {
     let mut start = 1;
     if args.get(1).map(|s| s.as_str()) == Some("-n") { no_newline = true; start = 2; }
     let output = args[start..].join(" ").replace("\\n", "\n").replace("\\t", "\t");
-    if no_newline { print!("{}", output); } else { println!("{}", output); }
+    if no_newline { out!("{}", output); } else { outln!("{}", output); }
     0
 }
 
+/// How many lines `yes` prints to a terminal, where nothing would stop it.
+const YES_TERMINAL_LINES: usize = 1000;
+
+/// yes [STRING...] — print STRING (default y) over and over, until the
+/// reader goes away. Straight to a terminal it stops after a screenful or so.
+pub fn builtin_yes(args: &[String]) -> i32 {
+    use std::io::Write;
+    let line = if args.len() > 1 { args[1..].join(" ") } else { "y".to_string() };
+    let limit = if super::io::stdout_is_terminal() { YES_TERMINAL_LINES } else { usize::MAX };
+    // Written in large blocks; a write error means the reader has closed
+    let block = format!("{}\n", line).repeat((8192 / (line.len() + 1)).max(1));
+    let per_block = block.len() / (line.len() + 1);
+    let mut out = super::io::stdout();
+    let mut written = 0;
+    while written < limit {
+        let result = if limit - written < per_block {
+            out.write_all(format!("{}\n", line).repeat(limit - written).as_bytes())
+        } else {
+            out.write_all(block.as_bytes())
+        };
+        if result.and_then(|_| out.flush()).is_err() { return 0; }
+        written = written.saturating_add(per_block);
+    }
+    0
+}
+
+/// Names accepted by `set -o` / `set +o`.
+const SET_OPTIONS: &[&str] = &["autocorrect", "errexit", "fuzzycomplete", "nohistory", "pipefail", "posix"];
+
+fn option_flag<'a>(shell: &'a mut Shell, name: &str) -> Option<&'a mut bool> {
+    match name {
+        "autocorrect" => Some(&mut shell.autocorrect),
+        "errexit"   => Some(&mut shell.exit_on_error),
+        "fuzzycomplete" => Some(&mut shell.fuzzy_complete),
+        "nohistory" => Some(&mut shell.no_history),
+        "pipefail"  => Some(&mut shell.pipefail),
+        "posix"     => Some(&mut shell.posix),
+        _           => None,
+    }
+}
+
+pub fn builtin_set(shell: &mut Shell, args: &[String]) -> i32 {
+    match args.get(1).map(|s| s.as_str()) {
+        // set -e / set +e
+        Some("-e") => { shell.exit_on_error = true;  0 }
+        Some("+e") => { shell.exit_on_error = false; 0 }
+
+        // set -o NAME / set +o NAME, or list options with a bare -o
+        Some(flag @ ("-o" | "+o")) => {
+            let Some(name) = args.get(2) else {
+                for name in SET_OPTIONS {
+                    let on = option_flag(shell, name).map(|f| *f).unwrap_or(false);
+                    outln!("{:<14} {}", name, if on { "on" } else { "off" });
+                }
+                return 0;
+            };
+            match option_flag(shell, name) {
+                Some(value) => { *value = flag == "-o"; 0 }
+                None => { errln!("set: {}: invalid option name", name); 1 }
+            }
+        }
+
+        // Bare `set` lists every variable, exported or not
+        None => {
+            let mut vars: Vec<_> = shell.env.iter().collect();
+            vars.sort();
+            for (k, v) in vars { outln!("{}={}", k, printf::shell_quote(v)); }
+            0
+        }
+
+        _ => builtin_export(shell, args),
+    }
+}
+
+/// export [-n] [-p] [NAME[=VALUE]...] — mark variables for passing to
+/// child processes. `-n` turns the attribute off again.
 pub fn builtin_export(shell: &mut Shell, args: &[String]) -> i32 {
-    // Handle set -e / set +e
-    if args.get(1).map(|s| s.as_str()) == Some("-e") {
-        shell.exit_on_error = true;
-        return 0;
+    let mut unexport = false;
+    let mut names = Vec::new();
+    let mut options_done = false;
+    for arg in &args[1..] {
+        match arg.as_str() {
+            _ if options_done => names.push(arg.as_str()),
+            "--" => options_done = true,
+            "-n" => unexport = true,
+            "-p" => {}
+            _    => names.push(arg.as_str()),
+        }
     }
-    if args.get(1).map(|s| s.as_str()) == Some("+e") {
-        shell.exit_on_error = false;
+    if names.is_empty() {
+        print_vars(shell, "export", |shell, k| shell.exported.contains(k));
         return 0;
     }
-    
-    if args.len() == 1 {
-        for (k, v) in &shell.env { println!("{}={}", k, v); }
-        return 0;
+
+    let mut code = 0;
+    for arg in names {
+        let (name, value) = split_assignment(arg);
+        if !is_valid_name(name) {
+            errln!("export: `{}': not a valid identifier", arg);
+            code = 1;
+            continue;
+        }
+        if let Some(v) = value {
+            if !shell.set_var(name, &v) { code = 1; continue; }
+        }
+        shell.set_exported(name, !unexport);
     }
+    code
+}
+
+/// declare [-x|+x] [-r] [-p] [NAME[=VALUE]...] — set variables and their
+/// attributes. With no names, lists variables (only those with the given
+/// attribute if -x or -r is passed).
+pub fn builtin_declare(shell: &mut Shell, args: &[String]) -> i32 {
+    let mut export:   Option<bool> = None;
+    let mut readonly = false;
+    let mut names = Vec::new();
     for arg in &args[1..] {
-        if let Some((k, v)) = arg.split_once('=') {
-            let v = v.trim_matches('"').trim_matches('\'').to_string();
-            shell.env.insert(k.to_string(), v.clone());
-            unsafe { std::env::set_var(k, &v); }
+        match arg.as_str() {
+            "-x" => export = Some(true),
+            "+x" => export = Some(false),
+            "-r" => readonly = true,
+            "-p" => {}
+            s if s.starts_with(['-', '+']) && s.len() > 1 => {
+                errln!("declare: {}: invalid option", s);
+                return 2;
+            }
+            _ => names.push(arg.as_str()),
         }
     }
-    0
+    if names.is_empty() {
+        print_vars(shell, "declare", |shell, k| {
+            (export != Some(true) || shell.exported.contains(k))
+                && (!readonly || shell.readonly.contains(k))
+        });
+        return 0;
+    }
+
+    let mut code = 0;
+    for arg in names {
+        let (name, value) = split_assignment(arg);
+        if !is_valid_name(name) {
+            errln!("declare: `{}': not a valid identifier", arg);
+            code = 1;
+            continue;
+        }
+        // `declare NAME` creates the variable empty if it doesn't exist
+        let value = value.or_else(|| (!shell.env.contains_key(name)).then(String::new));
+        if let Some(v) = value {
+            if !shell.set_var(name, &v) { code = 1; continue; }
+        }
+        if let Some(on) = export { shell.set_exported(name, on); }
+        if readonly { shell.readonly.insert(name.to_string()); }
+    }
+    code
+}
+
+/// readonly [-p] [NAME[=VALUE]...] — make variables unchangeable for the
+/// rest of the session.
+pub fn builtin_readonly(shell: &mut Shell, args: &[String]) -> i32 {
+    let mut declare_args = vec!["declare".to_string(), "-r".to_string()];
+    declare_args.extend(args[1..].iter().filter(|a| *a != "-p").cloned());
+    if declare_args.len() == 2 {
+        print_vars(shell, "readonly", |shell, k| shell.readonly.contains(k));
+        return 0;
+    }
+    builtin_declare(shell, &declare_args)
 }
 
 pub fn builtin_unset(shell: &mut Shell, args: &[String]) -> i32 {
+    let mut code = 0;
     for arg in &args[1..] {
-        shell.env.remove(arg);
-        unsafe { std::env::remove_var(arg); }
+        if !shell.unset_var(arg) { code = 1; }
+    }
+    code
+}
+
+/// `NAME=value` → (NAME, Some(value)), dropping quotes default mode leaves
+/// around the value.
+fn split_assignment(arg: &str) -> (&str, Option<String>) {
+    match arg.split_once('=') {
+        Some((k, v)) => (k, Some(v.trim_matches('"').trim_matches('\'').to_string())),
+        None         => (arg, None),
+    }
+}
+
+/// List variables matching `keep` as `prefix [-rx] NAME=value`, sorted, in
+/// a form that can be read back in.
+fn print_vars(shell: &Shell, prefix: &str, keep: impl Fn(&Shell, &str) -> bool) {
+    let mut names: Vec<&String> = shell.env.keys().filter(|k| keep(shell, k)).collect();
+    names.sort();
+    for name in names {
+        let mut attrs = String::new();
+        if shell.readonly.contains(name) { attrs.push('r'); }
+        if shell.exported.contains(name) { attrs.push('x'); }
+        let flags = match (prefix, attrs.is_empty()) {
+            ("declare", false) => format!(" -{}", attrs),
+            ("declare", true)  => " --".to_string(),
+            _                  => String::new(),
+        };
+        outln!("{}{} {}={}", prefix, flags, name, printf::shell_quote(&shell.env[name]));
     }
-    0
 }
 
 pub fn builtin_alias(shell: &mut Shell, args: &[String]) -> i32 {
-    if args.len() == 1 {
-        for (k, v) in &shell.aliases { println!("alias {}='{}'", k, v); }
-        return 0;
+    if args.len() == 1 || args[1] == "--search" || args[1].starts_with("--search=") {
+        return list_aliases(shell, args);
     }
 
     // Rejoin all args after "alias" — handles cases where the shell
@@ -93,7 +288,7 @@ pub fn builtin_alias(shell: &mut Shell, args: &[String]) -> i32 {
         let k = k.trim().trim_matches('"').trim_matches('\'').to_string();
         let v = v.trim().trim_matches('"').trim_matches('\'').to_string();
         if k.is_empty() {
-            eprintln!("alias: invalid syntax");
+            errln!("alias: invalid syntax");
             return 1;
         }
         shell.aliases.insert(k, v);
@@ -104,115 +299,731 @@ pub fn builtin_alias(shell: &mut Shell, args: &[String]) -> i32 {
     // No = found — just show existing alias
     for arg in &args[1..] {
         if let Some(v) = shell.aliases.get(arg.as_str()) {
-            println!("alias {}='{}'", arg, v);
+            outln!("alias {}='{}'", arg, v);
         } else {
-            eprintln!("alias: {}: not found", arg);
+            errln!("alias: {}: not found", arg);
         }
     }
     0
 }
 
+/// alias [--search PAT] — every alias, sorted, coloured and paged.
+fn list_aliases(shell: &Shell, args: &[String]) -> i32 {
+    let (search, _) = match pager::take_search(&args[1..]) {
+        Ok(parsed) => parsed,
+        Err(e) => { errln!("alias: {}", e); return 1; }
+    };
+    let mut names: Vec<&String> = shell.aliases.keys().collect();
+    names.sort();
+    let mut entries: Vec<String> = names.into_iter()
+        .map(|k| format!("\x1b[34malias\x1b[0m \x1b[32m{}\x1b[0m='{}'", k, shell.aliases[k]))
+        .collect();
+
+    if let Some(pattern) = &search {
+        entries = pager::filter_entries(entries, pattern);
+        if entries.is_empty() { errln!("alias: no matches for '{}'", pattern); return 1; }
+    }
+    if entries.is_empty() { return 0; }
+    pager::page("alias", &(entries.join("\n") + "\n"));
+    0
+}
+
 pub fn builtin_unalias(shell: &mut Shell, args: &[String]) -> i32 {
     for arg in &args[1..] { shell.aliases.remove(arg.as_str()); }
     0
 }
 
-pub fn builtin_history(shell: &Shell) -> i32 {
-    for (i, line) in shell.history.iter().enumerate() {
-        println!("{:4}  {}", i + 1, line);
+/// wrap [NAME [--before CMD] [--after CMD]] | wrap -r NAME... — run
+/// commands before and after every use of NAME. `$?` in the after
+/// command is NAME's exit status.
+pub fn builtin_wrap(shell: &mut Shell, args: &[String]) -> i32 {
+    if args.len() == 1 {
+        let mut names: Vec<&String> = shell.wrappers.keys().collect();
+        names.sort();
+        for name in names { outln!("{}", shell.wrappers[name].command_line(name)); }
+        return 0;
+    }
+
+    if args[1] == "-r" {
+        let mut code = 0;
+        for name in &args[2..] {
+            if shell.wrappers.remove(name).is_none() {
+                errln!("wrap: {}: not wrapped", name);
+                code = 1;
+            }
+        }
+        if !shell.loading_rc { shell.save_wrappers(); }
+        return code;
+    }
+
+    let name = &args[1];
+    if args.len() == 2 {
+        return match shell.wrappers.get(name) {
+            Some(w) => { outln!("{}", w.command_line(name)); 0 }
+            None    => { errln!("wrap: {}: not wrapped", name); 1 }
+        };
+    }
+
+    let mut wrapper = CommandWrapper::default();
+    let mut i = 2;
+    while i < args.len() {
+        let (flag, inline) = match args[i].split_once('=') {
+            Some((f, v)) => (f, Some(v.to_string())),
+            None         => (args[i].as_str(), None),
+        };
+        let slot = match flag {
+            "--before" => &mut wrapper.before,
+            "--after"  => &mut wrapper.after,
+            _ => {
+                errln!("wrap: unknown option: {}", args[i]);
+                errln!("usage: wrap NAME [--before CMD] [--after CMD]");
+                return 1;
+            }
+        };
+        let value = match inline {
+            Some(v) => v,
+            None => {
+                i += 1;
+                match args.get(i) {
+                    Some(v) => v.clone(),
+                    None    => { errln!("wrap: {}: missing command", flag); return 1; }
+                }
+            }
+        };
+        *slot = Some(value);
+        i += 1;
     }
+
+    shell.wrappers.insert(name.clone(), wrapper);
+    if !shell.loading_rc { shell.save_wrappers(); }
     0
 }
 
+/// hook [list] | hook add EVENT FUNC... | hook rm EVENT FUNC... — run
+/// functions before each command, before each prompt, or after cd.
+pub fn builtin_hook(shell: &mut Shell, args: &[String]) -> i32 {
+    const USAGE: &str = "usage: hook [list] | hook add|rm EVENT FUNC... (EVENT: preexec, precmd, chpwd)";
+    let sub = args.get(1).map(|s| s.as_str()).unwrap_or("list");
+    if sub == "list" {
+        for line in shell.hooks.command_lines() { outln!("{}", line); }
+        return 0;
+    }
+    if !matches!(sub, "add" | "rm") || args.len() < 4 {
+        errln!("{}", USAGE);
+        return 2;
+    }
+    let Some(names) = shell.hooks.get_mut(&args[2]) else {
+        errln!("hook: {}: unknown event (one of: {})", args[2], HOOK_EVENTS.join(", "));
+        return 1;
+    };
+
+    let mut code = 0;
+    for name in &args[3..] {
+        if sub == "add" {
+            if !is_valid_name(name) { errln!("hook: {}: not a valid function name", name); code = 1; continue; }
+            if !names.contains(name) { names.push(name.clone()); }
+        } else if let Some(i) = names.iter().position(|n| n == name) {
+            names.remove(i);
+        } else {
+            errln!("hook: {}: not a {} hook", name, args[2]);
+            code = 1;
+        }
+    }
+    if !shell.loading_rc { shell.save_hooks(); }
+    code
+}
+
+pub fn builtin_history(shell: &mut Shell, args: &[String]) -> i32 {
+    let p = match HISTORY.parse(args) { Ok(p) => p, Err(code) => return code };
+    if let Some(pattern) = p.value("forget") {
+        let removed = shell.forget_history(pattern);
+        outln!("history: forgot {} entr{}", removed, if removed == 1 { "y" } else { "ies" });
+        return 0;
+    }
+    if p.has("clear") {
+        shell.clear_history();
+        return 0;
+    }
+    if let Some(n) = p.value("delete") {
+        // Negative counts back from the end
+        let entry = match n.parse::<i64>() {
+            Ok(n) if n < 0 => shell.history.len() as i64 + 1 + n,
+            Ok(n) => n,
+            Err(_) => { errln!("history: {}: not a number", n); return 2; }
+        };
+        if shell.delete_history(usize::try_from(entry).unwrap_or(0)).is_none() {
+            errln!("history: {}: no such entry", n);
+            return 1;
+        }
+        return 0;
+    }
+
+    // `history 20` is `history -n 20`; other words are something to look for
+    let (count, pattern) = match p.operands[..] {
+        [n] if n.parse::<usize>().is_ok() => (Some(n), None),
+        [] => (None, None),
+        ref words => (None, Some(words.join(" "))),
+    };
+    let last = match p.value("lines").or(count).map(str::parse::<usize>) {
+        None => None,
+        Some(Ok(n)) => Some(n),
+        Some(Err(_)) => { errln!("history: invalid number: '{}'", p.value("lines").unwrap_or_default()); return 2; }
+    };
+    let times = if p.has("time") { shell.history_times() } else { Vec::new() };
+
+    // Entries keep their numbers, so `!N` still finds them
+    let shown: Vec<usize> = (0..shell.history.len())
+        .filter(|&i| pattern.as_ref().is_none_or(|pat| crate::shell::history::history_matches(&shell.history[i], pat)))
+        .collect();
+    let skip = last.map_or(0, |n| shown.len().saturating_sub(n));
+    for &i in &shown[skip..] {
+        let line = &shell.history[i];
+        match times.get(i) {
+            Some(Some(secs)) => {
+                let (year, month, day, hour, min, _) = local_datetime(*secs);
+                outln!("{:4}  {}-{:02}-{:02} {:02}:{:02}  {}", i + 1, year, month, day, hour, min, line);
+            }
+            Some(None) => outln!("{:4}  {:16}  {}", i + 1, "", line),
+            None => outln!("{:4}  {}", i + 1, line),
+        }
+    }
+    if pattern.is_some() && shown.is_empty() { 1 } else { 0 }
+}
+
+/// fc [-e EDITOR] [FIRST [LAST]] — open history entries (the previous
+/// command by default) in an editor, then run what's saved.
+/// fc -s [OLD=NEW] [COMMAND] — run a past command again, with each OLD in
+/// it replaced by NEW.
+///
+/// FIRST and LAST are entry numbers, counts back from the end (-2), or the
+/// start of a command, for the latest one that begins with it. Whatever
+/// fc runs takes the place of the fc line in the history.
+pub fn builtin_fc(shell: &mut Shell, args: &[String]) -> i32 {
+    let mut editor = None;
+    let mut rerun = false;
+    let mut i = 1;
+    while let Some(word) = args.get(i) {
+        match word.as_str() {
+            "-e" => { i += 1; editor = args.get(i).cloned(); }
+            "-s" => rerun = true,
+            "--" => { i += 1; break; }
+            "--help" => {
+                outln!("usage: fc [-e EDITOR] [FIRST [LAST]]\n       fc -s [OLD=NEW] [COMMAND]");
+                return 0;
+            }
+            // Negative numbers are entries, not options
+            w if w.starts_with('-') && w[1..].parse::<usize>().is_err() => {
+                errln!("fc: invalid option: {}", w);
+                return 2;
+            }
+            _ => break,
+        }
+        i += 1;
+    }
+    let operands = &args[i.min(args.len())..];
+
+    // The fc line itself, if it was recorded, isn't one to edit
+    let fc_recorded = shell.history.last().is_some_and(|l| l.split_whitespace().next() == Some("fc"));
+    let past = shell.history.len() - usize::from(fc_recorded);
+    let entry = |spec: Option<&String>| -> Result<usize, String> {
+        let Some(spec) = spec else {
+            return past.checked_sub(1).ok_or_else(|| "no command in the history".to_string());
+        };
+        let found = match spec.parse::<i64>() {
+            Ok(n) if n < 0 => usize::try_from(past as i64 + n).ok(),
+            Ok(n) => usize::try_from(n - 1).ok().filter(|&i| i < past),
+            Err(_) => shell.history[..past].iter().rposition(|l| l.starts_with(spec.as_str())),
+        };
+        found.ok_or_else(|| format!("{}: no such command in the history", spec))
+    };
+
+    let commands = if rerun {
+        let (substitution, rest) = match operands.first() {
+            Some(first) if first.contains('=') => (first.split_once('='), &operands[1..]),
+            _ => (None, operands),
+        };
+        let at = match entry(rest.first()) { Ok(at) => at, Err(e) => { errln!("fc: {}", e); return 1; } };
+        let command = &shell.history[at];
+        vec![match substitution {
+            Some((old, new)) if !old.is_empty() => command.replace(old, new),
+            _ => command.clone(),
+        }]
+    } else {
+        let range = entry(operands.first()).and_then(|first| {
+            entry(operands.get(1).or(operands.first())).map(|last| (first, last))
+        });
+        let (first, last) = match range { Ok(r) => r, Err(e) => { errln!("fc: {}", e); return 1; } };
+        let mut lines: Vec<String> = shell.history[first.min(last)..=first.max(last)].to_vec();
+        if first > last { lines.reverse(); }
+        match edit_commands(shell, &lines, editor) {
+            Ok(commands) => commands,
+            Err(code) => return code,
+        }
+    };
+
+    // What's run goes in the history instead of the fc line
+    if fc_recorded { shell.delete_history(shell.history.len()); }
+    let mut code = 0;
+    for command in commands {
+        errln!("{}", command);
+        if shell.should_record(&command) {
+            shell.history.push(command.clone());
+            shell.save_history_line(&command);
+        }
+        code = match shell.eval(&command) {
+            Ok(()) => shell.last_exit_code,
+            Err(e) => { errln!("fc: {e}"); 1 }
+        };
+    }
+    code
+}
+
+/// Open `lines` in an editor ($FCEDIT, $VISUAL, $EDITOR or vi) and return
+/// the commands in the saved file; Err with a status if the editor failed.
+fn edit_commands(shell: &mut Shell, lines: &[String], editor: Option<String>) -> Result<Vec<String>, i32> {
+    let editor = editor
+        .or_else(|| ["FCEDIT", "VISUAL", "EDITOR"].iter().find_map(|v| shell.env.get(*v).filter(|e| !e.is_empty()).cloned()))
+        .unwrap_or_else(|| "vi".to_string());
+    use std::io::Write;
+    let (path, mut file) = match new_temp_file("fc", "sh") {
+        Ok(created) => created,
+        Err(e) => { errln!("fc: {}: {}", std::env::temp_dir().display(), e); return Err(1); }
+    };
+    if let Err(e) = file.write_all((lines.join("\n") + "\n").as_bytes()) {
+        errln!("fc: {}: {}", path.display(), e);
+        std::fs::remove_file(&path).ok();
+        return Err(1);
+    }
+    // Through the shell, so an editor given with options works and gets
+    // the terminal like any foreground command
+    let edited = shell.eval(&format!("{} {}", editor, printf::shell_quote(&path.to_string_lossy())));
+    let content = std::fs::read_to_string(&path);
+    std::fs::remove_file(&path).ok();
+    if edited.is_err() || shell.last_exit_code != 0 {
+        errln!("fc: {} failed; nothing run", editor);
+        return Err(shell.last_exit_code.max(1));
+    }
+    Ok(crate::parser::logical_lines(&content.unwrap_or_default()))
+}
+
 pub fn builtin_source(shell: &mut Shell, args: &[String]) -> i32 {
-    if args.len() < 2 { eprintln!("source: filename required"); return 1; }
-    let path = shell.cwd.join(&args[1]);
+    let Some(file) = operands(args).first() else { errln!("source: filename required"); return 1 };
+    let path = shell.cwd.join(file);
     match std::fs::read_to_string(&path) {
         Ok(content) => {
-            for line in content.lines() {
-                let line = line.trim();
-                if line.is_empty() || line.starts_with('#') { continue; }
-                if let Err(e) = shell.eval(line) { eprintln!("source: {e}"); }
+            for cmd in crate::parser::logical_lines(&content) {
+                if let Err(e) = shell.eval(&cmd) { errln!("source: {e}"); }
             }
             0
         }
-        Err(e) => { eprintln!("source: {}: {e}", args[1]); 1 }
+        Err(e) => { errln!("source: {}: {e}", file); 1 }
     }
 }
 
 pub fn builtin_clear() -> i32 {
-    print!("\x1B[2J\x1B[H");
+    out!("\x1B[2J\x1B[H");
     use std::io::Write;
-    std::io::stdout().flush().ok();
+    super::io::stdout().flush().ok();
     0
 }
 
+/// Status for `exit [N]`: N if given, else the last command's status.
+pub fn exit_status(shell: &Shell, args: &[String]) -> i32 {
+    match args.get(1) {
+        None => shell.last_exit_code,
+        Some(n) => match n.parse::<i32>() {
+            Ok(code) => code & 0xff,
+            Err(_) => { errln!("exit: {}: numeric argument required", n); 2 }
+        },
+    }
+}
+
 pub fn builtin_sleep(args: &[String]) -> i32 {
-    if args.len() < 2 { eprintln!("usage: sleep <seconds>"); return 1; }
+    if args.len() < 2 { errln!("usage: sleep <seconds>"); return 1; }
     match args[1].parse::<f64>() {
         Ok(secs) => { std::thread::sleep(std::time::Duration::from_secs_f64(secs)); 0 }
-        Err(_) => { eprintln!("sleep: invalid time: {}", args[1]); 1 }
+        Err(_) => { errln!("sleep: invalid time: {}", args[1]); 1 }
     }
 }
 
-pub fn builtin_functions(shell: &Shell) -> i32 {
-    if shell.functions.is_empty() { println!("No functions defined."); return 0; }
-    for (name, func) in &shell.functions {
-        println!("function {}() {{", name);
-        for line in &func.body { println!("  {}", line); }
-        println!("}}");
+/// functions [--search PAT] — list definitions, shell-highlighted and paged.
+pub fn builtin_functions(shell: &Shell, args: &[String]) -> i32 {
+    let (search, _) = match pager::take_search(&args[1..]) {
+        Ok(parsed) => parsed,
+        Err(e) => { errln!("functions: {}", e); return 1; }
+    };
+    if shell.functions.is_empty() { outln!("No functions defined."); return 0; }
+
+    let mut names: Vec<&String> = shell.functions.keys().collect();
+    names.sort();
+    let mut defs: Vec<String> = names.into_iter().map(|name| {
+        let mut def = pager::highlight_shell(&format!("function {}() {{", name));
+        for line in &shell.functions[name].source {
+            def.push_str(&format!("\n  {}", pager::highlight_shell(line)));
+        }
+        def + "\n}"
+    }).collect();
+
+    if let Some(pattern) = &search {
+        defs = pager::filter_entries(defs, pattern);
+        if defs.is_empty() { errln!("functions: no matches for '{}'", pattern); return 1; }
     }
+    pager::page("functions", &(defs.join("\n") + "\n"));
     0
 }
 
 // ── which ─────────────────────────────────────────────────────────────────────
 
-pub fn builtin_which(args: &[String]) -> i32 {
-    if args.len() < 2 { eprintln!("usage: which <command> [command2 ...]"); return 1; }
+pub const WHICH: Options = Options {
+    name: "which",
+    operands: "COMMAND...",
+    about: "Show what running each COMMAND would run: an alias, function, builtin or file.",
+    opts: &[
+        Opt::new("a", "all", "show every match, PATH entries included, not just the first"),
+    ],
+    numeric: None,
+};
 
-    let builtins = [
-        "cd","pwd","echo","export","unset","alias","unalias","history",
-        "source","help","jobs","fg","bg","kill","clear","cls","exit","quit",
-        "ls","true","false","test","functions","sleep","touch","mkdir",
-        "rm","cp","mv","cat","which","pushd","popd","dirs","grep",
-    ];
+pub fn builtin_which(shell: &Shell, args: &[String]) -> i32 {
+    let p = match WHICH.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.is_empty() { errln!("usage: which [-a] <command> [command2 ...]"); return 1; }
 
-    let sep = if cfg!(windows) { ';' } else { ':' };
-    let path_var = std::env::var("PATH").unwrap_or_default();
     let mut code = 0;
+    for name in &p.operands {
+        let mut found = resolve_all(shell, name);
+        if !p.has("all") { found.truncate(1); }
+        if found.is_empty() { errln!("{}: not found", name); code = 1; }
+        for res in found {
+            match res {
+                Resolution::Alias(v)  => outln!("{}: aliased to {}", name, v),
+                Resolution::Function  => outln!("{}: shell function", name),
+                Resolution::Builtin   => outln!("{}: shell builtin", name),
+                Resolution::File(p)   => outln!("{}", paths::display(&p)),
+            }
+        }
+    }
+    code
+}
+
+// ── type / command ────────────────────────────────────────────────────────────
 
-    for name in &args[1..] {
-        if builtins.contains(&name.as_str()) {
-            println!("{}: shell builtin", name);
+/// What a command name resolves to, in the order the executor tries them.
+enum Resolution {
+    Alias(String),
+    Function,
+    Builtin,
+    File(std::path::PathBuf),
+}
+
+/// Everything `name` could run, first the one that would; shared by
+/// which, type and command.
+fn resolve_all(shell: &Shell, name: &str) -> Vec<Resolution> {
+    let mut found = Vec::new();
+    // A path is run as it is, never looked up
+    if name.contains(['/', '\\']) {
+        let path = shell.cwd.join(name);
+        if path.is_file() && is_executable(&path) { found.push(Resolution::File(PathBuf::from(name))); }
+        return found;
+    }
+    if let Some(value) = shell.aliases.get(name) {
+        found.push(Resolution::Alias(value.clone()));
+    }
+    if shell.functions.contains_key(name) {
+        found.push(Resolution::Function);
+    }
+    if crate::executor::pipeline::is_builtin_cmd(name) {
+        found.push(Resolution::Builtin);
+    }
+    // A location hashed with `hash -p` is the one the executor uses
+    let hashed = shell.command_hash.get(name).map(|h| h.path.clone()).filter(|p| p.is_file());
+    let on_path: Vec<PathBuf> = find_in_path(name).into_iter().filter(|p| Some(p) != hashed.as_ref()).collect();
+    found.extend(hashed.into_iter().chain(on_path).map(Resolution::File));
+    found
+}
+
+/// What running `name` would run, in a few words, for `explain`.
+pub fn describe_name(shell: &Shell, name: &str) -> String {
+    match resolve_all(shell, name).into_iter().next() {
+        Some(Resolution::Alias(value)) => format!("alias for `{}'", value),
+        Some(Resolution::Function)     => "function".to_string(),
+        Some(Resolution::Builtin)      => "builtin".to_string(),
+        Some(Resolution::File(path))   => paths::display(&path),
+        None                           => "not found".to_string(),
+    }
+}
+
+/// type [-a] [-t] [-P] name ...
+pub fn builtin_type(shell: &Shell, args: &[String]) -> i32 {
+    let mut all       = false;
+    let mut terse     = false;
+    let mut path_only = false;
+    let mut names     = Vec::new();
+
+    for arg in super::util::args(args) {
+        match arg {
+            Arg::Operand(name) => names.push(name),
+            Arg::Option("-a") => all       = true,
+            Arg::Option("-t") => terse     = true,
+            Arg::Option("-P") => path_only = true,
+            Arg::Option(s) => {
+                errln!("type: unknown option: {}", s);
+                return 1;
+            }
+        }
+    }
+    if names.is_empty() { errln!("usage: type [-a] [-t] [-P] <name> ..."); return 1; }
+
+    let mut code = 0;
+    for name in names {
+        let mut found = resolve_all(shell, name);
+        if path_only { found.retain(|r| matches!(r, Resolution::File(_))); }
+        if !all { found.truncate(1); }
+
+        if found.is_empty() {
+            if !terse && !path_only { errln!("type: {}: not found", name); }
+            code = 1;
             continue;
         }
 
-        let mut found = false;
-        'outer: for dir in path_var.split(sep) {
-            let base = std::path::Path::new(dir).join(name);
-            let candidates = if cfg!(windows) {
-                vec![base.clone(), std::path::Path::new(dir).join(format!("{}.exe", name))]
-            } else {
-                vec![base]
+        for res in &found {
+            match res {
+                Resolution::File(_) if terse   => outln!("file"),
+                Resolution::File(p) if path_only => outln!("{}", paths::display(p)),
+                Resolution::File(p)   => outln!("{} is {}", name, paths::display(p)),
+                Resolution::Alias(_) if terse  => outln!("alias"),
+                Resolution::Alias(v)  => outln!("{} is aliased to `{}'", name, v),
+                Resolution::Function if terse  => outln!("function"),
+                Resolution::Function  => {
+                    outln!("{} is a function", name);
+                    outln!("{}() {{", name);
+                    for line in &shell.functions[name].source { outln!("  {}", line); }
+                    outln!("}}");
+                }
+                Resolution::Builtin if terse   => outln!("builtin"),
+                Resolution::Builtin   => outln!("{} is a shell builtin", name),
+            }
+        }
+    }
+    code
+}
+
+/// `command -v name` / `command -V name`. Running `command name args` is
+/// handled by the executor, which skips alias and function lookup for it.
+pub fn builtin_command(shell: &Shell, args: &[String]) -> i32 {
+    let Some(flag) = args.get(1) else { return 0; };
+    match flag.as_str() {
+        "-V" => {
+            let mut type_args = vec!["type".to_string()];
+            type_args.extend_from_slice(&args[2..]);
+            builtin_type(shell, &type_args)
+        }
+        "-v" => {
+            let mut code = 0;
+            for name in &args[2..] {
+                match resolve_all(shell, name).first() {
+                    Some(Resolution::Alias(v)) => outln!("alias {}='{}'", name, v),
+                    Some(Resolution::File(p))  => outln!("{}", paths::display(p)),
+                    Some(_)                    => outln!("{}", name),
+                    None                       => code = 1,
+                }
+            }
+            code
+        }
+        other => { errln!("command: unknown option: {}", other); 2 }
+    }
+}
+
+/// `builtin NAME args` is run by the executor too; what reaches here has
+/// no NAME.
+pub fn builtin_builtin(args: &[String]) -> i32 {
+    if let Some(option) = args.get(1) {
+        errln!("builtin: unknown option: {}", option);
+    }
+    errln!("usage: builtin NAME [ARG...]");
+    2
+}
+
+// ── hash ──────────────────────────────────────────────────────────────────────
+
+/// hash [-r] [-t NAME...] [-p PATH NAME] [NAME...]
+/// hash -d [-r] [NAME=DIR...]
+///
+/// Shows or fills the command-location cache, or with -d manages the named
+/// directories that `~NAME` expands to.
+pub fn builtin_hash(shell: &mut Shell, args: &[String]) -> i32 {
+    let mut rest = &args[1..];
+    if rest.first().map(|a| a == "-d").unwrap_or(false) {
+        return hash_named_dirs(shell, &rest[1..]);
+    }
+
+    match rest.first().map(|s| s.as_str()) {
+        None => {
+            if shell.command_hash.is_empty() { outln!("hash: hash table empty"); return 0; }
+            let mut entries: Vec<_> = shell.command_hash.iter().collect();
+            entries.sort_by_key(|(name, _)| name.as_str());
+            outln!("hits\tcommand");
+            for (_, entry) in entries {
+                outln!("{:>4}\t{}", entry.hits, paths::display(&entry.path));
+            }
+            return 0;
+        }
+        Some("-r") => {
+            shell.clear_command_hash();
+            rest = &rest[1..];
+        }
+        Some("-p") => {
+            let (Some(path), Some(name)) = (rest.get(1), rest.get(2)) else {
+                errln!("usage: hash -p <path> <name>");
+                return 2;
             };
-            for candidate in candidates {
-                if candidate.exists() {
-                    println!("{}", candidate.display().to_string().replace('\\', "/"));
-                    found = true;
-                    break 'outer;
+            shell.command_hash.insert(name.clone(), HashedCommand { path: PathBuf::from(path), hits: 0 });
+            return 0;
+        }
+        Some("-t") => {
+            let mut code = 0;
+            for name in &rest[1..] {
+                match shell.lookup_command(name) {
+                    Some(path) => outln!("{}", paths::display(&path)),
+                    None => { errln!("hash: {}: not found", name); code = 1; }
                 }
             }
+            return code;
+        }
+        Some(opt) if opt.starts_with('-') => {
+            errln!("hash: {}: invalid option", opt);
+            return 2;
+        }
+        Some(_) => {}
+    }
+
+    let mut code = 0;
+    for name in rest {
+        // Builtins are never looked up on PATH, so there's nothing to hash
+        if crate::executor::pipeline::is_builtin_cmd(name) { continue; }
+        if shell.lookup_command(name).is_none() {
+            errln!("hash: {}: not found", name);
+            code = 1;
         }
+    }
+    code
+}
 
-        if !found { eprintln!("{}: not found", name); code = 1; }
+fn hash_named_dirs(shell: &mut Shell, args: &[String]) -> i32 {
+    if args.is_empty() {
+        let mut dirs: Vec<_> = shell.named_dirs.iter().collect();
+        dirs.sort_by_key(|(name, _)| name.as_str());
+        for (name, dir) in dirs { outln!("{}={}", name, paths::display(dir)); }
+        return 0;
+    }
+    if args[0] == "-r" {
+        shell.named_dirs.clear();
+        shell.save_named_dirs();
+        return 0;
     }
+
+    let mut code = 0;
+    for arg in args {
+        let Some((name, dir)) = arg.split_once('=').filter(|(n, _)| !n.is_empty()) else {
+            errln!("hash: {}: expected NAME=DIR", arg);
+            code = 1;
+            continue;
+        };
+        let dir = shell.cwd.join(dir);
+        if !dir.is_dir() {
+            errln!("hash: {}: not a directory", dir.display());
+            code = 1;
+            continue;
+        }
+        shell.named_dirs.insert(name.to_string(), dir.canonicalize().unwrap_or(dir));
+    }
+    shell.save_named_dirs();
     code
 }
 
+// ── guard ─────────────────────────────────────────────────────────────────────
+
+/// guard [list]
+/// guard add [--block | --confirm] PATTERN [MESSAGE...]
+/// guard rm N
+/// guard test COMMAND...
+///
+/// Manages the rules checked before each command runs. PATTERN is a
+/// regular expression matched against the expanded command line.
+pub fn builtin_guard(shell: &mut Shell, args: &[String]) -> i32 {
+    match args.get(1).map(|s| s.as_str()) {
+        None | Some("list") => {
+            if shell.guard_rules.is_empty() { outln!("No guard rules."); }
+            for (i, rule) in shell.guard_rules.iter().enumerate() {
+                let action = match rule.action { GuardAction::Block => "block", GuardAction::Confirm => "confirm" };
+                let message = rule.message.as_deref().map(|m| format!("  \x1b[90m# {}\x1b[0m", m)).unwrap_or_default();
+                outln!("{:>3}  {:<8} {}{}", i + 1, action, rule.pattern, message);
+            }
+            0
+        }
+        Some("add") => {
+            let mut action = GuardAction::Confirm;
+            let mut rest = &args[2..];
+            match rest.first().map(|s| s.as_str()) {
+                Some("--block")   => { action = GuardAction::Block;   rest = &rest[1..]; }
+                Some("--confirm") => { rest = &rest[1..]; }
+                _ => {}
+            }
+            let Some(pattern) = rest.first() else {
+                errln!("usage: guard add [--block | --confirm] PATTERN [MESSAGE]");
+                return 2;
+            };
+            let message = (rest.len() > 1).then(|| rest[1..].join(" "));
+            match GuardRule::new(pattern, action, message) {
+                Ok(rule) => {
+                    shell.guard_rules.push(rule);
+                    shell.save_guard_rules();
+                    0
+                }
+                Err(e) => { errln!("guard: {}: {}", pattern, e); 1 }
+            }
+        }
+        Some("rm") | Some("remove") => {
+            let n = args.get(2).and_then(|n| n.parse::<usize>().ok());
+            match n.filter(|n| (1..=shell.guard_rules.len()).contains(n)) {
+                Some(n) => {
+                    shell.guard_rules.remove(n - 1);
+                    shell.save_guard_rules();
+                    0
+                }
+                None => { errln!("guard: rm: expected a rule number from `guard list`"); 1 }
+            }
+        }
+        Some("test") => {
+            let line = args[2..].join(" ");
+            match shell.guard_rules.iter().position(|r| r.matches(&line)) {
+                Some(i) => { outln!("rule {}: {}", i + 1, shell.guard_rules[i].describe()); 0 }
+                None    => { outln!("no rule matches"); 1 }
+            }
+        }
+        Some(other) => {
+            errln!("guard: unknown subcommand '{}' (list, add, rm, test)", other);
+            2
+        }
+    }
+}
+
 // ── pushd / popd / dirs ───────────────────────────────────────────────────────
+//
+// The stack as dirs shows it starts with the current directory, entry 0;
+// `+N` counts from there and `-N` from the other end. `pushd +N` turns the
+// stack round until entry N is on top, and `popd +N` drops entry N.
 
 pub fn builtin_pushd(shell: &mut Shell, args: &[String]) -> i32 {
-    match args.get(1) {
+    match operands(args).first() {
+        Some(arg) if stack_index(arg, 0).is_some() => {
+            let mut stack = dir_stack(shell);
+            let n = match stack_index(arg, stack.len()) {
+                Some(Ok(n)) => n,
+                _ => { errln!("pushd: {}: directory stack index out of range", arg); return 1; }
+            };
+            stack.rotate_left(n);
+            if !set_dir_stack(shell, stack) { return 1; }
+        }
         Some(dir) => {
             shell.dir_stack.push(shell.cwd.clone());
             let cd_args = vec!["cd".to_string(), dir.clone()];
@@ -230,230 +1041,183 @@ pub fn builtin_pushd(shell: &mut Shell, args: &[String]) -> i32 {
                         return 1;
                     }
                 }
-                None => { eprintln!("pushd: directory stack empty"); return 1; }
+                None => { errln!("pushd: directory stack empty"); return 1; }
             }
         }
     }
-    print_dir_stack(shell);
+    print_dir_stack(shell, false, true);
     0
 }
 
-pub fn builtin_popd(shell: &mut Shell) -> i32 {
+pub fn builtin_popd(shell: &mut Shell, args: &[String]) -> i32 {
+    if let Some(arg) = args.get(1) {
+        let mut stack = dir_stack(shell);
+        let n = match stack_index(arg, stack.len()) {
+            Some(Ok(n)) => n,
+            Some(Err(())) => { errln!("popd: {}: directory stack index out of range", arg); return 1; }
+            None => { errln!("popd: {}: invalid argument (use +N or -N)", arg); return 2; }
+        };
+        if stack.len() == 1 { errln!("popd: directory stack empty"); return 1; }
+        // Dropping any entry but the current directory stays put
+        if n > 0 {
+            stack.remove(n);
+            shell.dir_stack = stack[1..].iter().rev().cloned().collect();
+            print_dir_stack(shell, false, true);
+            return 0;
+        }
+    }
     match shell.dir_stack.pop() {
         Some(dir) => {
             let dir_str = dir.display().to_string();
             let cd_args = vec!["cd".to_string(), dir_str];
             let result = builtin_cd(shell, &cd_args);
-            if result == 0 { print_dir_stack(shell); }
+            if result == 0 { print_dir_stack(shell, false, true); }
             result
         }
-        None => { eprintln!("popd: directory stack empty"); 1 }
+        None => { errln!("popd: directory stack empty"); 1 }
     }
 }
 
-pub fn builtin_dirs(shell: &Shell) -> i32 {
-    let home = dirs::home_dir().map(|h| h.display().to_string()).unwrap_or_default();
-    let cwd = shell.cwd.display().to_string();
-    let cwd = if cwd.starts_with(&home) { cwd.replacen(&home, "~", 1) } else { cwd };
-    print!("{}", cwd);
-    for dir in shell.dir_stack.iter().rev() {
-        let d = dir.display().to_string();
-        let d = if d.starts_with(&home) { d.replacen(&home, "~", 1) } else { d };
-        print!("  {}", d);
+/// dirs [-c] [-l] [-p] [-v] [+N | -N]
+pub fn builtin_dirs(shell: &mut Shell, args: &[String]) -> i32 {
+    let (mut numbered, mut one_per_line, mut tilde) = (false, false, true);
+    for arg in &args[1..] {
+        if let Some(found) = stack_index(arg, dir_stack(shell).len()) {
+            let Ok(n) = found else { errln!("dirs: {}: directory stack index out of range", arg); return 1; };
+            let dir = &dir_stack(shell)[n];
+            outln!("{}", if tilde { tilde_path(dir) } else { paths::display(dir) });
+            return 0;
+        }
+        match arg.as_str() {
+            "-c" => { shell.dir_stack.clear(); return 0; }
+            "-v" => numbered = true,
+            "-p" => one_per_line = true,
+            "-l" => tilde = false,
+            _ => { errln!("dirs: {}: invalid option\nusage: dirs [-clpv] [+N] [-N]", arg); return 2; }
+        }
+    }
+    if numbered {
+        for (i, dir) in dir_stack(shell).iter().enumerate() {
+            outln!("{:2}  {}", i, if tilde { tilde_path(dir) } else { paths::display(dir) });
+        }
+    } else {
+        print_dir_stack(shell, one_per_line, tilde);
     }
-    println!();
     0
 }
 
-fn print_dir_stack(shell: &Shell) {
-    let home = dirs::home_dir().map(|h| h.display().to_string()).unwrap_or_default();
-    let cwd = shell.cwd.display().to_string();
-    let cwd = if cwd.starts_with(&home) { cwd.replacen(&home, "~", 1) } else { cwd };
-    print!("{}", cwd);
-    for dir in shell.dir_stack.iter().rev() {
-        let d = dir.display().to_string();
-        let d = if d.starts_with(&home) { d.replacen(&home, "~", 1) } else { d };
-        print!("  {}", d);
+/// The whole stack, current directory first.
+fn dir_stack(shell: &Shell) -> Vec<PathBuf> {
+    std::iter::once(shell.cwd.clone()).chain(shell.dir_stack.iter().rev().cloned()).collect()
+}
+
+/// Make `stack` the directory stack, going to its first entry. False,
+/// with the stack left alone, if that can't be done.
+fn set_dir_stack(shell: &mut Shell, stack: Vec<PathBuf>) -> bool {
+    let Some(top) = stack.first() else { return false };
+    let cd_args = vec!["cd".to_string(), top.display().to_string()];
+    if builtin_cd(shell, &cd_args) != 0 { return false; }
+    shell.dir_stack = stack[1..].iter().rev().cloned().collect();
+    true
+}
+
+/// The entry `+N` or `-N` names in a stack of `len`: None if `arg` isn't
+/// one, Err if there's no such entry.
+fn stack_index(arg: &str, len: usize) -> Option<Result<usize, ()>> {
+    let (sign, digits) = arg.split_at_checked(1)?;
+    let n: usize = digits.parse().ok().filter(|_| sign == "+" || sign == "-")?;
+    let index = if sign == "+" { Some(n) } else { len.checked_sub(n + 1) };
+    Some(index.filter(|&i| i < len).ok_or(()))
+}
+
+fn tilde_path(dir: &std::path::Path) -> String {
+    let home = dirs::home_dir().map(|h| paths::display(&h)).unwrap_or_default();
+    let d = paths::display(dir);
+    if !home.is_empty() && d.starts_with(&home) { d.replacen(&home, "~", 1) } else { d }
+}
+
+fn print_dir_stack(shell: &Shell, one_per_line: bool, tilde: bool) {
+    let shown: Vec<String> = dir_stack(shell).iter()
+        .map(|dir| if tilde { tilde_path(dir) } else { paths::display(dir) })
+        .collect();
+    if one_per_line {
+        for dir in shown { outln!("{}", dir); }
+    } else {
+        outln!("{}", shown.join("  "));
     }
-    println!();
 }
 
+const HELP_TOPICS: &[&str] = &[
+    "nav", "files", "search", "text", "shell", "editor", "jobs", "pkg", "scripting",
+];
+
+/// help [--search PAT] [topic] — coloured and paged; with --search only
+/// matching lines are shown (from every topic unless one is named).
 pub fn builtin_help_topic(args: &[String]) -> i32 {
-    let topic = args.get(1).map(|s| s.as_str()).unwrap_or("overview");
-    help_topic(topic)
-}
-
-fn help_topic(topic: &str) -> i32 {
-    match topic {
-        "overview" | "help" => {
-            println!(r#"
-╔══════════════════════════════════════════════╗
-║          rshell  —  Built-in Commands        ║
-╚══════════════════════════════════════════════╝
-
-  Type 'help <topic>' for details on a topic.
-
-  Topics:
-    help nav          Navigation  (cd, pushd, popd ...)
-    help files        Files       (ls, cp, mv, rm ...)
-    help search       Search      (grep, find)
-    help text         Text        (head, tail, wc, sort ...)
-    help shell        Shell       (alias, export, history ...)
-    help editor       Editor      (mini)
-    help jobs         Job Control (jobs, fg, bg ...)
-    help pkg          Packages    (install, uninstall ...)
-    help scripting    Scripting   (if, for, $VAR, globs ...)
-    help all          Show everything at once
-"#);
-        }
-
-        "nav" | "navigation" => {
-            println!(r#"
-  Navigation:
-    cd [dir]           Change directory (- for previous, ~ for home)
-    pwd                Print working directory
-    pushd [dir]        Push directory onto stack and cd
-    popd               Pop directory stack and cd back
-    dirs               Show directory stack
-"#);
-        }
-
-        "files" | "file" => {
-            println!(r#"
-  Files:
-    ls [-la] [dir]     List directory contents
-    mkdir [-p] DIR     Create directory
-    rm [-rf] FILE      Remove file or directory
-    cp [-r] SRC DEST   Copy file or directory
-    mv SRC DEST        Move or rename file
-    cat FILE           Print file contents
-    touch FILE         Create or update file timestamp
-    chmod MODE FILE    Change file permissions
-    ln [-s] SRC DEST   Create hard or symbolic link
-"#);
-        }
-
-        "search" => {
-            println!(r#"
-  Search:
-    grep [-rnivc] PAT [FILE]   Search for pattern in files
-      -r  recursive   -n  line numbers   -i  ignore case
-      -v  invert      -c  count matches
-    find [DIR] [-name PATTERN] Search for files by name
-"#);
-        }
-
-        "text" => {
-            println!(r#"
-  Text Processing:
-    head [-n N] FILE   Show first N lines (default 10)
-    tail [-n N] FILE   Show last N lines (default 10)
-    wc [-lwc] FILE     Count lines (-l), words (-w), chars (-c)
-    sort [-rn] FILE    Sort lines (-r reverse, -n numeric)
-    uniq [-c] FILE     Remove duplicate lines (-c count)
-    xargs CMD          Build and run commands from stdin
-    env                Show all environment variables
-"#);
-        }
-
-        "shell" => {
-            println!(r#"
-  Shell:
-    echo [-n] [args]   Print text (-n no newline)
-    export [VAR=VAL]   Set or show environment variables
-    unset VAR          Remove environment variable
-    alias [k=v]        Set or show aliases
-    unalias NAME       Remove alias
-    history            Show command history
-    source FILE        Execute commands from a file
-    functions          List defined shell functions
-    which CMD          Show path to a command
-    clear / cls        Clear the screen
-    sleep SECS         Wait for N seconds
-    help [topic]       Show this help
-    exit               Exit rshell
-"#);
-        }
-
-        "editor" | "mini" => {
-            println!(r#"
-  Editor:
-    mini FILE          Open file in built-in text editor
-
-    Keybindings:
-      Ctrl+S           Save
-      Ctrl+Z           Undo
-      Ctrl+Q           Quit
-      Arrow keys       Move cursor
-      Home / End       Start / end of line
-
-    Syntax highlighting is applied automatically based on
-    file extension: .rs  .py  .js  .ts  .sh
-"#);
-        }
-
-        "jobs" | "job" => {
-            println!(r#"
-  Job Control:
-    jobs               List background jobs
-    fg [%id]           Bring job to foreground
-    bg [%id]           Resume stopped job in background
-    kill [%id|pid]     Kill a job or process
-    cmd &              Run command in background
-    Ctrl+Z             Suspend foreground job
-"#);
-        }
-
-        "pkg" | "packages" | "package" => {
-            println!(r#"
-  Package Manager:
-    pkg list           List installed packages
-    install PKG        Download and install a package
-    uninstall PKG      Remove an installed package
-"#);
-        }
-
-        "scripting" | "script" => {
-            println!(r#"
-  Scripting:
-    Control flow:
-      if CMD; then ... fi
-      if CMD; then ... else ... fi
-      for VAR in ITEMS; do ... done
-      while CMD; do ... done
-      function name() {{ ... }}
-
-    Expansion:
-      $VAR / ${{VAR}}      variable
-      $((2 + 2))         arithmetic
-      $(command)         command substitution
-      $#                 number of arguments
-      $@  $*             all arguments
-      $?                 last exit code
-      $$                 current process id
-
-    Globs:
-      *                  match any characters
-      ?                  match one character
-      [abc]              match character class
-      [a-z]              match character range
-
-  Operators:
-    |   pipe      &&  and    ||  or    ;  sequence   &  background
-    >   stdout    >>  append  <  stdin  2>  stderr
-"#);
-        }
-
-        "all" => {
-            for t in &["nav", "files", "search", "text", "shell", "editor", "jobs", "pkg", "scripting"] {
-                help_topic(t);
-            }
-        }
+    let (search, rest) = match pager::take_search(&args[1..]) {
+        Ok(parsed) => parsed,
+        Err(e) => { errln!("help: {}", e); return 1; }
+    };
+    let default = if search.is_some() { "all" } else { "overview" };
+    let topic = rest.first().map(|s| s.as_str()).unwrap_or(default);
 
-        _ => {
-            eprintln!("help: unknown topic '{}' — type 'help' for a list of topics", topic);
-            return 1;
-        }
+    let Some(text) = help_text(topic) else {
+        errln!("help: unknown topic '{}' — type 'help' for a list of topics", topic);
+        return 1;
+    };
+    let lines: Vec<String> = text.lines().map(color_help_line).collect();
+
+    let Some(pattern) = search else {
+        pager::page("help", &(lines.join("\n") + "\n"));
+        return 0;
+    };
+    let found = pager::filter_entries(lines, &pattern);
+    if found.is_empty() {
+        errln!("help: no matches for '{}'", pattern);
+        return 1;
     }
+    pager::page("help", &(found.join("\n") + "\n"));
     0
+}
+
+/// The help lines documenting builtin `name`, for hover text in `rshell lsp`.
+pub fn help_entries(name: &str) -> Vec<String> {
+    help_text("all").unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.split_whitespace().next() == Some(name))
+        .map(String::from)
+        .collect()
+}
+
+/// Section headings in yellow, command synopses (the first column of an
+/// indented entry) in cyan.
+fn color_help_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let indent  = line.len() - trimmed.len();
+    if trimmed.ends_with(':') && !trimmed.contains("  ") {
+        return format!("\x1b[1;33m{}\x1b[0m", line);
+    }
+    if indent >= 4 {
+        if let Some(gap) = trimmed.find("  ") {
+            let (synopsis, desc) = trimmed.split_at(gap);
+            return format!("{}\x1b[36m{}\x1b[0m{}", &line[..indent], synopsis, desc);
+        }
+    }
+    line.to_string()
+}
+
+fn help_text(topic: &str) -> Option<String> {
+    let topic = match topic {
+        "help"                 => "overview",
+        "navigation"           => "nav",
+        "file"                 => "files",
+        "mini"                 => "editor",
+        "job"                  => "jobs",
+        "packages" | "package" => "pkg",
+        "script"               => "scripting",
+        "all" => return Some(HELP_TOPICS.iter().filter_map(|t| help_text(t)).collect()),
+        other => other,
+    };
+    crate::messages::get().help(topic).map(str::to_string)
 }
\ No newline at end of file
@@ -0,0 +1,73 @@
+// src/executor/builtin/du.rs
+// du [-h] [-s] [-d N] [PATH]... — the disk space each directory takes up,
+// counted the same way as `cleanup` does (see util::disk_usage). Sizes are
+// in 1024-byte blocks unless -h or --si asks for readable ones.
+
+use std::path::Path;
+
+use crate::paths;
+use crate::shell::Shell;
+use super::util::{disk_usage_by_dir, format_size_as, Opt, Options, SizeUnits};
+
+pub const DU: Options = Options {
+    name: "du",
+    operands: "[PATH]...",
+    about: "Show the disk space used by each directory under PATH (default: .).",
+    opts: &[
+        Opt::new("h", "human-readable", "sizes in powers of 1024 (1.5K, 20M)"),
+        Opt::new("", "si", "sizes in powers of 1000"),
+        Opt::new("s", "summarize", "only a total for each PATH"),
+        Opt::new("d", "max-depth", "only list directories up to N levels below PATH").takes("N"),
+    ],
+    numeric: None,
+};
+
+pub fn builtin_du(shell: &Shell, args: &[String]) -> i32 {
+    let p = match DU.parse(args) { Ok(p) => p, Err(code) => return code };
+    let mut units = None;
+    for (key, _) in &p.given {
+        match *key {
+            "human-readable" => units = Some(SizeUnits::Binary),
+            "si"             => units = Some(SizeUnits::Si),
+            _ => {}
+        }
+    }
+    let max_depth = match p.value("max-depth") {
+        _ if p.has("summarize") => 0,
+        None => usize::MAX,
+        Some(n) => match n.parse() {
+            Ok(n) => n,
+            Err(_) => { errln!("du: invalid maximum depth '{}'", n); return 2; }
+        },
+    };
+    let size = |bytes: u64| match units {
+        Some(units) => format_size_as(bytes, units, ""),
+        None => bytes.div_ceil(1024).to_string(),
+    };
+
+    let mut operands = p.operands;
+    if operands.is_empty() { operands.push("."); }
+    let mut code = 0;
+    for operand in operands {
+        let root = shell.cwd.join(operand);
+        let Ok(meta) = root.symlink_metadata() else {
+            errln!("du: {}: No such file or directory", operand);
+            code = 1;
+            continue;
+        };
+        let total = disk_usage_by_dir(&root, &mut |dir, depth, total| {
+            if depth > max_depth { return; }
+            let shown = match dir.strip_prefix(&root) {
+                Ok(below) if below.as_os_str().is_empty() => operand.to_string(),
+                Ok(below) => paths::display(&Path::new(operand).join(below)),
+                Err(_) => paths::display(dir),
+            };
+            outln!("{}\t{}", size(total), shown);
+        });
+        // A file (or symlink) named on the command line is listed too
+        if !meta.is_dir() {
+            outln!("{}\t{}", size(total), operand);
+        }
+    }
+    code
+}
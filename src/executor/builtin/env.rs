@@ -0,0 +1,147 @@
+// src/executor/builtin/env.rs
+// env — show the environment children get, run a command in a changed
+// one, and compare the environment against a snapshot:
+//
+//   env [-i] [-u NAME]... [NAME=VALUE]... [COMMAND [ARG]...]
+//                         -i starts from nothing, -u drops NAME
+//   env snapshot [NAME]   remember the exported variables as they are now
+//   env diff [NAME]       show what has been added, removed or changed since
+//
+// A snapshot before `source venv/bin/activate` and a diff after shows
+// exactly what the activation did. Snapshots last until the shell exits.
+
+use std::collections::{BTreeSet, HashMap};
+use crate::shell::Shell;
+
+pub fn builtin_env(shell: &mut Shell, args: &[String]) -> i32 {
+    match args.get(1).map(String::as_str) {
+        Some("snapshot") => return snapshot(shell, &args[2..]),
+        Some("diff")     => return diff(shell, &args[2..]),
+        _ => {}
+    }
+
+    // What a child gets: the exported variables, less any -i / -u says
+    // to drop, plus NAME=VALUE words
+    let mut vars: HashMap<String, String> = exported(shell);
+    let mut i = 1;
+    while let Some(arg) = args.get(i) {
+        match arg.as_str() {
+            "-i" | "-" | "--ignore-environment" => vars.clear(),
+            "-u" | "--unset" => {
+                let Some(name) = args.get(i + 1) else {
+                    errln!("env: option requires an argument -- 'u'");
+                    return 2;
+                };
+                vars.remove(name);
+                i += 1;
+            }
+            "--" => { i += 1; break; }
+            s if s.starts_with("--unset=") => { vars.remove(&s["--unset=".len()..]); }
+            s if s.starts_with("-u") => { vars.remove(&s[2..]); }
+            s if s.starts_with('-') => {
+                errln!("env: invalid option -- '{}'", s.trim_start_matches('-'));
+                errln!("usage: env [-i] [-u NAME]... [NAME=VALUE]... [COMMAND [ARG]...]");
+                return 2;
+            }
+            _ => break,
+        }
+        i += 1;
+    }
+    while let Some((k, v)) = args.get(i).and_then(|arg| arg.split_once('=')) {
+        vars.insert(k.to_string(), v.to_string());
+        i += 1;
+    }
+
+    if i >= args.len() {
+        let mut vars: Vec<(String, String)> = vars.into_iter().collect();
+        vars.sort_by(|a, b| a.0.cmp(&b.0));
+        for (k, v) in vars { outln!("{}={}", k, v); }
+        return 0;
+    }
+    let command = &args[i..];
+    let mut cmd = match crate::executor::build_command(shell, command, &[]) {
+        Ok(cmd) => cmd,
+        Err(e) => { errln!("env: {}", e); return 1; }
+    };
+    cmd.env_clear().envs(&vars);
+    match cmd.status() {
+        Ok(status) => super::status::exit_status(&command[0], status),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => { errln!("env: {}: No such file or directory", command[0]); 127 }
+        Err(e) => { errln!("env: {}: {}", command[0], e); 126 }
+    }
+}
+
+// ── Snapshots ─────────────────────────────────────────────────────────────────
+
+fn exported(shell: &Shell) -> HashMap<String, String> {
+    shell.child_env().map(|(k, v)| (k.clone(), v.clone())).collect()
+}
+
+fn snapshot(shell: &mut Shell, args: &[String]) -> i32 {
+    if args.len() > 1 { errln!("usage: env snapshot [NAME]"); return 2; }
+    let name = args.first().cloned().unwrap_or_default();
+    let vars = exported(shell);
+    let count = vars.len();
+    shell.env_snapshots.insert(name.clone(), vars);
+    if name.is_empty() {
+        outln!("env: snapshot of {} variables", count);
+    } else {
+        outln!("env: snapshot '{}' of {} variables", name, count);
+    }
+    0
+}
+
+/// Print the changes since a snapshot. Like `diff`, the status is 0 when
+/// nothing changed, 1 when something did, and 2 for an error.
+fn diff(shell: &Shell, args: &[String]) -> i32 {
+    if args.len() > 1 { errln!("usage: env diff [NAME]"); return 2; }
+    let name = args.first().map(String::as_str).unwrap_or("");
+    let Some(before) = shell.env_snapshots.get(name) else {
+        match name {
+            "" => errln!("env: no snapshot yet (take one with 'env snapshot')"),
+            _  => errln!("env: no snapshot named '{}'", name),
+        }
+        return 2;
+    };
+    let after = exported(shell);
+
+    let colour = super::io::stdout_is_terminal();
+    let paint = |code: &str, text: String| if colour { format!("\x1b[{}m{}\x1b[0m", code, text) } else { text };
+
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut changed = false;
+    for name in names {
+        match (before.get(name), after.get(name)) {
+            (None, Some(new)) => outln!("{}", paint("32", format!("+ {}={}", name, new))),
+            (Some(old), None) => outln!("{}", paint("31", format!("- {}={}", name, old))),
+            (Some(old), Some(new)) if old != new => {
+                outln!("{}", paint("33", format!("~ {}={}", name, new)));
+                print_change(old, new, &paint);
+            }
+            _ => continue,
+        }
+        changed = true;
+    }
+    if changed { 1 } else { 0 }
+}
+
+/// Under a changed variable, what changed: for a list like PATH the
+/// entries added and removed, otherwise the old value.
+fn print_change(old: &str, new: &str, paint: &dyn Fn(&str, String) -> String) {
+    let sep = if cfg!(windows) { ';' } else { ':' };
+    if !(old.contains(sep) && new.contains(sep)) {
+        outln!("    was {}", old);
+        return;
+    }
+    let old_items: Vec<&str> = old.split(sep).collect();
+    let new_items: Vec<&str> = new.split(sep).collect();
+    for item in new_items.iter().filter(|i| !old_items.contains(i)) {
+        outln!("    {}", paint("32", format!("+ {}", item)));
+    }
+    for item in old_items.iter().filter(|i| !new_items.contains(i)) {
+        outln!("    {}", paint("31", format!("- {}", item)));
+    }
+    if old_items.iter().all(|i| new_items.contains(i)) && new_items.iter().all(|i| old_items.contains(i)) {
+        outln!("    (reordered)");
+    }
+}
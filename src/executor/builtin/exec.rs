@@ -0,0 +1,157 @@
+// src/executor/builtin/exec.rs
+// `exec` — replace the shell with a command, or redirect the shell's own
+// streams for the rest of the session.
+//
+// `exec > build.log 2>&1` points fds 1 and 2 at the file, so everything
+// that follows — builtins, external commands, error messages — goes there
+// until `exec -r` puts the original streams back. The line editor has to
+// keep talking to the terminal, so the interactive loop switches to the
+// saved terminal fds while it reads a line (`to_terminal`) and back to the
+// session's before running it (`to_session`).
+
+use crate::parser::ast::Redirect;
+use crate::shell::Shell;
+
+#[cfg(unix)]
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+#[cfg(unix)]
+use std::sync::Mutex;
+
+/// The shell's stdin, stdout and stderr as they were before the first
+/// `exec` redirection, and what each has been redirected to since.
+#[cfg(unix)]
+struct Session {
+    saved:   [Option<OwnedFd>; 3],
+    current: [Option<OwnedFd>; 3],
+}
+
+#[cfg(unix)]
+static SESSION: Mutex<Session> = Mutex::new(Session {
+    saved:   [None, None, None],
+    current: [None, None, None],
+});
+
+pub fn builtin_exec(shell: &mut Shell, args: &[String], redirects: &[Redirect]) -> i32 {
+    let words = super::util::operands(args);
+    match args.get(1).map(String::as_str) {
+        Some("-r") if args.len() == 2 => { restore(); 0 }
+        _ if words.is_empty() => match redirect_session(redirects) {
+            Ok(())  => 0,
+            Err(e)  => { errln!("exec: {}", e); 1 }
+        },
+        _ => replace_shell(shell, words, redirects),
+    }
+}
+
+/// `exec CMD ARGS...`: run CMD in place of the shell. Only returns if it
+/// couldn't be started.
+fn replace_shell(shell: &mut Shell, args: &[String], redirects: &[Redirect]) -> i32 {
+    let mut cmd = match crate::executor::build_command(shell, args, redirects) {
+        Ok(c)  => c,
+        Err(e) => { errln!("exec: {}", e); return 1; }
+    };
+    cmd.env_clear().envs(shell.child_env());
+    flush();
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Signals the shell ignores go back to their defaults for CMD
+        crate::executor::jobctl::setup_child(&mut cmd, None, true, true);
+        let e = cmd.exec();
+        crate::executor::report_exec_error(shell, &args[0], &e)
+    }
+    // No exec on Windows: run the command and exit with its status
+    #[cfg(windows)]
+    match cmd.status() {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(e)     => crate::executor::report_exec_error(shell, &args[0], &e),
+    }
+}
+
+fn flush() {
+    use std::io::Write;
+    super::io::stdout().flush().ok();
+    std::io::stdout().flush().ok();
+    std::io::stderr().flush().ok();
+}
+
+/// Point the shell's own fds at the files `redirects` name. All of them
+/// are opened before any is installed, so a bad name changes nothing.
+#[cfg(unix)]
+fn redirect_session(redirects: &[Redirect]) -> std::io::Result<()> {
+    let mut opened: [Option<OwnedFd>; 3] = [None, None, None];
+    for redirect in redirects {
+        let fd = match redirect {
+            Redirect::StdinFrom(_) => 0,
+            Redirect::StdoutTo(_) | Redirect::StdoutAppend(_) => 1,
+            Redirect::StderrTo(_) => 2,
+            // 2>&1 follows stdout as redirected so far
+            Redirect::StderrToStdout => {
+                opened[2] = match &opened[1] {
+                    Some(out) => Some(out.try_clone()?),
+                    None      => Some(dup(1)?),
+                };
+                continue;
+            }
+        };
+        opened[fd] = crate::executor::open_redirect(redirect)?.map(OwnedFd::from);
+    }
+
+    flush();
+    let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    for (fd, file) in opened.into_iter().enumerate() {
+        let Some(file) = file else { continue };
+        if session.saved[fd].is_none() { session.saved[fd] = Some(dup(fd as i32)?); }
+        unsafe { libc::dup2(file.as_raw_fd(), fd as i32); }
+        session.current[fd] = Some(file);
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn redirect_session(_redirects: &[Redirect]) -> std::io::Result<()> {
+    Err(std::io::Error::other("redirecting the shell's own streams is not supported on Windows"))
+}
+
+#[cfg(unix)]
+fn dup(fd: i32) -> std::io::Result<OwnedFd> {
+    match unsafe { libc::dup(fd) } {
+        -1  => Err(std::io::Error::last_os_error()),
+        new => Ok(unsafe { OwnedFd::from_raw_fd(new) }),
+    }
+}
+
+/// Put back the streams the shell had before its first `exec` redirection.
+fn restore() {
+    to_terminal();
+    #[cfg(unix)]
+    {
+        let mut session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+        session.current = [None, None, None];
+    }
+}
+
+/// Give the line editor the shell's original streams while it reads input.
+pub fn to_terminal() {
+    #[cfg(unix)]
+    swap(|s, fd| s.current[fd].as_ref().and(s.saved[fd].as_ref()));
+}
+
+/// Reinstate the session's `exec` redirections before running a command.
+pub fn to_session() {
+    #[cfg(unix)]
+    swap(|s, fd| s.current[fd].as_ref());
+}
+
+#[cfg(unix)]
+fn swap(target: impl Fn(&Session, usize) -> Option<&OwnedFd>) {
+    let session = SESSION.lock().unwrap_or_else(|e| e.into_inner());
+    if session.current.iter().all(Option::is_none) { return; }
+    flush();
+    for fd in 0..3 {
+        if let Some(file) = target(&session, fd) {
+            unsafe { libc::dup2(file.as_raw_fd(), fd as i32); }
+        }
+    }
+}
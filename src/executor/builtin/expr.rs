@@ -0,0 +1,225 @@
+// src/executor/builtin/expr.rs
+// expr EXPRESSION — POSIX expr, each operator and operand its own word:
+//
+//   A | B    A if it is neither empty nor 0, else B
+//   A & B    A if neither is empty or 0, else 0
+//   A < B    also <= = != >= >: 1 or 0, comparing as integers when both are
+//   A + B    also - * / %: integer arithmetic
+//   A : RE   the length of the match of RE at the start of A, or the \(…\)
+//            group if there is one (RE is a basic regular expression)
+//   ( A )    grouping
+//
+// and the common extensions `length S`, `substr S POS LEN`, `index S CHARS`
+// and `match S RE`. The status is 0 when the result is neither empty nor 0,
+// 1 when it is, and 2 for a bad expression.
+
+/// Evaluate and print `expr`'s arguments.
+pub fn builtin_expr(args: &[String]) -> i32 {
+    let words: Vec<&str> = args[1..].iter().map(String::as_str).collect();
+    if words.is_empty() { errln!("expr: missing operand"); return 2; }
+    match evaluate(&words) {
+        Ok(value) => {
+            outln!("{}", value);
+            if is_null(&value) { 1 } else { 0 }
+        }
+        Err(e) => { errln!("expr: {}", e); 2 }
+    }
+}
+
+fn evaluate(words: &[&str]) -> Result<String, String> {
+    let mut parser = Parser { words, pos: 0 };
+    let value = parser.or()?;
+    match parser.words.get(parser.pos) {
+        None => Ok(value),
+        Some(word) => Err(format!("syntax error: unexpected argument '{}'", word)),
+    }
+}
+
+fn is_null(value: &str) -> bool {
+    value.is_empty() || value.parse::<i64>() == Ok(0)
+}
+
+fn integer(value: &str) -> Result<i64, String> {
+    value.parse().map_err(|_| "non-integer argument".to_string())
+}
+
+struct Parser<'a> {
+    words: &'a [&'a str],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.words.get(self.pos).copied()
+    }
+
+    /// The next word if it is one of `ops`.
+    fn operator(&mut self, ops: &[&str]) -> Option<&'a str> {
+        let word = self.peek().filter(|w| ops.contains(w))?;
+        self.pos += 1;
+        Some(word)
+    }
+
+    fn operand(&mut self) -> Result<&'a str, String> {
+        let word = self.peek().ok_or("syntax error: missing argument")?;
+        self.pos += 1;
+        Ok(word)
+    }
+
+    fn or(&mut self) -> Result<String, String> {
+        let mut left = self.and()?;
+        while self.operator(&["|"]).is_some() {
+            let right = self.and()?;
+            if is_null(&left) { left = if is_null(&right) { "0".to_string() } else { right }; }
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<String, String> {
+        let mut left = self.compare()?;
+        while self.operator(&["&"]).is_some() {
+            let right = self.compare()?;
+            if is_null(&left) || is_null(&right) { left = "0".to_string(); }
+        }
+        Ok(left)
+    }
+
+    fn compare(&mut self) -> Result<String, String> {
+        let mut left = self.sum()?;
+        while let Some(op) = self.operator(&["<", "<=", "=", "==", "!=", ">=", ">"]) {
+            let right = self.sum()?;
+            let order = match (left.parse::<i64>(), right.parse::<i64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                _ => left.cmp(&right),
+            };
+            let holds = match op {
+                "<"  => order.is_lt(),
+                "<=" => order.is_le(),
+                "=" | "==" => order.is_eq(),
+                "!=" => order.is_ne(),
+                ">=" => order.is_ge(),
+                _    => order.is_gt(),
+            };
+            left = (holds as i32).to_string();
+        }
+        Ok(left)
+    }
+
+    fn sum(&mut self) -> Result<String, String> {
+        let mut left = self.product()?;
+        while let Some(op) = self.operator(&["+", "-"]) {
+            let (a, b) = (integer(&left)?, integer(&self.product()?)?);
+            let result = if op == "+" { a.checked_add(b) } else { a.checked_sub(b) };
+            left = result.ok_or("result out of range")?.to_string();
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<String, String> {
+        let mut left = self.matching()?;
+        while let Some(op) = self.operator(&["*", "/", "%"]) {
+            let (a, b) = (integer(&left)?, integer(&self.matching()?)?);
+            if op != "*" && b == 0 { return Err("division by zero".to_string()); }
+            let result = match op {
+                "*" => a.checked_mul(b),
+                "/" => a.checked_div(b),
+                _   => a.checked_rem(b),
+            };
+            left = result.ok_or("result out of range")?.to_string();
+        }
+        Ok(left)
+    }
+
+    fn matching(&mut self) -> Result<String, String> {
+        let mut left = self.primary()?;
+        while self.operator(&[":"]).is_some() {
+            let pattern = self.primary()?;
+            left = match_start(&left, &pattern)?;
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self) -> Result<String, String> {
+        let word = self.operand()?;
+        // A keyword only counts as one with enough words after it to be
+        // its arguments; otherwise `expr length` prints "length"
+        let left = self.words.len() - self.pos;
+        match word {
+            "(" => {
+                let value = self.or()?;
+                if self.operator(&[")"]).is_none() { return Err("syntax error: expecting ')'".to_string()); }
+                Ok(value)
+            }
+            "+" if left >= 1 => Ok(self.operand()?.to_string()),
+            "length" if left >= 1 => Ok(self.primary()?.chars().count().to_string()),
+            "match" if left >= 2 => {
+                let (s, pattern) = (self.primary()?, self.primary()?);
+                match_start(&s, &pattern)
+            }
+            "index" if left >= 2 => {
+                let (s, chars) = (self.primary()?, self.primary()?);
+                let at = s.chars().position(|c| chars.contains(c)).map_or(0, |i| i + 1);
+                Ok(at.to_string())
+            }
+            "substr" if left >= 3 => {
+                let s = self.primary()?;
+                let (pos, len) = (integer(&self.primary()?)?, integer(&self.primary()?)?);
+                if pos < 1 || len < 1 { return Ok(String::new()); }
+                Ok(s.chars().skip(pos as usize - 1).take(len as usize).collect())
+            }
+            _ => Ok(word.to_string()),
+        }
+    }
+}
+
+/// `s : pattern`: the match anchored at the start of `s`.
+fn match_start(s: &str, pattern: &str) -> Result<String, String> {
+    let anchored = format!("^(?:{})", super::sed::basic_to_extended(pattern));
+    let re = regex::Regex::new(&anchored).map_err(|e| format!("invalid regular expression: {}", e))?;
+    let Some(caps) = re.captures(s) else {
+        return Ok(if re.captures_len() > 1 { String::new() } else { "0".to_string() });
+    };
+    Ok(match caps.get(1) {
+        Some(group) => group.as_str().to_string(),
+        None if re.captures_len() > 1 => String::new(),
+        None => caps[0].chars().count().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expr(line: &str) -> Result<String, String> {
+        evaluate(&line.split(' ').collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn arithmetic_and_precedence() {
+        assert_eq!(expr("1 + 2 * 3").unwrap(), "7");
+        assert_eq!(expr("( 1 + 2 ) * 3").unwrap(), "9");
+        assert_eq!(expr("7 % 3 - 10 / 4").unwrap(), "-1");
+        assert!(expr("1 / 0").is_err());
+        assert!(expr("a + 1").is_err());
+        assert!(expr("1 +").is_err());
+    }
+
+    #[test]
+    fn comparisons_and_logic() {
+        assert_eq!(expr("10 > 9").unwrap(), "1");
+        assert_eq!(expr("abc < abd").unwrap(), "1");
+        assert_eq!(expr("0 | fallback").unwrap(), "fallback");
+        assert_eq!(expr("x & 0").unwrap(), "0");
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(expr("abcabc : abc").unwrap(), "3");
+        assert_eq!(expr(r"file.tar.gz : \(.*\)\.gz").unwrap(), "file.tar");
+        assert_eq!(expr("xyz : a").unwrap(), "0");
+        assert_eq!(expr("length hello").unwrap(), "5");
+        assert_eq!(expr("substr hello 2 3").unwrap(), "ell");
+        assert_eq!(expr("index hello lo").unwrap(), "3");
+        assert_eq!(expr("length").unwrap(), "length");
+    }
+}
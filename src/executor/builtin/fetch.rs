@@ -0,0 +1,174 @@
+// src/executor/builtin/fetch.rs
+// fetch URL [-o FILE | -O] [-H 'Name: value']... [-X METHOD] [-d BODY]
+//
+// A small HTTP client for when curl isn't installed: the body goes to
+// stdout, or to FILE with -o (-O names it after the URL). -d sends BODY,
+// or the contents of FILE for `-d @FILE`, and makes the default method
+// POST. -i puts the status line and headers before the body.
+//
+// While saving to a file, or piping, a progress bar is drawn on stderr
+// (the same one `pkg install` shows) if stderr is a terminal. An HTTP error
+// status prints the body all the same, but sets the exit status to 1.
+
+use std::io::{IsTerminal, Read, Write};
+
+use attohttpc::header::HeaderName;
+use attohttpc::Method;
+
+use crate::shell::Shell;
+use super::util::{Opt, Options};
+
+pub const FETCH: Options = Options {
+    name: "fetch",
+    operands: "URL",
+    about: "Download URL, or send a request to it.",
+    opts: &[
+        Opt::new("o", "output", "write the body to FILE").takes("FILE"),
+        Opt::new("O", "remote-name", "write the body to a file named after the URL"),
+        Opt::new("H", "header", "add a request header, like 'Accept: text/plain'").takes("HEADER"),
+        Opt::new("X", "request", "use METHOD (default GET, or POST with -d)").takes("METHOD"),
+        Opt::new("d", "data", "send BODY (@FILE sends the file)").takes("BODY"),
+        Opt::new("i", "include", "print the status line and headers first"),
+        Opt::new("s", "silent", "no progress bar"),
+    ],
+    numeric: None,
+};
+
+pub fn builtin_fetch(shell: &Shell, args: &[String]) -> i32 {
+    let p = match FETCH.parse(args) { Ok(p) => p, Err(code) => return code };
+    let [url] = p.operands[..] else { errln!("usage: fetch URL [-o FILE] [-H HEADER]... [-X METHOD] [-d BODY]"); return 2 };
+    let url = if url.contains("://") { url.to_string() } else { format!("https://{}", url) };
+
+    let body = match p.value("data") {
+        Some(data) => match data.strip_prefix('@') {
+            Some(file) => match std::fs::read(shell.cwd.join(file)) {
+                Ok(bytes) => Some(bytes),
+                Err(e) => { errln!("fetch: {}: {}", file, e); return 1; }
+            },
+            None => Some(data.as_bytes().to_vec()),
+        },
+        None => None,
+    };
+    let method = p.value("request").unwrap_or(if body.is_some() { "POST" } else { "GET" });
+    let Ok(method) = Method::from_bytes(method.to_ascii_uppercase().as_bytes()) else {
+        errln!("fetch: invalid method '{}'", method);
+        return 2;
+    };
+
+    let mut request = attohttpc::RequestBuilder::new(method, &url);
+    for (key, value) in &p.given {
+        if *key != "header" { continue; }
+        let header = value.unwrap_or_default();
+        let Some((name, value)) = header.split_once(':') else {
+            errln!("fetch: header '{}' isn't in 'Name: value' form", header);
+            return 2;
+        };
+        let name = match HeaderName::from_bytes(name.trim().as_bytes()) {
+            Ok(name) => name,
+            Err(e) => { errln!("fetch: bad header '{}': {}", header, e); return 2; }
+        };
+        request = match request.try_header_append(name, value.trim()) {
+            Ok(request) => request,
+            Err(e) => { errln!("fetch: bad header '{}': {}", header, e); return 2; }
+        };
+    }
+
+    let response = match body {
+        Some(body) => request.bytes(body).send(),
+        None => request.send(),
+    };
+    let response = match response {
+        Ok(r) => r,
+        Err(e) => { errln!("fetch: {}: {}", url, e); return 1; }
+    };
+    let status = response.status();
+
+    let output = match (p.value("output"), p.has("remote-name")) {
+        (Some(file), _) => Some(file.to_string()),
+        (None, true) => match remote_name(&url) {
+            Some(name) => Some(name),
+            None => { errln!("fetch: {}: no file name in the URL (use -o FILE)", url); return 2; }
+        },
+        (None, false) => None,
+    };
+    let mut sink: Box<dyn Write> = match &output {
+        Some(file) => match std::fs::File::create(shell.cwd.join(file)) {
+            Ok(f) => Box::new(std::io::BufWriter::new(f)),
+            Err(e) => { errln!("fetch: {}: {}", file, e); return 1; }
+        },
+        None => Box::new(super::io::stdout()),
+    };
+
+    if p.has("include") {
+        // attohttpc only speaks HTTP/1.1
+        let mut head = format!("HTTP/1.1 {}\r\n", status);
+        for (name, value) in response.headers() {
+            head.push_str(&format!("{}: {}\r\n", name, String::from_utf8_lossy(value.as_bytes())));
+        }
+        head.push_str("\r\n");
+        if let Err(e) = sink.write_all(head.as_bytes()) {
+            if !(output.is_none() && e.kind() == std::io::ErrorKind::BrokenPipe) {
+                errln!("fetch: {}", e);
+            }
+            return 1;
+        }
+    }
+
+    let total = response.headers().get("content-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+    let progress = !p.has("silent")
+        && std::io::stderr().is_terminal()
+        && (output.is_some() || !super::io::stdout_is_terminal());
+
+    let mut reader = response;
+    let mut chunk = [0u8; 64 * 1024];
+    let mut received = 0u64;
+    let mut closed = false;
+    loop {
+        let n = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) => {
+                if progress { errln!(); }
+                errln!("fetch: {}: {}", url, e);
+                return 1;
+            }
+        };
+        if let Err(e) = sink.write_all(&chunk[..n]) {
+            // The reader going away (`fetch URL | head`) just ends the transfer
+            if output.is_none() && e.kind() == std::io::ErrorKind::BrokenPipe {
+                closed = true;
+                break;
+            }
+            if progress { errln!(); }
+            errln!("fetch: {}: {}", output.as_deref().unwrap_or("stdout"), e);
+            return 1;
+        }
+        received += n as u64;
+        if progress { err!("{}", super::pkg::download_progress_line(received, total)); }
+    }
+    if progress { err!("\r{}\r", " ".repeat(70)); }
+    if let (false, Err(e)) = (closed, sink.flush()) {
+        errln!("fetch: {}", e);
+        return 1;
+    }
+    if let Some(file) = output.filter(|_| !p.has("silent")) {
+        errln!("fetch: saved {} ({} bytes)", file, received);
+    }
+
+    if status.is_client_error() || status.is_server_error() {
+        errln!("fetch: {}: HTTP {}", url, status);
+        return 1;
+    }
+    0
+}
+
+/// The file name -O saves to: the last part of the URL's path.
+fn remote_name(url: &str) -> Option<String> {
+    let path = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+    let (_, path) = path.split_once('/')?;
+    let name = path.rsplit('/').next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
@@ -0,0 +1,218 @@
+// src/executor/builtin/file.rs
+// file [-b] [-L] FILE... — what kind of file each one is, judged by its
+// first bytes rather than its name: executables (ELF, PE, Mach-O), images,
+// archives and compressed data, or text and its encoding. Worth a look
+// before `cat` on something unknown fills the terminal with binary.
+
+use std::io::Read;
+
+use crate::shell::Shell;
+use super::util::{Opt, Options};
+
+pub const FILE: Options = Options {
+    name: "file",
+    operands: "FILE...",
+    about: "Guess the kind of each FILE from its contents.",
+    opts: &[
+        Opt::new("b", "brief", "don't print the file names"),
+        Opt::new("L", "dereference", "follow symlinks"),
+    ],
+    numeric: None,
+};
+
+/// How much of a file is read to decide.
+const HEAD_LEN: usize = 8192;
+
+pub fn builtin_file(shell: &Shell, args: &[String]) -> i32 {
+    let p = match FILE.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.is_empty() { errln!("file: missing operand"); return 2; }
+    let mut code = 0;
+    for name in &p.operands {
+        let path = shell.cwd.join(name);
+        let kind = match describe(&path, p.has("dereference")) {
+            Ok(kind) => kind,
+            Err(e) => { code = 1; format!("cannot open ({})", e) }
+        };
+        if p.has("brief") { outln!("{}", kind); } else { outln!("{}: {}", name, kind); }
+    }
+    code
+}
+
+fn describe(path: &std::path::Path, follow: bool) -> std::io::Result<String> {
+    let meta = if follow { path.metadata()? } else { path.symlink_metadata()? };
+    let ft = meta.file_type();
+    if ft.is_symlink() {
+        return Ok(format!("symbolic link to {}", crate::paths::display(&std::fs::read_link(path)?)));
+    }
+    if ft.is_dir() { return Ok("directory".to_string()); }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if ft.is_fifo() { return Ok("fifo (named pipe)".to_string()); }
+        if ft.is_socket() { return Ok("socket".to_string()); }
+        if ft.is_char_device() { return Ok("character special".to_string()); }
+        if ft.is_block_device() { return Ok("block special".to_string()); }
+    }
+    let mut head = Vec::with_capacity(HEAD_LEN);
+    std::fs::File::open(path)?.take(HEAD_LEN as u64).read_to_end(&mut head)?;
+    Ok(classify(&head))
+}
+
+/// Name the kind of data that starts with `head`.
+fn classify(head: &[u8]) -> String {
+    if head.is_empty() { return "empty".to_string(); }
+    if let Some(kind) = magic(head) { return kind; }
+    text(head).unwrap_or_else(|| "data".to_string())
+}
+
+/// Formats recognised by a signature at a fixed place.
+fn magic(head: &[u8]) -> Option<String> {
+    let at = |offset: usize, sig: &[u8]| head.get(offset..offset + sig.len()) == Some(sig);
+    let kind = match () {
+        _ if at(0, b"\x7fELF") => return Some(elf(head)),
+        _ if at(0, b"MZ") => return Some(pe(head)),
+        _ if at(0, b"\xcf\xfa\xed\xfe") => "Mach-O 64-bit executable",
+        _ if at(0, b"\xce\xfa\xed\xfe") => "Mach-O 32-bit executable",
+        _ if at(0, b"\xca\xfe\xba\xbe") => "Mach-O universal binary (or Java class data)",
+        _ if at(0, b"\0asm") => "WebAssembly (wasm) binary module",
+        _ if at(0, b"\x89PNG\r\n\x1a\n") => return Some(png(head)),
+        _ if at(0, b"\xff\xd8\xff") => "JPEG image data",
+        _ if at(0, b"GIF87a") || at(0, b"GIF89a") => "GIF image data",
+        _ if at(0, b"RIFF") && at(8, b"WEBP") => "WebP image data",
+        _ if at(0, b"RIFF") && at(8, b"WAVE") => "WAVE audio",
+        _ if at(0, b"%PDF-") => return Some(format!("PDF document, version {}", version(&head[5..]))),
+        _ if at(0, b"\x1f\x8b") => "gzip compressed data",
+        _ if at(0, b"BZh") => "bzip2 compressed data",
+        _ if at(0, b"\xfd7zXZ\0") => "XZ compressed data",
+        _ if at(0, b"\x28\xb5\x2f\xfd") => "Zstandard compressed data",
+        _ if at(0, b"7z\xbc\xaf\x27\x1c") => "7-zip archive data",
+        _ if at(0, b"PK\x03\x04") || at(0, b"PK\x05\x06") => "Zip archive data",
+        _ if at(257, b"ustar") => "POSIX tar archive",
+        _ if at(0, b"SQLite format 3\0") => "SQLite 3.x database",
+        _ if at(0, b"ID3") => "MP3 audio (ID3 tagged)",
+        _ if at(4, b"ftyp") => "ISO Media (MP4, MOV ...)",
+        _ if at(0, b"OggS") => "Ogg data",
+        _ => return None,
+    };
+    Some(kind.to_string())
+}
+
+fn elf(head: &[u8]) -> String {
+    let bits = match head.get(4) { Some(1) => "32-bit", Some(2) => "64-bit", _ => "unknown-class" };
+    let little = head.get(5) == Some(&1);
+    let half = |offset: usize| -> u16 {
+        let b = [head.get(offset).copied().unwrap_or(0), head.get(offset + 1).copied().unwrap_or(0)];
+        if little { u16::from_le_bytes(b) } else { u16::from_be_bytes(b) }
+    };
+    let kind = match half(16) {
+        1 => "relocatable",
+        2 => "executable",
+        3 => "shared object (or PIE executable)",
+        4 => "core file",
+        _ => "file",
+    };
+    let machine = match half(18) {
+        0x03 => ", Intel 80386",
+        0x28 => ", ARM",
+        0x3e => ", x86-64",
+        0xb7 => ", ARM aarch64",
+        0xf3 => ", RISC-V",
+        _ => "",
+    };
+    format!("ELF {} {}{}", bits, kind, machine)
+}
+
+fn pe(head: &[u8]) -> String {
+    let word = |offset: usize| head.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    let Some(pe) = word(0x3c).map(|o| o as usize).filter(|&o| head.get(o..o + 4) == Some(b"PE\0\0")) else {
+        return "MS-DOS executable".to_string();
+    };
+    let optional = pe + 24;
+    let format = match head.get(optional..optional + 2) {
+        Some([0x0b, 0x02]) => "PE32+",
+        _ => "PE32",
+    };
+    // IMAGE_FILE_DLL (0x2000) in the characteristics
+    let dll = head.get(pe + 23).is_some_and(|c| c & 0x20 != 0);
+    format!("{} executable{} (Windows)", format, if dll { " (DLL)" } else { "" })
+}
+
+fn png(head: &[u8]) -> String {
+    let dim = |offset: usize| head.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+    match (dim(16), dim(20)) {
+        (Some(w), Some(h)) => format!("PNG image data, {} x {}", w, h),
+        _ => "PNG image data".to_string(),
+    }
+}
+
+/// The version number at the start of `rest`, like `1.7`.
+fn version(rest: &[u8]) -> String {
+    rest.iter().take_while(|b| b.is_ascii_digit() || **b == b'.').map(|&b| b as char).collect()
+}
+
+/// Text, with its encoding, line endings and the interpreter of a script;
+/// None if this looks like binary.
+fn text(head: &[u8]) -> Option<String> {
+    let (bom, body) = match head.strip_prefix(b"\xef\xbb\xbf") {
+        Some(body) => (true, body),
+        None => (false, head),
+    };
+    if body.contains(&0) { return None; }
+    let valid = match std::str::from_utf8(body) {
+        Ok(_) => true,
+        // Cut off in the middle of a character by the end of the sample
+        Err(e) => e.error_len().is_none(),
+    };
+    if !valid { return None; }
+    // Control characters other than whitespace and escape mean binary
+    let control = body.iter().filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0c | 0x1b)).count();
+    if control * 20 > body.len() { return None; }
+
+    let mut kind = if body.is_ascii() { "ASCII text".to_string() } else { "UTF-8 text".to_string() };
+    if bom { kind = "UTF-8 (with BOM) text".to_string(); }
+    if let Some(line) = body.strip_prefix(b"#!") {
+        let line = String::from_utf8_lossy(line.split(|&b| b == b'\n').next().unwrap_or_default());
+        let mut words = line.split_whitespace();
+        let interpreter = match words.next() {
+            Some(env) if env.ends_with("/env") => words.find(|w| !w.starts_with('-')),
+            other => other,
+        };
+        if let Some(interpreter) = interpreter {
+            let name = interpreter.rsplit('/').next().unwrap_or(interpreter);
+            kind = format!("{} script, {}", name, kind);
+        }
+    }
+    if body.windows(2).any(|w| w == b"\r\n") {
+        kind.push_str(", with CRLF line terminators");
+    } else if body.contains(&b'\r') {
+        kind.push_str(", with CR line terminators");
+    }
+    Some(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binaries_by_signature() {
+        let mut elf = b"\x7fELF\x02\x01\x01".to_vec();
+        elf.resize(20, 0);
+        elf[16] = 3;
+        elf[18] = 0x3e;
+        assert_eq!(classify(&elf), "ELF 64-bit shared object (or PIE executable), x86-64");
+        assert_eq!(classify(b"\x1f\x8b\x08\0"), "gzip compressed data");
+        assert_eq!(classify(b"%PDF-1.7\n"), "PDF document, version 1.7");
+        assert_eq!(classify(b"MZ\x90\0"), "MS-DOS executable");
+        assert_eq!(classify(b"\x01\x02\x00\xff"), "data");
+    }
+
+    #[test]
+    fn text_kinds() {
+        assert_eq!(classify(b""), "empty");
+        assert_eq!(classify(b"hello\n"), "ASCII text");
+        assert_eq!(classify("h\u{e9}llo\r\n".as_bytes()), "UTF-8 text, with CRLF line terminators");
+        assert_eq!(classify(b"#!/usr/bin/env python3\nprint(1)\n"), "python3 script, ASCII text");
+        assert_eq!(classify(b"#!/bin/sh\n"), "sh script, ASCII text");
+    }
+}
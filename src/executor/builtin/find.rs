@@ -1,135 +1,515 @@
 // src/executor/builtin/find.rs
-// Basic find command: find [dir] [-name pattern] [-type f/d] [-maxdepth N]
+// Basic find command: find [-H|-L|-P] [dir] [-name pattern] [-type f/d/l] [-maxdepth N]
+//
+// Entries can also be picked by -size [+-]N[ckMG] (in 512-byte blocks
+// without a unit, rounded up), -mtime [+-]N (whole days since modified),
+// -newer FILE and -empty. A + means more than N, a - less.
+//
+// Tests combine as in POSIX find: side by side (or with -a) both have to
+// pass, -o takes either, -not (or !) turns one around, and ( ... ) groups.
+// Without -print or -delete, each entry the whole expression holds for is
+// printed. -delete removes the entry (a directory only once empty) and
+// turns on depth-first order so contents go first; it is refused ahead of
+// the tests that choose what it deletes.
+//
+// Symlinks are not followed by default (-P): a link is listed as type `l`
+// and never descended into. -H follows a link given as the start
+// directory, -L follows every link, with a check for links that loop back
+// into a directory already being searched.
+
+use std::cell::OnceCell;
+use std::path::Path;
+use std::time::SystemTime;
+use super::util::{unix_secs, LoopGuard};
+
+/// Which symlinks the walk looks through.
+#[derive(Clone, Copy, PartialEq)]
+enum Follow {
+    Never,
+    StartOnly,
+    Always,
+}
 
 pub fn builtin_find(args: &[String]) -> i32 {
     let mut start_dir = ".".to_string();
-    let mut name_pat: Option<String> = None;
-    let mut file_type: Option<char> = None; // 'f' = file, 'd' = dir
-    let mut max_depth: Option<usize> = None;
-    let mut min_depth: Option<usize> = None;
 
+    // -H, -L and -P come before the start directory; the last one wins
+    let mut follow = Follow::Never;
     let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-name" => {
-                i += 1;
-                if let Some(pat) = args.get(i) {
-                    name_pat = Some(pat.clone());
-                }
+    while let Some(opt) = args.get(i) {
+        follow = match opt.as_str() {
+            "-P" => Follow::Never,
+            "-H" => Follow::StartOnly,
+            "-L" => Follow::Always,
+            _ => break,
+        };
+        i += 1;
+    }
+
+    match args.get(i).map(String::as_str) {
+        // `find -- -dir`: a start directory that looks like an option
+        Some("--") => {
+            if let Some(dir) = args.get(i + 1) { start_dir = dir.clone(); }
+            i += 2;
+        }
+        Some(dir) if !dir.starts_with('-') && dir != "(" && dir != "!" => {
+            start_dir = dir.to_string();
+            i += 1;
+        }
+        _ => {}
+    }
+
+    let mut parser = Parser { args: &args[i.min(args.len())..], pos: 0, max_depth: None, min_depth: None };
+    let expr = match parser.expression() {
+        Ok(expr) => expr,
+        Err(e) => { errln!("find: {}", e); return 1; }
+    };
+    if let Err(e) = expr.check_delete() {
+        errln!("find: {}", e);
+        return 1;
+    }
+
+    let path = Path::new(&start_dir);
+    if path.symlink_metadata().is_err() {
+        errln!("find: {}: no such file or directory", start_dir);
+        return 1;
+    }
+
+    let mut walk = Walk {
+        print: !expr.has_action(),
+        depth_first: expr.deletes(),
+        expr,
+        now: SystemTime::now(),
+        max_depth: parser.max_depth,
+        min_depth: parser.min_depth,
+        follow: follow == Follow::Always,
+        open: LoopGuard::default(),
+        found: Found::default(),
+    };
+    // Under -P a symlinked start directory is not searched
+    if follow != Follow::Never || !super::util::is_symlink(path) {
+        walk.open.enter(path).ok();
+        walk.dir(path, 0);
+    }
+
+    for r in &walk.found.results {
+        outln!("{}", r);
+    }
+
+    if walk.found.failed || (walk.found.results.is_empty() && walk.found.deleted == 0) { 1 } else { 0 }
+}
+
+/// What an entry is checked against: tests, actions and the ways of
+/// combining them, with `a b` meaning `a -a b`.
+enum Expr {
+    /// Always true: where -maxdepth and the like stood
+    True,
+    Test(Test),
+    Not(Box<Expr>),
+    /// The right side is only tried when the left one holds
+    And(Box<Expr>, Box<Expr>),
+    /// The right side is only tried when the left one fails
+    Or(Box<Expr>, Box<Expr>),
+    Print,
+    Delete,
+}
+
+/// Reads an expression from find's arguments, lowest precedence first:
+/// `-o`, then `-a` (or nothing), then `-not`/`!`, then a test, an action
+/// or `( ... )`.
+struct Parser<'a> {
+    args: &'a [String],
+    pos: usize,
+    max_depth: Option<usize>,
+    min_depth: Option<usize>,
+}
+
+impl Parser<'_> {
+    /// The whole expression: everything if there's nothing to check.
+    fn expression(&mut self) -> Result<Expr, String> {
+        if self.args.is_empty() { return Ok(Expr::True); }
+        let expr = self.or()?;
+        match self.peek() {
+            Some(extra) => Err(format!("unexpected `{}'", extra)),
+            None => Ok(expr),
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.args.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        self.pos += 1;
+        self.args.get(self.pos - 1).map(String::as_str)
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while matches!(self.peek(), Some("-o" | "-or")) {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.not()?;
+        loop {
+            match self.peek() {
+                None | Some(")" | "-o" | "-or") => return Ok(left),
+                Some("-a" | "-and") => self.pos += 1,
+                Some(_) => {}
             }
-            "-type" => {
-                i += 1;
-                if let Some(t) = args.get(i) {
-                    file_type = t.chars().next();
-                }
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        if matches!(self.peek(), Some("-not" | "!")) {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let Some(word) = self.next().map(str::to_string) else {
+            return Err("expected an expression at the end".to_string());
+        };
+        let expr = match word.as_str() {
+            "(" => {
+                if self.peek() == Some(")") { return Err("empty parentheses are not allowed".to_string()); }
+                let inner = self.or()?;
+                if self.next() != Some(")") { return Err("missing `)'".to_string()); }
+                inner
             }
-            "-maxdepth" => {
-                i += 1;
-                if let Some(n) = args.get(i) {
-                    max_depth = n.parse().ok();
-                }
+            ")" | "-o" | "-or" | "-a" | "-and" => return Err(format!("expected an expression before `{}'", word)),
+            "-print" => Expr::Print,
+            "-delete" => Expr::Delete,
+            "-empty" => Expr::Test(Test::Empty),
+            "-name" => Expr::Test(Test::Name(self.argument(&word)?.to_string())),
+            // 'f' = file, 'd' = dir, 'l' = link
+            "-type" => match self.argument(&word)? {
+                t @ ("f" | "d" | "l") => Expr::Test(Test::Type(t.chars().next().unwrap_or('f'))),
+                t => return Err(format!("unknown argument to -type: {}", t)),
+            },
+            "-size" | "-mtime" | "-newer" => {
+                let arg = self.argument(&word)?;
+                Expr::Test(Test::parse(&word, arg)?)
             }
-            "-mindepth" => {
-                i += 1;
-                if let Some(n) = args.get(i) {
-                    min_depth = n.parse().ok();
-                }
+            "-maxdepth" | "-mindepth" => {
+                let arg = self.argument(&word)?;
+                let n = arg.parse().map_err(|_| format!("invalid argument `{}' to `{}'", arg, word))?;
+                if word == "-maxdepth" { self.max_depth = Some(n) } else { self.min_depth = Some(n) }
+                Expr::True
             }
-            s if !s.starts_with('-') && i == 1 => {
-                start_dir = s.to_string();
+            _ => return Err(format!("unknown option: {}", word)),
+        };
+        Ok(expr)
+    }
+
+    fn argument(&mut self, test: &str) -> Result<&str, String> {
+        self.next().ok_or_else(|| format!("missing argument to `{}'", test))
+    }
+}
+
+impl Expr {
+    /// Whether anything prints or deletes; if not, each entry the
+    /// expression holds for is printed.
+    fn has_action(&self) -> bool {
+        match self {
+            Expr::Print | Expr::Delete => true,
+            Expr::Not(e) => e.has_action(),
+            Expr::And(l, r) | Expr::Or(l, r) => l.has_action() || r.has_action(),
+            Expr::True | Expr::Test(_) => false,
+        }
+    }
+
+    fn deletes(&self) -> bool {
+        match self {
+            Expr::Delete => true,
+            Expr::Not(e) => e.deletes(),
+            Expr::And(l, r) | Expr::Or(l, r) => l.deletes() || r.deletes(),
+            _ => false,
+        }
+    }
+
+    fn tests(&self) -> bool {
+        match self {
+            Expr::Test(_) => true,
+            Expr::Not(e) => e.tests(),
+            Expr::And(l, r) | Expr::Or(l, r) => l.tests() || r.tests(),
+            _ => false,
+        }
+    }
+
+    /// Refuse `-delete` ahead of tests it would otherwise depend on:
+    /// `find . -delete -name '*.o'` deletes everything, then checks names.
+    fn check_delete(&self) -> Result<(), String> {
+        match self {
+            Expr::And(l, r) => {
+                if l.deletes() && r.tests() {
+                    return Err("-delete has to come after the tests that pick what to delete".to_string());
+                }
+                l.check_delete()?;
+                r.check_delete()
             }
-            unknown => {
-                eprintln!("find: unknown option: {}", unknown);
-                return 1;
+            Expr::Or(l, r) => { l.check_delete()?; r.check_delete() }
+            Expr::Not(e) => e.check_delete(),
+            _ => Ok(()),
+        }
+    }
+
+    fn eval(&self, entry: &Entry, found: &mut Found) -> bool {
+        match self {
+            Expr::True => true,
+            Expr::Test(test) => test.passes(entry),
+            Expr::Not(e) => !e.eval(entry, found),
+            Expr::And(l, r) => l.eval(entry, found) && r.eval(entry, found),
+            Expr::Or(l, r) => l.eval(entry, found) || r.eval(entry, found),
+            Expr::Print => { found.results.push(display(entry.path)); true }
+            Expr::Delete => {
+                // The entry itself, never what a link points to
+                let is_dir = entry.path.symlink_metadata().is_ok_and(|m| m.is_dir());
+                let removed = if is_dir { std::fs::remove_dir(entry.path) } else { std::fs::remove_file(entry.path) };
+                match removed {
+                    Ok(()) => { found.deleted += 1; true }
+                    Err(e) => {
+                        errln!("find: cannot delete '{}': {}", display(entry.path), e);
+                        found.failed = true;
+                        false
+                    }
+                }
             }
         }
-        i += 1;
     }
+}
 
-    let path = std::path::Path::new(&start_dir);
-    if !path.exists() {
-        eprintln!("find: {}: no such file or directory", start_dir);
-        return 1;
+/// What the actions did over the walk.
+#[derive(Default)]
+struct Found {
+    results: Vec<String>,
+    deleted: usize,
+    failed: bool,
+}
+
+/// The entry being checked, stat'ed only if a test needs it.
+struct Entry<'a> {
+    path: &'a Path,
+    name: &'a str,
+    kind: std::fs::FileType,
+    follow: bool,
+    now: SystemTime,
+    meta: OnceCell<Option<std::fs::Metadata>>,
+}
+
+impl Entry<'_> {
+    fn meta(&self) -> Option<&std::fs::Metadata> {
+        self.meta.get_or_init(|| {
+            if self.follow { self.path.metadata().or_else(|_| self.path.symlink_metadata()).ok() } else { self.path.symlink_metadata().ok() }
+        }).as_ref()
     }
+}
 
-    let mut results: Vec<String> = Vec::new();
-    walk_find(
-        path,
-        &name_pat,
-        file_type,
-        max_depth,
-        min_depth,
-        0,
-        &mut results,
-    );
+/// One test an entry has to pass to be listed.
+enum Test {
+    Name(String),
+    Type(char),
+    /// Size in units of `unit` bytes, rounded up
+    Size(Compare, u64, u64),
+    /// Whole days since the last modification
+    Mtime(Compare, i64),
+    /// Modified after this
+    Newer(SystemTime),
+    /// An empty file or directory
+    Empty,
+}
+
+/// How a number in a test compares: `+N` is more than N, `-N` less.
+#[derive(Clone, Copy)]
+enum Compare { Less, Equal, More }
 
-    for r in &results {
-        println!("{}", r);
+impl Compare {
+    fn split(arg: &str) -> (Compare, &str) {
+        match arg.as_bytes().first() {
+            Some(b'+') => (Compare::More, &arg[1..]),
+            Some(b'-') => (Compare::Less, &arg[1..]),
+            _ => (Compare::Equal, arg),
+        }
+    }
+
+    fn holds<T: Ord>(self, value: T, n: T) -> bool {
+        match self {
+            Compare::Less  => value < n,
+            Compare::Equal => value == n,
+            Compare::More  => value > n,
+        }
+    }
+}
+
+impl Test {
+    /// -size, -mtime or -newer and its argument.
+    fn parse(test: &str, arg: &str) -> Result<Test, String> {
+        let (compare, number) = Compare::split(arg);
+        match test {
+            "-size" => {
+                let (digits, unit) = match number.char_indices().last() {
+                    Some((i, c)) if c.is_ascii_alphabetic() => (&number[..i], c),
+                    _ => (number, 'b'),
+                };
+                let unit = match unit {
+                    'c' => 1,
+                    'b' => 512,
+                    'k' => 1024,
+                    'M' => 1024 * 1024,
+                    'G' => 1024 * 1024 * 1024,
+                    _ => return Err(format!("invalid -size unit '{}' (use c, k, M or G)", unit)),
+                };
+                let n = digits.parse().map_err(|_| format!("invalid argument `{}' to `-size'", arg))?;
+                Ok(Test::Size(compare, n, unit))
+            }
+            "-mtime" => {
+                let days = number.parse().map_err(|_| format!("invalid argument `{}' to `-mtime'", arg))?;
+                Ok(Test::Mtime(compare, days))
+            }
+            _ => {
+                let modified = std::fs::metadata(arg).and_then(|m| m.modified())
+                    .map_err(|e| format!("{}: {}", arg, e))?;
+                Ok(Test::Newer(modified))
+            }
+        }
     }
 
-    if results.is_empty() { 1 } else { 0 }
+    fn passes(&self, entry: &Entry) -> bool {
+        let (kind, meta) = (entry.kind, || entry.meta());
+        match self {
+            Test::Name(pat) => crate::glob::matches_pattern(entry.name, pat),
+            Test::Type('f') => kind.is_file(),
+            Test::Type('d') => kind.is_dir(),
+            Test::Type('l') => kind.is_symlink(),
+            Test::Type(_) => true,
+            Test::Size(compare, n, unit) => meta().is_some_and(|m| compare.holds(m.len().div_ceil(*unit), *n)),
+            Test::Mtime(compare, days) => meta().and_then(|m| m.modified().ok()).is_some_and(|modified| {
+                compare.holds((unix_secs(entry.now) - unix_secs(modified)).div_euclid(86400), *days)
+            }),
+            Test::Newer(time) => meta().and_then(|m| m.modified().ok()).is_some_and(|modified| modified > *time),
+            Test::Empty => {
+                if kind.is_dir() {
+                    std::fs::read_dir(entry.path).is_ok_and(|mut entries| entries.next().is_none())
+                } else {
+                    kind.is_file() && meta().is_some_and(|m| m.len() == 0)
+                }
+            }
+        }
+    }
 }
 
-fn walk_find(
-    dir: &std::path::Path,
-    name_pat: &Option<String>,
-    file_type: Option<char>,
+struct Walk {
+    expr: Expr,
+    /// Print what the expression holds for, as it has no action of its own
+    print: bool,
+    /// -delete: check a directory's contents before the directory, so it
+    /// can be emptied first
+    depth_first: bool,
+    now: SystemTime,
     max_depth: Option<usize>,
     min_depth: Option<usize>,
-    depth: usize,
-    results: &mut Vec<String>,
-) {
-    // Check depth limits
-    if let Some(max) = max_depth {
-        if depth > max { return; }
-    }
+    /// -L: look through symlinks, to what they point at
+    follow: bool,
+    open: LoopGuard,
+    found: Found,
+}
 
-    let entries = match std::fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return,
-    };
+impl Walk {
+    fn dir(&mut self, dir: &Path, depth: usize) {
+        // Check depth limits
+        if let Some(max) = self.max_depth {
+            if depth > max { return; }
+        }
 
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let is_dir = path.is_dir();
-        let name = entry.file_name().to_string_lossy().to_string();
-
-        // Check type filter
-        let type_ok = match file_type {
-            Some('f') => !is_dir,
-            Some('d') => is_dir,
-            Some('l') => path.symlink_metadata()
-                .map(|m| m.file_type().is_symlink())
-                .unwrap_or(false),
-            _ => true,
+        let entries = match std::fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(_) => return,
         };
 
-        // Check name pattern
-        let name_ok = match name_pat {
-            Some(pat) => crate::glob::matches_pattern(&name, pat),
-            None => true,
-        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // A link's own type, or under -L its target's (a broken link
+            // stays a link)
+            let Ok(mut kind) = entry.file_type() else { continue };
+            if self.follow && kind.is_symlink() {
+                if let Ok(meta) = path.metadata() { kind = meta.file_type(); }
+            }
+            let is_dir = kind.is_dir();
+            let name = entry.file_name().to_string_lossy().to_string();
 
-        // Check mindepth
-        let depth_ok = match min_depth {
-            Some(min) => depth + 1 >= min,
-            None => true,
-        };
+            if !self.depth_first { self.check(&path, &name, kind, depth); }
+
+            // Recurse into directories
+            if is_dir {
+                if let Some(max) = self.max_depth {
+                    if depth + 1 > max { continue; }
+                }
+                if let Err(first) = self.open.enter(&path) {
+                    errln!("find: File system loop detected; '{}' is part of the same file system loop as '{}'.",
+                        display(&path), display(&first));
+                    continue;
+                }
+                self.dir(&path, depth + 1);
+                self.open.leave();
+            }
 
-        if type_ok && name_ok && depth_ok {
-            // Normalise path separators
-            let display = path.display().to_string().replace('\\', "/");
-            // Strip leading ./ for cleaner output
-            let display = display.strip_prefix("./").unwrap_or(&display).to_string();
-            results.push(display);
+            if self.depth_first { self.check(&path, &name, kind, depth); }
         }
+    }
 
-        // Recurse into directories
-        if is_dir {
-            if let Some(max) = max_depth {
-                if depth + 1 > max { continue; }
-            }
-            walk_find(&path, name_pat, file_type, max_depth, min_depth, depth + 1, results);
+    /// Run the expression on one entry of a directory at `depth`.
+    fn check(&mut self, path: &Path, name: &str, kind: std::fs::FileType, depth: usize) {
+        if self.min_depth.is_some_and(|min| depth + 1 < min) { return; }
+        let entry = Entry { path, name, kind, follow: self.follow, now: self.now, meta: OnceCell::new() };
+        if self.expr.eval(&entry, &mut self.found) && self.print {
+            self.found.results.push(display(path));
         }
     }
-}
\ No newline at end of file
+}
+
+/// A path as find prints it: forward slashes, no leading `./`.
+fn display(path: &Path) -> String {
+    let display = crate::paths::display(path);
+    display.strip_prefix("./").unwrap_or(&display).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Expr, String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        let expr = Parser { args: &args, pos: 0, max_depth: None, min_depth: None }.expression()?;
+        expr.check_delete()?;
+        Ok(expr)
+    }
+
+    #[test]
+    fn expressions() {
+        // -a binds tighter than -o
+        let Ok(Expr::Or(left, right)) = parse(&["-name", "a", "-type", "f", "-o", "-name", "b"]) else { panic!() };
+        assert!(matches!(*left, Expr::And(..)));
+        assert!(matches!(*right, Expr::Test(Test::Name(_))));
+        assert!(matches!(parse(&["!", "(", "-empty", "-o", "-name", "x", ")"]), Ok(Expr::Not(_))));
+        assert!(parse(&["(", "-name", "a"]).is_err());
+        assert!(parse(&["(", ")"]).is_err());
+        assert!(parse(&["-name"]).is_err());
+        assert!(parse(&["-o", "-empty"]).is_err());
+        assert!(parse(&["-name", "a", ")"]).is_err());
+    }
+
+    #[test]
+    fn delete_after_its_tests() {
+        assert!(parse(&["-name", "*.o", "-delete"]).is_ok_and(|e| e.deletes() && e.has_action()));
+        assert!(parse(&["-delete"]).is_ok());
+        assert!(parse(&["-delete", "-name", "*.o"]).is_err());
+        assert!(parse(&["-name", "a", "-delete", "-o", "-name", "b", "-print"]).is_ok());
+    }
+}
@@ -1,207 +1,518 @@
 // src/executor/builtin/fs.rs
-use crate::shell::Shell;
-use super::util::{strip_ansi_len, format_size, color_name};
+// File operations: mkdir, rm, cp, mv, cat, touch, chmod, chown, ln
+//
+// chmod -R and chown -R change a directory and everything in it. Symlinks
+// met on the way are left alone by chmod (their mode means nothing) and
+// changed themselves by chown; neither goes through them.
 
-fn normalise_str(s: &str) -> String {
-    let s = s.trim_start_matches("\\\\?\\");
-    s.replace('\\', "/")
-}
+use std::io::{BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use super::util::{Opt, Options, Parsed};
 
-fn normalise_cwd(p: &std::path::Path) -> std::path::PathBuf {
-    std::path::PathBuf::from(normalise_str(&p.display().to_string()))
-}
+pub const MKDIR: Options = Options {
+    name: "mkdir",
+    operands: "DIR...",
+    about: "Create directories.",
+    opts: &[
+        Opt::new("p", "parents", "create missing parents; no error if it exists"),
+        Opt::new("m", "mode", "give the directory MODE (octal or u+x style) rather than the umask's").takes("MODE"),
+    ],
+    numeric: None,
+};
 
-pub fn builtin_ls(shell: &Shell, args: &[String]) -> i32 {
-    let mut show_hidden = false;
-    let mut long_format = false;
-    let mut targets: Vec<std::path::PathBuf> = Vec::new();
+pub const RM: Options = Options {
+    name: "rm",
+    operands: "FILE...",
+    about: "Remove files, and directories with -r. A symlink is removed itself, \
+            never what it points to.",
+    opts: &[
+        Opt::new("rR", "recursive", "remove directories and their contents"),
+        Opt::new("f", "force", "ignore files that don't exist"),
+        Opt::new("t", "trash", "move to the trash instead (see trash)"),
+    ],
+    numeric: None,
+};
 
-    for arg in &args[1..] {
-        if arg.starts_with('-') {
-            for ch in arg.chars().skip(1) {
-                match ch { 'a'|'A' => show_hidden = true, 'l' => long_format = true, _ => {} }
-            }
-        } else {
-            let joined = shell.cwd.join(arg);
-            targets.push(std::path::PathBuf::from(normalise_str(&joined.display().to_string())));
-        }
-    }
+pub const CP: Options = Options {
+    name: "cp",
+    operands: "SOURCE... DEST",
+    about: "Copy files, and directories with -r. A recursive copy keeps modes, \
+            modification times, symlinks and hard links.",
+    opts: &[
+        Opt::new("rR", "recursive", "copy directories and their contents"),
+        Opt::new("L", "dereference", "with -r, copy what symlinks point to, not the links"),
+        Opt::new("f", "force", "overwrite existing files (the default)"),
+        Opt::new("i", "interactive", "ask before overwriting a file"),
+        Opt::new("n", "no-clobber", "never overwrite a file"),
+        Opt::new("p", "preserve", "keep the mode and times (-r always does)"),
+        Opt::new("v", "verbose", "print each file copied"),
+    ],
+    numeric: None,
+};
 
-    if targets.is_empty() { targets.push(normalise_cwd(&shell.cwd)); }
+pub const MV: Options = Options {
+    name: "mv",
+    operands: "SOURCE... DEST",
+    about: "Move or rename files and directories.",
+    opts: &[
+        Opt::new("f", "force", "overwrite existing files (the default)"),
+        Opt::new("i", "interactive", "ask before overwriting a file"),
+        Opt::new("n", "no-clobber", "never overwrite a file"),
+        Opt::new("v", "verbose", "print each file moved"),
+    ],
+    numeric: None,
+};
 
-    let mut code = 0;
-    for target in &targets {
-        if target.is_file() {
-            let name = target.file_name().map(|n| n.to_string_lossy().to_string())
-                .unwrap_or_else(|| target.display().to_string());
-            if long_format {
-                if let Ok(meta) = target.metadata() {
-                    println!("-  {:>10}  {}", format_size(meta.len()), color_name(&name, false, target));
-                }
-            } else {
-                println!("{}", color_name(&name, false, target));
-            }
-            continue;
-        }
-
-        let entries = match std::fs::read_dir(target) {
-            Ok(e) => e,
-            Err(e) => { eprintln!("ls: {}: {}", target.display(), e); code = 1; continue; }
-        };
-
-        let mut items: Vec<std::fs::DirEntry> = entries.flatten()
-            .filter(|e| show_hidden || !e.file_name().to_string_lossy().starts_with('.'))
-            .collect();
-
-        items.sort_by(|a, b| {
-            let ad = a.file_type().map(|t| t.is_dir()).unwrap_or(false);
-            let bd = b.file_type().map(|t| t.is_dir()).unwrap_or(false);
-            match (ad, bd) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a.file_name().cmp(&b.file_name()),
-            }
-        });
-
-        if long_format {
-            for item in &items {
-                let meta = match item.metadata() { Ok(m) => m, Err(_) => continue };
-                let name = item.file_name().to_string_lossy().to_string();
-                let is_dir = meta.is_dir();
-                println!("{} {:>10}  {}", if is_dir { "d" } else { "-" },
-                    format_size(meta.len()), color_name(&name, is_dir, &item.path()));
-            }
-            continue;
-        }
+pub const CAT: Options = Options {
+    name: "cat",
+    operands: "[FILE]...",
+    about: "Print files, or standard input if none (or -) is given.",
+    opts: &[
+        Opt::new("n", "number", "number every line"),
+        Opt::new("b", "number-nonblank", "number the lines that aren't empty"),
+        Opt::new("s", "squeeze-blank", "print runs of empty lines as one"),
+        Opt::new("v", "show-nonprinting", "show control bytes as ^X and high ones as M-X"),
+        Opt::new("E", "show-ends", "show the end of each line as $"),
+        Opt::new("T", "show-tabs", "show tabs as ^I"),
+        Opt::new("A", "show-all", "the same as -vET"),
+        Opt::new("x", "hex", "print a hex dump instead"),
+    ],
+    numeric: None,
+};
 
-        let names: Vec<String> = items.iter().map(|item| {
-            let name = item.file_name().to_string_lossy().to_string();
-            let is_dir = item.file_type().map(|t| t.is_dir()).unwrap_or(false);
-            color_name(&name, is_dir, &item.path())
-        }).collect();
+pub const TOUCH: Options = Options {
+    name: "touch",
+    operands: "FILE...",
+    about: "Create empty files, or update the modification time of existing ones.",
+    opts: &[],
+    numeric: None,
+};
 
-        let max_len = names.iter().map(|n| strip_ansi_len(n)).max().unwrap_or(0);
-        let col_width = (max_len + 2).max(16);
-        let cols = (80usize / col_width).max(1);
+pub const CHOWN: Options = Options {
+    name: "chown",
+    operands: "OWNER[:GROUP] FILE...",
+    about: "Change the owner and/or group of each FILE. OWNER and GROUP are\n\
+            names or numbers; `:GROUP` leaves the owner, `OWNER:` takes the\n\
+            owner's login group.",
+    opts: &[
+        Opt::new("R", "recursive", "change directories and everything in them"),
+        Opt::new("h", "no-dereference", "change a symlink itself rather than what it points to"),
+    ],
+    numeric: None,
+};
 
-        for (i, name) in names.iter().enumerate() {
-            let padding = col_width.saturating_sub(strip_ansi_len(name));
-            print!("{}{}", name, " ".repeat(padding));
-            if (i + 1) % cols == 0 { println!(); }
-        }
-        if !names.is_empty() && names.len() % cols != 0 { println!(); }
-    }
-    code
-}
+pub const LN: Options = Options {
+    name: "ln",
+    operands: "TARGET LINK",
+    about: "Make a hard link, or a symbolic one with -s.",
+    opts: &[
+        Opt::new("s", "symbolic", "make a symbolic link"),
+        Opt::new("f", "force", "replace an existing LINK"),
+    ],
+    numeric: None,
+};
 
 pub fn builtin_mkdir(args: &[String]) -> i32 {
-    if args.len() < 2 { eprintln!("usage: mkdir [-p] <dir>"); return 1; }
-    let mut parents = false;
-    let mut dirs = Vec::new();
-    for arg in &args[1..] {
-        if arg == "-p" { parents = true; } else { dirs.push(arg); }
-    }
+    let p = match MKDIR.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.is_empty() { errln!("usage: mkdir [-p] <dir>"); return 1; }
+    let parents = p.has("parents");
+    let mode = p.value("mode");
     let mut code = 0;
-    for dir in dirs {
-        let result = if parents { std::fs::create_dir_all(dir) } else { std::fs::create_dir(dir) };
+    for dir in p.operands {
+        let mut result = if parents { std::fs::create_dir_all(dir) } else { std::fs::create_dir(dir) };
+        if let (Ok(()), Some(mode)) = (&result, mode) {
+            result = set_mode(Path::new(dir), mode);
+        }
         match result {
-            Ok(_) => println!("created {}", dir),
-            Err(e) => { eprintln!("mkdir: {}: {}", dir, e); code = 1; }
+            Ok(_) => outln!("created {}", dir),
+            Err(e) => { errln!("mkdir: {}: {}", dir, e); code = 1; }
         }
     }
     code
 }
 
 pub fn builtin_rm(args: &[String]) -> i32 {
-    if args.len() < 2 { eprintln!("usage: rm [-rf] <file> [file2 ...]"); return 1; }
-    let mut recursive = false;
-    let mut force = false;
-    let mut targets = Vec::new();
-    for arg in &args[1..] {
-        if arg.starts_with('-') {
-            for ch in arg.chars().skip(1) {
-                match ch { 'r'|'R' => recursive = true, 'f' => force = true, _ => {} }
-            }
-        } else { targets.push(arg); }
-    }
+    let p = match RM.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.is_empty() { errln!("usage: rm [-rf] <file> [file2 ...]"); return 1; }
+    let recursive = p.has("recursive");
+    let force = p.has("force");
+    let to_trash = p.has("trash");
     let mut code = 0;
-    for target in targets {
+    for target in p.operands {
         let path = std::path::Path::new(target);
-        if !path.exists() {
-            if !force { eprintln!("rm: {}: no such file or directory", target); code = 1; }
+        // `rm -r link/` would mean the directory behind the link
+        let bare = target.trim_end_matches('/');
+        if bare.len() < target.len() && super::util::is_symlink(Path::new(bare)) {
+            errln!("rm: {}: is a symlink; remove '{}' to delete the link", target, bare);
+            code = 1;
             continue;
         }
-        let result = if path.is_dir() {
-            if recursive { std::fs::remove_dir_all(path) }
-            else { eprintln!("rm: {}: is a directory (use -r)", target); code = 1; continue; }
-        } else { std::fs::remove_file(path) };
-        if let Err(e) = result { eprintln!("rm: {}: {}", target, e); code = 1; }
+        // A symlink is removed itself, never what it points to
+        let Ok(meta) = path.symlink_metadata() else {
+            if !force { errln!("rm: {}: no such file or directory", target); code = 1; }
+            continue;
+        };
+        let result = if meta.is_dir() {
+            // remove_dir_all doesn't follow links inside the tree either
+            if recursive && to_trash { super::trash::put(path) }
+            else if recursive { std::fs::remove_dir_all(path) }
+            else { errln!("rm: {}: is a directory (use -r)", target); code = 1; continue; }
+        } else if to_trash { super::trash::put(path) } else { std::fs::remove_file(path) };
+        if let Err(e) = result { errln!("rm: {}: {}", target, e); code = 1; }
     }
     code
 }
 
 pub fn builtin_cp(args: &[String]) -> i32 {
-    if args.len() < 3 { eprintln!("usage: cp [-r] <source> <dest>"); return 1; }
-    let mut recursive = false;
-    let mut files = Vec::new();
-    for arg in &args[1..] {
-        if matches!(arg.as_str(), "-r"|"-R"|"-rf"|"-fr") { recursive = true; }
-        else { files.push(arg.as_str()); }
-    }
-    if files.len() < 2 { eprintln!("cp: missing destination"); return 1; }
-    let dest = std::path::Path::new(files[files.len() - 1]);
+    let p = match CP.parse(args) { Ok(p) => p, Err(code) => return code };
+    let files = &p.operands;
+    if files.is_empty() { errln!("usage: cp [-r] <source> <dest>"); return 1; }
+    if files.len() < 2 { errln!("cp: missing destination"); return 1; }
+    let recursive = p.has("recursive");
+    let preserve = p.has("preserve");
+    let sources = &files[..files.len() - 1];
+    let mut tree = TreeCopy {
+        dereference: p.has("dereference"),
+        clobber: Clobber::from(&p),
+        verbose: p.has("verbose"),
+        progress: Progress::for_copy(&sources.iter().map(Path::new).collect::<Vec<_>>(), recursive && !p.has("dereference")),
+        linked: Default::default(),
+        open: Default::default(),
+    };
+    let dest = Path::new(files[files.len() - 1]);
     let mut code = 0;
-    for src in &files[..files.len() - 1] {
-        let src_path = std::path::Path::new(src);
-        if !src_path.exists() { eprintln!("cp: {}: no such file or directory", src); code = 1; continue; }
+    for src in sources {
+        let src_path = Path::new(src);
+        if src_path.symlink_metadata().is_err() { errln!("cp: {}: no such file or directory", src); code = 1; continue; }
         let actual_dest = if dest.is_dir() { dest.join(src_path.file_name().unwrap_or_default()) }
                           else { dest.to_path_buf() };
-        let result = if src_path.is_dir() {
-            if recursive { copy_dir_all(src_path, &actual_dest) }
-            else { eprintln!("cp: {}: is a directory (use -r)", src); code = 1; continue; }
-        } else { std::fs::copy(src_path, &actual_dest).map(|_| ()) };
-        if let Err(e) = result { eprintln!("cp: {}: {}", src, e); code = 1; }
+        let result = if recursive {
+            tree.copy(src_path, &actual_dest)
+        } else if src_path.is_dir() {
+            errln!("cp: {}: is a directory (use -r)", src); code = 1; continue;
+        } else {
+            tree.copy_contents(src_path, &actual_dest)
+                .and_then(|copied| if copied && preserve { preserve_times(src_path, &actual_dest) } else { Ok(()) })
+        };
+        if let Err(e) = result { tree.clear_progress(); errln!("cp: {}: {}", src, e); code = 1; }
     }
+    tree.clear_progress();
     code
 }
 
 pub fn builtin_mv(args: &[String]) -> i32 {
-    if args.len() < 3 { eprintln!("usage: mv <source> <dest>"); return 1; }
-    let dest = std::path::Path::new(&args[args.len() - 1]);
+    let p = match MV.parse(args) { Ok(p) => p, Err(code) => return code };
+    let files = &p.operands;
+    if files.len() < 2 { errln!("usage: mv <source> <dest>"); return 1; }
+    let clobber = Clobber::from(&p);
+    let dest = std::path::Path::new(files[files.len() - 1]);
     let mut code = 0;
-    for src in &args[1..args.len() - 1] {
+    for src in &files[..files.len() - 1] {
         let src_path = std::path::Path::new(src);
-        if !src_path.exists() { eprintln!("mv: {}: no such file or directory", src); code = 1; continue; }
+        if !src_path.exists() { errln!("mv: {}: no such file or directory", src); code = 1; continue; }
         let actual_dest = if dest.is_dir() { dest.join(src_path.file_name().unwrap_or_default()) }
                           else { dest.to_path_buf() };
-        if let Err(e) = std::fs::rename(src_path, &actual_dest) { eprintln!("mv: {}: {}", src, e); code = 1; }
+        if actual_dest.symlink_metadata().is_ok() && !clobber.allows("mv", &actual_dest) { continue; }
+        let moved = std::fs::rename(src_path, &actual_dest).or_else(|e| match e.kind() {
+            std::io::ErrorKind::CrossesDevices => move_across(src_path, &actual_dest),
+            _ => Err(e),
+        });
+        match moved {
+            Ok(()) => if p.has("verbose") { outln!("{} -> {}", src, crate::paths::display(&actual_dest)); },
+            Err(e) => { errln!("mv: {}: {}", src, e); code = 1; }
+        }
     }
     code
 }
 
+/// Move `src` to `dest` on another filesystem, where a rename can't: copy
+/// it, links, modes and times included, then delete it. If the copy fails
+/// the source is left alone and what was copied is removed again.
+pub(super) fn move_across(src: &Path, dest: &Path) -> std::io::Result<()> {
+    let existed = match dest.symlink_metadata() {
+        // As with a rename, a file in the way is replaced
+        Ok(meta) if !meta.is_dir() => { std::fs::remove_file(dest)?; false }
+        Ok(_) => true,
+        Err(_) => false,
+    };
+    let mut tree = TreeCopy {
+        dereference: false,
+        clobber: Clobber::Overwrite,
+        verbose: false,
+        progress: Progress::for_copy(&[src], true),
+        linked: Default::default(),
+        open: Default::default(),
+    };
+    let copied = tree.copy(src, dest);
+    tree.clear_progress();
+    if let Err(e) = copied {
+        if !existed { remove_all(dest).ok(); }
+        return Err(e);
+    }
+    remove_all(src)
+}
+
+/// Delete a file, symlink or whole directory.
+fn remove_all(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() { std::fs::remove_dir_all(path) } else { std::fs::remove_file(path) }
+}
+
+/// What cp and mv do when the destination exists: overwrite it (-f, the
+/// default), ask (-i) or leave it (-n), whichever was given last.
+enum Clobber { Overwrite, Ask, Keep }
+
+impl Clobber {
+    fn from(p: &Parsed) -> Clobber {
+        p.given.iter().rev().find_map(|(key, _)| match *key {
+            "force"       => Some(Clobber::Overwrite),
+            "interactive" => Some(Clobber::Ask),
+            "no-clobber"  => Some(Clobber::Keep),
+            _ => None,
+        }).unwrap_or(Clobber::Overwrite)
+    }
+
+    /// Whether `dest`, which exists, may be replaced.
+    fn allows(&self, command: &str, dest: &Path) -> bool {
+        match self {
+            Clobber::Overwrite => true,
+            Clobber::Keep => false,
+            Clobber::Ask => {
+                crossterm::terminal::disable_raw_mode().ok();
+                err!("{}: overwrite '{}'? [y/N] ", command, crate::paths::display(dest));
+                std::io::stderr().flush().ok();
+                // From the builtin's own stdin, which may be a pipe or file
+                let answer = super::io::read_line().unwrap_or_default();
+                matches!(answer.trim(), "y" | "Y" | "yes")
+            }
+        }
+    }
+}
+
+/// `cp -p`: give `dest` the access and modification times of `src`.
+fn preserve_times(src: &Path, dest: &Path) -> std::io::Result<()> {
+    let meta = std::fs::metadata(src)?;
+    filetime::set_file_times(dest,
+        filetime::FileTime::from_last_access_time(&meta),
+        filetime::FileTime::from_last_modification_time(&meta))
+}
+
+/// Copies this big get a progress bar, if stderr is a terminal.
+const LARGE_COPY: u64 = 64 * 1024 * 1024;
+
+/// The progress bar of a large copy, drawn on stderr.
+struct Progress {
+    total: u64,
+    done: u64,
+    drawn: Instant,
+}
+
+impl Progress {
+    fn for_copy(sources: &[&Path], recursive: bool) -> Option<Progress> {
+        if !std::io::stderr().is_terminal() { return None; }
+        let total: u64 = sources.iter().map(|s| bytes_to_copy(s, recursive)).sum();
+        (total >= LARGE_COPY).then(|| Progress { total, done: 0, drawn: Instant::now() })
+    }
+
+    /// Copy a file's contents and mode, drawing the bar as it goes.
+    fn copy(&mut self, src: &Path, dest: &Path) -> std::io::Result<()> {
+        let mut from = std::fs::File::open(src)?;
+        let permissions = from.metadata()?.permissions();
+        let mut to = std::fs::File::create(dest)?;
+        let mut buf = vec![0u8; 1024 * 1024];
+        loop {
+            let n = from.read(&mut buf)?;
+            if n == 0 { break; }
+            to.write_all(&buf[..n])?;
+            self.done += n as u64;
+            if self.drawn.elapsed() >= Duration::from_millis(100) {
+                err!("{}", super::pkg::download_progress_line(self.done.min(self.total), Some(self.total)));
+                self.drawn = Instant::now();
+            }
+        }
+        std::fs::set_permissions(dest, permissions)
+    }
+}
+
+/// The bytes of file contents a copy of `path` reads (without following
+/// symlinks), for the progress bar.
+fn bytes_to_copy(path: &Path, recursive: bool) -> u64 {
+    let Ok(meta) = (if recursive { path.symlink_metadata() } else { path.metadata() }) else { return 0 };
+    if !meta.is_dir() { return if meta.is_file() { meta.len() } else { 0 }; }
+    if !recursive { return 0; }
+    std::fs::read_dir(path).into_iter().flatten().flatten()
+        .map(|entry| bytes_to_copy(&entry.path(), true))
+        .sum()
+}
+
+/// cat [FILE...] — with no files, copies stdin.
 pub fn builtin_cat(args: &[String]) -> i32 {
-    if args.len() < 2 { eprintln!("usage: cat <file> [file2 ...]"); return 1; }
+    let p = match CAT.parse(args) { Ok(p) => p, Err(code) => return code };
+    let all = p.has("show-all");
+    let mut format = CatFormat {
+        number: p.has("number") || p.has("number-nonblank"),
+        nonblank: p.has("number-nonblank"),
+        squeeze: p.has("squeeze-blank"),
+        nonprinting: all || p.has("show-nonprinting"),
+        ends: all || p.has("show-ends"),
+        tabs: all || p.has("show-tabs"),
+        line: 0,
+        blank: false,
+    };
+    let hex = p.has("hex");
+    let plain = !hex && !format.changes_anything();
+    // Only a terminal is spared binary; a pipe or file gets the bytes
+    let guard = plain && super::io::stdout_is_terminal();
+
+    let mut files = p.operands;
+    if files.is_empty() { files.push("-"); }
     let mut code = 0;
-    for filename in &args[1..] {
-        match std::fs::read_to_string(filename) {
-            Ok(contents) => print!("{}", contents),
-            Err(e) => { eprintln!("cat: {}: {}", filename, e); code = 1; }
+    let mut out = super::io::stdout();
+    let mut offset = 0;
+    for filename in files {
+        let result = super::io::open_input(filename).and_then(|mut r| {
+            if guard && looks_binary(r.fill_buf()?) {
+                let mut head = Vec::new();
+                r.by_ref().take(BINARY_PREVIEW as u64).read_to_end(&mut head)?;
+                let rest = std::io::copy(&mut r, &mut std::io::sink())?;
+                hex_dump(&mut head.as_slice(), &mut out, &mut 0)?;
+                if rest == 0 {
+                    errln!("cat: {}: binary file, shown in hex (-v to show it as text)", filename);
+                } else {
+                    errln!("cat: {}: binary file of {} bytes, the first {} shown (-x for all of it, -v as text)",
+                        filename, head.len() as u64 + rest, head.len());
+                }
+                return Ok(());
+            }
+            if hex { return hex_dump(&mut r, &mut out, &mut offset); }
+            if plain { return std::io::copy(&mut r, &mut out).map(|_| ()); }
+            let mut line = Vec::new();
+            while r.read_until(b'\n', &mut line)? > 0 {
+                format.write_line(&line, &mut out)?;
+                line.clear();
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            // The reader going away isn't worth reporting
+            if e.kind() == std::io::ErrorKind::BrokenPipe { break; }
+            errln!("cat: {}: {}", filename, e);
+            code = 1;
         }
     }
     code
 }
 
+/// How much of a binary file cat shows a terminal.
+const BINARY_PREVIEW: usize = 256;
+
+/// cat's line options. Numbering and squeezing carry on from one file to
+/// the next, as if they were one stream.
+struct CatFormat {
+    number: bool,
+    nonblank: bool,
+    squeeze: bool,
+    nonprinting: bool,
+    ends: bool,
+    tabs: bool,
+    /// The last line number printed
+    line: usize,
+    /// Whether the last line was empty
+    blank: bool,
+}
+
+impl CatFormat {
+    fn changes_anything(&self) -> bool {
+        self.number || self.squeeze || self.nonprinting || self.ends || self.tabs
+    }
+
+    /// Write one line, with its newline if it has one.
+    fn write_line(&mut self, line: &[u8], out: &mut impl Write) -> std::io::Result<()> {
+        let (body, newline) = match line.strip_suffix(b"\n") {
+            Some(body) => (body, true),
+            None => (line, false),
+        };
+        let blank = body.is_empty();
+        if blank && self.blank && self.squeeze { return Ok(()); }
+        self.blank = blank;
+        if self.number && !(blank && self.nonblank) {
+            self.line += 1;
+            write!(out, "{:6}\t", self.line)?;
+        }
+        let mut shown = Vec::with_capacity(body.len());
+        for &b in body {
+            match b {
+                b'\t' if self.tabs => shown.extend_from_slice(b"^I"),
+                b'\t' => shown.push(b),
+                _ if self.nonprinting => push_visible(&mut shown, b),
+                _ => shown.push(b),
+            }
+        }
+        out.write_all(&shown)?;
+        if self.ends && newline { out.write_all(b"$")?; }
+        if newline { out.write_all(b"\n")?; }
+        Ok(())
+    }
+}
+
+/// `cat -v`'s notation: ^X for control bytes, ^? for DEL and M- before
+/// the rest of a byte with the high bit set.
+fn push_visible(shown: &mut Vec<u8>, b: u8) {
+    let low = if b >= 0x80 { shown.extend_from_slice(b"M-"); b - 0x80 } else { b };
+    match low {
+        0x7f => shown.extend_from_slice(b"^?"),
+        0..=0x1f => { shown.push(b'^'); shown.push(low + b'@'); }
+        _ => shown.push(low),
+    }
+}
+
+/// Whether the start of a file looks like binary rather than text: a NUL,
+/// or more than the odd control character. Latin-1 and other non-UTF-8
+/// text still counts as text.
+pub(super) fn looks_binary(head: &[u8]) -> bool {
+    let control = head.iter().filter(|&&b| b < 0x20 && !matches!(b, b'\n' | b'\r' | b'\t' | 0x0c | 0x1b)).count();
+    head.contains(&0) || control * 20 > head.len()
+}
+
+/// `hexdump -C` style lines: the offset, sixteen bytes in hex and the
+/// same bytes as text. `offset` carries on across files.
+fn hex_dump(input: &mut impl Read, out: &mut impl Write, offset: &mut u64) -> std::io::Result<()> {
+    let mut row = [0u8; 16];
+    loop {
+        // Fill a whole row, even from a pipe that hands over less
+        let mut filled = 0;
+        while filled < row.len() {
+            match input.read(&mut row[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        if filled == 0 { return Ok(()); }
+        let bytes = &row[..filled];
+        let mut hex = String::new();
+        for (i, b) in bytes.iter().enumerate() {
+            if i == 8 { hex.push(' '); }
+            hex.push_str(&format!("{:02x} ", b));
+        }
+        let text: String = bytes.iter().map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }).collect();
+        writeln!(out, "{:08x}  {:<49} |{}|", offset, hex, text)?;
+        *offset += filled as u64;
+        if filled < row.len() { return Ok(()); }
+    }
+}
+
 pub fn builtin_touch(args: &[String]) -> i32 {
-    if args.len() < 2 { eprintln!("usage: touch <file> [file2 ...]"); return 1; }
+    let files = match TOUCH.parse(args) { Ok(p) => p.operands, Err(code) => return code };
+    if files.is_empty() { errln!("usage: touch <file> [file2 ...]"); return 1; }
     let mut code = 0;
-    for filename in &args[1..] {
+    for filename in files {
         let path = std::path::Path::new(filename);
         if path.exists() {
             if let Err(e) = filetime::set_file_mtime(path, filetime::FileTime::now()) {
-                eprintln!("touch: {}: {}", filename, e); code = 1;
+                errln!("touch: {}: {}", filename, e); code = 1;
             }
         } else if let Err(e) = std::fs::File::create(path) {
-            eprintln!("touch: {}: {}", filename, e); code = 1;
+            errln!("touch: {}: {}", filename, e); code = 1;
         }
     }
     code
@@ -209,36 +520,128 @@ pub fn builtin_touch(args: &[String]) -> i32 {
 
 pub fn builtin_chmod(_args: &[String]) -> i32 {
     #[cfg(windows)]
-    { eprintln!("chmod: not supported on Windows"); return 1; }
+    { errln!("chmod: not supported on Windows"); return 1; }
 
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        if _args.len() < 3 { eprintln!("usage: chmod <mode> <file> [file2 ...]"); return 1; }
-        let mode_str = &_args[1];
+        // A mode like `-w` looks like an option, so only -R and `--` are
+        // special
+        let mut words = &_args[1..];
+        let mut recursive = false;
+        while let Some(first) = words.first() {
+            match first.as_str() {
+                "-R" | "--recursive" => recursive = true,
+                "--" => { words = &words[1..]; break; }
+                _ => break,
+            }
+            words = &words[1..];
+        }
+        if words.len() < 2 { errln!("usage: chmod [-R] <mode> <file> [file2 ...]"); return 1; }
+        let mode_str = &words[0];
+        if parse_chmod_mode(mode_str, 0, false).is_none() { errln!("chmod: invalid mode: {}", mode_str); return 1; }
         let mut code = 0;
-        for file in &_args[2..] {
+        for file in &words[1..] {
             let path = std::path::Path::new(file);
-            if !path.exists() { eprintln!("chmod: {}: no such file or directory", file); code = 1; continue; }
-            let current = match std::fs::metadata(path) {
-                Ok(m) => m.permissions().mode(),
-                Err(e) => { eprintln!("chmod: {}: {}", file, e); code = 1; continue; }
-            };
-            let new_mode = match parse_chmod_mode(mode_str, current) {
-                Some(m) => m,
-                None => { eprintln!("chmod: invalid mode: {}", mode_str); return 1; }
-            };
-            let perms = std::fs::Permissions::from_mode(new_mode);
-            if let Err(e) = std::fs::set_permissions(path, perms) {
-                eprintln!("chmod: {}: {}", file, e); code = 1;
-            }
+            if !path.exists() { errln!("chmod: {}: no such file or directory", file); code = 1; continue; }
+            let changed = change_tree("chmod", path, recursive, &mut |path, below| {
+                let meta = if below { path.symlink_metadata()? } else { std::fs::metadata(path)? };
+                if meta.file_type().is_symlink() { return Ok(()); }
+                let new_mode = parse_chmod_mode(mode_str, meta.permissions().mode(), meta.is_dir()).unwrap_or_default();
+                std::fs::set_permissions(path, std::fs::Permissions::from_mode(new_mode))
+            });
+            if !changed { code = 1; }
         }
         code
     }
 }
 
+pub fn builtin_chown(args: &[String]) -> i32 {
+    let p = match CHOWN.parse(args) { Ok(p) => p, Err(code) => return code };
+    let [spec, files @ ..] = &p.operands[..] else { errln!("usage: chown [-R] OWNER[:GROUP] FILE..."); return 2 };
+    if files.is_empty() { errln!("usage: chown [-R] OWNER[:GROUP] FILE..."); return 2; }
+
+    #[cfg(windows)]
+    { let _ = spec; errln!("chown: not supported on Windows"); 1 }
+
+    #[cfg(unix)]
+    {
+        let (uid, gid) = match owner_and_group(spec) {
+            Ok(ids) => ids,
+            Err(e) => { errln!("chown: {}", e); return 1; }
+        };
+        let no_dereference = p.has("no-dereference");
+        let mut code = 0;
+        for file in files {
+            let path = Path::new(file);
+            if path.symlink_metadata().is_err() { errln!("chown: {}: no such file or directory", file); code = 1; continue; }
+            let changed = change_tree("chown", path, p.has("recursive"), &mut |path, below| {
+                if below || no_dereference {
+                    std::os::unix::fs::lchown(path, uid, gid)
+                } else {
+                    std::os::unix::fs::chown(path, uid, gid)
+                }
+            });
+            if !changed { code = 1; }
+        }
+        code
+    }
+}
+
+/// The uid and gid in chown's `OWNER[:GROUP]`; None for one left as it is.
+#[cfg(unix)]
+fn owner_and_group(spec: &str) -> Result<(Option<u32>, Option<u32>), String> {
+    let (owner, group) = match spec.split_once(':') {
+        Some((owner, group)) => (owner, Some(group)),
+        None => (spec, None),
+    };
+    let mut login_group = None;
+    let uid = match owner {
+        "" => None,
+        name => match super::util::user_id(name) {
+            Some((uid, gid)) => { login_group = Some(gid); Some(uid) }
+            None => Some(name.parse().map_err(|_| format!("invalid user: '{}'", name))?),
+        },
+    };
+    let gid = match group {
+        None => None,
+        // `OWNER:` is the owner's login group
+        Some("") if owner.is_empty() => None,
+        Some("") => Some(login_group.ok_or_else(|| format!("'{}' has no login group", owner))?),
+        Some(name) => match super::util::group_id(name) {
+            Some(gid) => Some(gid),
+            None => Some(name.parse().map_err(|_| format!("invalid group: '{}'", name))?),
+        },
+    };
+    Ok((uid, gid))
+}
+
+/// Call `change` on `path` and, if `recursive`, everything under it,
+/// reporting any failure; false if there was one. `change` is told
+/// whether the path is below the one given. Symlinks aren't followed into.
+#[cfg(unix)]
+fn change_tree(command: &str, path: &Path, recursive: bool, change: &mut dyn FnMut(&Path, bool) -> std::io::Result<()>) -> bool {
+    fn walk(command: &str, path: &Path, below: bool, recursive: bool, change: &mut dyn FnMut(&Path, bool) -> std::io::Result<()>) -> bool {
+        let mut ok = true;
+        if let Err(e) = change(path, below) {
+            errln!("{}: {}: {}", command, crate::paths::display(path), e);
+            ok = false;
+        }
+        if !recursive || !path.symlink_metadata().is_ok_and(|m| m.is_dir()) { return ok; }
+        let entries = match std::fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => { errln!("{}: {}: {}", command, crate::paths::display(path), e); return false; }
+        };
+        for entry in entries.flatten() {
+            ok &= walk(command, &entry.path(), true, recursive, change);
+        }
+        ok
+    }
+    walk(command, path, false, recursive, change)
+}
+
 #[cfg(unix)]
-fn parse_chmod_mode(mode_str: &str, current: u32) -> Option<u32> {
+fn parse_chmod_mode(mode_str: &str, current: u32, is_dir: bool) -> Option<u32> {
     // Octal e.g. 755
     if mode_str.chars().all(|c| c.is_ascii_digit()) {
         return u32::from_str_radix(mode_str, 8).ok();
@@ -254,7 +657,14 @@ fn parse_chmod_mode(mode_str: &str, current: u32) -> Option<u32> {
         let perms_str = &rest[1..];
         let mut bits: u32 = 0;
         for c in perms_str.chars() {
-            match c { 'r' => bits |= 4, 'w' => bits |= 2, 'x' => bits |= 1, _ => return None }
+            match c {
+                'r' => bits |= 4,
+                'w' => bits |= 2,
+                'x' => bits |= 1,
+                // Execute for directories, and files someone can already run
+                'X' => if is_dir || current & 0o111 != 0 { bits |= 1 },
+                _ => return None,
+            }
         }
         for w in who.chars() {
             let shifts: Vec<u32> = match w {
@@ -275,25 +685,15 @@ fn parse_chmod_mode(mode_str: &str, current: u32) -> Option<u32> {
 }
 
 pub fn builtin_ln(args: &[String]) -> i32 {
-    let mut symbolic = false;
-    let mut force = false;
-    let mut targets = Vec::new();
-
-    for arg in &args[1..] {
-        if arg.starts_with('-') {
-            for ch in arg.chars().skip(1) {
-                match ch { 's' => symbolic = true, 'f' => force = true, _ => {} }
-            }
-        } else { targets.push(arg.clone()); }
-    }
-
-    if targets.len() < 2 { eprintln!("usage: ln [-sf] <target> <link_name>"); return 1; }
+    let p = match LN.parse(args) { Ok(p) => p, Err(code) => return code };
+    let (symbolic, force, targets) = (p.has("symbolic"), p.has("force"), p.operands);
+    if targets.len() < 2 { errln!("usage: ln [-sf] <target> <link_name>"); return 1; }
 
     let target = &targets[0];
     let link = std::path::Path::new(&targets[1]);
 
     if force && link.exists() {
-        if let Err(e) = std::fs::remove_file(link) { eprintln!("ln: {}", e); return 1; }
+        if let Err(e) = std::fs::remove_file(link) { errln!("ln: {}", e); return 1; }
     }
 
     let result = if symbolic {
@@ -309,16 +709,121 @@ pub fn builtin_ln(args: &[String]) -> i32 {
         std::fs::hard_link(target, link)
     };
 
-    match result { Ok(_) => 0, Err(e) => { eprintln!("ln: {}", e); 1 } }
+    match result { Ok(_) => 0, Err(e) => { errln!("ln: {}", e); 1 } }
+}
+
+/// `cp -r`: copies a tree keeping each entry's mode and modification
+/// time. Symlinks are copied as links unless `dereference` (-L), and files
+/// with several names in the tree stay hard-linked in the copy.
+struct TreeCopy {
+    dereference: bool,
+    clobber: Clobber,
+    verbose: bool,
+    progress: Option<Progress>,
+    /// The first copy of each multiply-linked file, by (device, inode)
+    #[cfg_attr(windows, allow(dead_code))]
+    linked: std::collections::HashMap<(u64, u64), PathBuf>,
+    /// With -L, the source directories being copied, to catch links
+    /// that lead back into one of them
+    open: super::util::LoopGuard,
+}
+
+impl TreeCopy {
+    fn copy(&mut self, src: &Path, dest: &Path) -> std::io::Result<()> {
+        let meta = if self.dereference { std::fs::metadata(src)? } else { std::fs::symlink_metadata(src)? };
+
+        if meta.file_type().is_symlink() {
+            return copy_symlink(src, dest);
+        }
+        if meta.is_dir() {
+            if self.dereference {
+                if let Err(first) = self.open.enter(src) {
+                    errln!("cp: {}: skipped, it leads back into {}", crate::paths::display(src), crate::paths::display(&first));
+                    return Ok(());
+                }
+            }
+            let result = self.copy_dir(src, dest, &meta);
+            if self.dereference { self.open.leave(); }
+            result?;
+        } else if let Some(first) = self.first_link(&meta, dest) {
+            return std::fs::hard_link(first, dest);
+        } else if !self.copy_contents(src, dest)? {
+            return Ok(());
+        }
+        filetime::set_file_mtime(dest, filetime::FileTime::from_last_modification_time(&meta))
+    }
+
+    /// Copy a file's contents and mode bits to `dest`, unless it exists and
+    /// -i or -n says to leave it. False if it was left.
+    fn copy_contents(&mut self, src: &Path, dest: &Path) -> std::io::Result<bool> {
+        if dest.symlink_metadata().is_ok() && !self.clobber.allows("cp", dest) { return Ok(false); }
+        match &mut self.progress {
+            Some(progress) => progress.copy(src, dest)?,
+            None => { std::fs::copy(src, dest)?; }
+        }
+        if self.verbose {
+            self.clear_progress();
+            outln!("{} -> {}", crate::paths::display(src), crate::paths::display(dest));
+        }
+        Ok(true)
+    }
+
+    /// Rub out the progress bar, before a message or at the end.
+    fn clear_progress(&self) {
+        if self.progress.is_some() { err!("\r{}\r", " ".repeat(70)); }
+    }
+
+    fn copy_dir(&mut self, src: &Path, dest: &Path, meta: &std::fs::Metadata) -> std::io::Result<()> {
+        if let Err(e) = std::fs::create_dir(dest) {
+            if !dest.is_dir() { return Err(e); }
+        }
+        for entry in std::fs::read_dir(src)? {
+            let entry = entry?;
+            self.copy(&entry.path(), &dest.join(entry.file_name()))?;
+        }
+        // Only now, so a read-only directory can still be filled
+        std::fs::set_permissions(dest, meta.permissions())
+    }
+
+    /// Where an earlier name of this file was copied to, if it has one;
+    /// otherwise `dest` is remembered for the file's other names.
+    #[cfg_attr(windows, allow(unused_variables))]
+    fn first_link(&mut self, meta: &std::fs::Metadata, dest: &Path) -> Option<PathBuf> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            if meta.nlink() > 1 {
+                let key = (meta.dev(), meta.ino());
+                if let Some(first) = self.linked.get(&key) { return Some(first.clone()); }
+                self.linked.insert(key, dest.to_path_buf());
+            }
+        }
+        None
+    }
+}
+
+fn copy_symlink(src: &Path, dest: &Path) -> std::io::Result<()> {
+    let target = std::fs::read_link(src)?;
+    #[cfg(unix)]
+    { std::os::unix::fs::symlink(target, dest) }
+    #[cfg(windows)]
+    {
+        if src.is_dir() { std::os::windows::fs::symlink_dir(target, dest) }
+        else { std::os::windows::fs::symlink_file(target, dest) }
+    }
 }
 
-fn copy_dir_all(src: &std::path::Path, dest: &std::path::Path) -> std::io::Result<()> {
-    std::fs::create_dir_all(dest)?;
-    for entry in std::fs::read_dir(src)? {
-        let entry = entry?;
-        let dest_path = dest.join(entry.file_name());
-        if entry.file_type()?.is_dir() { copy_dir_all(&entry.path(), &dest_path)?; }
-        else { std::fs::copy(entry.path(), dest_path)?; }
+/// `mkdir -m`: set a new directory's mode, octal or symbolic (relative to
+/// rwxrwxrwx, not the umask).
+#[cfg_attr(windows, allow(unused_variables))]
+fn set_mode(dir: &Path, mode: &str) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let bits = parse_chmod_mode(mode, 0o777, true).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("invalid mode: {}", mode))
+        })?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(bits))?;
     }
     Ok(())
 }
\ No newline at end of file
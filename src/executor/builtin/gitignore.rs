@@ -0,0 +1,151 @@
+// src/executor/builtin/gitignore.rs
+// Enough of git's ignore rules for a recursive search to skip what git
+// does: each directory's .gitignore, plus the repository's
+// .git/info/exclude. A pattern with a slash in it (other than at the end)
+// is relative to the directory of its .gitignore, one without matches a
+// name at any depth; a trailing slash only matches directories, `**`
+// spans directories, and `!` takes a file back. The last rule to match
+// wins, the deeper .gitignore over the shallower.
+//
+// Global excludes (core.excludesFile) aren't read.
+
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+
+/// The rules in force at one directory of a walk.
+#[derive(Clone, Default)]
+pub(super) struct GitIgnore {
+    sets: Vec<RuleSet>,
+}
+
+#[derive(Clone)]
+struct RuleSet {
+    /// Where the walk is, as it names the directory: the rules' own for
+    /// a .gitignore met on the walk, or where it started for one above
+    base: PathBuf,
+    /// The way from the rules' directory down to `base`, as `a/b/`
+    prefix: String,
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone)]
+struct Rule {
+    pattern: Pattern,
+    negated: bool,
+    dir_only: bool,
+    /// Matched against the whole relative path rather than the name
+    anchored: bool,
+}
+
+const MATCH: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+impl GitIgnore {
+    /// The rules for a walk starting at `dir`: those of the .gitignore
+    /// files between the top of its repository and `dir` itself. Nothing
+    /// outside a repository.
+    pub(super) fn starting_at(dir: &Path) -> GitIgnore {
+        let real = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+        let Some(root) = real.ancestors().find(|d| d.join(".git").exists()) else { return GitIgnore::default() };
+        let mut ignore = GitIgnore::default();
+        let inside: Vec<&Path> = real.ancestors().take_while(|d| d.starts_with(root)).collect();
+        for (i, d) in inside.iter().rev().enumerate() {
+            let down = real.strip_prefix(d).unwrap_or(Path::new(""));
+            let prefix = if down.as_os_str().is_empty() { String::new() } else { format!("{}/", slashed(down)) };
+            if i == 0 { ignore.add(dir, &prefix, &d.join(".git/info/exclude")); }
+            ignore.add(dir, &prefix, &d.join(".gitignore"));
+        }
+        ignore
+    }
+
+    /// The rules for `dir`, a subdirectory met on the walk: these, and its
+    /// own .gitignore if it has one.
+    pub(super) fn enter(&self, dir: &Path) -> GitIgnore {
+        let mut ignore = self.clone();
+        ignore.add(dir, "", &dir.join(".gitignore"));
+        ignore
+    }
+
+    /// Whether git would ignore `path`. The .git directory always is.
+    pub(super) fn ignores(&self, path: &Path, is_dir: bool) -> bool {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        if name == ".git" { return true; }
+        let mut ignored = false;
+        for set in &self.sets {
+            let Ok(relative) = path.strip_prefix(&set.base) else { continue };
+            let relative = format!("{}{}", set.prefix, slashed(relative));
+            for rule in &set.rules {
+                if rule.dir_only && !is_dir { continue; }
+                let subject = if rule.anchored { relative.as_str() } else { name.as_str() };
+                if rule.pattern.matches_with(subject, MATCH) { ignored = !rule.negated; }
+            }
+        }
+        ignored
+    }
+
+    fn add(&mut self, base: &Path, prefix: &str, file: &Path) {
+        let Ok(text) = std::fs::read_to_string(file) else { return };
+        let rules = parse(&text);
+        if !rules.is_empty() {
+            self.sets.push(RuleSet { base: base.to_path_buf(), prefix: prefix.to_string(), rules });
+        }
+    }
+}
+
+/// A relative path with `/` between its parts, as in .gitignore.
+fn slashed(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn parse(text: &str) -> Vec<Rule> {
+    text.lines().filter_map(|line| {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') { return None; }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        let pattern = Pattern::new(line).ok()?;
+        Some(Rule { pattern, negated, dir_only, anchored })
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignore(rules: &str) -> GitIgnore {
+        GitIgnore { sets: vec![RuleSet { base: PathBuf::from("/repo"), prefix: String::new(), rules: parse(rules) }] }
+    }
+
+    #[test]
+    fn names_paths_and_negation() {
+        let g = ignore("# build output\ntarget/\n*.log\n!keep.log\n/docs/*.html\nsrc/**/gen\n");
+        assert!(g.ignores(Path::new("/repo/target"), true));
+        assert!(g.ignores(Path::new("/repo/sub/target"), true));
+        assert!(!g.ignores(Path::new("/repo/target"), false));
+        assert!(g.ignores(Path::new("/repo/a/b/run.log"), false));
+        assert!(!g.ignores(Path::new("/repo/keep.log"), false));
+        assert!(g.ignores(Path::new("/repo/docs/index.html"), false));
+        assert!(!g.ignores(Path::new("/repo/docs/api/index.html"), false));
+        assert!(!g.ignores(Path::new("/repo/sub/docs/index.html"), false));
+        assert!(g.ignores(Path::new("/repo/src/a/b/gen"), true));
+        assert!(g.ignores(Path::new("/repo/.git"), true));
+        assert!(!g.ignores(Path::new("/repo/main.rs"), false));
+
+        // A search started in /repo/docs, given as `.`
+        let below = GitIgnore { sets: vec![RuleSet { base: PathBuf::from("."), prefix: "docs/".to_string(), rules: parse("/docs/*.html") }] };
+        assert!(below.ignores(Path::new("./index.html"), false));
+        assert!(!below.ignores(Path::new("./api/index.html"), false));
+    }
+}
@@ -1,176 +1,411 @@
 // src/executor/builtin/grep.rs
-// Built-in grep — basic pattern matching in files or stdin
+// Built-in grep — regular expression search in files or stdin
+//
+// PATTERN is a POSIX basic regular expression (`\(` `\)` group, `+` is a
+// literal) as in sed, an extended one with -E, or a plain string with -F.
+// -w only takes matches that are whole words, and -o prints just the
+// matched text, one match to a line.
+//
+// With -A, -B or -C, lines around each match are printed too, marked with
+// `-` where a match has `:`, and groups that don't touch are split by `--`.
+//
+// -r can be narrowed by file name (--include, --exclude, --exclude-dir) and,
+// with --gitignore, skips what git ignores. A binary file only gets a
+// "Binary file ... matches" line, unless -a searches it as text or -I
+// skips it.
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::path::Path;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::glob::matches_pattern;
+use super::gitignore::GitIgnore;
+use super::util::{Opt, Options};
+
+pub const GREP: Options = Options {
+    name: "grep",
+    operands: "PATTERN [FILE]...",
+    about: "Print lines matching PATTERN, from files or standard input.",
+    opts: &[
+        Opt::new("rR", "recursive", "search directories and their contents"),
+        Opt::new("E", "extended-regexp", "PATTERN is an extended regular expression"),
+        Opt::new("G", "basic-regexp", "PATTERN is a basic regular expression (the default)"),
+        Opt::new("F", "fixed-strings", "PATTERN is plain text"),
+        Opt::new("i", "ignore-case", "ignore case"),
+        Opt::new("w", "word-regexp", "only match whole words"),
+        Opt::new("v", "invert-match", "print lines that don't match"),
+        Opt::new("o", "only-matching", "print only the matched parts, each on its own line"),
+        Opt::new("n", "line-number", "show line numbers"),
+        Opt::new("c", "count", "only count matching lines"),
+        Opt::new("m", "max-count", "stop after NUM matching lines").takes("NUM"),
+        Opt::new("A", "after-context", "also print NUM lines after each match").takes("NUM"),
+        Opt::new("B", "before-context", "also print NUM lines before each match").takes("NUM"),
+        Opt::new("C", "context", "NUM lines before and after (or -NUM)").takes("NUM"),
+        Opt::new("l", "files-with-matches", "only print the names of files with a match"),
+        Opt::new("L", "files-without-match", "only print the names of files without one"),
+        Opt::new("H", "with-filename", "print the file name with each match"),
+        Opt::new("h", "no-filename", "never print file names"),
+        Opt::new("", "include", "with -r, only search files whose name matches GLOB").takes("GLOB"),
+        Opt::new("", "exclude", "skip files whose name matches GLOB").takes("GLOB"),
+        Opt::new("", "exclude-dir", "with -r, skip directories whose name matches GLOB").takes("GLOB"),
+        Opt::new("", "gitignore", "with -r, skip .git and what .gitignore files ignore"),
+        Opt::new("I", "", "skip binary files"),
+        Opt::new("a", "text", "search binary files as if they were text"),
+    ],
+    numeric: Some("context"),
+};
+
+/// How PATTERN is read: the last of -G, -E and -F given wins.
+#[derive(Clone, Copy, PartialEq)]
+enum Syntax { Basic, Extended, Fixed }
 
 pub fn builtin_grep(args: &[String]) -> i32 {
     if args.len() < 2 {
-        eprintln!("usage: grep [-rnivc] <pattern> [file ...]");
+        errln!("usage: grep [-rnivcEFwolLhH] [-A|-B|-C NUM] [-m NUM] <pattern> [file ...]");
         return 1;
     }
 
-    let mut recursive  = false;
-    let mut ignore_case = false;
-    let mut invert     = false;
-    let mut line_nums  = false;
-    let mut count_only = false;
-    let mut pattern_set = false;
-    let mut pattern    = String::new();
-    let mut files      = Vec::new();
-
-    for arg in &args[1..] {
-        if arg.starts_with('-') && !pattern_set {
-            for ch in arg.chars().skip(1) {
-                match ch {
-                    'r' | 'R' => recursive   = true,
-                    'i'       => ignore_case = true,
-                    'v'       => invert      = true,
-                    'n'       => line_nums   = true,
-                    'c'       => count_only  = true,
-                    _         => {}
-                }
-            }
-        } else if !pattern_set {
-            pattern = arg.clone();
-            pattern_set = true;
-        } else {
-            files.push(arg.clone());
-        }
-    }
-
-    if !pattern_set {
-        eprintln!("grep: missing pattern");
+    let p = match GREP.parse(args) { Ok(p) => p, Err(code) => return code };
+    let syntax = p.given.iter().rev().find_map(|(key, _)| match *key {
+        "basic-regexp"    => Some(Syntax::Basic),
+        "extended-regexp" => Some(Syntax::Extended),
+        "fixed-strings"   => Some(Syntax::Fixed),
+        _ => None,
+    }).unwrap_or(Syntax::Basic);
+    let recursive = p.has("recursive");
+    let Some((pattern, files)) = p.operands.split_first() else {
+        errln!("grep: missing pattern");
         return 1;
-    }
+    };
+    let re = match compile(pattern, syntax, p.has("ignore-case")) {
+        Ok(re) => re,
+        Err(e) => { errln!("grep: {}", e); return 2; }
+    };
+    let mut files = files.to_vec();
 
-    let search_pat = if ignore_case { pattern.to_lowercase() } else { pattern.clone() };
+    // No files — search stdin
+    if files.is_empty() { files.push("-"); }
 
-    if files.is_empty() {
-        // No files — would need stdin, just print usage hint for now
-        eprintln!("grep: no files specified (stdin not yet supported)");
-        return 1;
+    let mut numbers = [None; 4];
+    for (slot, key) in numbers.iter_mut().zip(["max-count", "after-context", "before-context", "context"]) {
+        match p.value(key).map(str::parse::<usize>) {
+            Some(Ok(n)) => *slot = Some(n),
+            Some(Err(_)) => { errln!("grep: invalid {} '{}'", key, p.value(key).unwrap_or_default()); return 2; }
+            None => {}
+        }
     }
+    let [max_count, after, before, context] = numbers;
+    let show_names = p.given.iter().rev().find_map(|(key, _)| match *key {
+        "with-filename" => Some(true),
+        "no-filename"   => Some(false),
+        _ => None,
+    }).unwrap_or(files.len() > 1 || recursive);
 
-    let mut total_matches = 0i32;
-    let multiple_files = files.len() > 1 || recursive;
+    // --include and the like can be given more than once
+    let values = |key: &str| -> Vec<&str> {
+        p.given.iter().filter(|(k, _)| *k == key).filter_map(|(_, v)| *v).collect()
+    };
+    let grep = Grep {
+        re,
+        words: p.has("word-regexp"),
+        invert: p.has("invert-match"),
+        only_matching: p.has("only-matching"),
+        line_nums: p.has("line-number"),
+        count_only: p.has("count"),
+        include: values("include"),
+        exclude: values("exclude"),
+        exclude_dirs: values("exclude-dir"),
+        skip_binary: p.has("I"),
+        text: p.has("text"),
+        max_count,
+        after: after.or(context).unwrap_or(0),
+        before: before.or(context).unwrap_or(0),
+        list_files: p.has("files-with-matches"),
+        list_others: p.has("files-without-match"),
+        show_names,
+        separated: Cell::new(false),
+        // Colour is for people; a pipe or file gets the plain text
+        colour: super::io::stdout_is_terminal(),
+    };
 
+    let mut total_matches = 0;
     for file in &files {
-        let path = std::path::Path::new(file);
+        let path = Path::new(file);
         if path.is_dir() {
             if recursive {
-                total_matches += grep_dir(path, &search_pat, &pattern,
-                    ignore_case, invert, line_nums, count_only, multiple_files);
+                let ignore = p.has("gitignore").then(|| GitIgnore::starting_at(path));
+                total_matches += grep.dir(path, ignore.as_ref());
             } else {
-                eprintln!("grep: {}: is a directory (use -r)", file);
+                errln!("grep: {}: is a directory (use -r)", file);
             }
-        } else {
-            total_matches += grep_file(path, file, &search_pat,
-                ignore_case, invert, line_nums, count_only, multiple_files);
+        } else if *file == "-" || grep.wanted(&path.file_name().unwrap_or_default().to_string_lossy()) {
+            total_matches += grep.file(path, file);
         }
     }
 
     if total_matches > 0 { 0 } else { 1 }
 }
 
-fn grep_dir(
-    dir: &std::path::Path,
-    search_pat: &str,
-    original_pat: &str,
-    ignore_case: bool,
+/// PATTERN in the regex crate's syntax.
+fn compile(pattern: &str, syntax: Syntax, ignore_case: bool) -> Result<Regex, String> {
+    let pattern = match syntax {
+        Syntax::Basic    => super::sed::basic_to_extended(pattern),
+        Syntax::Extended => pattern.to_string(),
+        Syntax::Fixed    => regex::escape(pattern),
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| format!("invalid pattern: {}", e))
+}
+
+/// One search: the pattern and how matches are shown.
+struct Grep<'a> {
+    re: Regex,
+    words: bool,
     invert: bool,
+    only_matching: bool,
     line_nums: bool,
     count_only: bool,
-    _multiple_files: bool,
-) -> i32 {
-    let mut total = 0;
-    let Ok(entries) = std::fs::read_dir(dir) else { return 0 };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let name = path.display().to_string();
-        if path.is_dir() {
-            total += grep_dir(&path, search_pat, original_pat,
-                ignore_case, invert, line_nums, count_only, true);
-        } else {
-            total += grep_file(&path, &name, search_pat,
-                ignore_case, invert, line_nums, count_only, true);
+    include: Vec<&'a str>,
+    exclude: Vec<&'a str>,
+    exclude_dirs: Vec<&'a str>,
+    /// -I
+    skip_binary: bool,
+    text: bool,
+    max_count: Option<usize>,
+    after: usize,
+    before: usize,
+    /// -l and -L
+    list_files: bool,
+    list_others: bool,
+    show_names: bool,
+    /// Whether a group of context lines has been printed, so the next one
+    /// needs a `--` before it
+    separated: Cell<bool>,
+    colour: bool,
+}
+
+impl Grep<'_> {
+    /// Search a directory's tree, with the .gitignore rules in force in
+    /// it if --gitignore was given.
+    fn dir(&self, dir: &Path, ignore: Option<&GitIgnore>) -> usize {
+        let mut total = 0;
+        let Ok(entries) = std::fs::read_dir(dir) else { return 0 };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            // Like GNU grep -r, links met on the way are skipped, which also
+            // keeps a link back up the tree from looping
+            let Ok(kind) = entry.file_type() else { continue };
+            if kind.is_symlink() { continue; }
+            if ignore.is_some_and(|ignore| ignore.ignores(&path, kind.is_dir())) { continue; }
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if kind.is_dir() {
+                if self.exclude_dirs.iter().any(|glob| matches_pattern(&name, glob)) { continue; }
+                total += self.dir(&path, ignore.map(|ignore| ignore.enter(&path)).as_ref());
+            } else if self.wanted(&name) {
+                total += self.file(&path, &crate::paths::display(&path));
+            }
         }
+        total
     }
-    total
-}
 
-fn grep_file(
-    path: &std::path::Path,
-    display_name: &str,
-    search_pat: &str,
-    ignore_case: bool,
-    invert: bool,
-    line_nums: bool,
-    count_only: bool,
-    show_filename: bool,
-) -> i32 {
-    let content = match std::fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(_) => return 0,
-    };
+    /// Whether --include and --exclude let a file with this name be searched.
+    fn wanted(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|glob| matches_pattern(name, glob)))
+            && !self.exclude.iter().any(|glob| matches_pattern(name, glob))
+    }
 
-    let mut match_count = 0;
+    /// Search one file. Returns its hits for the exit status: the lines
+    /// that matched or, with -L, 1 if it was listed for having none.
+    fn file(&self, path: &Path, display_name: &str) -> usize {
+        // Lines are matched as they arrive, so grep can follow a stream
+        let mut input = match super::io::open_input(&path.to_string_lossy()) {
+            Ok(r) => r,
+            Err(_) => return 0,
+        };
+        // Binary is only searched for whether it matches, unless -a
+        let binary = !self.text && input.fill_buf().is_ok_and(super::fs::looks_binary);
+        if binary && self.skip_binary { return 0; }
 
-    for (i, line) in content.lines().enumerate() {
-        let compare = if ignore_case { line.to_lowercase() } else { line.to_string() };
-        let matched = compare.contains(search_pat);
-        let show = if invert { !matched } else { matched };
+        let mut match_count = 0;
+        let (name_on, num_on, sep_on, off) = if self.colour {
+            ("\x1b[35m", "\x1b[32m", "\x1b[36m", "\x1b[0m")
+        } else {
+            ("", "", "", "")
+        };
+        // A context line's prefix ends in `-` where a match's ends in `:`
+        let prefix = |i: usize, mark: char| {
+            let mut prefix = String::new();
+            if self.show_names { prefix.push_str(&format!("{}{}{}{}{}{}", name_on, display_name, off, sep_on, mark, off)); }
+            if self.line_nums { prefix.push_str(&format!("{}{}{}{}{}{}", num_on, i + 1, off, sep_on, mark, off)); }
+            prefix
+        };
+        let mut before: VecDeque<(usize, String)> = VecDeque::with_capacity(self.before);
+        let mut after_left = 0;
+        // The last line printed, to tell when a `--` goes between groups
+        let mut last_printed: Option<usize> = None;
+        let listing = self.list_files || self.list_others;
 
-        if show {
+        // Lines that aren't UTF-8 are still searched, as near as can be
+        let lines = input.split(b'\n').map_while(Result::ok).map(|mut line| {
+            if line.last() == Some(&b'\r') { line.pop(); }
+            String::from_utf8(line).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+        });
+        for (i, line) in lines.enumerate() {
+            let more = self.max_count.is_none_or(|max| match_count < max);
+            if !more && after_left == 0 { break; }
+            let (matched, spans) = self.matches(&line);
+            let selected = more && matched != self.invert;
+            if !selected {
+                if after_left > 0 {
+                    after_left -= 1;
+                    outln!("{}{}", prefix(i, '-'), line);
+                    last_printed = Some(i);
+                } else if self.before > 0 {
+                    if before.len() == self.before { before.pop_front(); }
+                    before.push_back((i, line));
+                }
+                continue;
+            }
             match_count += 1;
-            if !count_only {
-                // Highlight the match in the line
-                let highlighted = highlight_match(line, search_pat, ignore_case);
-                if show_filename && line_nums {
-                    println!("\x1b[35m{}\x1b[0m:\x1b[32m{}\x1b[0m:{}", display_name, i + 1, highlighted);
-                } else if show_filename {
-                    println!("\x1b[35m{}\x1b[0m:{}", display_name, highlighted);
-                } else if line_nums {
-                    println!("\x1b[32m{}\x1b[0m:{}", i + 1, highlighted);
-                } else {
-                    println!("{}", highlighted);
+            if listing { break; }
+            if self.count_only { continue; }
+            if binary {
+                outln!("Binary file {} matches", display_name);
+                break;
+            }
+
+            if self.before > 0 || self.after > 0 {
+                let first = before.front().map_or(i, |(n, _)| *n);
+                let adjacent = last_printed.is_some_and(|last| last + 1 >= first);
+                if self.separated.get() && !adjacent { outln!("{}--{}", sep_on, off); }
+                self.separated.set(true);
+            }
+            for (n, context) in before.drain(..) { outln!("{}{}", prefix(n, '-'), context); }
+            if self.only_matching {
+                // An inverted match has no matched text to show
+                for &(start, end) in &spans {
+                    outln!("{}{}", prefix(i, ':'), self.paint(&line[start..end]));
                 }
+            } else {
+                outln!("{}{}", prefix(i, ':'), self.highlight(&line, &spans));
+            }
+            last_printed = Some(i);
+            after_left = self.after;
+        }
+
+        if self.list_files {
+            if match_count > 0 { outln!("{}{}{}", name_on, display_name, off); }
+        } else if self.list_others {
+            if match_count > 0 { return 0; }
+            outln!("{}{}{}", name_on, display_name, off);
+            return 1;
+        } else if self.count_only {
+            if self.show_names {
+                outln!("{}:{}", display_name, match_count);
+            } else {
+                outln!("{}", match_count);
             }
         }
+
+        match_count
     }
 
-    if count_only {
-        if show_filename {
-            println!("{}:{}", display_name, match_count);
-        } else {
-            println!("{}", match_count);
+    /// Whether the pattern matches `line`, and where, leaving out empty
+    /// matches. With -w, matches with a letter, digit or underscore beside
+    /// them don't count.
+    fn matches(&self, line: &str) -> (bool, Vec<(usize, usize)>) {
+        let mut matched = false;
+        let mut spans = Vec::new();
+        let mut at = 0;
+        while let Some(m) = self.re.find_at(line, at) {
+            let next = m.start() + line[m.start()..].chars().next().map_or(1, char::len_utf8);
+            if self.words && !is_whole_word(line, m.start(), m.end()) {
+                at = next;
+            } else {
+                matched = true;
+                if m.is_empty() { at = next; } else { spans.push((m.start(), m.end())); at = m.end(); }
+            }
+            if at > line.len() { break; }
+        }
+        (matched, spans)
+    }
+
+    /// The line with each match in bold red.
+    fn highlight(&self, line: &str, spans: &[(usize, usize)]) -> String {
+        if !self.colour || self.invert { return line.to_string(); }
+        let mut result = String::new();
+        let mut last = 0;
+        for &(start, end) in spans {
+            result.push_str(&line[last..start]);
+            result.push_str(&self.paint(&line[start..end]));
+            last = end;
         }
+        result.push_str(&line[last..]);
+        result
     }
 
-    match_count
+    fn paint(&self, text: &str) -> String {
+        if self.colour && !text.is_empty() { format!("\x1b[31m\x1b[1m{}\x1b[0m", text) } else { text.to_string() }
+    }
 }
 
-/// Highlight matching text in red within a line
-fn highlight_match(line: &str, pattern: &str, ignore_case: bool) -> String {
-    let compare = if ignore_case { line.to_lowercase() } else { line.to_string() };
-    let pat = if ignore_case { pattern.to_lowercase() } else { pattern.to_string() };
+/// Whether `line[start..end]` has no word character just before or after it.
+fn is_whole_word(line: &str, start: usize, end: usize) -> bool {
+    let word = |c: char| c.is_alphanumeric() || c == '_';
+    !line[..start].chars().next_back().is_some_and(word) && !line[end..].chars().next().is_some_and(word)
+}
 
-    let mut result = String::new();
-    let mut rest = line;
-    let mut compare_rest = compare.as_str();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    loop {
-        match compare_rest.find(&pat) {
-            None => {
-                result.push_str(rest);
-                break;
-            }
-            Some(pos) => {
-                result.push_str(&rest[..pos]);
-                result.push_str("\x1b[31m\x1b[1m");
-                result.push_str(&rest[pos..pos + pat.len()]);
-                result.push_str("\x1b[0m");
-                rest = &rest[pos + pat.len()..];
-                compare_rest = &compare_rest[pos + pat.len()..];
-            }
+    fn grep(pattern: &str, syntax: Syntax, words: bool) -> Grep<'static> {
+        Grep {
+            re: compile(pattern, syntax, false).unwrap(),
+            words,
+            invert: false,
+            only_matching: true,
+            line_nums: false,
+            count_only: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            exclude_dirs: Vec::new(),
+            skip_binary: false,
+            text: false,
+            max_count: None,
+            after: 0,
+            before: 0,
+            list_files: false,
+            list_others: false,
+            show_names: false,
+            separated: Cell::new(false),
+            colour: false,
         }
     }
-    result
-}
\ No newline at end of file
+
+    fn found<'a>(g: &Grep, line: &'a str) -> Vec<&'a str> {
+        g.matches(line).1.into_iter().map(|(s, e)| &line[s..e]).collect()
+    }
+
+    #[test]
+    fn pattern_syntaxes() {
+        assert_eq!(found(&grep("a\\+b", Syntax::Basic, false), "a+b aab"), vec!["aab"]);
+        assert_eq!(found(&grep("a+b", Syntax::Basic, false), "a+b aab"), vec!["a+b"]);
+        assert_eq!(found(&grep("a+b", Syntax::Extended, false), "a+b aab"), vec!["aab"]);
+        assert_eq!(found(&grep("a.b", Syntax::Fixed, false), "axb a.b"), vec!["a.b"]);
+    }
+
+    #[test]
+    fn whole_words() {
+        let g = grep("cat", Syntax::Basic, true);
+        assert_eq!(found(&g, "concat cat cats _cat"), vec!["cat"]);
+        assert!(!g.matches("concatenate").0);
+        assert!(grep("x*", Syntax::Basic, false).matches("abc").0);
+        assert_eq!(found(&grep("-v", Syntax::Fixed, true), "grep -v x"), vec!["-v"]);
+    }
+}
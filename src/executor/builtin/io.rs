@@ -0,0 +1,199 @@
+// src/executor/builtin/io.rs
+// Standard streams for builtins.
+//
+// Builtins never touch the process's standard streams directly: output
+// goes through `out!` / `outln!`, errors through `err!` / `errln!` and
+// input through `io::stdin()`, which use the streams installed for the
+// current thread. By default those are the process's own. A pipeline stage
+// installs the ends of its OS pipes instead, so several builtins can run
+// at once on their own threads, each streaming into the next stage; a
+// redirection installs the file.
+//
+// Pipe ends and files are both held as `File`s, which can also be handed
+// to child processes as their stdio.
+
+use std::cell::RefCell;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Write};
+
+#[derive(Default)]
+struct Streams {
+    stdin:  Option<File>,
+    stdout: Option<BufWriter<File>>,
+    /// Unbuffered, as errors should show at once
+    stderr: Option<File>,
+}
+
+thread_local! {
+    static STREAMS: RefCell<Streams> = RefCell::default();
+}
+
+/// Run `f` with this thread's stdin, stdout and/or stderr replaced. `None`
+/// keeps the current stream. The output buffer is flushed before
+/// returning, so dropping the writer afterwards signals end-of-file to the
+/// reader.
+pub fn with_streams<T>(
+    stdin: Option<File>,
+    stdout: Option<File>,
+    stderr: Option<File>,
+    f: impl FnOnce() -> T,
+) -> T {
+    let (old_in, old_out, old_err) = STREAMS.with(|s| {
+        let mut s = s.borrow_mut();
+        let old_in  = stdin.map(|r| s.stdin.replace(r));
+        let old_out = stdout.map(|w| s.stdout.replace(BufWriter::new(w)));
+        let old_err = stderr.map(|w| s.stderr.replace(w));
+        (old_in, old_out, old_err)
+    });
+
+    let result = f();
+
+    STREAMS.with(|s| {
+        let mut s = s.borrow_mut();
+        if let Some(old) = old_in { s.stdin = old; }
+        if let Some(old) = old_out {
+            if let Some(mut w) = std::mem::replace(&mut s.stdout, old) { w.flush().ok(); }
+        }
+        if let Some(old) = old_err { s.stderr = old; }
+    });
+    result
+}
+
+/// A copy of this thread's stdin if it has been replaced, for handing to a
+/// child process or another pipeline stage. None means the process stdin.
+pub fn current_stdin() -> Option<File> {
+    STREAMS.with(|s| s.borrow().stdin.as_ref().and_then(|r| r.try_clone().ok()))
+}
+
+/// Like `current_stdin`, for stdout. Pending output is flushed first so it
+/// stays ahead of whatever the new holder writes.
+pub fn current_stdout() -> Option<File> {
+    STREAMS.with(|s| {
+        let mut s = s.borrow_mut();
+        let w = s.stdout.as_mut()?;
+        w.flush().ok();
+        w.get_ref().try_clone().ok()
+    })
+}
+
+/// Like `current_stdin`, for stderr.
+pub fn current_stderr() -> Option<File> {
+    STREAMS.with(|s| s.borrow().stderr.as_ref().and_then(|w| w.try_clone().ok()))
+}
+
+/// Where this thread's stdout goes, as a file of its own: what `2>&1`
+/// sends stderr to.
+pub fn stdout_file() -> io::Result<File> {
+    if let Some(file) = current_stdout() { return Ok(file); }
+    #[cfg(unix)]
+    { use std::os::fd::AsFd; io::stdout().as_fd().try_clone_to_owned().map(File::from) }
+    #[cfg(windows)]
+    { use std::os::windows::io::AsHandle; io::stdout().as_handle().try_clone_to_owned().map(File::from) }
+}
+
+/// An OS pipe as (read end, write end).
+pub fn pipe() -> io::Result<(File, File)> {
+    let (r, w) = io::pipe()?;
+    #[cfg(unix)]
+    return Ok((File::from(std::os::fd::OwnedFd::from(r)), File::from(std::os::fd::OwnedFd::from(w))));
+    #[cfg(windows)]
+    {
+        use std::os::windows::io::OwnedHandle;
+        Ok((File::from(OwnedHandle::from(r)), File::from(OwnedHandle::from(w))))
+    }
+}
+
+/// Whether builtin output is going straight to a terminal (and so may be
+/// coloured, paged or laid out for a human).
+pub fn stdout_is_terminal() -> bool {
+    STREAMS.with(|s| s.borrow().stdout.is_none()) && io::stdout().is_terminal()
+}
+
+pub struct Stdin;
+
+pub fn stdin() -> Stdin { Stdin }
+
+impl Read for Stdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        STREAMS.with(|s| match s.borrow_mut().stdin.as_mut() {
+            Some(r) => r.read(buf),
+            None    => io::stdin().read(buf),
+        })
+    }
+}
+
+/// One line from stdin, without its newline; empty at the end of input.
+/// Read a byte at a time, so what follows the line is left for whoever
+/// reads next.
+pub fn read_line() -> io::Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8];
+    while stdin().read(&mut byte)? == 1 && byte[0] != b'\n' {
+        line.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+pub struct Stdout;
+
+pub fn stdout() -> Stdout { Stdout }
+
+impl Write for Stdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        STREAMS.with(|s| match s.borrow_mut().stdout.as_mut() {
+            Some(w) => w.write(buf),
+            None    => io::stdout().write(buf),
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        STREAMS.with(|s| match s.borrow_mut().stdout.as_mut() {
+            Some(w) => w.flush(),
+            None    => io::stdout().flush(),
+        })
+    }
+}
+
+pub struct Stderr;
+
+pub fn stderr() -> Stderr { Stderr }
+
+impl Write for Stderr {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Output written before the error comes out before it
+        stdout().flush().ok();
+        STREAMS.with(|s| match s.borrow_mut().stderr.as_mut() {
+            Some(w) => w.write(buf),
+            None    => io::stderr().write(buf),
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        STREAMS.with(|s| match s.borrow_mut().stderr.as_mut() {
+            Some(w) => w.flush(),
+            None    => io::stderr().flush(),
+        })
+    }
+}
+
+/// Open an input file for reading line by line, where `-` means stdin.
+/// Unlike `read_to_string` this doesn't wait for the end of the input, so
+/// `head` can stop early and `grep` can follow a stream as it arrives.
+pub fn open_input(path: &str) -> io::Result<Box<dyn BufRead>> {
+    if path == "-" {
+        Ok(Box::new(BufReader::new(stdin())))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// Read a whole input file, where `-` means stdin.
+pub fn read_to_string(path: &str) -> io::Result<String> {
+    if path == "-" {
+        let mut s = String::new();
+        stdin().read_to_string(&mut s)?;
+        Ok(s)
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
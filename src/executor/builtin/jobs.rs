@@ -1,77 +1,272 @@
 // src/executor/builtin/jobs.rs
+use super::status;
+use crate::executor::jobctl;
 use crate::shell::{Shell, JobStatus};
+use super::util::{Opt, Options};
+
+pub const DISOWN: Options = Options {
+    name: "disown",
+    operands: "[%JOB]...",
+    about: "Forget jobs, so closing the terminal doesn't hang them up.",
+    opts: &[
+        Opt::new("h", "", "keep the jobs listed, but spare them the hangup"),
+        Opt::new("a", "all", "every job"),
+    ],
+    numeric: None,
+};
 
 pub fn builtin_jobs(shell: &mut Shell) -> i32 {
     shell.reap_jobs();
-    if shell.jobs.is_empty() { println!("No jobs"); return 0; }
+    if shell.jobs.is_empty() { outln!("No jobs"); return 0; }
+    let recent = by_recency(shell);
     let mut job_list: Vec<_> = shell.jobs.values().collect();
     job_list.sort_by_key(|j| j.id);
     for job in job_list {
-        let marker = if job.status == JobStatus::Running { "+" } else { "-" };
-        println!("[{}] {} {:10} {}", job.id, marker, job.status.to_string(), job.command);
+        // `%+` and `%-`, as fg and bg would pick them
+        let marker = match recent.iter().position(|id| *id == job.id) {
+            Some(0) => "+",
+            Some(1) => "-",
+            _       => " ",
+        };
+        outln!("[{}] {} {:10} {}", job.id, marker, job.status.to_string(), job.command);
     }
     0
 }
 
 pub fn builtin_fg(shell: &mut Shell, args: &[String]) -> i32 {
-    let job_id = get_job_id(shell, args);
-    let (_pid, command) = match job_id.and_then(|id| shell.jobs.get(&id)) {
-        Some(job) => (job.pid, job.command.clone()),
-        None => { eprintln!("fg: no such job"); return 1; }
+    let job = match get_job_id(shell, args) {
+        Ok(id) => shell.jobs.remove(&id).unwrap(),
+        Err(e) => { errln!("fg: {}", e); return 1; }
     };
-    println!("{}", command);
+    outln!("{}", job.command);
+    if let JobStatus::Done(code) = job.status { return code; }
+
+    // Hand the job the terminal, then wake it up if it was stopped
+    jobctl::give_terminal(job.pid);
     #[cfg(unix)]
-    {
-        unsafe { libc::kill(_pid as i32, libc::SIGCONT); }
-        let mut status = 0i32;
-        unsafe { libc::waitpid(_pid as i32, &mut status, 0); }
-        if let Some(id) = job_id { shell.jobs.remove(&id); }
-        if libc::WIFEXITED(status) { libc::WEXITSTATUS(status) } else { 1 }
+    jobctl::signal_job(job.pid, libc::SIGCONT).ok();
+    let code = job.wait();
+    jobctl::take_terminal();
+
+    match code {
+        Some(code) => code,
+        None => {
+            let id = job.id;
+            shell.jobs.insert(id, job);
+            shell.stop_job(id)
+        }
     }
-    #[cfg(windows)]
-    { eprintln!("fg: job control not fully supported on Windows"); 1 }
 }
 
 pub fn builtin_bg(shell: &mut Shell, args: &[String]) -> i32 {
-    let job_id = get_job_id(shell, args);
-    let (_pid, command) = match job_id.and_then(|id| shell.jobs.get_mut(&id)) {
-        Some(job) => { job.status = JobStatus::Running; (job.pid, job.command.clone()) }
-        None => { eprintln!("bg: no such job"); return 1; }
+    let job = match get_job_id(shell, args) {
+        Ok(id) => shell.jobs.get_mut(&id).unwrap(),
+        Err(e) => { errln!("bg: {}", e); return 1; }
     };
+    job.status = JobStatus::Running;
     #[cfg(unix)]
-    unsafe { libc::kill(_pid as i32, libc::SIGCONT); }
-    println!("[{}] {}", job_id.unwrap_or(0), command);
+    jobctl::signal_job(job.pid, libc::SIGCONT).ok();
+    outln!("[{}] {}", job.id, job.command);
+    // A job resumed in the background counts as the latest one started
+    shell.last_bg_pid = Some(job.pid);
     0
 }
 
-pub fn builtin_kill(shell: &mut Shell, args: &[String]) -> i32 {
-    if args.len() < 2 { eprintln!("usage: kill [%jobid | pid]"); return 1; }
-    let target = &args[1];
-    if target.starts_with('%') {
-        let id: usize = match target[1..].parse() {
-            Ok(n) => n,
-            Err(_) => { eprintln!("kill: invalid job id"); return 1; }
-        };
-        if let Some(_job) = shell.jobs.get(&id) {
-            #[cfg(unix)] unsafe { libc::kill(_job.pid as i32, libc::SIGTERM); }
-            #[cfg(windows)] eprintln!("kill: not fully supported on Windows");
+/// disown [-h] [-a] [%JOB...] — forget jobs, so closing the terminal
+/// doesn't hang them up; with -h they stay listed but are spared the hangup.
+pub fn builtin_disown(shell: &mut Shell, args: &[String]) -> i32 {
+    let p = match DISOWN.parse(args) { Ok(p) => p, Err(code) => return code };
+    let (mark_only, all, specs) = (p.has("h"), p.has("all"), p.operands);
+
+    let mut ids = Vec::new();
+    let mut code = 0;
+    if all {
+        ids.extend(shell.jobs.keys().copied());
+    } else if specs.is_empty() {
+        match by_recency(shell).first() {
+            Some(id) => ids.push(*id),
+            None => { errln!("disown: no current job"); return 1; }
+        }
+    }
+    for spec in specs {
+        match find_job(shell, spec) {
+            Ok(id) => ids.push(id),
+            Err(e) => { errln!("disown: {}", e); code = 1; }
+        }
+    }
+
+    for id in ids {
+        if mark_only {
+            if let Some(job) = shell.jobs.get_mut(&id) { job.nohup = true; }
+        } else {
             shell.jobs.remove(&id);
-        } else { eprintln!("kill: no such job: {}", id); return 1; }
-    } else {
-        let pid: i32 = match target.parse() {
-            Ok(n) => n,
-            Err(_) => { eprintln!("kill: invalid pid"); return 1; }
+        }
+    }
+    shell.update_hangup();
+    code
+}
+
+/// kill [-s SIG | -SIG] %JOB|PID... — send a signal, TERM by default.
+/// kill -l [STATUS...] — list signals, or name the signal behind a status.
+pub fn builtin_kill(shell: &mut Shell, args: &[String]) -> i32 {
+    const USAGE: &str = "usage: kill [-s SIG | -SIG] [--] %job|pid... | kill -l [STATUS...]";
+    let mut rest = &args[1..];
+    let signal = match rest.first().map(String::as_str) {
+        Some("--") => None,
+        Some("-l") | Some("-L") => return list_signals(&rest[1..]),
+        Some("-s") => {
+            let Some(sig) = rest.get(1) else { errln!("{}", USAGE); return 1 };
+            rest = &rest[2..];
+            Some(sig.as_str())
+        }
+        Some(s) if s.len() > 1 && s.starts_with('-') => {
+            rest = &rest[1..];
+            Some(&s[1..])
+        }
+        _ => None,
+    };
+    // `--` ends the options, so `kill -- -5` signals process group 5
+    if rest.first().is_some_and(|a| a == "--") { rest = &rest[1..]; }
+    let sig = match signal {
+        None => TERM,
+        Some(s) => match status::parse_signal(s) {
+            Some(sig) => sig,
+            None => { errln!("kill: {}: invalid signal", s); return 1; }
+        },
+    };
+    if rest.is_empty() { errln!("{}", USAGE); return 1; }
+
+    let mut result = 0;
+    for target in rest {
+        let sent = if target.starts_with('%') {
+            kill_job(shell, target, sig)
+        } else {
+            kill_pid(target, sig)
         };
-        #[cfg(unix)] unsafe { libc::kill(pid, libc::SIGTERM); }
-        #[cfg(windows)] { std::process::Command::new("taskkill").args(["/PID", &pid.to_string(), "/F"]).output().ok(); }
+        if !sent { result = 1; }
+    }
+    result
+}
+
+#[cfg(unix)]
+const TERM: i32 = libc::SIGTERM;
+#[cfg(windows)]
+const TERM: i32 = 15;
+
+#[cfg_attr(windows, allow(unused_variables))]
+fn kill_job(shell: &mut Shell, spec: &str, sig: i32) -> bool {
+    let job = match find_job(shell, spec) {
+        Ok(id) => shell.jobs.get_mut(&id).unwrap(),
+        Err(e) => { errln!("kill: {}", e); return false; }
+    };
+    #[cfg(unix)]
+    {
+        if let Err(e) = jobctl::signal_job(job.pid, sig) {
+            errln!("kill: {}: {}", spec, e);
+            return false;
+        }
+        match sig {
+            libc::SIGSTOP | libc::SIGTSTP | libc::SIGTTIN | libc::SIGTTOU => job.status = JobStatus::Stopped,
+            libc::SIGCONT => job.status = JobStatus::Running,
+            0 => {}
+            // A stopped job only sees the signal once it runs again
+            _ if job.status == JobStatus::Stopped => {
+                jobctl::signal_job(job.pid, libc::SIGCONT).ok();
+                job.status = JobStatus::Running;
+            }
+            _ => {}
+        }
+    }
+    #[cfg(windows)]
+    if let Ok(mut child) = job.child.lock() {
+        child.kill().ok();
+    }
+    true
+}
+
+#[cfg_attr(windows, allow(unused_variables))]
+fn kill_pid(target: &str, sig: i32) -> bool {
+    let Ok(pid) = target.parse::<i32>() else {
+        errln!("kill: {}: invalid pid", target);
+        return false;
+    };
+    #[cfg(unix)]
+    if unsafe { libc::kill(pid, sig) } != 0 {
+        errln!("kill: ({}) - {}", pid, std::io::Error::last_os_error());
+        return false;
+    }
+    // Windows has no signals: any of them ends the process
+    #[cfg(windows)]
+    { std::process::Command::new("taskkill").args(["/PID", &pid.to_string(), "/F"]).output().ok(); }
+    true
+}
+
+/// kill -l: every signal, or the signal name for each status or number.
+fn list_signals(args: &[String]) -> i32 {
+    if args.is_empty() {
+        for (num, name, _) in status::SIGNALS {
+            outln!("{:2}) {}", num, name);
+        }
+        return 0;
+    }
+    let mut result = 0;
+    for arg in args {
+        // A status of 128 + N means signal N
+        let name = arg.parse::<i32>().ok()
+            .map(|n| if n > 128 { n - 128 } else { n })
+            .and_then(status::signal_name);
+        match name {
+            Some(name) => outln!("{}", &name[3..]),
+            None => { errln!("kill: {}: invalid signal", arg); result = 1; }
+        }
+    }
+    result
+}
+
+/// The job fg or bg should act on: the one named by a job spec, or the
+/// current job.
+pub fn get_job_id(shell: &Shell, args: &[String]) -> Result<usize, String> {
+    match args.get(1) {
+        Some(spec) => find_job(shell, spec),
+        None => by_recency(shell).first().copied().ok_or_else(|| "no current job".to_string()),
     }
-    0
 }
 
-pub fn get_job_id(shell: &Shell, args: &[String]) -> Option<usize> {
-    if let Some(arg) = args.get(1) {
-        arg.trim_start_matches('%').parse().ok()
-    } else {
-        shell.jobs.keys().max().copied()
+/// The job a job spec names, as in bash:
+///   %N           job number N (the `%` is optional)
+///   %+  %%       the current job
+///   %-           the previous job
+///   %STRING      the job whose command starts with STRING
+///   %?STRING     the job whose command contains STRING
+fn find_job(shell: &Shell, spec: &str) -> Result<usize, String> {
+    let body = spec.strip_prefix('%').unwrap_or(spec);
+    if let Ok(id) = body.parse::<usize>() {
+        return if shell.jobs.contains_key(&id) { Ok(id) } else { Err(format!("{}: no such job", spec)) };
     }
-}
\ No newline at end of file
+
+    let recent = by_recency(shell);
+    let found: Vec<usize> = match body {
+        "+" | "%" => recent.first().copied().into_iter().collect(),
+        "-"       => recent.get(1).copied().into_iter().collect(),
+        _ => {
+            let matches = |command: &str| match body.strip_prefix('?') {
+                Some(needle) => command.contains(needle),
+                None         => command.starts_with(body),
+            };
+            recent.into_iter().filter(|id| matches(&shell.jobs[id].command)).collect()
+        }
+    };
+    match found[..] {
+        [id] => Ok(id),
+        []   => Err(format!("{}: no such job", spec)),
+        _    => Err(format!("{}: ambiguous job spec", spec)),
+    }
+}
+
+/// Job numbers, current job first: stopped jobs are preferred, since they
+/// are waiting on the user, then the most recently started.
+fn by_recency(shell: &Shell) -> Vec<usize> {
+    let mut jobs: Vec<_> = shell.jobs.values().collect();
+    jobs.sort_by_key(|j| std::cmp::Reverse((j.status == JobStatus::Stopped, j.id)));
+    jobs.into_iter().map(|j| j.id).collect()
+}
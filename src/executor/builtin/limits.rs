@@ -0,0 +1,264 @@
+// src/executor/builtin/limits.rs
+// Process limits inherited by every child the shell spawns: umask, ulimit
+//
+// Both change the shell's own process state, so commands started by the
+// executor pick the new values up without any extra plumbing.
+
+use super::util::Arg;
+
+// ── umask ─────────────────────────────────────────────────────────────────────
+
+/// umask [-S] [MODE] — show or set the file creation mask. MODE is octal
+/// (`022`) or symbolic (`u=rwx,g=rx,o=`), like `umask -S` prints it.
+pub fn builtin_umask(args: &[String]) -> i32 {
+    let mut symbolic = false;
+    let mut mode: Option<&str> = None;
+    for arg in super::util::args(args) {
+        match arg {
+            Arg::Option("-S") => symbolic = true,
+            Arg::Option(s) => { errln!("umask: invalid option: {}", s); return 2; }
+            Arg::Operand(s) => mode = Some(s),
+        }
+    }
+
+    let Some(mode) = mode else {
+        let mask = current_umask();
+        if symbolic { outln!("{}", symbolic_mask(mask)); } else { outln!("{:04o}", mask); }
+        return 0;
+    };
+
+    let parsed = if mode.chars().all(|c| c.is_digit(8)) {
+        u32::from_str_radix(mode, 8).ok().filter(|m| *m <= 0o777)
+    } else {
+        parse_symbolic(mode, current_umask())
+    };
+    match parsed {
+        Some(mask) => { set_umask(mask); 0 }
+        None => { errln!("umask: {}: invalid mode", mode); 1 }
+    }
+}
+
+#[cfg(unix)]
+fn current_umask() -> u32 {
+    // umask() can only be read by setting it, so put the old value back
+    unsafe {
+        let old = libc::umask(0);
+        libc::umask(old);
+        old as u32
+    }
+}
+
+#[cfg(not(unix))]
+fn current_umask() -> u32 { 0o022 }
+
+#[cfg(unix)]
+fn set_umask(mask: u32) {
+    unsafe { libc::umask(mask as libc::mode_t); }
+}
+
+#[cfg(not(unix))]
+fn set_umask(_mask: u32) {
+    errln!("umask: file creation masks are not supported on this platform");
+}
+
+/// Render a mask the way `umask -S` does: the permissions it *allows*.
+fn symbolic_mask(mask: u32) -> String {
+    let allowed = !mask & 0o777;
+    ["u", "g", "o"].iter().enumerate()
+        .map(|(i, who)| {
+            let bits = (allowed >> (6 - 3 * i)) & 0o7;
+            let perms: String = [(4, 'r'), (2, 'w'), (1, 'x')].iter()
+                .filter(|(b, _)| bits & b != 0)
+                .map(|(_, c)| *c)
+                .collect();
+            format!("{}={}", who, perms)
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Apply clauses like `u=rwx,g+w,o-rwx` (in terms of allowed permissions)
+/// to `mask`. Returns None on a malformed mode.
+fn parse_symbolic(mode: &str, mask: u32) -> Option<u32> {
+    let mut allowed = !mask & 0o777;
+    for clause in mode.split(',') {
+        let op_pos = clause.find(['=', '+', '-'])?;
+        let (who, rest) = clause.split_at(op_pos);
+        let op = rest.chars().next()?;
+
+        let mut perms = 0;
+        for c in rest[1..].chars() {
+            perms |= match c { 'r' => 4, 'w' => 2, 'x' => 1, _ => return None };
+        }
+
+        let who = if who.is_empty() { "a" } else { who };
+        for w in who.chars() {
+            let shifts: &[u32] = match w {
+                'u' => &[6], 'g' => &[3], 'o' => &[0], 'a' => &[6, 3, 0],
+                _ => return None,
+            };
+            for &shift in shifts {
+                let bits = perms << shift;
+                match op {
+                    '=' => allowed = (allowed & !(0o7 << shift)) | bits,
+                    '+' => allowed |= bits,
+                    _   => allowed &= !bits,
+                }
+            }
+        }
+    }
+    Some(!allowed & 0o777)
+}
+
+// ── ulimit ────────────────────────────────────────────────────────────────────
+
+/// (flag, description, unit, bytes per unit)
+const LIMITS: &[(char, &str, &str, u64)] = &[
+    ('c', "core file size",     "blocks",  512),
+    ('d', "data seg size",      "kbytes",  1024),
+    ('f', "file size",          "blocks",  512),
+    ('n', "open files",         "count",   1),
+    ('s', "stack size",         "kbytes",  1024),
+    ('t', "cpu time",           "seconds", 1),
+    ('u', "max user processes", "count",   1),
+    ('v', "virtual memory",     "kbytes",  1024),
+];
+
+/// ulimit [-SH] [-a | -c|-d|-f|-n|-s|-t|-u|-v [LIMIT]]
+///
+/// Shows or sets resource limits via getrlimit/setrlimit. Setting changes
+/// both the soft and hard limit unless -S or -H is given; showing reports
+/// the soft limit unless -H is given. LIMIT may be `unlimited`.
+#[cfg(unix)]
+pub fn builtin_ulimit(args: &[String]) -> i32 {
+    let mut soft  = false;
+    let mut hard  = false;
+    let mut all   = false;
+    let mut flags: Vec<char> = Vec::new();
+    let mut value: Option<&str> = None;
+
+    for arg in &args[1..] {
+        if let Some(opts) = arg.strip_prefix('-').filter(|o| !o.is_empty()) {
+            for c in opts.chars() {
+                match c {
+                    'S' => soft = true,
+                    'H' => hard = true,
+                    'a' => all  = true,
+                    c if LIMITS.iter().any(|l| l.0 == c) => flags.push(c),
+                    c => { errln!("ulimit: -{}: invalid option", c); return 2; }
+                }
+            }
+        } else {
+            value = Some(arg);
+        }
+    }
+
+    if all {
+        for &(flag, desc, unit, _) in LIMITS {
+            let shown = show_limit(flag, hard).unwrap_or_else(|e| e);
+            outln!("{:<24}({}, -{}) {}", desc, unit, flag, shown);
+        }
+        return 0;
+    }
+    // A bare `ulimit` reports the file size limit, as in POSIX
+    if flags.is_empty() { flags.push('f'); }
+
+    let Some(value) = value else {
+        let mut code = 0;
+        for &flag in &flags {
+            match show_limit(flag, hard) {
+                Ok(v)  => outln!("{}", v),
+                Err(e) => { errln!("ulimit: {}", e); code = 1; }
+            }
+        }
+        return code;
+    };
+
+    let (soft, hard) = if soft || hard { (soft, hard) } else { (true, true) };
+    let mut code = 0;
+    for &flag in &flags {
+        if let Err(e) = set_limit(flag, value, soft, hard) {
+            errln!("ulimit: {}", e);
+            code = 1;
+        }
+    }
+    code
+}
+
+#[cfg(not(unix))]
+pub fn builtin_ulimit(_args: &[String]) -> i32 {
+    errln!("ulimit: resource limits are not supported on this platform");
+    1
+}
+
+#[cfg(all(unix, target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type Resource = libc::c_int;
+
+#[cfg(unix)]
+fn resource(flag: char) -> (Resource, u64) {
+    let scale = LIMITS.iter().find(|l| l.0 == flag).map(|l| l.3).unwrap_or(1);
+    let res = match flag {
+        'c' => libc::RLIMIT_CORE,
+        'd' => libc::RLIMIT_DATA,
+        'f' => libc::RLIMIT_FSIZE,
+        'n' => libc::RLIMIT_NOFILE,
+        's' => libc::RLIMIT_STACK,
+        't' => libc::RLIMIT_CPU,
+        'u' => libc::RLIMIT_NPROC,
+        _   => libc::RLIMIT_AS,
+    };
+    (res, scale)
+}
+
+#[cfg(unix)]
+fn get_rlimit(res: Resource) -> Result<libc::rlimit, String> {
+    let mut lim = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+    if unsafe { libc::getrlimit(res, &mut lim) } != 0 {
+        return Err(std::io::Error::last_os_error().to_string());
+    }
+    Ok(lim)
+}
+
+#[cfg(unix)]
+fn show_limit(flag: char, hard: bool) -> Result<String, String> {
+    let (res, scale) = resource(flag);
+    let lim = get_rlimit(res)?;
+    let v = if hard { lim.rlim_max } else { lim.rlim_cur };
+    Ok(if v == libc::RLIM_INFINITY { "unlimited".to_string() } else { (v / scale).to_string() })
+}
+
+#[cfg(unix)]
+fn set_limit(flag: char, value: &str, soft: bool, hard: bool) -> Result<(), String> {
+    let (res, scale) = resource(flag);
+    let new = if value == "unlimited" {
+        libc::RLIM_INFINITY
+    } else {
+        let n: u64 = value.parse().map_err(|_| format!("{}: invalid number", value))?;
+        n.saturating_mul(scale) as libc::rlim_t
+    };
+
+    let mut lim = get_rlimit(res)?;
+    if soft { lim.rlim_cur = new; }
+    if hard { lim.rlim_max = new; }
+    if unsafe { libc::setrlimit(res, &lim) } != 0 {
+        return Err(format!("-{}: {}", flag, std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbolic_umask() {
+        assert_eq!(symbolic_mask(0o022), "u=rwx,g=rx,o=rx");
+        assert_eq!(symbolic_mask(0o077), "u=rwx,g=,o=");
+        assert_eq!(parse_symbolic("u=rwx,g=rx,o=", 0), Some(0o027));
+        assert_eq!(parse_symbolic("g-w", 0o002), Some(0o022));
+        assert_eq!(parse_symbolic("a+r", 0o777), Some(0o333));
+        assert_eq!(parse_symbolic("z=r", 0), None);
+    }
+}
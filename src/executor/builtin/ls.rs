@@ -0,0 +1,526 @@
+// src/executor/builtin/ls.rs
+// Directory listing: ls [-laLHtSrR] [dir ...]
+//
+// Symlinks are listed as links: cyan (red if broken), and in the long
+// format as `name -> target`. -L describes what they point to instead.
+//
+// `ls -l` columns come from [ls] columns in ~/.rshell/config.toml and are
+// rendered from each entry's metadata. Available columns:
+//
+//   perm   permission string, e.g. drwxr-xr-x
+//   links  number of hard links
+//   owner  owning user (Unix only)
+//   group  owning group (Unix only)
+//   size   human-readable size ([format] size_units, or --si / --bytes)
+//   mtime  modification time
+//   git    git status for small directories: what's staged, then what
+//          isn't, as in `git status --short` (A-, -M, MM, ?? ...)
+//
+// --git adds the git column for one listing, and implies -l.
+//
+// A directory's long listing starts with the space its entries take up
+// on disk, as `total`.
+//
+// Entries are stat'ed on a pool of threads, which keeps a directory on
+// NFS or SMB from taking a round trip per entry. Nothing is printed until
+// every stat is back; for a directory too big to wait on, past [ls]
+// stat_max_entries entries the short listing colours names from what the
+// directory listing says, without a stat at all.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::paths;
+use crate::shell::Shell;
+use super::util::{
+    display_width, format_mtime, color_name, paint_name, permission_string, stat_batch, NameKind, NumberFormat,
+    Opt, Options, SizeUnits,
+};
+
+pub const LS: Options = Options {
+    name: "ls",
+    operands: "[DIR|FILE]...",
+    about: "List directory contents.",
+    opts: &[
+        Opt::new("aA", "all", "show hidden entries"),
+        Opt::new("l", "", "long format, with the columns from [ls] columns"),
+        Opt::new("t", "", "sort by modification time, newest first"),
+        Opt::new("S", "", "sort by size, largest first"),
+        Opt::new("r", "reverse", "reverse the order"),
+        Opt::new("R", "recursive", "list subdirectories too, each under its own heading"),
+        Opt::new("h", "human-readable", "sizes in powers of 1024"),
+        Opt::new("", "si", "sizes in powers of 1000"),
+        Opt::new("", "bytes", "exact sizes"),
+        Opt::new("L", "dereference", "describe what symlinks point to, not the links"),
+        Opt::new("H", "dereference-command-line", "follow symlinks named as operands"),
+        Opt::new("", "git", "long format with each entry's git status, staged then unstaged"),
+    ],
+    numeric: None,
+};
+
+pub fn builtin_ls(shell: &Shell, args: &[String]) -> i32 {
+    let p = match LS.parse(args) { Ok(p) => p, Err(code) => return code };
+    let show_hidden = p.has("all");
+    let long_format = p.has("l") || p.has("git");
+    let dereference = p.has("dereference");
+    let follow_operands = dereference || p.has("dereference-command-line");
+    let mut numbers = NumberFormat::from_config(shell);
+    let mut sort = Sort::Name;
+    // The last size option given wins, and the last sort option
+    for (key, _) in &p.given {
+        match *key {
+            "human-readable" => numbers.units = SizeUnits::Binary,
+            "si"             => numbers.units = SizeUnits::Si,
+            "bytes"          => numbers.units = SizeUnits::Bytes,
+            "t"              => sort = Sort::Time,
+            "S"              => sort = Sort::Size,
+            _ => {}
+        }
+    }
+    let reverse = p.has("reverse");
+    let mut targets: Vec<PathBuf> = p.operands.iter()
+        .map(|target| PathBuf::from(paths::display(&shell.cwd.join(target))))
+        .collect();
+
+    if targets.is_empty() { targets.push(PathBuf::from(paths::display(&shell.cwd))); }
+
+    let mut columns = if long_format { configured_columns(shell) } else { Vec::new() };
+    if p.has("git") && !columns.contains(&Column::Git) {
+        // Just before the name, where it's configured by default
+        columns.push(Column::Git);
+    }
+    let recursive = p.has("recursive");
+    let listing = Listing { show_hidden, long_format, dereference, sort, reverse, columns, numbers };
+    let mut guard = super::util::LoopGuard::default();
+    let mut first = true;
+
+    let mut code = 0;
+    for (i, target) in targets.iter().enumerate() {
+        // A symlink operand is shown as a link in the long format, and
+        // looked through otherwise, unless -H or -L says to follow it
+        let link = super::util::is_symlink(target);
+        let as_link = link && !follow_operands && (long_format || !target.is_dir());
+        if (target.exists() && !target.is_dir()) || as_link {
+            let name = target.file_name().map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| paths::display(target));
+            if long_format {
+                let meta = if as_link { target.symlink_metadata() } else { target.metadata() };
+                if let Ok(meta) = meta {
+                    let git = if listing.columns.contains(&Column::Git) {
+                        target.parent().and_then(git_statuses)
+                    } else {
+                        None
+                    };
+                    print_long(&[Row::new(name, target.clone(), meta)], &listing.columns, git.as_ref(), &listing.numbers);
+                }
+            } else {
+                outln!("{}", color_name(&name, false, target));
+            }
+            continue;
+        }
+
+        let shown = p.operands.get(i).map_or_else(|| ".".to_string(), |o| o.to_string());
+        if recursive {
+            code |= list_tree(shell, target, &shown, &listing, &mut guard, &mut first);
+        } else if list_dir(shell, target, &listing).is_err() {
+            code = 1;
+        }
+    }
+    code
+}
+
+/// How the entries of each directory are listed.
+struct Listing {
+    show_hidden: bool,
+    long_format: bool,
+    dereference: bool,
+    sort: Sort,
+    reverse: bool,
+    columns: Vec<Column>,
+    numbers: NumberFormat,
+}
+
+/// `ls -R`: list `dir` under a heading, then each subdirectory in turn.
+/// `shown` is the path as the heading gives it, from the operand as typed.
+fn list_tree(shell: &Shell, dir: &Path, shown: &str, how: &Listing, guard: &mut super::util::LoopGuard, first: &mut bool) -> i32 {
+    if how.dereference {
+        if let Err(first) = guard.enter(dir) {
+            errln!("ls: {}: not listing again, it leads back into {}", shown, paths::display(&first));
+            return 0;
+        }
+    }
+    if !*first { outln!(); }
+    *first = false;
+    outln!("{}:", shown);
+    let mut code = 0;
+    match list_dir(shell, dir, how) {
+        Ok(subdirs) => for sub in subdirs {
+            let name = sub.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let shown = if shown.ends_with('/') { format!("{}{}", shown, name) } else { format!("{}/{}", shown, name) };
+            code |= list_tree(shell, &sub, &shown, how, guard, first);
+        },
+        Err(()) => code = 1,
+    }
+    if how.dereference { guard.leave(); }
+    code
+}
+
+/// List the entries of one directory; returns its subdirectories, in the
+/// order listed, for -R.
+fn list_dir(shell: &Shell, target: &Path, how: &Listing) -> Result<Vec<PathBuf>, ()> {
+    let entries = match std::fs::read_dir(target) {
+        Ok(e) => e,
+        Err(e) => { errln!("ls: {}: {}", paths::display(target), e); return Err(()); }
+    };
+
+    let items: Vec<std::fs::DirEntry> = entries.flatten()
+        .filter(|e| how.show_hidden || !e.file_name().to_string_lossy().starts_with('.'))
+        .collect();
+
+    // Without -L a link to a directory sorts and shows as a link, and
+    // the directory listing itself says which entries are directories
+    let dirs: Vec<bool> = if how.dereference {
+        stat_batch(&items, |item| item.path().is_dir())
+    } else {
+        items.iter().map(|item| item.file_type().map(|t| t.is_dir()).unwrap_or(false)).collect()
+    };
+    let items: Vec<(std::fs::DirEntry, bool)> = items.into_iter().zip(dirs).collect();
+    let items = sorted(items, how.sort, how.reverse, how.dereference);
+    let subdirs: Vec<PathBuf> = items.iter().filter(|(_, is_dir)| *is_dir).map(|(item, _)| item.path()).collect();
+
+    if how.long_format {
+        // Asking git about a huge directory is slow, so the git column is
+        // only filled in below the configured entry count.
+        let git = if how.columns.contains(&Column::Git)
+            && items.len() <= shell.config.ls.git_max_entries
+        {
+            git_statuses(target)
+        } else {
+            None
+        };
+        let rows: Vec<Row> = stat_batch(&items, |(item, _)| {
+                // A broken link has nothing to follow and stays a link
+                let followed = if how.dereference { item.path().metadata().ok() } else { None };
+                let meta = followed.or_else(|| item.metadata().ok())?;
+                Some(Row::new(item.file_name().to_string_lossy().to_string(), item.path(), meta))
+            })
+            .into_iter()
+            .flatten()
+            .collect();
+        outln!("total {}", how.numbers.size(rows.iter().map(|row| allocated(&row.meta)).sum()));
+        print_long(&rows, &how.columns, git.as_ref(), &how.numbers);
+        return Ok(subdirs);
+    }
+
+    // Piped output gets one plain name per line so the next stage can
+    // match and split it
+    if !super::io::stdout_is_terminal() {
+        for (item, _) in &items { outln!("{}", item.file_name().to_string_lossy()); }
+        return Ok(subdirs);
+    }
+
+    // Colouring takes a stat or two per entry. In a directory bigger
+    // than [ls] stat_max_entries that's too slow over a network, so
+    // names are coloured by what the listing itself says instead.
+    let kinds: Vec<NameKind> = if items.len() > shell.config.ls.stat_max_entries {
+        items.iter().map(|(item, is_dir)| match item.file_type() {
+            _ if *is_dir => NameKind::Dir,
+            Ok(t) if t.is_symlink() => NameKind::Link,
+            _ => NameKind::Plain,
+        }).collect()
+    } else {
+        stat_batch(&items, |(item, is_dir)| if *is_dir { NameKind::Dir } else { NameKind::of(&item.path()) })
+    };
+    let names: Vec<String> = items.iter().zip(kinds)
+        .map(|((item, _), kind)| paint_name(&item.file_name().to_string_lossy(), kind))
+        .collect();
+
+    let term_width = crossterm::terminal::size().map(|(cols, _)| cols as usize).unwrap_or(80);
+    let widths: Vec<usize> = names.iter().map(|n| display_width(n)).collect();
+    let (cols, col_width) = grid(&widths, term_width);
+
+    for (i, (name, width)) in names.iter().zip(&widths).enumerate() {
+        // The last column needs no padding after it
+        if (i + 1) % cols == 0 || i + 1 == names.len() {
+            outln!("{}", name);
+        } else {
+            out!("{}{}", name, " ".repeat(col_width.saturating_sub(*width)));
+        }
+    }
+    Ok(subdirs)
+}
+
+/// How many names fit across a terminal `term_width` columns wide, and
+/// how wide each column is: the widest name and a gap, at least 16.
+fn grid(widths: &[usize], term_width: usize) -> (usize, usize) {
+    let widest = widths.iter().copied().max().unwrap_or(0);
+    let col_width = (widest + 2).max(16);
+    // The last column's gap can hang off the edge
+    let cols = ((term_width + 2) / col_width).max(1);
+    (cols, col_width)
+}
+
+// ── Sorting ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Sort {
+    /// Directories first, then by name
+    Name,
+    /// Newest first, directories mixed in
+    Time,
+    /// Largest first, directories mixed in
+    Size,
+}
+
+fn sorted(
+    mut items: Vec<(std::fs::DirEntry, bool)>,
+    sort: Sort,
+    reverse: bool,
+    dereference: bool,
+) -> Vec<(std::fs::DirEntry, bool)> {
+    items = match sort {
+        Sort::Name => {
+            items.sort_by(|(a, ad), (b, bd)| bd.cmp(ad).then_with(|| a.file_name().cmp(&b.file_name())));
+            items
+        }
+        Sort::Time | Sort::Size => {
+            // Sizes and times take a stat each, done on the pool
+            let keys = stat_batch(&items, |(item, _)| {
+                let meta = if dereference { item.path().metadata().ok() } else { None };
+                let meta = meta.or_else(|| item.metadata().ok());
+                meta.map(|m| match sort {
+                    Sort::Size => (m.len(), std::time::SystemTime::UNIX_EPOCH),
+                    _ => (0, m.modified().unwrap_or(std::time::SystemTime::UNIX_EPOCH)),
+                })
+            });
+            let mut keyed: Vec<_> = items.into_iter().zip(keys).collect();
+            // Newest or largest first; ties, and entries that vanished, by name
+            keyed.sort_by(|((a, _), ak), ((b, _), bk)| bk.cmp(ak).then_with(|| a.file_name().cmp(&b.file_name())));
+            keyed.into_iter().map(|(item, _)| item).collect()
+        }
+    };
+    if reverse { items.reverse(); }
+    items
+}
+
+// ── Long format columns ───────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Column {
+    Perm,
+    Links,
+    Owner,
+    Group,
+    Size,
+    Mtime,
+    Git,
+}
+
+impl Column {
+    fn parse(name: &str) -> Option<Column> {
+        match name {
+            "perm" | "mode"           => Some(Column::Perm),
+            "links" | "nlink"         => Some(Column::Links),
+            "size"                    => Some(Column::Size),
+            "owner" | "user"          => Some(Column::Owner),
+            "group"                   => Some(Column::Group),
+            "mtime" | "time" | "date" => Some(Column::Mtime),
+            "git"                     => Some(Column::Git),
+            _                         => None,
+        }
+    }
+
+    fn right_aligned(self) -> bool {
+        matches!(self, Column::Size | Column::Links)
+    }
+}
+
+fn configured_columns(shell: &Shell) -> Vec<Column> {
+    let mut columns = Vec::new();
+    for name in &shell.config.ls.columns {
+        match Column::parse(name) {
+            Some(col) => columns.push(col),
+            None => errln!("ls: unknown column '{}' in config", name),
+        }
+    }
+    columns
+}
+
+/// An entry for the long format, with everything beyond its metadata
+/// that showing it takes.
+struct Row {
+    name: String,
+    meta: std::fs::Metadata,
+    kind: NameKind,
+    /// Where a symlink points
+    target: Option<PathBuf>,
+}
+
+impl Row {
+    fn new(name: String, path: PathBuf, meta: std::fs::Metadata) -> Row {
+        let kind = NameKind::from_meta(&meta, &path);
+        let target = if meta.file_type().is_symlink() { std::fs::read_link(&path).ok() } else { None };
+        Row { name, meta, kind, target }
+    }
+}
+
+fn print_long(
+    rows: &[Row],
+    columns: &[Column],
+    git: Option<&HashMap<String, String>>,
+    numbers: &NumberFormat,
+) {
+    let cells: Vec<Vec<String>> = rows.iter()
+        .map(|row| columns.iter().map(|col| render_cell(*col, &row.name, &row.meta, git, numbers)).collect())
+        .collect();
+
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|c| cells.iter().map(|row| row[c].chars().count()).max().unwrap_or(0))
+        .collect();
+
+    for (row, cells) in rows.iter().zip(&cells) {
+        let mut line = String::new();
+        for (c, cell) in cells.iter().enumerate() {
+            if columns[c].right_aligned() {
+                line.push_str(&format!("{:>width$}  ", cell, width = widths[c]));
+            } else {
+                line.push_str(&format!("{:<width$}  ", cell, width = widths[c]));
+            }
+        }
+        let target = row.target.as_ref().map(|t| format!(" -> {}", paths::display(t))).unwrap_or_default();
+        outln!("{}{}{}", line, paint_name(&row.name, row.kind), target);
+    }
+}
+
+fn render_cell(
+    col: Column,
+    name: &str,
+    meta: &std::fs::Metadata,
+    git: Option<&HashMap<String, String>>,
+    numbers: &NumberFormat,
+) -> String {
+    match col {
+        Column::Perm  => permission_string(meta),
+        Column::Size  => numbers.size(meta.len()),
+        Column::Links => link_count(meta).to_string(),
+        Column::Owner => owner_name(meta),
+        Column::Group => group_name(meta),
+        Column::Mtime => meta.modified().map(format_mtime).unwrap_or_else(|_| "-".to_string()),
+        // Blank outside a repository, and `--` for a file with no changes
+        Column::Git   => git
+            .map(|g| g.get(name).map_or("--", |s| s.as_str()))
+            .unwrap_or("  ")
+            .to_string(),
+    }
+}
+
+fn owner_name(_meta: &std::fs::Metadata) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        super::util::user_name(_meta.uid())
+    }
+
+    #[cfg(windows)]
+    { "-".to_string() }
+}
+
+fn group_name(_meta: &std::fs::Metadata) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        super::util::group_name(_meta.gid())
+    }
+
+    #[cfg(windows)]
+    { "-".to_string() }
+}
+
+fn link_count(_meta: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        _meta.nlink()
+    }
+
+    // Windows has the count, but std only exposes it on nightly
+    #[cfg(windows)]
+    { 1 }
+}
+
+/// The disk space an entry takes up: its allocated blocks on Unix, which
+/// is less than its size for a sparse file, and its size on Windows.
+fn allocated(meta: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        meta.blocks() * 512
+    }
+
+    #[cfg(windows)]
+    { meta.len() }
+}
+
+// ── Git status ────────────────────────────────────────────────────────────────
+
+/// Map each top-level entry of `dir` to its two-letter git status, using
+/// `git status --short` run from inside the directory. None outside a
+/// repository.
+fn git_statuses(dir: &Path) -> Option<HashMap<String, String>> {
+    let output = std::process::Command::new("git")
+        .arg("-C").arg(dir)
+        .args(["-c", "color.status=false", "status", "--short", "--", "."])
+        .output()
+        .ok()?;
+    if !output.status.success() { return None; }
+    Some(parse_git_status(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// The statuses in `git status --short` output, with `-` for "no change"
+/// on either side. Changes inside a subdirectory are attributed to the
+/// subdirectory's entry, which shows the first of each kind.
+fn parse_git_status(output: &str) -> HashMap<String, String> {
+    let mut statuses: HashMap<String, [char; 2]> = HashMap::new();
+    for line in output.lines() {
+        if line.len() < 4 { continue; }
+        let mut xy = line[..2].chars().map(|c| if c == ' ' { '-' } else { c });
+        let (x, y) = (xy.next().unwrap_or('-'), xy.next().unwrap_or('-'));
+
+        // Renames are shown as "old -> new"; the new name is what's on disk
+        let path = line[3..].rsplit(" -> ").next().unwrap_or("").trim_matches('"');
+        let first = path.split('/').next().unwrap_or("");
+        if first.is_empty() || first == ".." { continue; }
+        let status = statuses.entry(first.to_string()).or_insert([x, y]);
+        // Untracked only shows when there's nothing else to say
+        if *status == ['?', '?'] { *status = [x, y]; }
+        if x != '?' {
+            if status[0] == '-' { status[0] = x; }
+            if status[1] == '-' { status[1] = y; }
+        }
+    }
+    statuses.into_iter().map(|(name, [x, y])| (name, format!("{}{}", x, y))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_fits_the_terminal() {
+        assert_eq!(grid(&[3, 5, 4], 80), (5, 16));
+        assert_eq!(grid(&[3, 5, 4], 200), (12, 16));
+        assert_eq!(grid(&[38, 5], 80), (2, 40));
+        assert_eq!(grid(&[100], 80), (1, 102));
+        assert_eq!(display_width("\x1b[34m日本/\x1b[0m"), 5);
+    }
+
+    #[test]
+    fn git_status_letters() {
+        let statuses = parse_git_status(
+            "A  new.rs\n M src/a.rs\nM  src/b.rs\n?? src/c.rs\n?? notes/\nR  old.rs -> moved.rs\n");
+        assert_eq!(statuses["new.rs"], "A-");
+        assert_eq!(statuses["src"], "MM");
+        assert_eq!(statuses["notes"], "??");
+        assert_eq!(statuses["moved.rs"], "R-");
+        assert!(!statuses.contains_key("old.rs"));
+    }
+}
@@ -281,7 +281,7 @@ pub fn builtin_mini(args: &[String]) -> i32 {
     let filename = match args.get(1) {
         Some(f) => f.clone(),
         None => {
-            eprintln!("mini: usage: mini <filename>");
+            errln!("mini: usage: mini <filename>");
             return 1;
         }
     };
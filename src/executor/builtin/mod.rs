@@ -1,51 +1,214 @@
 // src/executor/builtin/mod.rs
+
+/// `print!` for builtins: writes to the current stdout stream (see io.rs).
+/// Errors such as a reader closing its end of a pipe are ignored.
+macro_rules! out {
+    ($($arg:tt)*) => {{
+        use std::io::Write as _;
+        let _ = write!($crate::executor::builtin::io::stdout(), $($arg)*);
+    }};
+}
+
+/// `println!` for builtins, see `out!`.
+macro_rules! outln {
+    () => { out!("\n") };
+    ($($arg:tt)*) => {{
+        use std::io::Write as _;
+        let _ = writeln!($crate::executor::builtin::io::stdout(), $($arg)*);
+    }};
+}
+
+/// `err!` for builtins: writes to the current stderr stream.
+macro_rules! err {
+    ($($arg:tt)*) => {{
+        use std::io::Write as _;
+        let _ = write!($crate::executor::builtin::io::stderr(), $($arg)*);
+    }};
+}
+
+/// `errln!` for builtins, see `err!`.
+macro_rules! errln {
+    () => { err!("\n") };
+    ($($arg:tt)*) => {{
+        use std::io::Write as _;
+        let _ = writeln!($crate::executor::builtin::io::stderr(), $($arg)*);
+    }};
+}
+
+mod checksum;
+mod cleanup;
+mod complete;
 mod core;
+mod du;
+mod env;
+mod expr;
+mod fetch;
+mod file;
+pub mod exec;
 mod find;
 mod fs;
+mod gitignore;
 mod grep;
+pub mod io;
 mod jobs;
+mod limits;
+mod ls;
+mod net;
+mod open;
+mod pager;
+mod params;
+mod pathname;
 pub mod pkg;
+mod printf;
+mod rename;
+mod sandbox;
+mod schedule;
+mod sed;
+mod serve;
+mod stat;
+mod stats;
+pub mod status;
+mod sysinfo;
 mod test;
 mod text;
+mod trash;
 mod util;
 
-pub use util::command_not_found;
+pub use self::core::help_entries;
+pub use printf::shell_quote;
+pub use schedule::{run_scheduler, start_scheduler};
+pub use util::{
+    command_not_found, find_in_path, format_ms, format_size_as, is_executable, new_temp_file, offer_correction,
+    stat_batch, unix_secs, Correction, Options, SizeUnits,
+};
 
 use crate::shell::Shell;
 
-pub fn run_builtin(shell: &mut Shell, args: &[String]) -> Option<i32> {
-    crossterm::terminal::disable_raw_mode().ok();
+/// The options a builtin declares (see util.rs), for completion.
+pub fn options(name: &str) -> Option<&'static Options> {
+    Some(match name {
+        "which"  => &core::WHICH,
+        "mkdir"  => &fs::MKDIR,
+        "rm"     => &fs::RM,
+        "cp"     => &fs::CP,
+        "mv"     => &fs::MV,
+        "rename" => &rename::RENAME,
+        "trash"  => &trash::TRASH,
+        "cat"    => &fs::CAT,
+        "touch"  => &fs::TOUCH,
+        "ln"     => &fs::LN,
+        "chown"  => &fs::CHOWN,
+        "head"   => &text::HEAD,
+        "tail"   => &text::TAIL,
+        "wc"     => &text::WC,
+        "sort"   => &text::SORT,
+        "uniq"   => &text::UNIQ,
+        "cut"    => &text::CUT,
+        "tr"     => &text::TR,
+        "xargs"  => &text::XARGS,
+        "grep"   => &grep::GREP,
+        "ls"     => &ls::LS,
+        "disown" => &jobs::DISOWN,
+        "history" => &core::HISTORY,
+        "complete" => &complete::COMPLETE,
+        "cleanup" => &cleanup::CLEANUP,
+        "du"     => &du::DU,
+        "basename" => &pathname::BASENAME,
+        "dirname"  => &pathname::DIRNAME,
+        "realpath" => &pathname::REALPATH,
+        "stat"   => &stat::STAT,
+        "file"   => &file::FILE,
+        "sha256sum" => &checksum::SHA256SUM,
+        "md5sum"    => &checksum::MD5SUM,
+        "fetch"  => &fetch::FETCH,
+        "serve"  => &serve::SERVE,
+        "ping"   => &net::PING,
+        "port"   => &net::PORT,
+        "hostname" => &sysinfo::HOSTNAME,
+        "uname"  => &sysinfo::UNAME,
+        "uptime" => &sysinfo::UPTIME,
+        "open"   => &open::OPEN,
+        _ => return None,
+    })
+}
 
-    let code = match args[0].as_str() {
+pub fn run_builtin(shell: &mut Shell, args: &[String]) -> Option<i32> {
+    match args[0].as_str() {
         // ── Core ──────────────────────────────────────────────
         "cd"              => Some(core::builtin_cd(shell, args)),
         "pwd"             => Some(core::builtin_pwd(shell)),
         "echo"            => Some(core::builtin_echo(args)),
-        "export" | "set"  => Some(core::builtin_export(shell, args)),
+        "yes"             => Some(core::builtin_yes(args)),
+        "expr"            => Some(expr::builtin_expr(args)),
+        "printf"          => Some(printf::builtin_printf(args)),
+        "quote"           => Some(printf::builtin_quote(args)),
+        "export"          => Some(core::builtin_export(shell, args)),
+        "declare"         => Some(core::builtin_declare(shell, args)),
+        "readonly"        => Some(core::builtin_readonly(shell, args)),
+        "set"             => Some(core::builtin_set(shell, args)),
         "unset"           => Some(core::builtin_unset(shell, args)),
         "alias"           => Some(core::builtin_alias(shell, args)),
         "unalias"         => Some(core::builtin_unalias(shell, args)),
-        "history"         => Some(core::builtin_history(shell)),
+        "wrap"            => Some(core::builtin_wrap(shell, args)),
+        "hook"            => Some(core::builtin_hook(shell, args)),
+        "history"         => Some(core::builtin_history(shell, args)),
+        "fc"              => Some(core::builtin_fc(shell, args)),
+        "stats"           => Some(stats::builtin_stats(args)),
+        "schedule"        => Some(schedule::builtin_schedule(args)),
+        "guard"           => Some(core::builtin_guard(shell, args)),
+        "explain"         => Some(status::builtin_explain(shell, args)),
         "source" | "."    => Some(core::builtin_source(shell, args)),
+        "exec"            => Some(exec::builtin_exec(shell, args, &[])),
+        "sandbox"         => Some(sandbox::builtin_sandbox(shell, args)),
         "clear" | "cls"   => Some(core::builtin_clear()),
         "sleep"           => Some(core::builtin_sleep(args)),
-        "functions"       => Some(core::builtin_functions(shell)),
+        "functions"       => Some(core::builtin_functions(shell, args)),
+        "shift"           => Some(params::builtin_shift(shell, args)),
+        "getopts"         => Some(params::builtin_getopts(shell, args)),
+        "umask"           => Some(limits::builtin_umask(args)),
+        "ulimit"          => Some(limits::builtin_ulimit(args)),
         "help"            => Some(core::builtin_help_topic(args)),
-        "which"           => Some(core::builtin_which(args)),
+        "which"           => Some(core::builtin_which(shell, args)),
+        "type"            => Some(core::builtin_type(shell, args)),
+        "command"         => Some(core::builtin_command(shell, args)),
+        "hash"            => Some(core::builtin_hash(shell, args)),
+        "complete"        => Some(complete::builtin_complete(shell, args)),
+        "builtin"         => Some(core::builtin_builtin(args)),
         "pushd"           => Some(core::builtin_pushd(shell, args)),
-        "popd"            => Some(core::builtin_popd(shell)),
-        "dirs"            => Some(core::builtin_dirs(shell)),
+        "popd"            => Some(core::builtin_popd(shell, args)),
+        "dirs"            => Some(core::builtin_dirs(shell, args)),
 
         // ── Filesystem ────────────────────────────────────────
-        "ls"              => Some(fs::builtin_ls(shell, args)),
+        "ls"              => Some(ls::builtin_ls(shell, args)),
         "mkdir"           => Some(fs::builtin_mkdir(args)),
         "rm"              => Some(fs::builtin_rm(args)),
         "cp"              => Some(fs::builtin_cp(args)),
         "mv"              => Some(fs::builtin_mv(args)),
+        "rename"          => Some(rename::builtin_rename(shell, args)),
+        "trash"           => Some(trash::builtin_trash(shell, args)),
         "cat"             => Some(fs::builtin_cat(args)),
         "touch"           => Some(fs::builtin_touch(args)),
         "chmod"           => Some(fs::builtin_chmod(args)),
+        "chown"           => Some(fs::builtin_chown(args)),
         "ln"              => Some(fs::builtin_ln(args)),
+        "cleanup"         => Some(cleanup::builtin_cleanup(shell, args)),
+        "du"              => Some(du::builtin_du(shell, args)),
+        "basename"        => Some(pathname::builtin_basename(args)),
+        "dirname"         => Some(pathname::builtin_dirname(args)),
+        "realpath"        => Some(pathname::builtin_realpath(shell, args)),
+        "stat"            => Some(stat::builtin_stat(shell, args)),
+        "file"            => Some(file::builtin_file(shell, args)),
+        "sha256sum"       => Some(checksum::builtin_checksum(shell, checksum::Algorithm::Sha256, args)),
+        "md5sum"          => Some(checksum::builtin_checksum(shell, checksum::Algorithm::Md5, args)),
+        "fetch"           => Some(fetch::builtin_fetch(shell, args)),
+        "serve"           => Some(serve::builtin_serve(shell, args)),
+        "ping"            => Some(net::builtin_ping(args)),
+        "port"            => Some(net::builtin_port(args)),
+        "whoami"          => Some(sysinfo::builtin_whoami(args)),
+        "hostname"        => Some(sysinfo::builtin_hostname(args)),
+        "uname"           => Some(sysinfo::builtin_uname(args)),
+        "uptime"          => Some(sysinfo::builtin_uptime(args)),
+        "open"            => Some(open::builtin_open(shell, args)),
 
         // ── Search ────────────────────────────────────────────
         "grep"            => Some(grep::builtin_grep(args)),
@@ -54,11 +217,14 @@ pub fn run_builtin(shell: &mut Shell, args: &[String]) -> Option<i32> {
         // ── Text processing ───────────────────────────────────
         "head"            => Some(text::builtin_head(args)),
         "tail"            => Some(text::builtin_tail(args)),
-        "wc"              => Some(text::builtin_wc(args)),
-        "env"             => Some(text::builtin_env(args)),
+        "wc"              => Some(text::builtin_wc(shell, args)),
+        "env"             => Some(env::builtin_env(shell, args)),
         "sort"            => Some(text::builtin_sort(args)),
         "uniq"            => Some(text::builtin_uniq(args)),
-        "xargs"           => Some(text::builtin_xargs(args)),
+        "cut"             => Some(text::builtin_cut(args)),
+        "tr"              => Some(text::builtin_tr(args)),
+        "xargs"           => Some(text::builtin_xargs(shell, args)),
+        "sed"             => Some(sed::builtin_sed(args)),
 
         // ── Package manager ───────────────────────────────────
         "pkg"             => Some(pkg::builtin_pkg(args)),
@@ -69,6 +235,7 @@ pub fn run_builtin(shell: &mut Shell, args: &[String]) -> Option<i32> {
         "jobs"            => Some(jobs::builtin_jobs(shell)),
         "fg"              => Some(jobs::builtin_fg(shell, args)),
         "bg"              => Some(jobs::builtin_bg(shell, args)),
+        "disown"          => Some(jobs::builtin_disown(shell, args)),
         "kill"            => Some(jobs::builtin_kill(shell, args)),
 
         // ── Test / conditionals ───────────────────────────────
@@ -77,11 +244,8 @@ pub fn run_builtin(shell: &mut Shell, args: &[String]) -> Option<i32> {
         // ── Shell primitives ──────────────────────────────────
         "true"            => Some(0),
         "false"           => Some(1),
-        "exit" | "quit"   => std::process::exit(shell.last_exit_code),
+        "exit" | "quit"   => std::process::exit(core::exit_status(shell, args)),
 
         _                 => None,
-    };
-
-    crossterm::terminal::enable_raw_mode().ok();
-    code
+    }
 }
\ No newline at end of file
@@ -0,0 +1,313 @@
+// src/executor/builtin/net.rs
+// Connectivity checks for basic network debugging:
+//
+//   ping [-c N] [-W SECS] [-p PORT] HOST   ICMP echo, once a second
+//   port [-W SECS] HOST:PORT...            can a TCP connection be made?
+//
+// ICMP needs a socket most systems only hand out to root, or (on Linux) to
+// the groups in net.ipv4.ping_group_range. Without one, and for IPv6, ping
+// times TCP connects to PORT instead (443 by default): a refused connection
+// still proves the host is up, so it counts as a reply.
+
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use super::util::{Opt, Options, Parsed};
+
+pub const PING: Options = Options {
+    name: "ping",
+    operands: "HOST",
+    about: "Check that HOST answers, and how quickly.",
+    opts: &[
+        Opt::new("c", "count", "stop after N replies are due (default 4)").takes("N"),
+        Opt::new("W", "timeout", "wait SECS for each reply (default 2)").takes("SECS"),
+        Opt::new("p", "port", "the port for TCP pings, when ICMP isn't allowed (default 443)").takes("PORT"),
+    ],
+    numeric: None,
+};
+
+pub const PORT: Options = Options {
+    name: "port",
+    operands: "HOST:PORT...",
+    about: "Check whether a TCP connection can be made to each HOST:PORT.",
+    opts: &[
+        Opt::new("W", "timeout", "give up after SECS (default 3)").takes("SECS"),
+    ],
+    numeric: None,
+};
+
+pub fn builtin_ping(args: &[String]) -> i32 {
+    let p = match PING.parse(args) { Ok(p) => p, Err(code) => return code };
+    let [host] = p.operands[..] else { errln!("usage: ping [-c N] [-W SECS] [-p PORT] HOST"); return 2 };
+    let Some(count) = number("ping", &p, "count", 4u32) else { return 2 };
+    let Some(timeout) = seconds("ping", &p, "timeout", 2.0) else { return 2 };
+    let Some(port) = number("ping", &p, "port", 443u16) else { return 2 };
+
+    let ip = match resolve(host, port) {
+        Ok(addr) => addr.ip(),
+        Err(e) => { errln!("ping: {}: {}", host, e); return 2; }
+    };
+    let prober = Prober::new(ip, port);
+    outln!("PING {} ({}) {}", host, ip, prober.describe());
+
+    let mut times = Vec::new();
+    for seq in 1..=count {
+        let started = Instant::now();
+        match prober.probe(seq as u16, timeout) {
+            Ok(Some(note)) => {
+                let ms = started.elapsed().as_secs_f64() * 1000.0;
+                outln!("reply from {}: seq={} time={:.2} ms{}", ip, seq, ms, note);
+                times.push(ms);
+            }
+            Ok(None) => outln!("no reply from {}: seq={} (timed out)", ip, seq),
+            Err(e) => outln!("no reply from {}: seq={} ({})", ip, seq, e),
+        }
+        if seq < count {
+            std::thread::sleep(Duration::from_secs(1).saturating_sub(started.elapsed()));
+        }
+    }
+
+    let loss = 100 - times.len() as u32 * 100 / count.max(1);
+    outln!("--- {}: {} sent, {} received, {}% loss", host, count, times.len(), loss);
+    if times.is_empty() { return 1; }
+    let min = times.iter().copied().fold(f64::MAX, f64::min);
+    let max = times.iter().copied().fold(0.0, f64::max);
+    let avg = times.iter().sum::<f64>() / times.len() as f64;
+    outln!("round trip min/avg/max = {:.2}/{:.2}/{:.2} ms", min, avg, max);
+    0
+}
+
+pub fn builtin_port(args: &[String]) -> i32 {
+    let p = match PORT.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.is_empty() { errln!("usage: port [-W SECS] HOST:PORT..."); return 2; }
+    let Some(timeout) = seconds("port", &p, "timeout", 3.0) else { return 2 };
+
+    let mut code = 0;
+    for target in &p.operands {
+        let Some((host, port)) = split_host_port(target) else {
+            errln!("port: {}: expected HOST:PORT", target);
+            code = 2;
+            continue;
+        };
+        let addr = match resolve(host, port) {
+            Ok(addr) => addr,
+            Err(e) => { errln!("port: {}: {}", host, e); code = 2; continue; }
+        };
+        let started = Instant::now();
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => outln!("{} ({}) open, connected in {:.2} ms",
+                target, addr, started.elapsed().as_secs_f64() * 1000.0),
+            Err(e) => {
+                if code == 0 { code = 1; }
+                match e.kind() {
+                    std::io::ErrorKind::ConnectionRefused => outln!("{} ({}) closed: connection refused", target, addr),
+                    std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock =>
+                        outln!("{} ({}) no answer after {:.1}s (filtered, or the host is down)", target, addr, timeout.as_secs_f64()),
+                    _ => outln!("{} ({}) unreachable: {}", target, addr, e),
+                }
+            }
+        }
+    }
+    code
+}
+
+/// `host:port`, `[v6]:port` or, with more than one colon and no brackets,
+/// a bare IPv6 address followed by `:port`.
+fn split_host_port(target: &str) -> Option<(&str, u16)> {
+    let (host, port) = match target.strip_prefix('[') {
+        Some(rest) => {
+            let (host, port) = rest.split_once(']')?;
+            (host, port.strip_prefix(':')?)
+        }
+        None => target.rsplit_once(':')?,
+    };
+    if host.is_empty() { return None; }
+    Some((host, port.parse().ok()?))
+}
+
+/// The first address for `host`, IPv4 first since that's what ICMP handles.
+fn resolve(host: &str, port: u16) -> std::io::Result<SocketAddr> {
+    let addrs: Vec<SocketAddr> = (host, port).to_socket_addrs()?.collect();
+    addrs.iter().find(|a| a.is_ipv4()).or(addrs.first()).copied()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no addresses found"))
+}
+
+/// The value of option `key`, or `default`; None (after saying so) if
+/// it doesn't parse.
+fn number<T: std::str::FromStr>(name: &str, p: &Parsed, key: &str, default: T) -> Option<T> {
+    match p.value(key) {
+        None => Some(default),
+        Some(v) => v.parse().ok().or_else(|| { errln!("{}: invalid --{} '{}'", name, key, v); None }),
+    }
+}
+
+fn seconds(name: &str, p: &Parsed, key: &str, default: f64) -> Option<Duration> {
+    let secs = number(name, p, key, default)?;
+    if !(secs > 0.0 && secs.is_finite()) {
+        errln!("{}: --{} must be a positive number of seconds", name, key);
+        return None;
+    }
+    Some(Duration::from_secs_f64(secs))
+}
+
+/// One way of asking a host whether it's there.
+enum Prober {
+    #[cfg(unix)]
+    Icmp(icmp::Socket, std::net::Ipv4Addr),
+    Tcp(SocketAddr),
+}
+
+impl Prober {
+    fn new(ip: IpAddr, port: u16) -> Self {
+        #[cfg(unix)]
+        if let IpAddr::V4(v4) = ip {
+            if let Some(socket) = icmp::Socket::open() {
+                return Prober::Icmp(socket, v4);
+            }
+        }
+        Prober::Tcp(SocketAddr::new(ip, port))
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            #[cfg(unix)]
+            Prober::Icmp(..) => "with ICMP echo".to_string(),
+            Prober::Tcp(addr) => format!("with TCP connects to port {}{}", addr.port(),
+                if addr.is_ipv4() { " (ICMP not available)" } else { "" }),
+        }
+    }
+
+    /// Some(note) for a reply, None when none came in time.
+    fn probe(&self, seq: u16, timeout: Duration) -> std::io::Result<Option<&'static str>> {
+        match self {
+            #[cfg(unix)]
+            Prober::Icmp(socket, ip) => Ok(socket.echo(*ip, seq, timeout)?.then_some("")),
+            Prober::Tcp(addr) => match TcpStream::connect_timeout(addr, timeout) {
+                Ok(_) => Ok(Some("")),
+                Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => Ok(Some(" (port closed)")),
+                Err(e) if matches!(e.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock) => Ok(None),
+                Err(e) => Err(e),
+            },
+        }
+    }
+}
+
+#[cfg(unix)]
+mod icmp {
+    use std::net::Ipv4Addr;
+    use std::time::{Duration, Instant};
+
+    const ECHO_REQUEST: u8 = 8;
+    const ECHO_REPLY: u8 = 0;
+
+    /// An ICMP socket: unprivileged "ping" datagrams where the system allows
+    /// them (the kernel fills in the identifier), raw otherwise.
+    pub struct Socket {
+        fd: libc::c_int,
+        raw: bool,
+        id: u16,
+    }
+
+    impl Socket {
+        pub fn open() -> Option<Self> {
+            for (kind, raw) in [(libc::SOCK_DGRAM, false), (libc::SOCK_RAW, true)] {
+                let fd = unsafe { libc::socket(libc::AF_INET, kind, libc::IPPROTO_ICMP) };
+                if fd >= 0 {
+                    return Some(Socket { fd, raw, id: std::process::id() as u16 });
+                }
+            }
+            None
+        }
+
+        /// Send echo request `seq` and wait for its reply.
+        pub fn echo(&self, ip: Ipv4Addr, seq: u16, timeout: Duration) -> std::io::Result<bool> {
+            let mut packet = [0u8; 64];
+            packet[0] = ECHO_REQUEST;
+            packet[4..6].copy_from_slice(&self.id.to_be_bytes());
+            packet[6..8].copy_from_slice(&seq.to_be_bytes());
+            for (i, b) in packet[8..].iter_mut().enumerate() { *b = i as u8; }
+            let sum = checksum(&packet);
+            packet[2..4].copy_from_slice(&sum.to_be_bytes());
+
+            let mut addr: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+            addr.sin_family = libc::AF_INET as libc::sa_family_t;
+            addr.sin_addr.s_addr = u32::from_ne_bytes(ip.octets());
+            let sent = unsafe {
+                libc::sendto(self.fd, packet.as_ptr().cast(), packet.len(), 0,
+                    (&addr as *const libc::sockaddr_in).cast(), std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t)
+            };
+            if sent < 0 { return Err(std::io::Error::last_os_error()); }
+
+            let deadline = Instant::now() + timeout;
+            let mut buf = [0u8; 1500];
+            loop {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() { return Ok(false); }
+                let mut pfd = libc::pollfd { fd: self.fd, events: libc::POLLIN, revents: 0 };
+                let ready = unsafe { libc::poll(&mut pfd, 1, left.as_millis().max(1) as libc::c_int) };
+                if ready < 0 {
+                    let e = std::io::Error::last_os_error();
+                    if e.kind() == std::io::ErrorKind::Interrupted { continue; }
+                    return Err(e);
+                }
+                if ready == 0 { return Ok(false); }
+
+                let mut from: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+                let mut from_len = std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+                let n = unsafe {
+                    libc::recvfrom(self.fd, buf.as_mut_ptr().cast(), buf.len(), 0,
+                        (&mut from as *mut libc::sockaddr_in).cast(), &mut from_len)
+                };
+                if n < 0 { return Err(std::io::Error::last_os_error()); }
+                if from.sin_addr.s_addr != u32::from_ne_bytes(ip.octets()) { continue; }
+                // A raw socket sees the IP header, and every ICMP message
+                // to this host, so the identifier has to be checked too
+                let reply = if self.raw { &buf[(buf[0] & 0x0f) as usize * 4..n as usize] } else { &buf[..n as usize] };
+                if reply.len() < 8 || reply[0] != ECHO_REPLY { continue; }
+                if self.raw && reply[4..6] != self.id.to_be_bytes() { continue; }
+                if reply[6..8] == seq.to_be_bytes() { return Ok(true); }
+            }
+        }
+    }
+
+    impl Drop for Socket {
+        fn drop(&mut self) {
+            unsafe { libc::close(self.fd); }
+        }
+    }
+
+    /// The Internet checksum: the ones' complement of the ones' complement
+    /// sum of 16-bit words.
+    pub(super) fn checksum(data: &[u8]) -> u16 {
+        let mut sum: u32 = data.chunks(2)
+            .map(|w| u32::from(u16::from_be_bytes([w[0], w.get(1).copied().unwrap_or(0)])))
+            .sum();
+        while sum > 0xffff { sum = (sum & 0xffff) + (sum >> 16); }
+        !(sum as u16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_and_port() {
+        assert_eq!(split_host_port("example.com:443"), Some(("example.com", 443)));
+        assert_eq!(split_host_port("[::1]:22"), Some(("::1", 22)));
+        assert_eq!(split_host_port("::1:22"), Some(("::1", 22)));
+        assert_eq!(split_host_port("example.com"), None);
+        assert_eq!(split_host_port(":80"), None);
+        assert_eq!(split_host_port("host:http"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn internet_checksum() {
+        // A correct checksum makes the whole packet sum to zero
+        let mut packet = [8u8, 0, 0, 0, 0x12, 0x34, 0, 1, b'a', b'b', b'c'];
+        let sum = icmp::checksum(&packet);
+        packet[2..4].copy_from_slice(&sum.to_be_bytes());
+        assert_eq!(icmp::checksum(&packet), 0);
+    }
+}
@@ -0,0 +1,129 @@
+// src/executor/builtin/open.rs
+// open [-a APP] FILE-OR-URL... — hand each file or link to whatever the
+// desktop opens it with: `open` on macOS, `start` on Windows and xdg-open
+// (or gio, or wslview under WSL) elsewhere. -a names the program instead.
+//
+// The opener runs detached with its output discarded, so a browser that
+// stays up doesn't hold the prompt, and a thread reaps it when it exits.
+
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use crate::shell::Shell;
+use super::util::{find_in_path, Opt, Options};
+
+pub const OPEN: Options = Options {
+    name: "open",
+    operands: "FILE-OR-URL...",
+    about: "Open each file or URL with its default application.",
+    opts: &[
+        Opt::new("a", "app", "open with APP instead").takes("APP"),
+    ],
+    numeric: None,
+};
+
+/// Openers tried in order on systems without a single standard one.
+#[cfg(not(any(target_os = "macos", windows)))]
+const OPENERS: &[&[&str]] = &[&["xdg-open"], &["gio", "open"], &["wslview"]];
+
+pub fn builtin_open(shell: &Shell, args: &[String]) -> i32 {
+    let p = match OPEN.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.is_empty() { errln!("usage: open [-a APP] FILE-OR-URL..."); return 2; }
+    let app = p.value("app");
+
+    let mut code = 0;
+    for operand in &p.operands {
+        let target = if is_url(operand) && !shell.cwd.join(operand).exists() {
+            operand.to_string()
+        } else {
+            let path = shell.cwd.join(operand);
+            if !path.exists() {
+                errln!("open: {}: No such file or directory", operand);
+                code = 1;
+                continue;
+            }
+            crate::paths::display(&path)
+        };
+        let mut command = match app {
+            Some(app) => with_app(app, &target),
+            None => match opener(&target) {
+                Some(command) => command,
+                None => {
+                    errln!("open: no opener found (install xdg-utils, or use -a APP)");
+                    return 1;
+                }
+            },
+        };
+        command.current_dir(&shell.cwd).stdin(Stdio::null()).stdout(Stdio::null());
+        match command.spawn() {
+            Ok(mut child) => { std::thread::spawn(move || child.wait()); }
+            Err(e) => {
+                errln!("open: {}: {}", command.get_program().to_string_lossy(), e);
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+/// A URL scheme like `https:` or `mailto:`; a Windows drive (`C:`) isn't one.
+fn is_url(s: &str) -> bool {
+    match s.split_once(':') {
+        Some((scheme, _)) => scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)),
+        None => false,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn opener(target: &str) -> Option<Command> {
+    let mut command = Command::new("open");
+    command.arg(target);
+    Some(command)
+}
+
+#[cfg(windows)]
+fn opener(target: &str) -> Option<Command> {
+    use std::os::windows::process::CommandExt;
+    // start's first quoted argument is a window title, so give it an empty one
+    let mut command = Command::new("cmd");
+    command.raw_arg(format!("/c start \"\" \"{}\"", target));
+    Some(command)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn opener(target: &str) -> Option<Command> {
+    let words = OPENERS.iter().find(|words| !find_in_path(words[0]).is_empty())?;
+    let mut command = Command::new(words[0]);
+    command.args(&words[1..]).arg(target);
+    Some(command)
+}
+
+fn with_app(app: &str, target: &str) -> Command {
+    if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.args(["-a", app, target]);
+        return command;
+    }
+    // A name on PATH, or a path to the program itself
+    let program = find_in_path(app).into_iter().next().unwrap_or_else(|| PathBuf::from(app));
+    let mut command = Command::new(program);
+    command.arg(target);
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_and_paths() {
+        assert!(is_url("https://example.com/a?b=c"));
+        assert!(is_url("mailto:someone@example.com"));
+        assert!(is_url("vscode://file/tmp"));
+        assert!(!is_url("C:\\Users\\me\\notes.txt"));
+        assert!(!is_url("notes.txt"));
+        assert!(!is_url("./odd:name"));
+    }
+}
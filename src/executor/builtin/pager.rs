@@ -0,0 +1,299 @@
+// src/executor/builtin/pager.rs
+// Built-in pager for long builtin output (help, functions, alias).
+//
+// Output that fits on one screen, or that isn't going to a terminal, is
+// printed as-is (colours stripped when piped). Anything longer opens a
+// small less-style viewer:
+//
+//   ↑ ↓ j k  Enter     scroll a line
+//   Space f  PageDown  next page        b  PageUp   previous page
+//   g Home / G End     top / bottom
+//   /text              search (case-insensitive), n / N next / previous
+//   q Esc              quit
+//
+// Lines wider than the terminal are wrapped onto as many rows as they
+// take, so a page is always one screen.
+
+use std::io::{self, Write};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use unicode_width::UnicodeWidthChar;
+use crate::ansi::strip_ansi;
+
+/// Show `text` (which may contain ANSI colours), paging it if it is taller
+/// than the terminal.
+pub fn page(title: &str, text: &str) {
+    if !super::io::stdout_is_terminal() {
+        out!("{}", strip_ansi(text));
+        return;
+    }
+    let mut stdout = io::stdout();
+
+    // An unknown (zero) terminal height means there's nothing to page against
+    let (cols, rows) = terminal::size().map(|(c, r)| (c as usize, r as usize)).unwrap_or((0, 0));
+    let lines: Vec<String> = text.lines().flat_map(|l| wrap(l, cols)).collect();
+    if rows < 2 || lines.len() < rows {
+        out!("{}", text);
+        stdout.flush().ok();
+        return;
+    }
+
+    terminal::enable_raw_mode().ok();
+    execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide).ok();
+    Viewer { title, lines: &lines, top: 0, query: String::new(), message: None }.run(&mut stdout);
+    execute!(stdout, cursor::Show, terminal::LeaveAlternateScreen).ok();
+    terminal::disable_raw_mode().ok();
+}
+
+/// Keep only the entries whose (uncoloured) text contains `term`,
+/// case-insensitively. Entries are lines for help, or whole definitions
+/// for functions and aliases.
+pub fn filter_entries(entries: Vec<String>, term: &str) -> Vec<String> {
+    let term = term.to_lowercase();
+    entries.into_iter()
+        .filter(|e| strip_ansi(e).to_lowercase().contains(&term))
+        .collect()
+}
+
+/// Parse a `--search PAT` / `--search=PAT` option out of `args`, returning
+/// the pattern and the remaining arguments.
+pub fn take_search(args: &[String]) -> Result<(Option<String>, Vec<String>), String> {
+    let mut search = None;
+    let mut rest   = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        let arg = &args[i];
+        if arg == "--search" {
+            i += 1;
+            match args.get(i) {
+                Some(p) => search = Some(p.clone()),
+                None    => return Err(format!("{} requires a pattern", arg)),
+            }
+        } else if let Some(p) = arg.strip_prefix("--search=") {
+            search = Some(p.to_string());
+        } else {
+            rest.push(arg.clone());
+        }
+        i += 1;
+    }
+    Ok((search, rest))
+}
+
+/// Colour a line of shell code: keywords blue, quoted strings green,
+/// comments grey.
+pub fn highlight_shell(line: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "if", "then", "else", "elif", "fi", "for", "in", "do", "done",
+        "while", "function", "return", "local", "export",
+    ];
+    let mut out  = String::new();
+    let mut word = String::new();
+    let flush = |word: &mut String, out: &mut String| {
+        if KEYWORDS.contains(&word.as_str()) {
+            out.push_str(&format!("\x1b[34m{}\x1b[0m", word));
+        } else {
+            out.push_str(word);
+        }
+        word.clear();
+    };
+
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' if word.is_empty() => {
+                let rest: String = std::iter::once(c).chain(chars.by_ref()).collect();
+                out.push_str(&format!("\x1b[90m{}\x1b[0m", rest));
+            }
+            '\'' | '"' => {
+                flush(&mut word, &mut out);
+                let mut quoted = String::from(c);
+                for ch in chars.by_ref() {
+                    quoted.push(ch);
+                    if ch == c { break; }
+                }
+                out.push_str(&format!("\x1b[32m{}\x1b[0m", quoted));
+            }
+            c if c.is_alphanumeric() || c == '_' => word.push(c),
+            c => { flush(&mut word, &mut out); out.push(c); }
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
+
+/// Split `line` into the rows it takes on a terminal `cols` wide. Colours
+/// set before a break carry on into the next row.
+fn wrap(line: &str, cols: usize) -> Vec<String> {
+    let mut rows    = Vec::new();
+    let mut row     = String::new();
+    let mut width   = 0;
+    let mut colours = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // CSI: ESC [ params final-byte; anything else is ESC and one more
+            let mut seq = String::from(c);
+            if let Some(next) = chars.next() {
+                seq.push(next);
+                if next == '[' {
+                    for c in chars.by_ref() {
+                        seq.push(c);
+                        if ('@'..='~').contains(&c) { break; }
+                    }
+                }
+            }
+            row.push_str(&seq);
+            colours.push_str(&seq);
+            continue;
+        }
+        let w = c.width().unwrap_or(0);
+        if cols > 0 && width > 0 && width + w > cols {
+            rows.push(std::mem::replace(&mut row, colours.clone()));
+            width = 0;
+        }
+        row.push(c);
+        width += w;
+    }
+    rows.push(row);
+    rows
+}
+
+// ── Viewer ────────────────────────────────────────────────────────────────────
+
+struct Viewer<'a> {
+    title:   &'a str,
+    lines:   &'a [String],
+    top:     usize,
+    query:   String,
+    message: Option<String>,
+}
+
+impl Viewer<'_> {
+    fn run(&mut self, stdout: &mut io::Stdout) {
+        loop {
+            self.render(stdout);
+            let Ok(Event::Key(key)) = event::read() else { continue };
+            if key.kind != event::KeyEventKind::Press { continue; }
+
+            let page = self.height().saturating_sub(1).max(1);
+            self.message = None;
+            match (key.modifiers, key.code) {
+                (_, KeyCode::Char('q') | KeyCode::Esc)                  => break,
+                (KeyModifiers::CONTROL, KeyCode::Char('c'))             => break,
+                (_, KeyCode::Down | KeyCode::Enter | KeyCode::Char('j')) => self.scroll(1),
+                (_, KeyCode::Up | KeyCode::Char('k'))                   => self.top = self.top.saturating_sub(1),
+                (_, KeyCode::PageDown | KeyCode::Char(' ') | KeyCode::Char('f')) => self.scroll(page),
+                (_, KeyCode::PageUp | KeyCode::Char('b'))               => self.top = self.top.saturating_sub(page),
+                (_, KeyCode::Home | KeyCode::Char('g'))                 => self.top = 0,
+                (_, KeyCode::End | KeyCode::Char('G'))                  => self.top = self.max_top(),
+                (_, KeyCode::Char('/')) => {
+                    if let Some(q) = self.prompt(stdout) {
+                        self.query = q;
+                        self.find(self.top, true);
+                    }
+                }
+                (_, KeyCode::Char('n')) => self.find(self.top + 1, true),
+                (_, KeyCode::Char('N')) => self.find(self.top.saturating_sub(1), false),
+                _ => {}
+            }
+        }
+    }
+
+    /// Rows available for text; the last row is the status bar.
+    fn height(&self) -> usize {
+        terminal::size().map(|(_, r)| r as usize).unwrap_or(24).saturating_sub(1)
+    }
+
+    fn max_top(&self) -> usize {
+        self.lines.len().saturating_sub(self.height())
+    }
+
+    fn scroll(&mut self, n: usize) {
+        self.top = (self.top + n).min(self.max_top());
+    }
+
+    /// Move the first line matching the query at or after (or before)
+    /// `from` to the top of the screen.
+    fn find(&mut self, from: usize, forward: bool) {
+        if self.query.is_empty() { return; }
+        let q = self.query.to_lowercase();
+        let matches = |i: &usize| strip_ansi(&self.lines[*i]).to_lowercase().contains(&q);
+        let hit = if forward {
+            (from..self.lines.len()).find(matches)
+        } else {
+            (0..=from.min(self.lines.len().saturating_sub(1))).rev().find(matches)
+        };
+        match hit {
+            Some(i) => self.top = i,
+            None    => self.message = Some(format!("Pattern not found: {}", self.query)),
+        }
+    }
+
+    /// Read a search pattern on the status line. None if cancelled.
+    fn prompt(&self, stdout: &mut io::Stdout) -> Option<String> {
+        let mut input = String::new();
+        let row = self.height() as u16;
+        loop {
+            queue!(stdout,
+                cursor::MoveTo(0, row),
+                terminal::Clear(ClearType::CurrentLine),
+                Print(format!("/{}", input)),
+            ).ok();
+            stdout.flush().ok();
+
+            let Ok(Event::Key(key)) = event::read() else { continue };
+            if key.kind != event::KeyEventKind::Press { continue; }
+            match key.code {
+                KeyCode::Enter     => return Some(input),
+                KeyCode::Esc       => return None,
+                // Backspace on an empty prompt cancels, as in less
+                KeyCode::Backspace => { input.pop()?; }
+                KeyCode::Char(c)   => input.push(c),
+                _ => {}
+            }
+        }
+    }
+
+    fn render(&self, stdout: &mut io::Stdout) {
+        let (cols, _) = terminal::size().unwrap_or((80, 24));
+        queue!(stdout, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0)).ok();
+
+        let height = self.height();
+        for (i, line) in self.lines.iter().skip(self.top).take(height).enumerate() {
+            queue!(stdout, cursor::MoveTo(0, i as u16), Print(line), Print("\x1b[0m")).ok();
+        }
+
+        let last = (self.top + height).min(self.lines.len());
+        let status = match &self.message {
+            Some(m) => format!(" {}", m),
+            None => format!(
+                " {} — lines {}-{} of {}  (Space page  / search  n next  q quit)",
+                self.title, self.top + 1, last, self.lines.len()
+            ),
+        };
+        queue!(stdout,
+            cursor::MoveTo(0, height as u16),
+            Print(format!("\x1b[7m{:<width$}\x1b[0m", status, width = cols as usize)),
+        ).ok();
+        stdout.flush().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_lines_wrap_by_width() {
+        assert_eq!(wrap("abcdefg", 3), ["abc", "def", "g"]);
+        assert_eq!(wrap("", 3), [""]);
+        // Colours take no room and carry on; wide characters take two
+        assert_eq!(wrap("\x1b[1mabcd\x1b[0m", 3), ["\x1b[1mabc", "\x1b[1md\x1b[0m"]);
+        assert_eq!(wrap("日本語", 4), ["日本", "語"]);
+    }
+}
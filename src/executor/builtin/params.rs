@@ -0,0 +1,150 @@
+// src/executor/builtin/params.rs
+// Positional parameter builtins: shift, getopts
+
+use crate::shell::Shell;
+
+/// shift [n] — drop the first n positional parameters.
+pub fn builtin_shift(shell: &mut Shell, args: &[String]) -> i32 {
+    let n = match super::util::operands(args).first() {
+        None    => 1,
+        Some(n) => match n.parse::<usize>() {
+            Ok(n)  => n,
+            Err(_) => { errln!("shift: {}: numeric argument required", n); return 1; }
+        },
+    };
+
+    let params = shell.positional_args();
+    if n > params.len() {
+        errln!("shift: shift count out of range");
+        return 1;
+    }
+
+    for i in 1..=9 {
+        match params.get(i - 1 + n) {
+            Some(v) => { shell.set_var(&i.to_string(), &v.clone()); }
+            None    => { shell.unset_var(&i.to_string()); }
+        }
+    }
+    0
+}
+
+/// getopts OPTSTRING NAME [ARG ...]
+///
+/// Parses the next option from ARGs (or the positional parameters) into
+/// NAME, leaving its argument in OPTARG and the next index in OPTIND.
+/// Returns 1 once the options are exhausted. A leading ':' in OPTSTRING
+/// selects silent error reporting, as in POSIX.
+pub fn builtin_getopts(shell: &mut Shell, args: &[String]) -> i32 {
+    if args.len() < 3 { errln!("usage: getopts <optstring> <name> [args ...]"); return 2; }
+    let optstring = args[1].as_str();
+    let name      = args[2].as_str();
+    let list: Vec<String> = if args.len() > 3 { args[3..].to_vec() } else { shell.positional_args() };
+
+    let silent = optstring.starts_with(':');
+    let mut optind = shell.env.get("OPTIND").and_then(|v| v.parse::<usize>().ok()).unwrap_or(1).max(1);
+
+    let Some(arg) = list.get(optind - 1).cloned() else {
+        return finish(shell, name, optind);
+    };
+    // A script that resets OPTIND, or calls getopts on other arguments,
+    // starts a fresh scan
+    let (pos_optind, pos_arg, pos_offset) = &shell.getopts_pos;
+    let resumes = *pos_optind == optind && *pos_arg == arg && *pos_offset < arg.chars().count();
+    let mut offset = if resumes { *pos_offset } else { 0 };
+    if offset == 0 {
+        if arg == "--" { return finish(shell, name, optind + 1); }
+        if !arg.starts_with('-') || arg == "-" { return finish(shell, name, optind); }
+        offset = 1;
+    }
+
+    let chars: Vec<char> = arg.chars().collect();
+    let opt = chars[offset];
+    offset += 1;
+    let at_end = offset >= chars.len();
+
+    let spec = optstring.trim_start_matches(':');
+    let takes_arg = match spec.find(opt).filter(|_| opt != ':') {
+        Some(pos) => spec[pos + opt.len_utf8()..].starts_with(':'),
+        None => {
+            if silent {
+                shell.set_var("OPTARG", &opt.to_string());
+            } else {
+                errln!("getopts: illegal option -- {}", opt);
+                shell.unset_var("OPTARG");
+            }
+            shell.set_var(name, "?");
+            return advance(shell, optind, &arg, offset, at_end);
+        }
+    };
+
+    if !takes_arg {
+        shell.unset_var("OPTARG");
+        shell.set_var(name, &opt.to_string());
+        return advance(shell, optind, &arg, offset, at_end);
+    }
+
+    // The argument is either the rest of this word (-ofile) or the next one
+    if !at_end {
+        shell.set_var("OPTARG", &chars[offset..].iter().collect::<String>());
+    } else if let Some(value) = list.get(optind) {
+        shell.set_var("OPTARG", &value.clone());
+        optind += 1;
+    } else {
+        if silent {
+            shell.set_var(name, ":");
+            shell.set_var("OPTARG", &opt.to_string());
+        } else {
+            errln!("getopts: option requires an argument -- {}", opt);
+            shell.set_var(name, "?");
+            shell.unset_var("OPTARG");
+        }
+        return advance(shell, optind, &arg, 0, true);
+    }
+    shell.set_var(name, &opt.to_string());
+    advance(shell, optind, &arg, 0, true)
+}
+
+/// Record progress after returning an option: move to the next argument
+/// once this one is used up, otherwise remember the offset within it.
+fn advance(shell: &mut Shell, optind: usize, arg: &str, offset: usize, at_end: bool) -> i32 {
+    let (optind, offset) = if at_end { (optind + 1, 0) } else { (optind, offset) };
+    shell.set_var("OPTIND", &optind.to_string());
+    shell.getopts_pos = (optind, arg.to_string(), offset);
+    0
+}
+
+/// No more options: NAME becomes '?' and OPTIND points at the first operand.
+fn finish(shell: &mut Shell, name: &str, optind: usize) -> i32 {
+    shell.set_var(name, "?");
+    shell.set_var("OPTIND", &optind.to_string());
+    shell.getopts_pos = (optind, String::new(), 0);
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn getopts(shell: &mut Shell, words: &[&str]) -> (i32, String) {
+        let mut args = vec!["getopts".to_string(), "ab".to_string(), "o".to_string()];
+        args.extend(words.iter().map(|w| w.to_string()));
+        let code = builtin_getopts(shell, &args);
+        (code, shell.env.get("o").cloned().unwrap_or_default())
+    }
+
+    #[test]
+    fn new_arguments_restart_a_cluster() {
+        let mut shell = Shell::new();
+        shell.set_var("OPTIND", "1");
+        assert_eq!(getopts(&mut shell, &["-ab"]), (0, "a".to_string()));
+        // OPTIND is still 1, halfway through -ab, when other arguments come
+        assert_eq!(getopts(&mut shell, &["-x"]), (0, "?".to_string()));
+        assert_eq!(shell.env["OPTIND"], "2");
+
+        shell.set_var("OPTIND", "1");
+        assert_eq!(getopts(&mut shell, &["-ab"]), (0, "a".to_string()));
+        assert_eq!(getopts(&mut shell, &["-ba"]), (0, "b".to_string()));
+        assert_eq!(getopts(&mut shell, &["-ba"]), (0, "a".to_string()));
+        assert_eq!(getopts(&mut shell, &["-ba"]).0, 1);
+    }
+}
@@ -0,0 +1,207 @@
+// src/executor/builtin/pathname.rs
+// Path names: basename, dirname, realpath
+//
+// basename and dirname only work on the text of the name, the way POSIX
+// describes them, so they never touch the filesystem. realpath does: it
+// resolves symlinks and `..` against what is actually on disk, and with -s
+// only tidies the name.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::paths;
+use crate::shell::Shell;
+use super::util::{Opt, Options};
+
+pub const BASENAME: Options = Options {
+    name: "basename",
+    operands: "NAME [SUFFIX]",
+    about: "Print NAME without its directory, and without SUFFIX if it ends with one.",
+    opts: &[
+        Opt::new("a", "multiple", "take several NAMEs"),
+        Opt::new("s", "suffix", "remove SUFFIX (implies -a)").takes("SUFFIX"),
+    ],
+    numeric: None,
+};
+
+pub const DIRNAME: Options = Options {
+    name: "dirname",
+    operands: "NAME...",
+    about: "Print each NAME without its last component (. if there is no directory part).",
+    opts: &[],
+    numeric: None,
+};
+
+pub const REALPATH: Options = Options {
+    name: "realpath",
+    operands: "PATH...",
+    about: "Print the absolute path of each PATH, with symlinks, . and .. resolved.",
+    opts: &[
+        Opt::new("e", "canonicalize-existing", "every part of the path must exist"),
+        Opt::new("s", "no-symlinks", "don't resolve symlinks, only . and .."),
+        Opt::new("", "relative-to", "print the path relative to DIR").takes("DIR"),
+    ],
+    numeric: None,
+};
+
+pub fn builtin_basename(args: &[String]) -> i32 {
+    let p = match BASENAME.parse(args) { Ok(p) => p, Err(code) => return code };
+    let (names, suffix) = match (p.value("suffix"), p.has("multiple")) {
+        (Some(suffix), _) => (&p.operands[..], suffix),
+        (None, true) => (&p.operands[..], ""),
+        (None, false) => match p.operands.as_slice() {
+            [_] => (&p.operands[..1], ""),
+            [_, suffix] => (&p.operands[..1], *suffix),
+            _ => { errln!("usage: basename NAME [SUFFIX]  (or -a NAME...)"); return 2; }
+        },
+    };
+    if names.is_empty() { errln!("basename: missing operand"); return 2; }
+    for name in names {
+        outln!("{}", base_name(name, suffix));
+    }
+    0
+}
+
+pub fn builtin_dirname(args: &[String]) -> i32 {
+    let p = match DIRNAME.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.is_empty() { errln!("dirname: missing operand"); return 2; }
+    for name in &p.operands {
+        outln!("{}", dir_name(name));
+    }
+    0
+}
+
+pub fn builtin_realpath(shell: &Shell, args: &[String]) -> i32 {
+    let p = match REALPATH.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.is_empty() { errln!("realpath: missing operand"); return 2; }
+    let resolve = |path: &str| -> std::io::Result<PathBuf> {
+        let path = shell.cwd.join(path);
+        match (p.has("no-symlinks"), p.has("canonicalize-existing")) {
+            (true, _) => Ok(tidy(&path)),
+            (false, true) => std::fs::canonicalize(&path),
+            (false, false) => resolve_existing(&path),
+        }
+    };
+    let base = match p.value("relative-to").map(resolve).transpose() {
+        Ok(base) => base,
+        Err(e) => { errln!("realpath: {}: {}", p.value("relative-to").unwrap_or(""), e); return 1; }
+    };
+
+    let mut code = 0;
+    for operand in &p.operands {
+        match resolve(operand) {
+            Ok(path) => {
+                let path = match &base { Some(base) => relative(&path, base), None => path };
+                outln!("{}", paths::display(&path));
+            }
+            Err(e) => { errln!("realpath: {}: {}", operand, e); code = 1; }
+        }
+    }
+    code
+}
+
+/// Whether `c` separates path components here: `/`, and `\` on Windows.
+fn is_sep(c: char) -> bool {
+    c == '/' || (cfg!(windows) && c == '\\')
+}
+
+/// The last component of `name`, less `suffix` unless that is all of it.
+fn base_name(name: &str, suffix: &str) -> String {
+    let trimmed = name.trim_end_matches(is_sep);
+    if trimmed.is_empty() {
+        return if name.is_empty() { String::new() } else { "/".to_string() };
+    }
+    let base = trimmed.rsplit(is_sep).next().unwrap_or(trimmed);
+    match base.strip_suffix(suffix) {
+        Some(stem) if !suffix.is_empty() && !stem.is_empty() => stem.to_string(),
+        _ => base.to_string(),
+    }
+}
+
+/// `name` without its last component.
+fn dir_name(name: &str) -> String {
+    let trimmed = name.trim_end_matches(is_sep);
+    if trimmed.is_empty() {
+        return if name.is_empty() { ".".to_string() } else { "/".to_string() };
+    }
+    match trimmed.rfind(is_sep) {
+        None => ".".to_string(),
+        Some(i) => {
+            let dir = trimmed[..i].trim_end_matches(is_sep);
+            if dir.is_empty() { "/".to_string() } else { dir.to_string() }
+        }
+    }
+}
+
+/// `path` with `.` dropped and each `..` taking off the component before
+/// it, without looking at the filesystem.
+fn tidy(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for part in path.components() {
+        match part {
+            Component::CurDir => {}
+            Component::ParentDir => { out.pop(); }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Like canonicalize, except that the end of the path needn't exist: the
+/// part that does is resolved, and the rest added on tidied.
+fn resolve_existing(path: &Path) -> std::io::Result<PathBuf> {
+    let mut existing = path;
+    let mut rest = Vec::new();
+    loop {
+        match std::fs::canonicalize(existing) {
+            Ok(mut resolved) => {
+                resolved.extend(rest.iter().rev());
+                return Ok(tidy(&resolved));
+            }
+            Err(e) => match (existing.parent(), existing.file_name()) {
+                (Some(parent), Some(name)) => { rest.push(name); existing = parent; }
+                _ => return Err(e),
+            },
+        }
+    }
+}
+
+/// `path` relative to the directory `base`, both absolute.
+fn relative(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let shared = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let mut out: PathBuf = base[shared..].iter().map(|_| Component::ParentDir).collect();
+    out.extend(&path[shared..]);
+    if out.as_os_str().is_empty() { PathBuf::from(".") } else { out }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basenames() {
+        assert_eq!(base_name("/usr/lib/libc.so", ""), "libc.so");
+        assert_eq!(base_name("/usr/lib/", ""), "lib");
+        assert_eq!(base_name("main.rs", ".rs"), "main");
+        assert_eq!(base_name(".rs", ".rs"), ".rs");
+        assert_eq!(base_name("//", ""), "/");
+    }
+
+    #[test]
+    fn dirnames() {
+        assert_eq!(dir_name("/usr/lib/libc.so"), "/usr/lib");
+        assert_eq!(dir_name("/usr/lib//"), "/usr");
+        assert_eq!(dir_name("main.rs"), ".");
+        assert_eq!(dir_name("/usr"), "/");
+        assert_eq!(dir_name("/"), "/");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tidies_and_relates() {
+        assert_eq!(tidy(Path::new("/a/./b/../c")), PathBuf::from("/a/c"));
+        assert_eq!(relative(Path::new("/a/b/c"), Path::new("/a/d")), PathBuf::from("../b/c"));
+        assert_eq!(relative(Path::new("/a"), Path::new("/a")), PathBuf::from("."));
+    }
+}
@@ -121,8 +121,8 @@ fn unpack_tar_with_progress<R: std::io::Read>(
         let mut entry = entry?;
         entry.unpack_in(dest)?;
         count += 1;
-        print!("\r   {} files extracted...", count);
-        std::io::stdout().flush().ok();
+        out!("\r   {} files extracted...", count);
+        super::super::io::stdout().flush().ok();
     }
     clear_progress_line();
     Ok(())
@@ -8,7 +8,10 @@
 //   builtin_pkg()        — `pkg <subcommand>` entry point
 //   builtin_install()    — `install <name>` shorthand
 //   builtin_uninstall()  — `uninstall <name>` shorthand
+//   SUBCOMMANDS, available_packages(), installed_packages() — for completion
 //   rshell_bin_dir()     — re-exported for the shell's PATH resolution
+//   rshell_dir()         — re-exported for the shell's config location
+//   download_progress_line() — the download bar, for `fetch`
 
 mod install;
 mod meta;
@@ -16,16 +19,27 @@ mod paths;
 mod progress;
 mod registry;
 
-pub use paths::rshell_bin_dir;
+pub use paths::{rshell_bin_dir, rshell_dir};
+pub use progress::download_progress_line;
 
 use install::{collect_files, create_shim, download, extract};
 use meta::{read_meta, write_meta, Meta};
 use paths::{package_dir, rshell_packages_dir};
 use progress::{clear_progress_line, print_uninstall_progress};
-use registry::{fetch_registry, platform_pkg};
+use registry::{cached_registry, fetch_registry, platform_pkg};
 
 // ── Public entry points ───────────────────────────────────────────────────────
 
+/// Each subcommand, with what it takes and what it does.
+pub const SUBCOMMANDS: &[(&str, &str, &str)] = &[
+    ("install",   "<name>",  "install a package"),
+    ("uninstall", "<name>",  "remove a package"),
+    ("upgrade",   "[name]",  "upgrade one or all packages"),
+    ("list",      "",        "show installed packages"),
+    ("search",    "[query]", "search available packages"),
+    ("update",    "",        "refresh the package registry"),
+];
+
 pub fn builtin_pkg(args: &[String]) -> i32 {
     match args.get(1).map(|s| s.as_str()) {
         Some("install")   => cmd_install(args.get(2).map(|s| s.as_str())),
@@ -35,15 +49,12 @@ pub fn builtin_pkg(args: &[String]) -> i32 {
         Some("upgrade")   => cmd_upgrade(args.get(2).map(|s| s.as_str())),
         Some("search")    => cmd_search(args.get(2).map(|s| s.as_str())),
         _ => {
-            println!("usage: pkg <command> [package]");
-            println!();
-            println!("commands:");
-            println!("  pkg install <name>     install a package");
-            println!("  pkg uninstall <name>   remove a package");
-            println!("  pkg upgrade [name]     upgrade one or all packages");
-            println!("  pkg list               show installed packages");
-            println!("  pkg search [query]     search available packages");
-            println!("  pkg update             refresh the package registry");
+            outln!("usage: pkg <command> [package]");
+            outln!();
+            outln!("commands:");
+            for (name, operands, about) in SUBCOMMANDS {
+                outln!("  {:<23}{}", format!("pkg {} {}", name, operands).trim_end(), about);
+            }
             1
         }
     }
@@ -57,48 +68,73 @@ pub fn builtin_uninstall(args: &[String]) -> i32 {
     cmd_uninstall(args.get(1).map(|s| s.as_str()))
 }
 
+/// The packages in the cached registry, each with its version and
+/// description. Never fetches: completion can't wait on the network.
+pub fn available_packages() -> Vec<(String, String)> {
+    let Some(registry) = cached_registry() else { return vec![] };
+    let mut packages: Vec<(String, String)> = registry.packages.into_iter()
+        .map(|(name, pkg)| (name, format!("{} {}", pkg.version, pkg.description)))
+        .collect();
+    packages.sort();
+    packages
+}
+
+/// The packages installed, each with its installed version.
+pub fn installed_packages() -> Vec<(String, String)> {
+    let Ok(entries) = std::fs::read_dir(rshell_packages_dir()) else { return vec![] };
+    let mut packages: Vec<(String, String)> = entries.flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| {
+            let version = read_meta(&e.path()).map(|m| format!("{} installed", m.version)).unwrap_or_else(|_| "installed".to_string());
+            (e.file_name().to_string_lossy().to_string(), version)
+        })
+        .collect();
+    packages.sort();
+    packages
+}
+
 // ── Commands ──────────────────────────────────────────────────────────────────
 
 fn cmd_install(name: Option<&str>) -> i32 {
     let name = match name {
         Some(n) => n,
-        None    => { eprintln!("pkg install: package name required"); return 1; }
+        None    => { errln!("pkg install: package name required"); return 1; }
     };
 
-    println!("📦 Fetching registry...");
+    outln!("📦 Fetching registry...");
     let registry = match fetch_registry() {
         Ok(r)  => r,
-        Err(e) => { eprintln!("pkg: failed to fetch registry: {}", e); return 1; }
+        Err(e) => { errln!("pkg: failed to fetch registry: {}", e); return 1; }
     };
 
     let pkg = match registry.packages.get(name) {
         Some(p) => p,
         None    => {
-            eprintln!("pkg: unknown package '{}'. Run 'pkg search' to see available packages.", name);
+            errln!("pkg: unknown package '{}'. Run 'pkg search' to see available packages.", name);
             return 1;
         }
     };
 
     let install_dir = package_dir(name);
     if install_dir.exists() {
-        println!("✅ {} is already installed ({})", name, pkg.version);
+        outln!("✅ {} is already installed ({})", name, pkg.version);
         return 0;
     }
 
     let platform = match platform_pkg(pkg) {
         Some(p) => p,
-        None    => { eprintln!("pkg: no binary available for this platform"); return 1; }
+        None    => { errln!("pkg: no binary available for this platform"); return 1; }
     };
 
-    println!("⬇️  Downloading {} {}...", name, pkg.version);
+    outln!("⬇️  Downloading {} {}...", name, pkg.version);
     let archive = match download(&platform.url) {
         Ok(b)  => b,
-        Err(e) => { eprintln!("\npkg: download failed: {}", e); return 1; }
+        Err(e) => { errln!("\npkg: download failed: {}", e); return 1; }
     };
 
-    println!("📂 Extracting...");
+    outln!("📂 Extracting...");
     if let Err(e) = extract(&archive, &platform.url, &install_dir) {
-        eprintln!("\npkg: extraction failed: {}", e);
+        errln!("\npkg: extraction failed: {}", e);
         let _ = std::fs::remove_dir_all(&install_dir);
         return 1;
     }
@@ -109,28 +145,28 @@ fn cmd_install(name: Option<&str>) -> i32 {
         bins:    platform.bins.clone(),
     };
     if let Err(e) = write_meta(&install_dir, &meta) {
-        eprintln!("pkg: warning: could not write metadata: {}", e);
+        errln!("pkg: warning: could not write metadata: {}", e);
     }
 
-    println!("🔗 Creating shims...");
+    outln!("🔗 Creating shims...");
     for bin in &platform.bins {
         if let Err(e) = create_shim(&install_dir, bin) {
-            eprintln!("pkg: warning: could not create shim for {}: {}", bin.shim, e);
+            errln!("pkg: warning: could not create shim for {}: {}", bin.shim, e);
         }
     }
 
-    println!("✅ Installed {} {}", name, pkg.version);
+    outln!("✅ Installed {} {}", name, pkg.version);
 
     let shim_names: Vec<&str> = platform.bins.iter()
         .map(|b| b.shim.trim_end_matches(".exe").trim_end_matches(".cmd"))
         .collect();
-    println!("   Available commands: {}", shim_names.join(", "));
+    outln!("   Available commands: {}", shim_names.join(", "));
 
     if name == "zig" {
-        println!();
-        println!("   💡 Use Zig as a C/C++ compiler:");
-        println!("      zig cc   hello.c   -o hello");
-        println!("      zig c++  hello.cpp -o hello");
+        outln!();
+        outln!("   💡 Use Zig as a C/C++ compiler:");
+        outln!("      zig cc   hello.c   -o hello");
+        outln!("      zig c++  hello.cpp -o hello");
     }
 
     0
@@ -139,12 +175,12 @@ fn cmd_install(name: Option<&str>) -> i32 {
 fn cmd_uninstall(name: Option<&str>) -> i32 {
     let name = match name {
         Some(n) => n,
-        None    => { eprintln!("pkg uninstall: package name required"); return 1; }
+        None    => { errln!("pkg uninstall: package name required"); return 1; }
     };
 
     let install_dir = package_dir(name);
     if !install_dir.exists() {
-        eprintln!("pkg: {} is not installed", name);
+        errln!("pkg: {} is not installed", name);
         return 1;
     }
 
@@ -168,7 +204,7 @@ fn cmd_uninstall(name: Option<&str>) -> i32 {
     let total = files.len();
 
     if total > 0 {
-        println!("🗑️  Removing {} files...", total);
+        outln!("🗑️  Removing {} files...", total);
         for (i, path) in files.iter().enumerate() {
             let filename = path.file_name()
                 .and_then(|n| n.to_str())
@@ -180,14 +216,14 @@ fn cmd_uninstall(name: Option<&str>) -> i32 {
     }
 
     let _ = std::fs::remove_dir_all(&install_dir);
-    println!("✅ Uninstalled {}", name);
+    outln!("✅ Uninstalled {}", name);
     0
 }
 
 fn cmd_list() -> i32 {
     let packages_dir = rshell_packages_dir();
     if !packages_dir.exists() {
-        println!("No packages installed.");
+        outln!("No packages installed.");
         return 0;
     }
 
@@ -198,13 +234,13 @@ fn cmd_list() -> i32 {
         .collect();
 
     if entries.is_empty() {
-        println!("No packages installed.");
+        outln!("No packages installed.");
         return 0;
     }
 
     entries.sort_by_key(|e| e.file_name());
-    println!("{:<20} {:<12} {}", "NAME", "VERSION", "COMMANDS");
-    println!("{}", "-".repeat(55));
+    outln!("{:<20} {:<12} {}", "NAME", "VERSION", "COMMANDS");
+    outln!("{}", "-".repeat(55));
 
     for entry in entries {
         let name    = entry.file_name().to_string_lossy().to_string();
@@ -216,32 +252,32 @@ fn cmd_list() -> i32 {
                 .collect::<Vec<_>>()
                 .join(", "))
             .unwrap_or_default();
-        println!("{:<20} {:<12} {}", name, version, cmds);
+        outln!("{:<20} {:<12} {}", name, version, cmds);
     }
     0
 }
 
 fn cmd_update() -> i32 {
-    println!("🔄 Refreshing registry...");
+    outln!("🔄 Refreshing registry...");
     let cache = paths::registry_cache_path();
     let _ = std::fs::remove_file(&cache);
     match fetch_registry() {
-        Ok(r)  => { println!("✅ Registry updated ({} packages available)", r.packages.len()); 0 }
-        Err(e) => { eprintln!("pkg: failed to update registry: {}", e); 1 }
+        Ok(r)  => { outln!("✅ Registry updated ({} packages available)", r.packages.len()); 0 }
+        Err(e) => { errln!("pkg: failed to update registry: {}", e); 1 }
     }
 }
 
 fn cmd_upgrade(name: Option<&str>) -> i32 {
     let registry = match fetch_registry() {
         Ok(r)  => r,
-        Err(e) => { eprintln!("pkg: failed to fetch registry: {}", e); return 1; }
+        Err(e) => { errln!("pkg: failed to fetch registry: {}", e); return 1; }
     };
 
     let packages_dir = rshell_packages_dir();
     let to_upgrade: Vec<String> = match name {
         Some(n) => vec![n.to_string()],
         None    => {
-            if !packages_dir.exists() { println!("No packages installed."); return 0; }
+            if !packages_dir.exists() { outln!("No packages installed."); return 0; }
             std::fs::read_dir(&packages_dir)
                 .unwrap_or_else(|_| panic!("could not read packages dir"))
                 .flatten()
@@ -254,27 +290,27 @@ fn cmd_upgrade(name: Option<&str>) -> i32 {
     let mut upgraded = 0;
     for pkg_name in &to_upgrade {
         let install_dir = package_dir(pkg_name);
-        if !install_dir.exists() { eprintln!("pkg: {} is not installed", pkg_name); continue; }
+        if !install_dir.exists() { errln!("pkg: {} is not installed", pkg_name); continue; }
 
         let registry_pkg = match registry.packages.get(pkg_name.as_str()) {
             Some(p) => p,
-            None    => { eprintln!("pkg: {} not found in registry", pkg_name); continue; }
+            None    => { errln!("pkg: {} not found in registry", pkg_name); continue; }
         };
 
         let installed_version = read_meta(&install_dir).map(|m| m.version).unwrap_or_default();
         if installed_version == registry_pkg.version {
-            println!("✅ {} is already up to date ({})", pkg_name, installed_version);
+            outln!("✅ {} is already up to date ({})", pkg_name, installed_version);
             continue;
         }
 
-        println!("⬆️  Upgrading {} {} → {}...", pkg_name, installed_version, registry_pkg.version);
+        outln!("⬆️  Upgrading {} {} → {}...", pkg_name, installed_version, registry_pkg.version);
         cmd_uninstall(Some(pkg_name.as_str()));
         cmd_install(Some(pkg_name.as_str()));
         upgraded += 1;
     }
 
     if upgraded == 0 && to_upgrade.len() > 1 {
-        println!("All packages are up to date.");
+        outln!("All packages are up to date.");
     }
     0
 }
@@ -282,12 +318,12 @@ fn cmd_upgrade(name: Option<&str>) -> i32 {
 fn cmd_search(query: Option<&str>) -> i32 {
     let registry = match fetch_registry() {
         Ok(r)  => r,
-        Err(e) => { eprintln!("pkg: failed to fetch registry: {}", e); return 1; }
+        Err(e) => { errln!("pkg: failed to fetch registry: {}", e); return 1; }
     };
 
     let packages_dir = rshell_packages_dir();
-    println!("{:<20} {:<12} {:<10} {}", "NAME", "VERSION", "STATUS", "DESCRIPTION");
-    println!("{}", "-".repeat(70));
+    outln!("{:<20} {:<12} {:<10} {}", "NAME", "VERSION", "STATUS", "DESCRIPTION");
+    outln!("{}", "-".repeat(70));
 
     let mut names: Vec<&String> = registry.packages.keys().collect();
     names.sort();
@@ -300,12 +336,12 @@ fn cmd_search(query: Option<&str>) -> i32 {
         }
         let installed = packages_dir.join(name).exists();
         let status    = if installed { "installed" } else { "" };
-        println!("{:<20} {:<12} {:<10} {}", name, pkg.version, status, pkg.description);
+        outln!("{:<20} {:<12} {:<10} {}", name, pkg.version, status, pkg.description);
         found = true;
     }
 
     if !found {
-        println!("No packages found matching '{}'", query.unwrap_or(""));
+        outln!("No packages found matching '{}'", query.unwrap_or(""));
     }
     0
 }
\ No newline at end of file
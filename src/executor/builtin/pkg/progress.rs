@@ -28,26 +28,28 @@ fn make_bar(percent: usize) -> String {
 // ── Public printers ───────────────────────────────────────────────────────────
 
 pub fn print_download_progress(downloaded: u64, total: Option<u64>) {
+    out!("{}", download_progress_line(downloaded, total));
+    super::super::io::stdout().flush().ok();
+}
+
+/// The download progress line, starting with `\r` to draw over the last.
+pub fn download_progress_line(downloaded: u64, total: Option<u64>) -> String {
+    let dl_mb = downloaded as f64 / 1_048_576.0;
     match total {
         Some(t) if t > 0 => {
             let percent  = ((downloaded * 100) / t) as usize;
-            let dl_mb    = downloaded as f64 / 1_048_576.0;
-            let total_mb = t          as f64 / 1_048_576.0;
-            print!("\r   {} {}%  {:.1}/{:.1} MB", make_bar(percent), percent, dl_mb, total_mb);
-        }
-        _ => {
-            let dl_mb = downloaded as f64 / 1_048_576.0;
-            print!("\r   ⬇️  {:.1} MB downloaded...", dl_mb);
+            let total_mb = t as f64 / 1_048_576.0;
+            format!("\r   {} {}%  {:.1}/{:.1} MB", make_bar(percent), percent, dl_mb, total_mb)
         }
+        _ => format!("\r   ⬇️  {:.1} MB downloaded...", dl_mb),
     }
-    std::io::stdout().flush().ok();
 }
 
 pub fn print_extract_progress(current: usize, total: usize) {
     if total == 0 { return; }
     let percent = (current * 100) / total;
-    print!("\r   {} {}%  ({}/{})", make_bar(percent), percent, current, total);
-    std::io::stdout().flush().ok();
+    out!("\r   {} {}%  ({}/{})", make_bar(percent), percent, current, total);
+    super::super::io::stdout().flush().ok();
 }
 
 pub fn print_uninstall_progress(current: usize, total: usize, filename: &str) {
@@ -58,11 +60,11 @@ pub fn print_uninstall_progress(current: usize, total: usize, filename: &str) {
     } else {
         filename.to_string()
     };
-    print!("\r   {} {}%  {}", make_bar(percent), percent, name);
-    std::io::stdout().flush().ok();
+    out!("\r   {} {}%  {}", make_bar(percent), percent, name);
+    super::super::io::stdout().flush().ok();
 }
 
 pub fn clear_progress_line() {
-    print!("\r{}\r", " ".repeat(70));
-    std::io::stdout().flush().ok();
+    out!("\r{}\r", " ".repeat(70));
+    super::super::io::stdout().flush().ok();
 }
\ No newline at end of file
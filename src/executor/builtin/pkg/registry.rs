@@ -63,6 +63,13 @@ pub fn fetch_registry() -> anyhow::Result<Registry> {
     Ok(serde_json::from_str(&content)?)
 }
 
+/// The registry as last fetched, however old, without going to the network;
+/// `None` if it has never been fetched.
+pub fn cached_registry() -> Option<Registry> {
+    let content = std::fs::read_to_string(registry_cache_path()).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
 /// Returns the `PlatformPkg` appropriate for the current OS, or `None` if the
 /// package has no binary for this platform.
 pub fn platform_pkg(pkg: &Package) -> Option<PlatformPkg> {
@@ -0,0 +1,380 @@
+// src/executor/builtin/printf.rs
+// printf FORMAT [ARG ...]
+// quote [ARG ...]
+//
+// Supports %s %c %d %i %u %o %x %X %f %q %% with flags (- + space 0 #),
+// width and precision (either may be `*`), backslash escapes in the
+// format, and bash-style argument recycling: the format is reused until
+// every argument has been consumed.
+//
+// `quote` and `%q` produce POSIX shell words, safe to paste into a command
+// line, `sh -c`, ssh or xargs input.
+
+use std::io::Write;
+
+pub fn builtin_printf(args: &[String]) -> i32 {
+    let [format, args @ ..] = super::util::operands(args) else {
+        errln!("usage: printf <format> [arguments ...]");
+        return 1;
+    };
+
+    let (output, ok) = format_all(format, args);
+    out!("{}", output);
+    super::io::stdout().flush().ok();
+    if ok { 0 } else { 1 }
+}
+
+/// quote — print each argument as a single shell-safe word.
+pub fn builtin_quote(args: &[String]) -> i32 {
+    let words: Vec<String> = args[1..].iter().map(|a| shell_quote(a)).collect();
+    outln!("{}", words.join(" "));
+    0
+}
+
+/// Quote `s` so a POSIX shell reads it back as exactly one word. Plain
+/// words are left alone; anything else is single-quoted, with embedded
+/// single quotes written as '\''.
+pub fn shell_quote(s: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-./=:,+@%".contains(c);
+    if !s.is_empty() && s.chars().all(plain) {
+        return s.to_string();
+    }
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Format `args` through `format`, recycling the format while arguments
+/// remain. Returns the output and whether every argument converted cleanly.
+pub fn format_all(format: &str, args: &[String]) -> (String, bool) {
+    let mut out  = String::new();
+    let mut ok   = true;
+    let mut next = 0;
+
+    loop {
+        let before = next;
+        if !format_once(format, args, &mut next, &mut out, &mut ok) { break; }
+        // Stop once arguments are exhausted, or if the format consumes none
+        if next >= args.len() || next == before { break; }
+    }
+    (out, ok)
+}
+
+/// One pass over the format string. Returns false if a `\c` escape asked
+/// for output to stop entirely.
+fn format_once(
+    format: &str,
+    args: &[String],
+    next: &mut usize,
+    out: &mut String,
+    ok: &mut bool,
+) -> bool {
+    let chars: Vec<char> = format.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                i += 1;
+                if chars.get(i) == Some(&'c') { return false; }
+                i = push_escape(&chars, i, out);
+            }
+            '%' if chars.get(i + 1) == Some(&'%') => { out.push('%'); i += 2; }
+            '%' => {
+                let (spec, end) = parse_spec(&chars, i + 1, args, next);
+                i = end;
+                match spec {
+                    Some(spec) => {
+                        let arg = args.get(*next).map(|s| s.as_str());
+                        if arg.is_some() { *next += 1; }
+                        out.push_str(&spec.render(arg.unwrap_or(""), ok));
+                    }
+                    None => {
+                        errln!("printf: invalid directive in format: {}", format);
+                        *ok = false;
+                        return false;
+                    }
+                }
+            }
+            c => { out.push(c); i += 1; }
+        }
+    }
+    true
+}
+
+/// Append the character for the escape starting at `chars[i]` (just past
+/// the backslash) and return the index after it.
+fn push_escape(chars: &[char], i: usize, out: &mut String) -> usize {
+    let Some(&c) = chars.get(i) else { out.push('\\'); return i; };
+    match c {
+        'n'  => out.push('\n'),
+        't'  => out.push('\t'),
+        'r'  => out.push('\r'),
+        'a'  => out.push('\x07'),
+        'b'  => out.push('\x08'),
+        'f'  => out.push('\x0c'),
+        'v'  => out.push('\x0b'),
+        'e'  => out.push('\x1b'),
+        '\\' => out.push('\\'),
+        '"'  => out.push('"'),
+        '\'' => out.push('\''),
+        '0'..='7' => {
+            // Up to three octal digits, e.g. \033
+            let digits: String = chars[i..].iter().take(3).take_while(|c| c.is_digit(8)).collect();
+            let value = u32::from_str_radix(&digits, 8).unwrap_or(0);
+            out.push(char::from_u32(value).unwrap_or('?'));
+            return i + digits.len();
+        }
+        'x' => {
+            let digits: String = chars[i + 1..].iter().take(2).take_while(|c| c.is_ascii_hexdigit()).collect();
+            if digits.is_empty() { out.push_str("\\x"); return i + 1; }
+            let value = u32::from_str_radix(&digits, 16).unwrap_or(0);
+            out.push(char::from_u32(value).unwrap_or('?'));
+            return i + 1 + digits.len();
+        }
+        other => { out.push('\\'); out.push(other); }
+    }
+    i + 1
+}
+
+// ── Conversion specs ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+struct Spec {
+    left:      bool,
+    plus:      bool,
+    space:     bool,
+    zero:      bool,
+    alt:       bool,
+    width:     Option<usize>,
+    precision: Option<usize>,
+    conv:      char,
+}
+
+/// The widest a field or precision can be. More is surely a mistake, and
+/// padding to it could take all the memory there is.
+const MAX_WIDTH: usize = 1 << 16;
+
+/// Parse flags/width/precision/conversion after a `%`. A `*` width or
+/// precision takes its value from the next argument.
+fn parse_spec(chars: &[char], mut i: usize, args: &[String], next: &mut usize) -> (Option<Spec>, usize) {
+    let mut spec = Spec::default();
+
+    while let Some(&c) = chars.get(i) {
+        match c {
+            '-' => spec.left  = true,
+            '+' => spec.plus  = true,
+            ' ' => spec.space = true,
+            '0' => spec.zero  = true,
+            '#' => spec.alt   = true,
+            _   => break,
+        }
+        i += 1;
+    }
+
+    let star = |next: &mut usize| -> usize {
+        let v = args.get(*next).and_then(|a| a.parse::<i64>().ok()).unwrap_or(0);
+        *next += 1;
+        v.max(0) as usize
+    };
+
+    if chars.get(i) == Some(&'*') {
+        spec.width = Some(star(next));
+        i += 1;
+    } else {
+        let digits: String = chars[i..].iter().take_while(|c| c.is_ascii_digit()).collect();
+        if !digits.is_empty() { spec.width = digits.parse().ok(); i += digits.len(); }
+    }
+
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        if chars.get(i) == Some(&'*') {
+            spec.precision = Some(star(next));
+            i += 1;
+        } else {
+            let digits: String = chars[i..].iter().take_while(|c| c.is_ascii_digit()).collect();
+            spec.precision = Some(digits.parse().unwrap_or(0));
+            i += digits.len();
+        }
+    }
+
+    spec.width = spec.width.map(|w| w.min(MAX_WIDTH));
+    spec.precision = spec.precision.map(|p| p.min(MAX_WIDTH));
+
+    match chars.get(i) {
+        Some(&c) if "scdiuoxXfFq".contains(c) => { spec.conv = c; (Some(spec), i + 1) }
+        _ => (None, i + 1),
+    }
+}
+
+impl Spec {
+    fn render(&self, arg: &str, ok: &mut bool) -> String {
+        match self.conv {
+            's' => {
+                let s: String = match self.precision {
+                    Some(p) => arg.chars().take(p).collect(),
+                    None    => arg.to_string(),
+                };
+                self.pad(s, false)
+            }
+            'c' => self.pad(arg.chars().next().map(String::from).unwrap_or_default(), false),
+            'q' => self.pad(shell_quote(arg), false),
+            'd' | 'i' => {
+                let n = parse_int(arg, ok);
+                let digits = self.min_digits(n.unsigned_abs().to_string());
+                self.pad(self.signed(n < 0, digits), true)
+            }
+            'u' | 'o' | 'x' | 'X' => {
+                let n = parse_int(arg, ok) as u64;
+                let (digits, prefix) = match self.conv {
+                    'o' => (format!("{:o}", n), "0"),
+                    'x' => (format!("{:x}", n), "0x"),
+                    'X' => (format!("{:X}", n), "0X"),
+                    _   => (n.to_string(), ""),
+                };
+                let digits = self.min_digits(digits);
+                let prefix = if self.alt && n != 0 { prefix } else { "" };
+                self.pad(format!("{}{}", prefix, digits), true)
+            }
+            'f' | 'F' => {
+                let n = parse_float(arg, ok);
+                let body = format!("{:.*}", self.precision.unwrap_or(6), n.abs());
+                self.pad(self.signed(n.is_sign_negative() && n != 0.0, body), true)
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Apply precision as a minimum digit count for integer conversions.
+    fn min_digits(&self, digits: String) -> String {
+        match self.precision {
+            Some(p) if digits.len() < p => format!("{}{}", "0".repeat(p - digits.len()), digits),
+            _ => digits,
+        }
+    }
+
+    fn signed(&self, negative: bool, body: String) -> String {
+        let sign = if negative { "-" } else if self.plus { "+" } else if self.space { " " } else { "" };
+        format!("{}{}", sign, body)
+    }
+
+    /// Pad to the field width. Zero padding goes after any sign or 0x prefix.
+    fn pad(&self, s: String, numeric: bool) -> String {
+        let width = self.width.unwrap_or(0);
+        let len = s.chars().count();
+        if len >= width { return s; }
+        let fill = width - len;
+
+        if self.left {
+            format!("{}{}", s, " ".repeat(fill))
+        } else if self.zero && numeric && (self.precision.is_none() || "fF".contains(self.conv)) {
+            let split = s.find(|c: char| c.is_ascii_digit()).unwrap_or(0);
+            let split = if s[split..].starts_with("0x") || s[split..].starts_with("0X") { split + 2 } else { split };
+            format!("{}{}{}", &s[..split], "0".repeat(fill), &s[split..])
+        } else {
+            format!("{}{}", " ".repeat(fill), s)
+        }
+    }
+}
+
+// ── Argument parsing ──────────────────────────────────────────────────────────
+
+/// Parse an integer argument: decimal, 0x hex, 0 octal, or 'c for a
+/// character code. Reports and returns 0 on failure.
+fn parse_int(arg: &str, ok: &mut bool) -> i64 {
+    let s = arg.trim();
+    if s.is_empty() { return 0; }
+    if let Some(c) = s.strip_prefix('\'').or_else(|| s.strip_prefix('"')) {
+        return c.chars().next().map(|c| c as i64).unwrap_or(0);
+    }
+
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let parsed = if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        i64::from_str_radix(hex, 16)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        i64::from_str_radix(&digits[1..], 8)
+    } else {
+        digits.parse::<i64>()
+    };
+
+    match parsed {
+        Ok(n) => if negative { -n } else { n },
+        Err(_) => {
+            errln!("printf: '{}': invalid number", arg);
+            *ok = false;
+            0
+        }
+    }
+}
+
+fn parse_float(arg: &str, ok: &mut bool) -> f64 {
+    let s = arg.trim();
+    if s.is_empty() { return 0.0; }
+    match s.parse::<f64>() {
+        Ok(n) => n,
+        Err(_) => {
+            errln!("printf: '{}': invalid number", arg);
+            *ok = false;
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fmt(format: &str, args: &[&str]) -> String {
+        let args: Vec<String> = args.iter().map(|s| s.to_string()).collect();
+        format_all(format, &args).0
+    }
+
+    #[test]
+    fn test_basic_conversions() {
+        assert_eq!(fmt("%s-%d\\n", &["a", "42"]), "a-42\n");
+        assert_eq!(fmt("%x %X %o", &["255", "255", "8"]), "ff FF 10");
+        assert_eq!(fmt("%.2f", &["3.14159"]), "3.14");
+        assert_eq!(fmt("100%%", &[]), "100%");
+    }
+
+    #[test]
+    fn test_width_and_flags() {
+        assert_eq!(fmt("[%5s]", &["ab"]), "[   ab]");
+        assert_eq!(fmt("[%-5s]", &["ab"]), "[ab   ]");
+        assert_eq!(fmt("[%05d]", &["-42"]), "[-0042]");
+        assert_eq!(fmt("[%+d]", &["7"]), "[+7]");
+        assert_eq!(fmt("[%.3s]", &["abcdef"]), "[abc]");
+        assert_eq!(fmt("[%*d]", &["4", "7"]), "[   7]");
+        assert_eq!(fmt("%#x", &["255"]), "0xff");
+        // Huge widths and precisions are capped rather than allocated
+        assert_eq!(fmt("%*d", &["99999999999", "1"]).len(), MAX_WIDTH);
+        assert_eq!(fmt("%99999999999s", &["x"]).len(), MAX_WIDTH);
+        assert_eq!(fmt("%.99999999999d", &["1"]).len(), MAX_WIDTH);
+    }
+
+    #[test]
+    fn test_argument_recycling() {
+        assert_eq!(fmt("%s=%s\\n", &["a", "1", "b", "2"]), "a=1\nb=2\n");
+        assert_eq!(fmt("%s,", &["x", "y", "z"]), "x,y,z,");
+        assert_eq!(fmt("%s %s\\n", &["only"]), "only \n");
+    }
+
+    #[test]
+    fn test_escapes_and_char_codes() {
+        assert_eq!(fmt("\\t\\101\\x42", &[]), "\tAB");
+        assert_eq!(fmt("%d", &["'A"]), "65");
+        assert_eq!(fmt("%d", &["0x10"]), "16");
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("plain-word.txt"), "plain-word.txt");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a b"), "'a b'");
+        assert_eq!(shell_quote("it's $HOME"), "'it'\\''s $HOME'");
+        // `^` is a pipe in some shells
+        assert_eq!(shell_quote("a^b"), "'a^b'");
+        assert_eq!(fmt("%q %q", &["x", "y z"]), "x 'y z'");
+    }
+}
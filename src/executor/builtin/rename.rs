@@ -0,0 +1,278 @@
+// src/executor/builtin/rename.rs
+// rename [-n] [-v] [-f] FROM TO [FILE...] — bulk renames, which mv can't do.
+// FROM and TO come in three forms:
+//
+//   rename %.jpeg %.jpg                wildcards: each % in TO takes the text
+//                                      its partner in FROM matched; with no
+//                                      FILEs, every match in the directory
+//   rename 's/IMG_(\d+)/pic-\1/' *     a sed-style substitution (g, i flags)
+//   rename ' ' '_' *                   a regular expression and replacement
+//                                      (\1 for groups), first match only
+//
+// Outside posix mode the shell expands `*`, `?` and `$1` even in quotes, so
+// the wildcard is `%` and groups are `\1`. `'*.jpeg' '*.jpg'` and `$1` still
+// work under `set -o posix`, where quotes keep them intact.
+//
+// Only the last part of each path is renamed. Nothing is touched when two
+// files would end up with the same name, or (without -f) when a new name is
+// taken; -n shows the plan without doing it.
+
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::shell::Shell;
+use super::util::{Opt, Options};
+
+pub const RENAME: Options = Options {
+    name: "rename",
+    operands: "FROM TO [FILE]...",
+    about: "Rename many files at once, by wildcards or a regular expression.",
+    opts: &[
+        Opt::new("n", "dry-run", "show what would be renamed, and do nothing"),
+        Opt::new("v", "verbose", "print each rename"),
+        Opt::new("f", "force", "replace files that already have a new name"),
+    ],
+    numeric: None,
+};
+
+/// How each file's name becomes its new one.
+enum Rule {
+    Wildcards { from: Regex, to: String },
+    Replace { from: Regex, to: String, all: bool },
+}
+
+pub fn builtin_rename(shell: &Shell, args: &[String]) -> i32 {
+    let p = match RENAME.parse(args) { Ok(p) => p, Err(code) => return code };
+    let (dry_run, verbose, force) = (p.has("dry-run"), p.has("verbose"), p.has("force"));
+
+    let (rule, files) = match parse_rule(&p.operands) {
+        Ok(parsed) => parsed,
+        Err(e) => { errln!("rename: {}", e); return 2; }
+    };
+    let files: Vec<String> = match (&rule, files.is_empty()) {
+        (Rule::Wildcards { from, .. }, true) => matching_entries(&shell.cwd, from),
+        (_, true) => { errln!("usage: rename [-n] FROM TO FILE..."); return 2; }
+        (_, false) => files.iter().map(|f| f.to_string()).collect(),
+    };
+
+    // Work out every new name before renaming anything
+    let mut code = 0;
+    let mut plan: Vec<(String, PathBuf, PathBuf)> = Vec::new();
+    for file in &files {
+        let path = shell.cwd.join(file);
+        if path.symlink_metadata().is_err() {
+            errln!("rename: {}: No such file or directory", file);
+            if !shell.posix && file.contains(['*', '?']) {
+                errln!("rename: the shell expands * and ? even in quotes; write % for the wildcard, as in: rename %.jpeg %.jpg");
+            }
+            code = 1;
+            continue;
+        }
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else { continue };
+        let Some(new_name) = rule.apply(&name).filter(|n| *n != name) else { continue };
+        if new_name.is_empty() || new_name.contains(['/', '\0']) || new_name == "." || new_name == ".." {
+            errln!("rename: {}: would get the invalid name '{}'", file, new_name);
+            code = 1;
+            continue;
+        }
+        let target = path.with_file_name(&new_name);
+        plan.push((file.clone(), path, target));
+    }
+
+    let mut conflict = false;
+    for (i, (file, _, target)) in plan.iter().enumerate() {
+        if let Some((other, _, _)) = plan[..i].iter().find(|(_, _, t)| t == target) {
+            errln!("rename: {} and {} would both become {}", other, file, display_name(target));
+            conflict = true;
+        } else if !force && target.symlink_metadata().is_ok() && !plan.iter().any(|(_, source, _)| source == target) {
+            errln!("rename: {}: {} already exists (use -f to replace it)", file, display_name(target));
+            conflict = true;
+        }
+    }
+    if conflict { return 1; }
+    if plan.is_empty() && code == 0 && (dry_run || verbose) { outln!("rename: nothing to rename"); }
+
+    // A target that is itself being renamed away has to go first
+    if !order_for_chains(&mut plan) {
+        errln!("rename: the new names go round in a circle; rename one file out of the way first");
+        return 1;
+    }
+    for (file, source, target) in &plan {
+        let shown = format!("{} -> {}", file, display_name(target));
+        if dry_run { outln!("{}", shown); continue; }
+        match std::fs::rename(source, target) {
+            Ok(()) => if verbose { outln!("{}", shown); },
+            Err(e) => { errln!("rename: {}: {}", file, e); code = 1; }
+        }
+    }
+    code
+}
+
+impl Rule {
+    /// The new name for `name`, or None if the rule doesn't apply to it.
+    fn apply(&self, name: &str) -> Option<String> {
+        match self {
+            Rule::Wildcards { from, to } => {
+                let caps = from.captures(name)?;
+                let mut groups = caps.iter().skip(1).map(|g| g.map_or("", |m| m.as_str()));
+                Some(to.chars().map(|c| match c {
+                    '%' | '*' | '?' => groups.next().unwrap_or_default().to_string(),
+                    c => c.to_string(),
+                }).collect())
+            }
+            Rule::Replace { from, to, all } => {
+                if !from.is_match(name) { return None; }
+                let limit = if *all { 0 } else { 1 };
+                Some(from.replacen(name, limit, to.as_str()).into_owned())
+            }
+        }
+    }
+}
+
+/// Split the operands into the rule and the files it applies to.
+fn parse_rule<'a>(operands: &'a [&'a str]) -> Result<(Rule, &'a [&'a str]), String> {
+    let Some(&first) = operands.first() else { return Err("missing FROM and TO".to_string()) };
+    if let Some(rule) = substitution(first)? {
+        return Ok((rule, &operands[1..]));
+    }
+    let Some(&to) = operands.get(1) else { return Err(format!("missing TO after '{}'", first)) };
+    let wildcards = |s: &str| s.chars().filter(|c| matches!(c, '%' | '*' | '?')).count();
+    if wildcards(first) > 0 && wildcards(first) == wildcards(to) {
+        let from = Regex::new(&glob_regex(first)).map_err(|e| format!("bad pattern '{}': {}", first, e))?;
+        return Ok((Rule::Wildcards { from, to: to.to_string() }, &operands[2..]));
+    }
+    let from = Regex::new(first).map_err(|e| format!("bad regular expression '{}': {}", first, e))?;
+    Ok((Rule::Replace { from, to: replacement(to), all: false }, &operands[2..]))
+}
+
+/// `s/FROM/TO/FLAGS`, with any punctuation as the delimiter; None if `expr`
+/// isn't one.
+fn substitution(expr: &str) -> Result<Option<Rule>, String> {
+    let mut chars = expr.chars();
+    let (Some('s'), Some(delim)) = (chars.next(), chars.next()) else { return Ok(None) };
+    if delim.is_alphanumeric() || delim.is_whitespace() || delim == '\\' { return Ok(None); }
+    let parts: Vec<&str> = chars.as_str().split(delim).collect();
+    let [from, to, flags] = parts[..] else { return Ok(None) };
+    if let Some(bad) = flags.chars().find(|c| !matches!(c, 'g' | 'i')) {
+        return Err(format!("unknown flag '{}' in '{}'", bad, expr));
+    }
+    let pattern = if flags.contains('i') { format!("(?i){}", from) } else { from.to_string() };
+    let from = Regex::new(&pattern).map_err(|e| format!("bad regular expression '{}': {}", from, e))?;
+    Ok(Some(Rule::Replace { from, to: replacement(to), all: flags.contains('g') }))
+}
+
+/// A replacement with `\1`-style groups rewritten for the regex crate, and
+/// `$1` kept from running into the text after it.
+fn replacement(to: &str) -> String {
+    let mut out = String::new();
+    let mut chars = to.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' | '$' if chars.peek().is_some_and(char::is_ascii_digit) => {
+                let mut group = String::new();
+                while let Some(d) = chars.next_if(char::is_ascii_digit) { group.push(d); }
+                out.push_str(&format!("${{{}}}", group));
+            }
+            '\\' if chars.peek() == Some(&'$') => { chars.next(); out.push_str("$$"); }
+            '\\' if chars.peek().is_some() => out.push(chars.next().unwrap_or_default()),
+            '$' if chars.peek() == Some(&'$') => { chars.next(); out.push_str("$$"); }
+            '$' if chars.peek() != Some(&'{') => out.push_str("$$"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// An anchored regex for a wildcard pattern, with a group per %, * and ?.
+fn glob_regex(pattern: &str) -> String {
+    let mut re = String::from("^");
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' | '*' => re.push_str("(.*?)"),
+            '?' => re.push_str("(.)"),
+            '[' => {
+                let class: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                let class = class.strip_prefix('!').map_or(class.clone(), |rest| format!("^{}", rest));
+                re.push_str(&format!("[{}]", class));
+            }
+            c => re.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    re.push('$');
+    re
+}
+
+/// The entries of `dir` the wildcard pattern matches, sorted. Hidden ones
+/// only match a pattern that starts with a dot.
+fn matching_entries(dir: &Path, from: &Regex) -> Vec<String> {
+    let show_hidden = from.as_str().starts_with(r"^\.");
+    let mut names: Vec<String> = std::fs::read_dir(dir).into_iter().flatten().flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|n| (show_hidden || !n.starts_with('.')) && from.is_match(n))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Reorder so that a file renamed to a name another file is leaving is
+/// renamed after it (a -> b, b -> c becomes b -> c, a -> b). False for a
+/// cycle (a -> b, b -> a), which no order can do safely.
+fn order_for_chains(plan: &mut Vec<(String, PathBuf, PathBuf)>) -> bool {
+    let mut ordered = Vec::with_capacity(plan.len());
+    while !plan.is_empty() {
+        let ready = (0..plan.len()).find(|&i| !plan.iter().any(|(_, source, _)| *source == plan[i].2));
+        let Some(ready) = ready else { return false };
+        ordered.push(plan.remove(ready));
+    }
+    *plan = ordered;
+    true
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename(operands: &[&str], name: &str) -> Option<String> {
+        let (rule, _) = parse_rule(operands).unwrap();
+        rule.apply(name)
+    }
+
+    #[test]
+    fn wildcards() {
+        assert_eq!(rename(&["%.jpeg", "%.jpg"], "cat.jpeg").as_deref(), Some("cat.jpg"));
+        assert_eq!(rename(&["%.jpeg", "%.jpg"], "cat.png"), None);
+        assert_eq!(rename(&["IMG_%_?.png", "photo-%-?.png"], "IMG_2024_a.png").as_deref(), Some("photo-2024-a.png"));
+        assert_eq!(rename(&["*.jpeg", "*.jpg"], "cat.jpeg").as_deref(), Some("cat.jpg"));
+        assert_eq!(rename(&["*.jpeg", "*.jpg"], "cat.png"), None);
+        assert_eq!(rename(&["IMG_*_?.png", "photo-*-?.png"], "IMG_2024_a.png").as_deref(), Some("photo-2024-a.png"));
+        assert_eq!(rename(&["[ab]*.txt", "*.md"], "apple.txt").as_deref(), Some("pple.md"));
+    }
+
+    #[test]
+    fn regular_expressions() {
+        assert_eq!(rename(&[" ", "_"], "a b c").as_deref(), Some("a_b c"));
+        assert_eq!(rename(&["s/ /_/g"], "a b c").as_deref(), Some("a_b_c"));
+        assert_eq!(rename(&[r"s#IMG_(\d+)#photo-\1#"], "IMG_42.jpg").as_deref(), Some("photo-42.jpg"));
+        assert_eq!(rename(&[r"s/(.)\.txt/\1.md/"], "c.txt").as_deref(), Some("c.md"));
+        assert_eq!(rename(&[r"s#IMG_(\d+)#photo-$1#"], "IMG_42.jpg").as_deref(), Some("photo-42.jpg"));
+        assert_eq!(rename(&[r"(\w+)\.(\w+)", r"\2.\1"], "notes.txt").as_deref(), Some("txt.notes"));
+        assert_eq!(rename(&["s/README/readme/i"], "Readme.md").as_deref(), Some("readme.md"));
+        assert_eq!(rename(&["x$", "$y"], "box").as_deref(), Some("bo$y"));
+    }
+
+    #[test]
+    fn chains_rename_in_order() {
+        let entry = |a: &str, b: &str| (a.to_string(), PathBuf::from(a), PathBuf::from(b));
+        let mut plan = vec![entry("a", "b"), entry("b", "c")];
+        assert!(order_for_chains(&mut plan));
+        assert_eq!(plan[0].0, "b");
+        assert_eq!(plan[1].0, "a");
+        assert!(!order_for_chains(&mut vec![entry("a", "b"), entry("b", "a")]));
+    }
+}
@@ -0,0 +1,396 @@
+// src/executor/builtin/sandbox.rs
+// sandbox run [-n] [-w] [-k] COMMAND [ARG]... — run a command that isn't
+// trusted yet (a downloaded install script, a fresh pkg binary) where it
+// can do less harm:
+//
+//   - HOME, and the XDG directories under it, point at an empty temporary
+//     directory, so dotfiles and credentials are out of reach
+//   - the current directory is read-only, unless -w
+//   - with -n, there's no network
+//
+// Afterwards every file the command left in the temporary HOME or changed
+// in the current directory is listed.
+//
+// On Linux the command runs in its own user, mount and (with -n) network
+// namespaces; on macOS under sandbox-exec. Where neither is available the
+// current directory stays writable, but what the command wrote to it is
+// still reported.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command as Proc;
+use std::time::SystemTime;
+
+use crate::executor::jobctl::{self, Wait};
+use crate::paths;
+use crate::shell::Shell;
+use super::util::{Opt, Options};
+
+pub const SANDBOX: Options = Options {
+    name: "sandbox run",
+    operands: "COMMAND [ARG]...",
+    about: "Run COMMAND with a temporary HOME and the current directory \
+            read-only, then report the files it wrote.",
+    opts: &[
+        Opt::new("n", "no-network", "cut COMMAND off from the network (Linux, macOS)"),
+        Opt::new("w", "writable", "let COMMAND write to the current directory"),
+        Opt::new("k", "keep", "keep the temporary HOME afterwards"),
+    ],
+    numeric: None,
+};
+
+/// Past this many files the current directory isn't compared before and
+/// after, which would take longer than the command itself.
+const MAX_FILES: usize = 100_000;
+
+pub fn builtin_sandbox(shell: &mut Shell, args: &[String]) -> i32 {
+    if args.get(1).map(String::as_str) != Some("run") {
+        errln!("usage: sandbox run [-n] [-w] [-k] COMMAND [ARG]...");
+        return 2;
+    }
+    // Options stop at COMMAND, so its own options reach it untouched
+    let rest = &args[2..];
+    let split = rest.iter().position(|a| !a.starts_with('-') || a == "--").unwrap_or(rest.len());
+    let p = match SANDBOX.parse(&args[1..2 + split]) { Ok(p) => p, Err(code) => return code };
+    let command = match rest.get(split) {
+        Some(a) if a == "--" => &rest[split + 1..],
+        _ => &rest[split..],
+    };
+    if command.is_empty() {
+        errln!("usage: sandbox run [-n] [-w] [-k] COMMAND [ARG]...");
+        return 2;
+    }
+
+    let home = match make_home() {
+        Ok(home) => home,
+        Err(e) => { errln!("sandbox: can't create a temporary HOME: {}", e); return 1; }
+    };
+    let plan = Plan {
+        cwd: shell.cwd.clone(),
+        home: home.clone(),
+        readonly: !p.has("writable"),
+        network: !p.has("no-network"),
+    };
+    let code = run(shell, &plan, command);
+
+    if p.has("keep") {
+        errln!("sandbox: temporary HOME kept at {}", paths::display(&home));
+    } else if let Err(e) = std::fs::remove_dir_all(&home) {
+        errln!("sandbox: {}: {}", paths::display(&home), e);
+    }
+    code
+}
+
+/// What the command is allowed, and where.
+struct Plan {
+    cwd: PathBuf,
+    home: PathBuf,
+    readonly: bool,
+    network: bool,
+}
+
+fn run(shell: &mut Shell, plan: &Plan, command: &[String]) -> i32 {
+    let command = plan.wrap(command);
+    let mut cmd = match crate::executor::build_command(shell, &command, &[]) {
+        Ok(cmd) => cmd,
+        Err(e) => { errln!("sandbox: {}", e); return 1; }
+    };
+    cmd.env_clear().envs(shell.child_env()).envs(home_vars(&plan.home));
+
+    // The sandbox is torn down as soon as the command returns, so Ctrl+Z
+    // can't leave it stopped for `fg` later
+    jobctl::setup_child(&mut cmd, None, true, false);
+    let protected = match plan.confine(&mut cmd) {
+        Ok(protected) => protected,
+        Err(e) => { errln!("sandbox: {}", e); return 1; }
+    };
+    // Compare the current directory before and after only if it could change
+    let before = if protected { None } else { Some(snapshot(&plan.cwd)) };
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return crate::executor::report_exec_error(shell, &command[0], &e),
+    };
+    jobctl::give_terminal(child.id());
+    let waited = jobctl::wait_foreground(&mut child);
+    jobctl::take_terminal();
+    let code = match waited {
+        Ok(Wait::Exited(status)) => super::status::exit_status(&command[0], status),
+        Ok(Wait::Stopped) => { errln!("sandbox: {}: stopped from outside the shell", command[0]); 1 }
+        Err(e) => { errln!("sandbox: {}: {}", command[0], e); 1 }
+    };
+
+    report(plan, before);
+    code
+}
+
+/// HOME and the XDG base directories under it. Left alone, an exported
+/// XDG_CONFIG_HOME would lead straight back to the real configuration.
+fn home_vars(home: &Path) -> Vec<(&'static str, PathBuf)> {
+    let mut vars = vec![
+        ("HOME", home.to_path_buf()),
+        ("XDG_CONFIG_HOME", home.join(".config")),
+        ("XDG_DATA_HOME", home.join(".local/share")),
+        ("XDG_STATE_HOME", home.join(".local/state")),
+        ("XDG_CACHE_HOME", home.join(".cache")),
+    ];
+    if cfg!(windows) {
+        vars.push(("USERPROFILE", home.to_path_buf()));
+        vars.push(("APPDATA", home.join("AppData/Roaming")));
+        vars.push(("LOCALAPPDATA", home.join("AppData/Local")));
+    }
+    vars
+}
+
+fn make_home() -> std::io::Result<PathBuf> {
+    let stamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let home = std::env::temp_dir().join(format!("rshell-sandbox-{}-{}", std::process::id(), stamp));
+    std::fs::create_dir(&home)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&home, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(home)
+}
+
+// ── Confinement ───────────────────────────────────────────────────────────────
+
+impl Plan {
+    /// The command line to run: on macOS, COMMAND under sandbox-exec.
+    fn wrap(&self, command: &[String]) -> Vec<String> {
+        if !cfg!(target_os = "macos") || (!self.readonly && self.network) {
+            return command.to_vec();
+        }
+        // Later rules win, so the temporary HOME stays writable even
+        // when it's inside the current directory
+        let quote = |p: &Path| format!("\"{}\"", p.display().to_string().replace('\\', "\\\\").replace('"', "\\\""));
+        let mut profile = String::from("(version 1) (allow default)");
+        if self.readonly {
+            profile.push_str(&format!(" (deny file-write* (subpath {}))", quote(&self.cwd)));
+            profile.push_str(&format!(" (allow file-write* (subpath {}))", quote(&self.home)));
+        }
+        if !self.network {
+            profile.push_str(" (deny network*)");
+        }
+        let mut wrapped = vec!["/usr/bin/sandbox-exec".to_string(), "-p".to_string(), profile];
+        wrapped.extend_from_slice(command);
+        wrapped
+    }
+
+    /// Confine `cmd` as far as this platform allows. Ok says whether the
+    /// current directory was made read-only; Err means -n can't be honoured.
+    #[cfg(target_os = "linux")]
+    fn confine(&self, cmd: &mut Proc) -> Result<bool, String> {
+        use std::os::unix::process::CommandExt;
+        if !self.readonly && self.network { return Ok(false); }
+
+        let isolation = linux::Isolation::new(self);
+        if let Err(e) = isolation.probe() {
+            if !self.network {
+                return Err(format!("can't cut the command off from the network here: {}", e));
+            }
+            errln!("sandbox: can't make the current directory read-only here: {}", e);
+            return Ok(false);
+        }
+        // Only async-signal-safe calls between fork and exec, which
+        // `enter` keeps to
+        unsafe { cmd.pre_exec(move || isolation.enter()); }
+        Ok(self.readonly)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn confine(&self, _cmd: &mut Proc) -> Result<bool, String> {
+        Ok(self.readonly)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    fn confine(&self, _cmd: &mut Proc) -> Result<bool, String> {
+        if !self.network {
+            return Err("cutting a command off from the network is not supported on this platform".to_string());
+        }
+        if self.readonly {
+            errln!("sandbox: the current directory can't be made read-only on this platform");
+        }
+        Ok(false)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::CString;
+    use std::io::{Error, Result};
+    use std::os::unix::ffi::OsStrExt;
+    use std::ptr::null;
+
+    /// Everything the child needs to confine itself, prepared before the
+    /// fork: after it, nothing may allocate.
+    pub struct Isolation {
+        flags: libc::c_int,
+        uid_map: Vec<u8>,
+        gid_map: Vec<u8>,
+        /// The current directory, the temporary HOME, and the mount flags
+        /// the read-only remount has to keep.
+        readonly: Option<(CString, CString, libc::c_ulong)>,
+    }
+
+    impl Isolation {
+        pub fn new(plan: &super::Plan) -> Isolation {
+            let mut flags = libc::CLONE_NEWUSER | libc::CLONE_NEWNS;
+            if !plan.network { flags |= libc::CLONE_NEWNET; }
+            let path = |p: &std::path::Path| CString::new(p.as_os_str().as_bytes()).unwrap_or_default();
+            let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+            Isolation {
+                flags,
+                // The same ids inside as out, so files keep their owners
+                uid_map: format!("{} {} 1", uid, uid).into_bytes(),
+                gid_map: format!("{} {} 1", gid, gid).into_bytes(),
+                readonly: plan.readonly.then(|| (path(&plan.cwd), path(&plan.home), locked_flags(&plan.cwd))),
+            }
+        }
+
+        /// Try `enter` in a throwaway child, to find out before the real
+        /// command runs whether the kernel allows it.
+        pub fn probe(&self) -> Result<()> {
+            unsafe {
+                let pid = match libc::fork() {
+                    -1 => return Err(Error::last_os_error()),
+                    0  => libc::_exit(match self.enter() { Ok(()) => 0, Err(e) => e.raw_os_error().unwrap_or(libc::EPERM) }),
+                    pid => pid,
+                };
+                let mut raw = 0;
+                while libc::waitpid(pid, &mut raw, 0) < 0 {
+                    let err = Error::last_os_error();
+                    if err.kind() != std::io::ErrorKind::Interrupted { return Err(err); }
+                }
+                match libc::WIFEXITED(raw).then(|| libc::WEXITSTATUS(raw)) {
+                    Some(0) => Ok(()),
+                    Some(errno) => Err(Error::from_raw_os_error(errno)),
+                    None => Err(Error::other("the test child was killed")),
+                }
+            }
+        }
+
+        /// Move into new namespaces and, for a read-only current
+        /// directory, mount it over itself read-only. The temporary HOME
+        /// is bound first, so it stays writable even inside it.
+        pub fn enter(&self) -> Result<()> {
+            unsafe {
+                check(libc::unshare(self.flags))?;
+                write(c"/proc/self/setgroups", b"deny")?;
+                write(c"/proc/self/uid_map", &self.uid_map)?;
+                write(c"/proc/self/gid_map", &self.gid_map)?;
+
+                let Some((cwd, home, locked)) = &self.readonly else { return Ok(()) };
+                // Keep these mounts from reaching the rest of the system
+                check(libc::mount(null(), c"/".as_ptr(), null(), libc::MS_REC | libc::MS_PRIVATE, null()))?;
+                check(libc::mount(home.as_ptr(), home.as_ptr(), null(), libc::MS_BIND, null()))?;
+                check(libc::mount(cwd.as_ptr(), cwd.as_ptr(), null(), libc::MS_BIND | libc::MS_REC, null()))?;
+                let remount = libc::MS_REMOUNT | libc::MS_BIND | libc::MS_RDONLY | locked;
+                check(libc::mount(null(), cwd.as_ptr(), null(), remount, null()))?;
+                // Step onto the new mount: the old directory is still
+                // writable underneath it
+                check(libc::chdir(cwd.as_ptr()))?;
+            }
+            Ok(())
+        }
+    }
+
+    fn check(ret: libc::c_int) -> Result<()> {
+        if ret < 0 { Err(Error::last_os_error()) } else { Ok(()) }
+    }
+
+    unsafe fn write(path: &std::ffi::CStr, data: &[u8]) -> Result<()> {
+        let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        check(fd)?;
+        let written = libc::write(fd, data.as_ptr().cast(), data.len());
+        libc::close(fd);
+        if written < 0 { Err(Error::last_os_error()) } else { Ok(()) }
+    }
+
+    /// A remount inside a user namespace must keep flags like nosuid that
+    /// the mount already has, or the kernel refuses it.
+    fn locked_flags(dir: &std::path::Path) -> libc::c_ulong {
+        let Ok(path) = CString::new(dir.as_os_str().as_bytes()) else { return 0 };
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 { return 0; }
+        [
+            (libc::ST_NOSUID, libc::MS_NOSUID),
+            (libc::ST_NODEV, libc::MS_NODEV),
+            (libc::ST_NOEXEC, libc::MS_NOEXEC),
+            (libc::ST_NOATIME, libc::MS_NOATIME),
+            (libc::ST_NODIRATIME, libc::MS_NODIRATIME),
+            (libc::ST_RELATIME, libc::MS_RELATIME),
+        ]
+        .into_iter()
+        .filter(|(st, _)| stat.f_flag & st != 0)
+        .fold(0, |flags, (_, ms)| flags | ms)
+    }
+}
+
+// ── Report ────────────────────────────────────────────────────────────────────
+
+/// The files under a directory with their size and modification time,
+/// or None if there were too many to list.
+type Snapshot = Option<HashMap<PathBuf, (u64, SystemTime)>>;
+
+fn snapshot(dir: &Path) -> Snapshot {
+    let mut files = HashMap::new();
+    list_files(dir, &mut files).then_some(files)
+}
+
+fn list_files(dir: &Path, files: &mut HashMap<PathBuf, (u64, SystemTime)>) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else { return true };
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else { continue };
+        // A link's own type, so links are never followed
+        if meta.is_dir() {
+            if !list_files(&entry.path(), files) { return false; }
+            continue;
+        }
+        if files.len() >= MAX_FILES { return false; }
+        files.insert(entry.path(), (meta.len(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)));
+    }
+    true
+}
+
+/// List what the command left behind: everything in the temporary HOME,
+/// and what changed in the current directory if it wasn't read-only.
+fn report(plan: &Plan, before: Option<Snapshot>) {
+    let mut lines = Vec::new();
+
+    let mut in_home: Vec<PathBuf> = snapshot(&plan.home).unwrap_or_default().into_keys().collect();
+    in_home.sort();
+    for path in in_home {
+        let shown = path.strip_prefix(&plan.home).unwrap_or(&path);
+        lines.push(format!("+ ~/{}", paths::display(shown)));
+    }
+
+    match (before, snapshot(&plan.cwd)) {
+        (Some(Some(before)), Some(after)) => {
+            let mut changes: Vec<(&PathBuf, char)> = after.iter()
+                .filter(|(path, _)| !path.starts_with(&plan.home))
+                .filter_map(|(path, meta)| match before.get(path) {
+                    None => Some((path, '+')),
+                    Some(old) if old != meta => Some((path, '~')),
+                    Some(_) => None,
+                })
+                .chain(before.keys().filter(|p| !after.contains_key(*p)).map(|p| (p, '-')))
+                .collect();
+            changes.sort();
+            for (path, mark) in changes {
+                let shown = path.strip_prefix(&plan.cwd).unwrap_or(path);
+                lines.push(format!("{} {}", mark, paths::display(shown)));
+            }
+        }
+        (Some(_), _) => errln!("sandbox: more than {} files here, so changes to them aren't reported", MAX_FILES),
+        (None, _) => {}
+    }
+
+    if lines.is_empty() {
+        errln!("sandbox: no files written");
+        return;
+    }
+    errln!("sandbox: files written:");
+    for line in lines { errln!("  {}", line); }
+}
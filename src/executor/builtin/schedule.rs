@@ -0,0 +1,356 @@
+// src/executor/builtin/schedule.rs
+// A small cron inside the shell:
+//   schedule add "0 9 * * 1-5" 'backup.sh'
+//   schedule [list]
+//   schedule rm ID...
+//
+// Jobs live in ~/.rshell/schedule.toml. A single COMMAND is kept as the
+// command line it is; several words are quoted and joined. While any
+// interactive shell is open, a background thread checks them once a
+// minute and runs each due command as `rshell -c CMD`, appending its
+// output to ~/.rshell/schedule.log. `rshell schedule` runs the same loop
+// in the foreground, for use from a login item or service manager where
+// the shell itself isn't kept open.
+//
+// Several shells may be open at once, so a lock file records which one
+// runs the jobs; the others take over if it stops refreshing the lock.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use super::pkg::rshell_dir;
+use super::util::local_datetime;
+
+// ── Builtin ───────────────────────────────────────────────────────────────────
+
+pub fn builtin_schedule(args: &[String]) -> i32 {
+    match args.get(1).map(|s| s.as_str()) {
+        None | Some("list") | Some("ls") => list_jobs(),
+
+        Some("add") => {
+            let (Some(spec), Some(_)) = (args.get(2), args.get(3)) else {
+                errln!("usage: schedule add \"MIN HOUR DAY MONTH WEEKDAY\" COMMAND");
+                return 1;
+            };
+            if let Err(e) = CronSpec::parse(spec) {
+                errln!("schedule: {}: {}", spec, e);
+                return 1;
+            }
+            let mut file = ScheduleFile::load();
+            let id = file.job.iter().map(|j| j.id).max().unwrap_or(0) + 1;
+            let command = command_line(&args[3..]);
+            file.job.push(ScheduledJob { id, when: spec.clone(), command });
+            if let Err(e) = file.save() {
+                errln!("schedule: could not save {}: {}", schedule_path().display(), e);
+                return 1;
+            }
+            start_scheduler();
+            outln!("[{}] scheduled", id);
+            0
+        }
+
+        Some("rm") | Some("remove") => {
+            if args.len() < 3 { errln!("usage: schedule rm ID..."); return 1; }
+            let mut file = ScheduleFile::load();
+            let mut code = 0;
+            for arg in &args[2..] {
+                let before = file.job.len();
+                file.job.retain(|j| j.id.to_string() != *arg);
+                if file.job.len() == before {
+                    errln!("schedule: {}: no such job", arg);
+                    code = 1;
+                }
+            }
+            if let Err(e) = file.save() {
+                errln!("schedule: could not save {}: {}", schedule_path().display(), e);
+                return 1;
+            }
+            code
+        }
+
+        Some(other) => {
+            errln!("schedule: unknown subcommand '{}' (add, list, rm)", other);
+            1
+        }
+    }
+}
+
+/// The command line to store for `schedule add ... COMMAND`.
+fn command_line(words: &[String]) -> String {
+    match words {
+        [line] => line.clone(),
+        _ => words.iter().map(|w| super::shell_quote(w)).collect::<Vec<_>>().join(" "),
+    }
+}
+
+/// `rshell schedule`: run the jobs in the foreground, without a shell.
+pub fn run_scheduler() -> i32 {
+    RUNNING.store(true, Ordering::SeqCst);
+    println!("schedule: running jobs from {} (Ctrl+C to stop)", schedule_path().display());
+    run_loop()
+}
+
+fn list_jobs() -> i32 {
+    let file = ScheduleFile::load();
+    if file.job.is_empty() {
+        outln!("No scheduled jobs.");
+        return 0;
+    }
+    let now = unix_minute(SystemTime::now());
+    for job in &file.job {
+        let next = match CronSpec::parse(&job.when) {
+            Ok(spec) => spec.next_after(now).map(format_minute).unwrap_or_else(|| "never".to_string()),
+            Err(e)   => format!("invalid: {}", e),
+        };
+        outln!("{:>3}  {:<16} next {:<16}  {}", job.id, job.when, next, job.command);
+    }
+    0
+}
+
+// ── Storage ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ScheduledJob {
+    id: usize,
+    when: String,
+    command: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ScheduleFile {
+    #[serde(default)]
+    job: Vec<ScheduledJob>,
+}
+
+fn schedule_path() -> PathBuf {
+    rshell_dir().join("schedule.toml")
+}
+
+impl ScheduleFile {
+    fn load() -> Self {
+        let path = schedule_path();
+        let Ok(content) = std::fs::read_to_string(&path) else { return Self::default() };
+        toml::from_str(&content).unwrap_or_else(|e| {
+            errln!("schedule: warning: {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let content = toml::to_string(self).map_err(std::io::Error::other)?;
+        std::fs::create_dir_all(rshell_dir())?;
+        std::fs::write(schedule_path(), content)
+    }
+}
+
+// ── Scheduler ─────────────────────────────────────────────────────────────────
+
+/// Whether this process is already running the scheduler loop.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Start the background scheduler thread, once per process.
+pub fn start_scheduler() {
+    if !RUNNING.swap(true, Ordering::SeqCst) {
+        std::thread::spawn(run_loop);
+    }
+}
+
+/// Check the jobs at the start of every minute, forever.
+fn run_loop() -> i32 {
+    // The minute we start in is skipped, so restarting a shell can't run
+    // a job twice
+    let mut last = unix_minute(SystemTime::now());
+    loop {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        std::thread::sleep(Duration::from_secs(60 - now.as_secs() % 60));
+
+        let minute = unix_minute(SystemTime::now());
+        if minute <= last { continue; }
+        last = minute;
+
+        let jobs = ScheduleFile::load().job;
+        if jobs.is_empty() || !claim_lock() { continue; }
+        for job in jobs {
+            if CronSpec::parse(&job.when).is_ok_and(|spec| spec.matches(minute)) {
+                run_job(&job, minute);
+            }
+        }
+    }
+}
+
+/// Become (or stay) the process that runs the jobs. The lock holds the
+/// owner's pid and is refreshed every minute; one left untouched for two
+/// minutes belongs to a shell that has gone away.
+fn claim_lock() -> bool {
+    let path = rshell_dir().join("schedule.lock");
+    let pid  = std::process::id().to_string();
+    let owner = std::fs::read_to_string(&path).unwrap_or_default();
+    let stale = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .map(|t| t.elapsed().unwrap_or_default() > Duration::from_secs(120))
+        .unwrap_or(true);
+    if owner.trim() != pid && !stale { return false; }
+    std::fs::write(&path, &pid).is_ok()
+}
+
+fn run_job(job: &ScheduledJob, minute: i64) {
+    use std::io::Write;
+    let log_path = rshell_dir().join("schedule.log");
+    let Ok(mut log) = std::fs::OpenOptions::new().create(true).append(true).open(&log_path) else { return };
+    writeln!(log, "[{}] job {}: {}", format_minute(minute), job.id, job.command).ok();
+
+    let Ok(exe) = std::env::current_exe() else { return };
+    let (Ok(out), Ok(err)) = (log.try_clone(), log.try_clone()) else { return };
+    let child = std::process::Command::new(exe)
+        .args(["-c", &job.command])
+        .stdin(std::process::Stdio::null())
+        .stdout(out)
+        .stderr(err)
+        .spawn();
+    match child {
+        // Reap it in the background so it doesn't linger as a zombie
+        Ok(mut child) => { std::thread::spawn(move || child.wait()); }
+        Err(e)        => { writeln!(log, "schedule: {}", e).ok(); }
+    }
+}
+
+fn unix_minute(time: SystemTime) -> i64 {
+    super::unix_secs(time).div_euclid(60)
+}
+
+fn format_minute(minute: i64) -> String {
+    let (y, mo, d, h, mi, _) = local_datetime(minute * 60);
+    format!("{}-{:02}-{:02} {:02}:{:02}", y, mo, d, h, mi)
+}
+
+// ── Cron expressions ──────────────────────────────────────────────────────────
+
+/// A parsed `MIN HOUR DAY MONTH WEEKDAY` expression, each field a bit set.
+#[derive(Debug, Clone, PartialEq)]
+struct CronSpec {
+    minutes:  u64,
+    hours:    u64,
+    days:     u64,
+    months:   u64,
+    weekdays: u64,
+    /// Whether DAY / WEEKDAY were `*`. When both are restricted, either
+    /// may match, as in cron.
+    any_day:     bool,
+    any_weekday: bool,
+}
+
+impl CronSpec {
+    fn parse(spec: &str) -> Result<Self, String> {
+        let spec = match spec.trim() {
+            "@hourly"              => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly"              => "0 0 * * 0",
+            "@monthly"             => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            s => s,
+        };
+        let fields: Vec<&str> = spec.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err("expected 5 fields: MIN HOUR DAY MONTH WEEKDAY".to_string());
+        }
+        // Sunday may be written as 0 or 7
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+        if weekdays & (1 << 7) != 0 { weekdays |= 1; }
+
+        Ok(CronSpec {
+            minutes:  parse_field(fields[0], 0, 59)?,
+            hours:    parse_field(fields[1], 0, 23)?,
+            days:     parse_field(fields[2], 1, 31)?,
+            months:   parse_field(fields[3], 1, 12)?,
+            weekdays,
+            any_day:     fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    /// Whether the job is due in the given minute (since the epoch), in
+    /// local time.
+    fn matches(&self, minute: i64) -> bool {
+        let (y, mo, d, h, mi, _) = local_datetime(minute * 60);
+        let bit = |set: u64, n: u32| set & (1 << n) != 0;
+
+        let day_ok     = bit(self.days, d);
+        let weekday_ok = bit(self.weekdays, weekday(y, mo, d));
+        let date_ok = if self.any_day || self.any_weekday {
+            day_ok && weekday_ok
+        } else {
+            day_ok || weekday_ok
+        };
+        date_ok && bit(self.minutes, mi) && bit(self.hours, h) && bit(self.months, mo)
+    }
+
+    /// The first minute after `minute` the job is due, looking up to a
+    /// year ahead.
+    fn next_after(&self, minute: i64) -> Option<i64> {
+        (minute + 1..=minute + 366 * 24 * 60).find(|m| self.matches(*m))
+    }
+}
+
+/// Parse one field — `*`, `N`, `A-B`, any of those with `/STEP`, or a
+/// comma-separated list of them — into a bit set of allowed values.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (r, s.parse::<u32>().ok().filter(|s| *s > 0)
+                .ok_or_else(|| format!("bad step in '{}'", part))?),
+            None => (part, 1),
+        };
+        let num = |s: &str| s.parse::<u32>().ok()
+            .filter(|n| (min..=max).contains(n))
+            .ok_or_else(|| format!("'{}' is not in {}-{}", s, min, max));
+
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            r => match r.split_once('-') {
+                Some((a, b)) => (num(a)?, num(b)?),
+                // `N/STEP` runs from N to the end of the range
+                None if step > 1 => (num(r)?, max),
+                None => { let n = num(r)?; (n, n) }
+            },
+        };
+        if lo > hi { return Err(format!("empty range '{}'", range)); }
+        for n in (lo..=hi).step_by(step as usize) { set |= 1 << n; }
+    }
+    Ok(set)
+}
+
+/// Day of the week, 0 = Sunday (Sakamoto's method).
+fn weekday(year: i64, month: u32, day: u32) -> u32 {
+    const T: [i64; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let y = if month < 3 { year - 1 } else { year };
+    ((y + y / 4 - y / 100 + y / 400 + T[month as usize - 1] + day as i64) % 7) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_several_words() {
+        let words = |w: &[&str]| w.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(command_line(&words(&["backup.sh --full >log"])), "backup.sh --full >log");
+        assert_eq!(command_line(&words(&["echo", "a b", "it's"])), "echo 'a b' 'it'\\''s'");
+    }
+
+    #[test]
+    fn test_cron_fields() {
+        assert_eq!(parse_field("*", 0, 3), Ok(0b1111));
+        assert_eq!(parse_field("1,3", 0, 5), Ok(0b1010));
+        assert_eq!(parse_field("*/2", 0, 5), Ok(0b10101));
+        assert_eq!(parse_field("1-4/3", 0, 5), Ok(0b10010));
+        assert_eq!(parse_field("10/20", 0, 59), Ok((1 << 10) | (1 << 30) | (1 << 50)));
+        assert!(parse_field("60", 0, 59).is_err());
+        assert!(parse_field("5-1", 0, 59).is_err());
+        assert!(CronSpec::parse("0 9 * *").is_err());
+        assert!(CronSpec::parse("@daily").is_ok());
+        assert_eq!(weekday(2024, 2, 29), 4);
+        assert_eq!(weekday(2000, 1, 1), 6);
+    }
+}
@@ -0,0 +1,430 @@
+// src/executor/builtin/sed.rs
+// sed [-n] [-E] [-i[SUFFIX]] [-e SCRIPT]... [SCRIPT] [FILE]... — the part of
+// sed that covers everyday substitutions:
+//
+//   s/PAT/REPL/[gipN]   replace the first (N-th, every with g) match; p
+//                       prints the line if it changed, i ignores case
+//   d                   delete the line
+//   p                   print the line (with -n, the only output)
+//
+// Each command can take an address — a line number, `$` for the last line,
+// or /PAT/ — or a range of two, `A,B`, and `!` to apply where it doesn't
+// match. Commands are separated by `;` or newlines, or given as several -e.
+//
+// Patterns are POSIX basic regular expressions (`\(` `\)` group, `+` is a
+// literal), or extended ones with -E. In REPL, `&` is the whole match and
+// `\1`..`\9` the groups. Files are read as one stream, except with -i,
+// which edits each file in place (keeping a copy with SUFFIX if given).
+
+use regex::{Captures, Regex, RegexBuilder};
+
+const USAGE: &str = "usage: sed [-n] [-E] [-i[SUFFIX]] [-e SCRIPT]... [SCRIPT] [FILE]...";
+
+pub fn builtin_sed(args: &[String]) -> i32 {
+    let mut quiet = false;
+    let mut extended = false;
+    let mut in_place: Option<String> = None;
+    let mut scripts: Vec<String> = Vec::new();
+    let mut operands: Vec<&str> = Vec::new();
+
+    let mut i = 1;
+    while let Some(arg) = args.get(i) {
+        i += 1;
+        if arg == "--" {
+            operands.extend(args[i..].iter().map(String::as_str));
+            break;
+        }
+        if !arg.starts_with('-') || arg == "-" {
+            operands.push(arg);
+            continue;
+        }
+        match arg.as_str() {
+            "--help" => { outln!("{}", USAGE); return 0; }
+            "-n" | "--quiet" | "--silent" => quiet = true,
+            "-E" | "-r" | "--regexp-extended" => extended = true,
+            "-e" | "--expression" => match args.get(i) {
+                Some(script) => { scripts.push(script.clone()); i += 1; }
+                None => { errln!("sed: option requires an argument -- 'e'"); return 2; }
+            },
+            s if s.starts_with("-i") => in_place = Some(s[2..].to_string()),
+            s if s.starts_with("--in-place") => {
+                in_place = Some(s.strip_prefix("--in-place=").unwrap_or("").to_string());
+            }
+            s => {
+                errln!("sed: invalid option -- '{}'", s.trim_start_matches('-'));
+                errln!("{}", USAGE);
+                return 2;
+            }
+        }
+    }
+    // Without -e, the first operand is the script
+    if scripts.is_empty() {
+        if operands.is_empty() { errln!("{}", USAGE); return 2; }
+        scripts.push(operands.remove(0).to_string());
+    }
+
+    let mut program = match parse(&scripts.join("\n"), extended) {
+        Ok(program) => program,
+        Err(e) => { errln!("sed: {}", e); return 1; }
+    };
+
+    if let Some(suffix) = in_place {
+        if operands.is_empty() { errln!("sed: no input files"); return 1; }
+        let mut code = 0;
+        for file in operands {
+            if let Err(e) = edit_in_place(&mut program, file, &suffix, quiet) {
+                errln!("sed: {}: {}", file, e);
+                code = 1;
+            }
+        }
+        return code;
+    }
+
+    if operands.is_empty() { operands.push("-"); }
+    let mut input = String::new();
+    let mut code = 0;
+    for file in operands {
+        match super::io::read_to_string(file) {
+            Ok(text) => {
+                // A file without a last newline still ends its last line
+                if !input.is_empty() && !input.ends_with('\n') { input.push('\n'); }
+                input.push_str(&text);
+            }
+            Err(e) => { errln!("sed: {}: {}", file, e); code = 1; }
+        }
+    }
+    out!("{}", run(&mut program, &input, quiet));
+    code
+}
+
+fn edit_in_place(program: &mut [Command], file: &str, suffix: &str, quiet: bool) -> std::io::Result<()> {
+    let text = std::fs::read_to_string(file)?;
+    if !suffix.is_empty() {
+        std::fs::copy(file, format!("{}{}", file, suffix))?;
+    }
+    for cmd in program.iter_mut() { cmd.active = false; }
+    // Writing over the file keeps its permissions and links
+    std::fs::write(file, run(program, &text, quiet))
+}
+
+// ── Scripts ───────────────────────────────────────────────────────────────────
+
+enum Address {
+    Line(usize),
+    Last,
+    Pattern(Regex),
+}
+
+enum Action {
+    Substitute { re: Regex, replacement: String, global: bool, nth: usize, print: bool },
+    Delete,
+    Print,
+}
+
+struct Command {
+    from: Option<Address>,
+    to: Option<Address>,
+    negate: bool,
+    action: Action,
+    /// Inside a range: `from` matched and `to` hasn't yet
+    active: bool,
+}
+
+impl Command {
+    fn selects(&mut self, n: usize, last: bool, line: &str) -> bool {
+        let hit = |addr: &Address| match addr {
+            Address::Line(k)    => n == *k,
+            Address::Last       => last,
+            Address::Pattern(r) => r.is_match(line),
+        };
+        let selected = match (&self.from, &self.to) {
+            (None, _) => true,
+            (Some(from), None) => hit(from),
+            (Some(from), Some(to)) => {
+                if self.active {
+                    // A line number already passed ends the range at once
+                    let done = match to { Address::Line(k) => n >= *k, _ => hit(to) };
+                    self.active = !done;
+                    true
+                } else if hit(from) {
+                    // The end is only looked for from the next line on,
+                    // except a line number, which may already be behind
+                    self.active = !matches!(to, Address::Line(k) if *k <= n);
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+        selected != self.negate
+    }
+}
+
+/// Run `program` over `input`, returning the output.
+fn run(program: &mut [Command], input: &str, quiet: bool) -> String {
+    let mut out = String::new();
+    // Split at '\n' alone, so a CRLF file keeps its '\r's
+    let lines: Vec<&str> = input.split_terminator('\n').collect();
+    let mut last_start = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let last = i + 1 == lines.len();
+        if last { last_start = out.len(); }
+        let mut text = line.to_string();
+        let mut deleted = false;
+        for cmd in program.iter_mut() {
+            if !cmd.selects(i + 1, last, &text) { continue; }
+            match &cmd.action {
+                Action::Delete => { deleted = true; break; }
+                Action::Print  => { out.push_str(&text); out.push('\n'); }
+                Action::Substitute { re, replacement, global, nth, print } => {
+                    if let Some(changed) = substitute(re, replacement, *global, *nth, &text) {
+                        text = changed;
+                        if *print { out.push_str(&text); out.push('\n'); }
+                    }
+                }
+            }
+        }
+        if !quiet && !deleted { out.push_str(&text); out.push('\n'); }
+    }
+    // Like sed, a last line without a newline is left without one
+    if !input.ends_with('\n') && out.len() > last_start { out.pop(); }
+    out
+}
+
+/// `line` with the `nth` match of `re` replaced (and every later one if
+/// `global`), or None if nothing was.
+fn substitute(re: &Regex, replacement: &str, global: bool, nth: usize, line: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut end = 0;
+    let mut changed = false;
+    for (k, caps) in re.captures_iter(line).enumerate() {
+        let k = k + 1;
+        if k < nth { continue; }
+        if k > nth && !global { break; }
+        let whole = caps.get(0).unwrap();
+        out.push_str(&line[end..whole.start()]);
+        out.push_str(&expand(replacement, &caps));
+        end = whole.end();
+        changed = true;
+    }
+    if !changed { return None; }
+    out.push_str(&line[end..]);
+    Some(out)
+}
+
+/// REPL with `&` and `\1`..`\9` filled in.
+fn expand(replacement: &str, caps: &Captures) -> String {
+    let mut out = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => out.push_str(&caps[0]),
+            '\\' => match chars.next() {
+                Some(d @ '0'..='9') => {
+                    let group = d.to_digit(10).unwrap() as usize;
+                    out.push_str(caps.get(group).map(|m| m.as_str()).unwrap_or(""));
+                }
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ── Parsing ───────────────────────────────────────────────────────────────────
+
+fn parse(script: &str, extended: bool) -> Result<Vec<Command>, String> {
+    let chars: Vec<char> = script.chars().collect();
+    let mut pos = 0;
+    let mut program = Vec::new();
+    loop {
+        while pos < chars.len() && (chars[pos].is_whitespace() || chars[pos] == ';') { pos += 1; }
+        if pos >= chars.len() { break; }
+
+        let from = parse_address(&chars, &mut pos, extended)?;
+        let mut to = None;
+        if from.is_some() && chars.get(pos) == Some(&',') {
+            pos += 1;
+            to = parse_address(&chars, &mut pos, extended)?;
+            if to.is_none() { return Err("unexpected `,'".to_string()); }
+        }
+        while chars.get(pos).is_some_and(|c| c.is_whitespace()) { pos += 1; }
+        let negate = chars.get(pos) == Some(&'!');
+        if negate { pos += 1; }
+        while chars.get(pos).is_some_and(|c| c.is_whitespace()) { pos += 1; }
+
+        let action = match chars.get(pos) {
+            Some('d') => { pos += 1; Action::Delete }
+            Some('p') => { pos += 1; Action::Print }
+            Some('s') => { pos += 1; parse_substitute(&chars, &mut pos, extended)? }
+            Some(c) => return Err(format!("unknown command: `{}'", c)),
+            None => return Err("missing command".to_string()),
+        };
+        while chars.get(pos).is_some_and(|c| *c == ' ' || *c == '\t') { pos += 1; }
+        if !matches!(chars.get(pos), None | Some(';') | Some('\n')) {
+            return Err(format!("extra characters after command: `{}'", chars[pos]));
+        }
+        program.push(Command { from, to, negate, action, active: false });
+    }
+    Ok(program)
+}
+
+fn parse_address(chars: &[char], pos: &mut usize, extended: bool) -> Result<Option<Address>, String> {
+    match chars.get(*pos) {
+        Some('$') => { *pos += 1; Ok(Some(Address::Last)) }
+        Some(c) if c.is_ascii_digit() => {
+            let start = *pos;
+            while chars.get(*pos).is_some_and(|c| c.is_ascii_digit()) { *pos += 1; }
+            let n: String = chars[start..*pos].iter().collect();
+            match n.parse() {
+                Ok(0) | Err(_) => Err(format!("invalid line number: {}", n)),
+                Ok(n) => Ok(Some(Address::Line(n))),
+            }
+        }
+        Some('/') => {
+            *pos += 1;
+            let pattern = read_delimited(chars, pos, '/')?;
+            Ok(Some(Address::Pattern(compile(&pattern, extended, false)?)))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_substitute(chars: &[char], pos: &mut usize, extended: bool) -> Result<Action, String> {
+    let Some(&delim) = chars.get(*pos) else { return Err("unterminated `s' command".to_string()) };
+    if delim == '\\' || delim == '\n' { return Err("unterminated `s' command".to_string()); }
+    *pos += 1;
+    let pattern = read_delimited(chars, pos, delim)?;
+    let replacement = read_delimited(chars, pos, delim)?;
+
+    let (mut global, mut print, mut ignore_case, mut nth) = (false, false, false, None);
+    while let Some(&c) = chars.get(*pos) {
+        match c {
+            'g' => global = true,
+            'p' => print = true,
+            'i' | 'I' => ignore_case = true,
+            d if d.is_ascii_digit() => {
+                let digit = d.to_digit(10).unwrap() as usize;
+                nth = Some(nth.unwrap_or(0) * 10 + digit);
+            }
+            _ => break,
+        }
+        *pos += 1;
+    }
+    if nth == Some(0) { return Err("number option to `s' command may not be zero".to_string()); }
+    Ok(Action::Substitute {
+        re: compile(&pattern, extended, ignore_case)?,
+        replacement,
+        global,
+        nth: nth.unwrap_or(1),
+        print,
+    })
+}
+
+/// The text up to the next unescaped `delim`, with `\delim` turned into
+/// the delimiter itself and other escapes kept for the regex.
+fn read_delimited(chars: &[char], pos: &mut usize, delim: char) -> Result<String, String> {
+    let mut text = String::new();
+    while let Some(&c) = chars.get(*pos) {
+        *pos += 1;
+        if c == delim { return Ok(text); }
+        if c == '\\' {
+            match chars.get(*pos) {
+                Some(&next) if next == delim => { text.push(next); *pos += 1; }
+                Some(&next) => { text.push('\\'); text.push(next); *pos += 1; }
+                None => text.push('\\'),
+            }
+            continue;
+        }
+        text.push(c);
+    }
+    Err(format!("unterminated address or `s' command (missing `{}')", delim))
+}
+
+fn compile(pattern: &str, extended: bool, ignore_case: bool) -> Result<Regex, String> {
+    let pattern = if extended { pattern.to_string() } else { basic_to_extended(pattern) };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|e| format!("invalid pattern: {}", e))
+}
+
+/// Rewrite a POSIX basic regular expression in the regex crate's syntax:
+/// `\(`, `\)`, `\{`, `\}`, `\|`, `\+` and `\?` are operators, and the bare
+/// characters are literal.
+pub(super) fn basic_to_extended(pattern: &str) -> String {
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(op @ ('(' | ')' | '{' | '}' | '|' | '+' | '?')) => out.push(op),
+                Some(other) => { out.push('\\'); out.push(other); }
+                None => out.push_str("\\\\"),
+            },
+            '(' | ')' | '{' | '}' | '|' | '+' | '?' => { out.push('\\'); out.push(c); }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sed(script: &str, input: &str, quiet: bool) -> String {
+        let mut program = parse(script, false).unwrap();
+        run(&mut program, input, quiet)
+    }
+
+    #[test]
+    fn substitutes() {
+        assert_eq!(sed("s/o/0/", "foo boo\n", false), "f0o boo\n");
+        assert_eq!(sed("s/o/0/g", "foo boo\n", false), "f00 b00\n");
+        assert_eq!(sed("s/o/0/3", "foo boo\n", false), "foo b0o\n");
+        assert_eq!(sed("s/FOO/x/i", "foo\n", false), "x\n");
+        assert_eq!(sed("s|/usr|/opt|", "/usr/bin\n", false), "/opt/bin\n");
+        assert_eq!(sed(r"s/\(a*\)b/[\1]&/", "aab\n", false), "[aa]aab\n");
+        assert_eq!(sed("s/a+/x/", "a+ aa\n", false), "x aa\n");
+    }
+
+    #[test]
+    fn addresses_and_ranges() {
+        let input = "one\ntwo\nthree\nfour\n";
+        assert_eq!(sed("2d", input, false), "one\nthree\nfour\n");
+        assert_eq!(sed("$d", input, false), "one\ntwo\nthree\n");
+        assert_eq!(sed("/t/d", input, false), "one\nfour\n");
+        assert_eq!(sed("2,3d", input, false), "one\nfour\n");
+        assert_eq!(sed("/two/,$!d", input, false), "two\nthree\nfour\n");
+        assert_eq!(sed("3,1p", input, true), "three\n");
+    }
+
+    #[test]
+    fn quiet_prints_only_what_is_asked() {
+        assert_eq!(sed("/o/p", "one\ntwo\nsix\n", true), "one\ntwo\n");
+        assert_eq!(sed("s/x/X/p", "six\nten\n", true), "siX\n");
+        assert_eq!(sed("s/a/b/; s/b/c/", "a", false), "c");
+        assert_eq!(sed("1p", "a\nb", true), "a\n");
+        assert_eq!(sed("p", "a\nb", false), "a\na\nb\nb");
+    }
+
+    #[test]
+    fn keeps_line_endings() {
+        assert_eq!(sed("s/a/b/", "a\r\nc\r\n", false), "b\r\nc\r\n");
+        assert_eq!(sed("2d", "a\n\nc\n", false), "a\nc\n");
+        assert_eq!(sed("s/x/y/", "", false), "");
+    }
+
+    #[test]
+    fn rejects_bad_scripts() {
+        assert!(parse("s/a/b", false).is_err());
+        assert!(parse("k", false).is_err());
+        assert!(parse("0d", false).is_err());
+        assert!(parse("s/a/b/0", false).is_err());
+    }
+}
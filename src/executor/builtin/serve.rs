@@ -0,0 +1,322 @@
+// src/executor/builtin/serve.rs
+// serve [DIR] [-p PORT] [-b ADDR] — share DIR (the current directory by
+// default) over HTTP for a quick download from a browser, or with
+// `-b 0.0.0.0` from another machine. Files are sent as they are; a
+// directory gets its index.html, or a listing of its entries.
+//
+// Each request is logged on stderr. Ctrl+C stops the server; `serve &`
+// keeps it running as a job instead. Only GET and HEAD are answered, and
+// no URL reaches outside DIR, not even through a symlink.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::shell::Shell;
+use super::util::{format_size_as, percent_decode, percent_encode, Opt, Options, SizeUnits};
+
+pub const SERVE: Options = Options {
+    name: "serve",
+    operands: "[DIR]",
+    about: "Serve DIR (default: the current directory) over HTTP.",
+    opts: &[
+        Opt::new("p", "port", "listen on PORT (default 8000, 0 picks a free one)").takes("PORT"),
+        Opt::new("b", "bind", "listen on ADDR (default 127.0.0.1, this machine only)").takes("ADDR"),
+    ],
+    numeric: None,
+};
+
+const DEFAULT_PORT: u16 = 8000;
+
+/// Set by Ctrl+C while the server runs in the foreground.
+static STOP: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_: libc::c_int) {
+    STOP.store(true, Ordering::SeqCst);
+}
+
+pub fn builtin_serve(shell: &Shell, args: &[String]) -> i32 {
+    let p = match SERVE.parse(args) { Ok(p) => p, Err(code) => return code };
+    let port = match p.value("port").map(str::parse::<u16>) {
+        None => DEFAULT_PORT,
+        Some(Ok(port)) => port,
+        Some(Err(_)) => { errln!("serve: invalid port '{}'", p.value("port").unwrap_or_default()); return 2; }
+    };
+    let bind = p.value("bind").unwrap_or("127.0.0.1");
+    let root = match p.operands[..] {
+        [] => shell.cwd.clone(),
+        [dir] => shell.cwd.join(dir),
+        _ => { errln!("usage: serve [DIR] [-p PORT] [-b ADDR]"); return 2; }
+    };
+    if !root.is_dir() {
+        errln!("serve: {}: not a directory", crate::paths::display(&root));
+        return 1;
+    }
+    // Requests are checked against the real path, symlinks resolved
+    let root = match root.canonicalize() {
+        Ok(root) => root,
+        Err(e) => { errln!("serve: {}: {}", crate::paths::display(&root), e); return 1; }
+    };
+
+    let listener = match TcpListener::bind((bind, port)) {
+        Ok(l) => l,
+        Err(e) => { errln!("serve: {}:{}: {}", bind, port, e); return 1; }
+    };
+    // Polled, so Ctrl+C gets a look in between connections
+    if let Err(e) = listener.set_nonblocking(true) {
+        errln!("serve: {}", e);
+        return 1;
+    }
+    let port = listener.local_addr().map_or(port, |a| a.port());
+    let host = if bind == "0.0.0.0" || bind == "::" { "localhost" } else { bind };
+    errln!("Serving {} at http://{}:{}/ (Ctrl+C to stop)", crate::paths::display(&root), host, port);
+
+    STOP.store(false, Ordering::SeqCst);
+    #[cfg(unix)]
+    let previous = unsafe { libc::signal(libc::SIGINT, on_sigint as *const () as libc::sighandler_t) };
+    // A background job ignores Ctrl+C, and should go on doing so
+    #[cfg(unix)]
+    if previous == libc::SIG_IGN {
+        unsafe { libc::signal(libc::SIGINT, libc::SIG_IGN); }
+    }
+
+    while !STOP.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let root = root.clone();
+                std::thread::spawn(move || handle(&root, stream));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(Duration::from_millis(50)),
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => { errln!("serve: {}", e); std::thread::sleep(Duration::from_millis(50)); }
+        }
+    }
+
+    #[cfg(unix)]
+    unsafe { libc::signal(libc::SIGINT, previous); }
+    errln!("serve: stopped");
+    0
+}
+
+/// Answer one request on `stream` and log it.
+fn handle(root: &Path, stream: TcpStream) {
+    let peer = stream.peer_addr().map(|a| a.ip().to_string()).unwrap_or_else(|_| "?".to_string());
+    // Accepted sockets inherit non-blocking mode on some systems
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(10)));
+
+    let mut reader = BufReader::new((&stream).take(64 * 1024));
+    let mut request = String::new();
+    if reader.read_line(&mut request).is_err() || request.is_empty() { return; }
+    // The headers aren't needed, but have to be read past
+    let mut line = String::new();
+    while matches!(reader.read_line(&mut line), Ok(n) if n > 0) && !line.trim_end().is_empty() {
+        line.clear();
+    }
+
+    let mut words = request.split_whitespace();
+    let (method, target) = (words.next().unwrap_or_default(), words.next().unwrap_or("/"));
+    let status = respond(root, &mut &stream, method, target);
+    errln!("{} \"{} {}\" {}", peer, method, target, status);
+}
+
+/// Write the response for `method target`, returning its status.
+fn respond(root: &Path, out: &mut impl Write, method: &str, target: &str) -> u16 {
+    let head_only = method == "HEAD";
+    if method != "GET" && !head_only {
+        return error(out, 405, head_only);
+    }
+    let url_path = target.split(['?', '#']).next().unwrap_or("/");
+    let Some(relative) = local_path(url_path) else { return error(out, 400, head_only) };
+    let path = match inside(root, &root.join(relative)) {
+        Ok(path) => path,
+        Err(status) => return error(out, status, head_only),
+    };
+    let Ok(meta) = path.metadata() else { return error(out, 404, head_only) };
+
+    if !meta.is_dir() {
+        return send_file(out, &path, head_only);
+    }
+    // Relative links in the listing need the trailing slash
+    if !url_path.ends_with('/') {
+        let location = format!("Location: {}/\r\n", url_path);
+        let _ = head(out, 301, "text/plain", 0, &location);
+        return 301;
+    }
+    let index = path.join("index.html");
+    if index.is_file() {
+        return match inside(root, &index) {
+            Ok(index) => send_file(out, &index, head_only),
+            Err(status) => error(out, status, head_only),
+        };
+    }
+    match listing(&path, url_path) {
+        Ok(page) => {
+            let _ = head(out, 200, "text/html; charset=utf-8", page.len() as u64, "")
+                .and_then(|_| if head_only { Ok(()) } else { out.write_all(page.as_bytes()) });
+            200
+        }
+        Err(_) => error(out, 403, head_only),
+    }
+}
+
+/// `path` with symlinks resolved, if that is still inside `root` (itself
+/// resolved); otherwise the status to answer with.
+fn inside(root: &Path, path: &Path) -> Result<PathBuf, u16> {
+    let path = path.canonicalize().map_err(|_| 404u16)?;
+    if path.starts_with(root) { Ok(path) } else { Err(403) }
+}
+
+fn send_file(out: &mut impl Write, path: &Path, head_only: bool) -> u16 {
+    let Ok(mut file) = std::fs::File::open(path) else { return error(out, 403, head_only) };
+    let len = file.metadata().map_or(0, |m| m.len());
+    // The client going away part way through isn't the server's problem
+    let _ = head(out, 200, content_type(path), len, "")
+        .and_then(|_| if head_only { Ok(()) } else { std::io::copy(&mut file, out).map(|_| ()) });
+    200
+}
+
+fn error(out: &mut impl Write, status: u16, head_only: bool) -> u16 {
+    let body = format!("{} {}\n", status, reason(status));
+    let _ = head(out, status, "text/plain; charset=utf-8", body.len() as u64, "")
+        .and_then(|_| if head_only { Ok(()) } else { out.write_all(body.as_bytes()) });
+    status
+}
+
+fn head(out: &mut impl Write, status: u16, content_type: &str, len: u64, extra: &str) -> std::io::Result<()> {
+    write!(out, "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n",
+        status, reason(status), content_type, len, extra)
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        301 => "Moved Permanently",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _   => "",
+    }
+}
+
+/// An HTML page listing `dir`: directories first, then files with sizes.
+fn listing(dir: &Path, url_path: &str) -> std::io::Result<String> {
+    let mut entries: Vec<(bool, String, u64)> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|e| {
+            let meta = e.path().metadata().ok();
+            let is_dir = meta.as_ref().is_some_and(|m| m.is_dir());
+            (is_dir, e.file_name().to_string_lossy().into_owned(), meta.map_or(0, |m| m.len()))
+        })
+        .collect();
+    entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.to_lowercase().cmp(&b.1.to_lowercase())));
+
+    let title = escape_html(&percent_decode(url_path).map(|b| String::from_utf8_lossy(&b).into_owned()).unwrap_or_default());
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Index of {0}</title></head>\n\
+         <body><h1>Index of {0}</h1><hr><pre>\n", title);
+    if url_path != "/" { page.push_str("<a href=\"../\">../</a>\n"); }
+    for (is_dir, name, size) in entries {
+        let slash = if is_dir { "/" } else { "" };
+        let shown = format!("{}{}", name, slash);
+        let pad = " ".repeat(40usize.saturating_sub(shown.chars().count()));
+        let size = if is_dir { "-".to_string() } else { format_size_as(size, SizeUnits::Binary, "") };
+        page.push_str(&format!("<a href=\"{}{}\">{}</a>{} {:>8}\n", percent_encode(&name, ""), slash, escape_html(&shown), pad, size));
+    }
+    page.push_str("</pre><hr></body></html>\n");
+    Ok(page)
+}
+
+/// The file a URL path names, relative to the served directory; None if it
+/// is malformed or tries to climb out with `..`.
+fn local_path(url_path: &str) -> Option<PathBuf> {
+    let decoded = String::from_utf8(percent_decode(url_path)?).ok()?;
+    let mut path = PathBuf::new();
+    for part in decoded.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            // A drive or a backslash would let join() leave the root on Windows
+            _ if part.contains(['\\', ':', '\0']) => return None,
+            _ => path.push(part),
+        }
+    }
+    Some(path)
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match ext.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css"          => "text/css; charset=utf-8",
+        "js" | "mjs"   => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "xml"          => "application/xml",
+        "csv"          => "text/csv; charset=utf-8",
+        "txt" | "md" | "rs" | "toml" | "log" | "sh" | "py" | "c" | "h" | "yaml" | "yml"
+                       => "text/plain; charset=utf-8",
+        "png"          => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif"          => "image/gif",
+        "svg"          => "image/svg+xml",
+        "webp"         => "image/webp",
+        "ico"          => "image/x-icon",
+        "pdf"          => "application/pdf",
+        "wasm"         => "application/wasm",
+        "zip"          => "application/zip",
+        "gz" | "tgz"   => "application/gzip",
+        "mp3"          => "audio/mpeg",
+        "mp4"          => "video/mp4",
+        "woff2"        => "font/woff2",
+        _              => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_stay_inside_the_root() {
+        assert_eq!(local_path("/a/b%20c.txt"), Some(PathBuf::from("a").join("b c.txt")));
+        assert_eq!(local_path("/"), Some(PathBuf::new()));
+        assert_eq!(local_path("/./x//y"), Some(PathBuf::from("x").join("y")));
+        assert_eq!(local_path("/../etc/passwd"), None);
+        assert_eq!(local_path("/a/%2e%2e/%2e%2e/secret"), None);
+        assert_eq!(local_path("/bad%zz"), None);
+        assert_eq!(local_path("/C:%5cWindows"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_stay_inside_the_root() {
+        let dir = std::env::temp_dir().join(format!("rshell-serve-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("root")).unwrap();
+        std::fs::write(dir.join("secret"), "secret").unwrap();
+        std::fs::write(dir.join("root/file"), "file").unwrap();
+        std::os::unix::fs::symlink(dir.join("secret"), dir.join("root/out")).unwrap();
+        std::os::unix::fs::symlink("file", dir.join("root/in")).unwrap();
+
+        let root = dir.join("root").canonicalize().unwrap();
+        let get = |target| respond(&root, &mut Vec::new(), "GET", target);
+        let (out, within, missing) = (get("/out"), get("/in"), get("/nope"));
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!((out, within, missing), (403, 200, 404));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        let name = "my file #1 (ü).txt";
+        assert_eq!(percent_encode("a b#", ""), "a%20b%23");
+        assert_eq!(percent_decode(&percent_encode(name, "")), Some(name.as_bytes().to_vec()));
+        assert_eq!(escape_html("<a&\"b\">"), "&lt;a&amp;&quot;b&quot;&gt;");
+    }
+}
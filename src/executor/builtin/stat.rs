@@ -0,0 +1,171 @@
+// src/executor/builtin/stat.rs
+// stat [-L] [-c FORMAT] FILE... — everything the metadata says about a
+// file: type, size, permissions, owner and its times. A symlink is
+// described as itself unless -L follows it.
+//
+// -c prints FORMAT for each file instead, for scripts:
+//
+//   %n name    %s size     %F type       %a mode (644)   %A mode (-rw-r--r--)
+//   %U owner   %u uid      %G group      %g gid          %h links   %i inode
+//   %x %y %z   access, modify, change time    %X %Y %Z  the same as seconds
+//   %%         a literal %
+//
+// Owners, groups, inodes and the change time are Unix ideas; on Windows
+// they print as `-`.
+
+use std::fs::Metadata;
+use std::time::SystemTime;
+
+use crate::shell::Shell;
+use super::util::{local_datetime, permission_string, unix_secs, Opt, Options};
+
+pub const STAT: Options = Options {
+    name: "stat",
+    operands: "FILE...",
+    about: "Show the type, size, permissions, owner and times of each FILE.",
+    opts: &[
+        Opt::new("L", "dereference", "follow symlinks"),
+        Opt::new("c", "format", "print FORMAT instead (%n %s %a %U %y ... see help)").takes("FORMAT"),
+    ],
+    numeric: None,
+};
+
+pub fn builtin_stat(shell: &Shell, args: &[String]) -> i32 {
+    let p = match STAT.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.operands.is_empty() { errln!("stat: missing operand"); return 2; }
+    let mut code = 0;
+    for file in &p.operands {
+        let path = shell.cwd.join(file);
+        let meta = if p.has("dereference") { path.metadata() } else { path.symlink_metadata() };
+        let meta = match meta {
+            Ok(meta) => meta,
+            Err(e) => { errln!("stat: {}: {}", file, e); code = 1; continue; }
+        };
+        match p.value("format") {
+            Some(format) => outln!("{}", expand(format, file, &meta)),
+            None => describe(file, &path, &meta),
+        }
+    }
+    code
+}
+
+fn describe(file: &str, path: &std::path::Path, meta: &Metadata) {
+    match std::fs::read_link(path).ok().filter(|_| meta.file_type().is_symlink()) {
+        Some(target) => outln!("  File: {} -> {}", file, crate::paths::display(&target)),
+        None => outln!("  File: {}", file),
+    }
+    outln!("  Type: {}", kind(meta));
+    outln!("  Size: {}", meta.len());
+    outln!(" Perms: ({}/{})", mode(meta), permission_string(meta));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        outln!(" Owner: {} ({})   Group: {} ({})",
+            super::util::user_name(meta.uid()), meta.uid(), super::util::group_name(meta.gid()), meta.gid());
+        outln!(" Links: {}   Inode: {}", meta.nlink(), meta.ino());
+    }
+    outln!("Access: {}", time(meta.accessed().ok()));
+    outln!("Modify: {}", time(meta.modified().ok()));
+    outln!("Change: {}", time(changed(meta)));
+    if let Ok(birth) = meta.created() { outln!(" Birth: {}", time(Some(birth))); }
+}
+
+/// FORMAT with each %-directive replaced for this file.
+fn expand(format: &str, file: &str, meta: &Metadata) -> String {
+    let secs = |t: Option<SystemTime>| t.map(|t| unix_secs(t).to_string()).unwrap_or_else(|| "-".to_string());
+    let mut out = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(other) => { out.push('\\'); out.push(other); }
+                None => out.push('\\'),
+            }
+            continue;
+        }
+        if c != '%' { out.push(c); continue; }
+        let Some(directive) = chars.next() else { out.push('%'); break };
+        let value = match directive {
+            'n' => file.to_string(),
+            's' => meta.len().to_string(),
+            'F' => kind(meta).to_string(),
+            'a' => mode(meta),
+            'A' => permission_string(meta),
+            'U' | 'u' | 'G' | 'g' | 'h' | 'i' => unix_field(directive, meta),
+            'x' => time(meta.accessed().ok()),
+            'y' => time(meta.modified().ok()),
+            'z' => time(changed(meta)),
+            'X' => secs(meta.accessed().ok()),
+            'Y' => secs(meta.modified().ok()),
+            'Z' => secs(changed(meta)),
+            '%' => "%".to_string(),
+            other => format!("%{}", other),
+        };
+        out.push_str(&value);
+    }
+    out
+}
+
+fn kind(meta: &Metadata) -> &'static str {
+    let ft = meta.file_type();
+    if ft.is_symlink() { return "symbolic link"; }
+    if ft.is_dir() { return "directory"; }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if ft.is_fifo() { return "fifo"; }
+        if ft.is_socket() { return "socket"; }
+        if ft.is_char_device() { return "character special file"; }
+        if ft.is_block_device() { return "block special file"; }
+    }
+    if meta.len() == 0 { "regular empty file" } else { "regular file" }
+}
+
+/// The permission bits in octal, as chmod takes them.
+fn mode(_meta: &Metadata) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        format!("{:o}", _meta.permissions().mode() & 0o7777)
+    }
+    #[cfg(windows)]
+    { if _meta.permissions().readonly() { "444".to_string() } else { "666".to_string() } }
+}
+
+fn unix_field(_directive: char, _meta: &Metadata) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match _directive {
+            'U' => super::util::user_name(_meta.uid()),
+            'u' => _meta.uid().to_string(),
+            'G' => super::util::group_name(_meta.gid()),
+            'g' => _meta.gid().to_string(),
+            'h' => _meta.nlink().to_string(),
+            _   => _meta.ino().to_string(),
+        }
+    }
+    #[cfg(windows)]
+    { "-".to_string() }
+}
+
+/// When the metadata (not the contents) last changed.
+fn changed(_meta: &Metadata) -> Option<SystemTime> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let secs = _meta.ctime();
+        let offset = std::time::Duration::from_secs(secs.unsigned_abs());
+        if secs >= 0 { SystemTime::UNIX_EPOCH.checked_add(offset) } else { SystemTime::UNIX_EPOCH.checked_sub(offset) }
+    }
+    #[cfg(windows)]
+    { None }
+}
+
+fn time(t: Option<SystemTime>) -> String {
+    let Some(t) = t else { return "-".to_string() };
+    let (year, month, day, hour, min, sec) = local_datetime(unix_secs(t));
+    format!("{}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, hour, min, sec)
+}
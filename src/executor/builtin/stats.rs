@@ -0,0 +1,176 @@
+// src/executor/builtin/stats.rs
+// Command analytics over the extended history store:
+//   stats [-n N] [--json]
+//
+// Everything is computed locally from ~/.rshell/history.jsonl.
+
+use std::collections::HashMap;
+use crate::shell::history::{load_extended_history, HistoryEntry};
+use super::util::format_ms;
+
+/// Commands need at least this many runs before their failure rate or
+/// average duration is considered meaningful.
+const MIN_RUNS: usize = 3;
+
+pub fn builtin_stats(args: &[String]) -> i32 {
+    let mut json = false;
+    let mut top  = 10usize;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--json" => json = true,
+            "-n" => {
+                i += 1;
+                top = match args.get(i).and_then(|n| n.parse().ok()) {
+                    Some(n) => n,
+                    None => { errln!("stats: -n requires a number"); return 1; }
+                };
+            }
+            other => { errln!("stats: unknown option: {}", other); return 1; }
+        }
+        i += 1;
+    }
+
+    let entries = load_extended_history();
+    if entries.is_empty() {
+        if json { outln!("{{}}"); } else { outln!("No command history recorded yet."); }
+        return 0;
+    }
+
+    let summary = Summary::compute(&entries, top);
+    if json { summary.print_json(); } else { summary.print(); }
+    0
+}
+
+// ── Aggregation ───────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct CommandStats {
+    runs:     usize,
+    failures: usize,
+    total_ms: u64,
+}
+
+struct Summary {
+    total:       usize,
+    first:       i64,
+    commands:    Vec<(String, usize)>,
+    directories: Vec<(String, usize)>,
+    slowest:     Vec<(String, u64)>,
+    failing:     Vec<(String, usize, usize)>,
+}
+
+impl Summary {
+    fn compute(entries: &[HistoryEntry], top: usize) -> Self {
+        let mut by_cmd: HashMap<String, CommandStats> = HashMap::new();
+        let mut by_dir: HashMap<String, usize> = HashMap::new();
+
+        for entry in entries {
+            let name = command_name(&entry.command);
+            if name.is_empty() { continue; }
+            let stats = by_cmd.entry(name).or_default();
+            stats.runs     += 1;
+            stats.total_ms += entry.duration_ms;
+            if entry.exit_code != 0 { stats.failures += 1; }
+            *by_dir.entry(entry.cwd.clone()).or_default() += 1;
+        }
+
+        let mut commands: Vec<(String, usize)> = by_cmd.iter()
+            .map(|(name, s)| (name.clone(), s.runs))
+            .collect();
+        sort_desc(&mut commands, top);
+
+        let mut directories: Vec<(String, usize)> = by_dir.into_iter().collect();
+        sort_desc(&mut directories, top);
+
+        let mut slowest: Vec<(String, u64)> = by_cmd.iter()
+            .filter(|(_, s)| s.runs >= MIN_RUNS)
+            .map(|(name, s)| (name.clone(), s.total_ms / s.runs as u64))
+            .collect();
+        sort_desc(&mut slowest, top);
+
+        let mut failing: Vec<(String, usize, usize)> = by_cmd.iter()
+            .filter(|(_, s)| s.runs >= MIN_RUNS && s.failures > 0)
+            .map(|(name, s)| (name.clone(), s.failures, s.runs))
+            .collect();
+        // Highest failure rate first, compared as failures/runs without floats
+        failing.sort_by(|a, b| (b.1 * a.2).cmp(&(a.1 * b.2)).then(a.0.cmp(&b.0)));
+        failing.truncate(top);
+
+        Summary {
+            total: entries.len(),
+            first: entries.iter().map(|e| e.started).min().unwrap_or(0),
+            commands,
+            directories,
+            slowest,
+            failing,
+        }
+    }
+
+    fn print(&self) {
+        let (y, m, d, _, _, _) = super::util::local_datetime(self.first);
+        outln!("{} commands recorded since {:04}-{:02}-{:02}", self.total, y, m, d);
+
+        outln!("\n\x1b[1mTop commands\x1b[0m");
+        for (name, runs) in &self.commands {
+            outln!("  {:>6}  {}", runs, name);
+        }
+
+        outln!("\n\x1b[1mTop directories\x1b[0m");
+        for (dir, runs) in &self.directories {
+            outln!("  {:>6}  {}", runs, dir);
+        }
+
+        if !self.slowest.is_empty() {
+            outln!("\n\x1b[1mSlowest on average\x1b[0m");
+            for (name, avg) in &self.slowest {
+                outln!("  {:>8}  {}", format_ms(*avg), name);
+            }
+        }
+
+        if !self.failing.is_empty() {
+            outln!("\n\x1b[1mMost failure-prone\x1b[0m");
+            for (name, failures, runs) in &self.failing {
+                let pct = failures * 100 / runs;
+                outln!("  {:>5}%  {}  ({}/{} failed)", pct, name, failures, runs);
+            }
+        }
+    }
+
+    fn print_json(&self) {
+        let value = serde_json::json!({
+            "total": self.total,
+            "since": self.first,
+            "top_commands": self.commands.iter()
+                .map(|(name, runs)| serde_json::json!({ "command": name, "runs": runs }))
+                .collect::<Vec<_>>(),
+            "top_directories": self.directories.iter()
+                .map(|(dir, runs)| serde_json::json!({ "directory": dir, "runs": runs }))
+                .collect::<Vec<_>>(),
+            "slowest": self.slowest.iter()
+                .map(|(name, avg)| serde_json::json!({ "command": name, "avg_ms": avg }))
+                .collect::<Vec<_>>(),
+            "failure_prone": self.failing.iter()
+                .map(|(name, failures, runs)| serde_json::json!({
+                    "command": name, "failures": failures, "runs": runs,
+                }))
+                .collect::<Vec<_>>(),
+        });
+        outln!("{}", serde_json::to_string_pretty(&value).unwrap_or_default());
+    }
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// The command word of a recorded line, e.g. "git" for "git commit -m x".
+fn command_name(line: &str) -> String {
+    line.split_whitespace().next().unwrap_or("").to_string()
+}
+
+/// Sort by count descending (ties by name) and keep the first `top`.
+fn sort_desc<T: Ord + Copy>(items: &mut Vec<(String, T)>, top: usize) {
+    items.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    items.truncate(top);
+}
+
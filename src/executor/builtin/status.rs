@@ -0,0 +1,188 @@
+// src/executor/builtin/status.rs
+// Exit statuses: turning a child's wait status into `$?`, reporting
+// commands killed by a signal, and `explain` for describing a status (or,
+// given a command line, how it parses).
+//
+// A command killed by signal N leaves `$?` at 128 + N, as in other shells,
+// so `explain $?` straight afterwards can say what happened.
+
+use std::process::ExitStatus;
+use crate::shell::Shell;
+
+// ── Signals ───────────────────────────────────────────────────────────────────
+
+/// Signal number, name and what it usually means.
+#[cfg(unix)]
+pub const SIGNALS: &[(i32, &str, &str)] = &[
+    (libc::SIGHUP,    "SIGHUP",    "hangup (terminal closed)"),
+    (libc::SIGINT,    "SIGINT",    "interrupted (Ctrl+C)"),
+    (libc::SIGQUIT,   "SIGQUIT",   "quit (Ctrl+\\)"),
+    (libc::SIGILL,    "SIGILL",    "illegal instruction"),
+    (libc::SIGTRAP,   "SIGTRAP",   "trace/breakpoint trap"),
+    (libc::SIGABRT,   "SIGABRT",   "aborted"),
+    (libc::SIGBUS,    "SIGBUS",    "bus error"),
+    (libc::SIGFPE,    "SIGFPE",    "arithmetic error"),
+    (libc::SIGKILL,   "SIGKILL",   "killed"),
+    (libc::SIGUSR1,   "SIGUSR1",   "user-defined signal 1"),
+    (libc::SIGSEGV,   "SIGSEGV",   "segmentation fault"),
+    (libc::SIGUSR2,   "SIGUSR2",   "user-defined signal 2"),
+    (libc::SIGPIPE,   "SIGPIPE",   "broken pipe"),
+    (libc::SIGALRM,   "SIGALRM",   "alarm clock"),
+    (libc::SIGTERM,   "SIGTERM",   "terminated"),
+    (libc::SIGCHLD,   "SIGCHLD",   "child status changed"),
+    (libc::SIGCONT,   "SIGCONT",   "continued"),
+    (libc::SIGSTOP,   "SIGSTOP",   "stopped"),
+    (libc::SIGTSTP,   "SIGTSTP",   "stopped (Ctrl+Z)"),
+    (libc::SIGTTIN,   "SIGTTIN",   "stopped (terminal input)"),
+    (libc::SIGTTOU,   "SIGTTOU",   "stopped (terminal output)"),
+    (libc::SIGXCPU,   "SIGXCPU",   "CPU time limit exceeded"),
+    (libc::SIGXFSZ,   "SIGXFSZ",   "file size limit exceeded"),
+    (libc::SIGWINCH,  "SIGWINCH",  "window size changed"),
+];
+
+#[cfg(windows)]
+pub const SIGNALS: &[(i32, &str, &str)] = &[];
+
+pub fn signal_name(sig: i32) -> Option<&'static str> {
+    SIGNALS.iter().find(|(n, _, _)| *n == sig).map(|(_, name, _)| *name)
+}
+
+/// A signal given as a number or a name, with or without `SIG`, in any
+/// case: `9`, `KILL`, `sigkill`.
+pub fn parse_signal(s: &str) -> Option<i32> {
+    if let Ok(n) = s.parse::<i32>() {
+        return (0..=64).contains(&n).then_some(n);
+    }
+    let upper = s.to_ascii_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SIGNALS.iter().find(|(_, n, _)| n[3..] == *name).map(|(num, _, _)| *num)
+}
+
+fn signal_meaning(sig: i32) -> Option<&'static str> {
+    SIGNALS.iter().find(|(n, _, _)| *n == sig).map(|(_, _, what)| *what)
+}
+
+// ── Wait statuses ─────────────────────────────────────────────────────────────
+
+/// The `$?` for a finished child: its exit code, or 128 + the signal that
+/// killed it.
+pub fn status_code(status: ExitStatus) -> i32 {
+    if let Some(code) = status.code() { return code; }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(sig) = status.signal() { return 128 + sig; }
+    }
+    1
+}
+
+/// `status_code` for a foreground command, also reporting a signal death
+/// on stderr the way bash does (`Killed  cmd`, `Segmentation fault (core
+/// dumped)  cmd`) — except for Ctrl+C and a closed pipe, which the user
+/// already knows about.
+#[cfg_attr(windows, allow(unused_variables))]
+pub fn exit_status(name: &str, status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(sig) = status.signal().filter(|s| *s != libc::SIGINT && *s != libc::SIGPIPE) {
+            let core = if status.core_dumped() { " (core dumped)" } else { "" };
+            errln!("{}{}  {}", death_notice(sig), core, name);
+        }
+    }
+    status_code(status)
+}
+
+/// What to call a death by `sig`: "Killed", "Terminated", ...
+fn death_notice(sig: i32) -> String {
+    let Some(what) = signal_meaning(sig) else { return format!("Killed by signal {}", sig) };
+    let mut chars = what.chars();
+    chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+}
+
+// ── explain ───────────────────────────────────────────────────────────────────
+
+/// explain [STATUS...] — describe exit statuses, by default `$?`.
+/// explain CMD LINE — show how the line parses, without running it.
+pub fn builtin_explain(shell: &Shell, args: &[String]) -> i32 {
+    if args[1..].iter().any(|a| a.parse::<i32>().is_err()) {
+        return explain_line(shell, &args[1..].join(" "));
+    }
+    let codes: Vec<String> = if args.len() > 1 {
+        args[1..].to_vec()
+    } else {
+        vec![shell.last_exit_code.to_string()]
+    };
+
+    let mut result = 0;
+    for code in &codes {
+        match code.parse::<i32>() {
+            Ok(n)  => outln!("{}: {}", n, explain(n)),
+            Err(_) => { errln!("explain: {}: not a number", code); result = 1; }
+        }
+    }
+    result
+}
+
+fn explain_line(shell: &Shell, line: &str) -> i32 {
+    let opts = crate::parser::ParseOptions { posix: shell.posix };
+    match crate::parser::parse(line, opts) {
+        Ok(cmd) => {
+            out!("{}", crate::parser::explain(&cmd, &|name| super::core::describe_name(shell, name)));
+            0
+        }
+        Err(e) => { errln!("explain: {}", e); 1 }
+    }
+}
+
+fn explain(code: i32) -> String {
+    match code {
+        0   => "success".to_string(),
+        1   => "general failure".to_string(),
+        2   => "incorrect usage (bad option or missing argument)".to_string(),
+        126 => "command found but not executable (permission denied?)".to_string(),
+        127 => "command not found".to_string(),
+        129..=192 => {
+            let sig = code - 128;
+            match (signal_name(sig), signal_meaning(sig)) {
+                (Some(name), Some(what)) => format!("killed by signal {} ({}: {})", sig, name, what),
+                _ => format!("killed by signal {}", sig),
+            }
+        }
+        n if !(0..=255).contains(&n) => "out of range; statuses are 0-255".to_string(),
+        _   => "failure (meaning depends on the command)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explains_common_statuses() {
+        assert_eq!(explain(0), "success");
+        assert_eq!(explain(127), "command not found");
+        assert_eq!(explain(3), "failure (meaning depends on the command)");
+        assert_eq!(explain(300), "out of range; statuses are 0-255");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn explains_signal_deaths() {
+        assert_eq!(explain(139), "killed by signal 11 (SIGSEGV: segmentation fault)");
+        assert_eq!(signal_name(libc::SIGTERM), Some("SIGTERM"));
+        assert_eq!(death_notice(libc::SIGKILL), "Killed");
+        assert_eq!(death_notice(libc::SIGTERM), "Terminated");
+        assert_eq!(death_notice(40), "Killed by signal 40");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parses_signal_names_and_numbers() {
+        assert_eq!(parse_signal("9"), Some(9));
+        assert_eq!(parse_signal("KILL"), Some(libc::SIGKILL));
+        assert_eq!(parse_signal("sigterm"), Some(libc::SIGTERM));
+        assert_eq!(parse_signal("NOPE"), None);
+        assert_eq!(parse_signal("100"), None);
+    }
+}
@@ -0,0 +1,237 @@
+// src/executor/builtin/sysinfo.rs
+// Who and where the shell is running: whoami, hostname, uname and uptime.
+//
+// Prompts and scripts lean on these, and minimal Windows installs have none
+// of them, so each works the same everywhere: from libc on Unix, and from
+// the environment and the system API on Windows.
+
+use std::time::SystemTime;
+
+use super::util::{local_datetime, unix_secs, Opt, Options};
+
+pub const HOSTNAME: Options = Options {
+    name: "hostname",
+    operands: "",
+    about: "Print this machine's name.",
+    opts: &[
+        Opt::new("s", "short", "only the part before the first dot"),
+    ],
+    numeric: None,
+};
+
+pub const UNAME: Options = Options {
+    name: "uname",
+    operands: "",
+    about: "Print system information (the kernel name with no options).",
+    opts: &[
+        Opt::new("a", "all", "everything below, in this order"),
+        Opt::new("s", "kernel-name", "the kernel name (Linux, Darwin, Windows_NT)"),
+        Opt::new("n", "nodename", "the host name"),
+        Opt::new("r", "kernel-release", "the kernel release"),
+        Opt::new("v", "kernel-version", "the kernel version"),
+        Opt::new("m", "machine", "the hardware (x86_64, aarch64 ...)"),
+        Opt::new("o", "operating-system", "the operating system"),
+    ],
+    numeric: None,
+};
+
+pub const UPTIME: Options = Options {
+    name: "uptime",
+    operands: "",
+    about: "Show how long the system has been up, and its load.",
+    opts: &[
+        Opt::new("p", "pretty", "just the time up, in words"),
+        Opt::new("s", "since", "when the system booted"),
+    ],
+    numeric: None,
+};
+
+pub fn builtin_whoami(args: &[String]) -> i32 {
+    if args.len() > 1 { errln!("usage: whoami"); return 2; }
+    match user() {
+        Some(name) => { outln!("{}", name); 0 }
+        None => { errln!("whoami: cannot find the user name"); 1 }
+    }
+}
+
+pub fn builtin_hostname(args: &[String]) -> i32 {
+    let p = match HOSTNAME.parse(args) { Ok(p) => p, Err(code) => return code };
+    if !p.operands.is_empty() { errln!("hostname: setting the host name isn't supported"); return 2; }
+    let Some(name) = host() else { errln!("hostname: cannot find the host name"); return 1 };
+    let name = if p.has("short") { name.split('.').next().unwrap_or_default().to_string() } else { name };
+    outln!("{}", name);
+    0
+}
+
+pub fn builtin_uname(args: &[String]) -> i32 {
+    let p = match UNAME.parse(args) { Ok(p) => p, Err(code) => return code };
+    if !p.operands.is_empty() { errln!("uname: extra operand '{}'", p.operands[0]); return 2; }
+    let info = Uname::get();
+    let all = p.has("all");
+    let fields = [
+        ("kernel-name", &info.sysname),
+        ("nodename", &info.nodename),
+        ("kernel-release", &info.release),
+        ("kernel-version", &info.version),
+        ("machine", &info.machine),
+        ("operating-system", &info.os),
+    ];
+    let mut chosen: Vec<&str> = fields.iter()
+        .filter(|(key, _)| all || p.has(key))
+        .map(|(_, value)| value.as_str())
+        .collect();
+    if chosen.is_empty() { chosen.push(&info.sysname); }
+    outln!("{}", chosen.join(" "));
+    0
+}
+
+pub fn builtin_uptime(args: &[String]) -> i32 {
+    let p = match UPTIME.parse(args) { Ok(p) => p, Err(code) => return code };
+    if !p.operands.is_empty() { errln!("uptime: extra operand '{}'", p.operands[0]); return 2; }
+    let Some(up) = uptime_secs() else { errln!("uptime: not available on this system"); return 1 };
+    let now = unix_secs(SystemTime::now());
+
+    if p.has("since") {
+        let (year, month, day, hour, min, sec) = local_datetime(now - up as i64);
+        outln!("{}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, hour, min, sec);
+    } else if p.has("pretty") {
+        outln!("up {}", pretty(up));
+    } else {
+        let (_, _, _, hour, min, sec) = local_datetime(now);
+        let days = up / 86400;
+        let days = match days { 0 => String::new(), 1 => "1 day, ".to_string(), n => format!("{} days, ", n) };
+        let mut line = format!(" {:02}:{:02}:{:02} up {}{}:{:02}", hour, min, sec, days, up % 86400 / 3600, up % 3600 / 60);
+        if let Some([one, five, fifteen]) = load_average() {
+            line.push_str(&format!(",  load average: {:.2}, {:.2}, {:.2}", one, five, fifteen));
+        }
+        outln!("{}", line);
+    }
+    0
+}
+
+/// `3 days, 4 hours, 1 minute`, leaving out what's zero.
+fn pretty(secs: u64) -> String {
+    let parts = [(secs / 604800, "week"), (secs % 604800 / 86400, "day"), (secs % 86400 / 3600, "hour"), (secs % 3600 / 60, "minute")];
+    let words: Vec<String> = parts.iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{} {}{}", n, unit, if *n == 1 { "" } else { "s" }))
+        .collect();
+    if words.is_empty() { "0 minutes".to_string() } else { words.join(", ") }
+}
+
+/// The name of the user the shell runs as.
+fn user() -> Option<String> {
+    #[cfg(unix)]
+    { Some(super::util::user_name(unsafe { libc::geteuid() })) }
+    #[cfg(windows)]
+    { std::env::var("USERNAME").ok() }
+}
+
+fn host() -> Option<String> {
+    #[cfg(unix)]
+    {
+        let mut buf = [0 as libc::c_char; 256];
+        if unsafe { libc::gethostname(buf.as_mut_ptr(), buf.len()) } != 0 { return None; }
+        let name = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+        Some(name.to_string_lossy().into_owned())
+    }
+    #[cfg(windows)]
+    { std::env::var("COMPUTERNAME").ok() }
+}
+
+struct Uname {
+    sysname: String,
+    nodename: String,
+    release: String,
+    version: String,
+    machine: String,
+    os: String,
+}
+
+impl Uname {
+    #[cfg(unix)]
+    fn get() -> Self {
+        let mut raw: libc::utsname = unsafe { std::mem::zeroed() };
+        unsafe { libc::uname(&mut raw); }
+        let field = |f: &[libc::c_char]| unsafe { std::ffi::CStr::from_ptr(f.as_ptr()) }.to_string_lossy().into_owned();
+        let sysname = field(&raw.sysname);
+        let os = if sysname == "Linux" { "GNU/Linux".to_string() } else { sysname.clone() };
+        Uname {
+            nodename: field(&raw.nodename),
+            release: field(&raw.release),
+            version: field(&raw.version),
+            machine: field(&raw.machine),
+            sysname,
+            os,
+        }
+    }
+
+    #[cfg(windows)]
+    fn get() -> Self {
+        // `ver` prints "Microsoft Windows [Version 10.0.22631.4169]"
+        let ver = std::process::Command::new("cmd").args(["/c", "ver"]).output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+            .unwrap_or_default();
+        let version = ver.split("Version ").nth(1)
+            .map(|v| v.trim().trim_end_matches(']').to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        let release = version.splitn(3, '.').take(2).collect::<Vec<_>>().join(".");
+        Uname {
+            sysname: "Windows_NT".to_string(),
+            nodename: host().unwrap_or_default(),
+            release,
+            version,
+            machine: std::env::consts::ARCH.to_string(),
+            os: "Windows".to_string(),
+        }
+    }
+}
+
+/// Seconds since the system booted.
+fn uptime_secs() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut info: libc::sysinfo = unsafe { std::mem::zeroed() };
+        (unsafe { libc::sysinfo(&mut info) } == 0).then_some(info.uptime as u64)
+    }
+    #[cfg(any(target_os = "macos", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+    {
+        let mut boot: libc::timeval = unsafe { std::mem::zeroed() };
+        let mut len = std::mem::size_of::<libc::timeval>();
+        let mut mib = [libc::CTL_KERN, libc::KERN_BOOTTIME];
+        let rc = unsafe {
+            libc::sysctl(mib.as_mut_ptr(), 2, (&mut boot as *mut libc::timeval).cast(), &mut len, std::ptr::null_mut(), 0)
+        };
+        if rc != 0 { return None; }
+        let now = unix_secs(SystemTime::now());
+        u64::try_from(now - boot.tv_sec as i64).ok()
+    }
+    #[cfg(windows)]
+    { Some(unsafe { windows_sys::Win32::System::SystemInformation::GetTickCount64() } / 1000) }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd",
+                  target_os = "openbsd", target_os = "netbsd", windows)))]
+    { None }
+}
+
+/// The 1, 5 and 15 minute load averages; Windows has no such thing.
+fn load_average() -> Option<[f64; 3]> {
+    #[cfg(unix)]
+    {
+        let mut loads = [0f64; 3];
+        (unsafe { libc::getloadavg(loads.as_mut_ptr(), 3) } == 3).then_some(loads)
+    }
+    #[cfg(windows)]
+    { None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pretty_uptime() {
+        assert_eq!(pretty(59), "0 minutes");
+        assert_eq!(pretty(3600 + 60), "1 hour, 1 minute");
+        assert_eq!(pretty(8 * 86400 + 2 * 3600 + 5 * 60), "1 week, 1 day, 2 hours, 5 minutes");
+    }
+}
@@ -1,201 +1,756 @@
 // src/executor/builtin/text.rs
-// Text processing commands: head, tail, wc, env, sort, uniq, xargs
+// Text processing commands: head, tail, wc, sort, uniq, cut, tr, xargs
+//
+// tail -f goes on printing what's added to its files, checking every
+// quarter second, until Ctrl+C. -F follows each name rather than the file
+// first opened under it, so a log that's rotated or recreated is picked up
+// again; either way a file that shrinks is read again from the start.
 
-pub fn builtin_head(args: &[String]) -> i32 {
-    let mut lines = 10usize;
-    let mut files = Vec::new();
-
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-n" => { i += 1; if let Some(n) = args.get(i) { lines = n.parse().unwrap_or(10); } }
-            s if s.starts_with("-n") => { lines = s[2..].parse().unwrap_or(10); }
-            s if s.starts_with('-') && s[1..].chars().all(|c| c.is_ascii_digit()) => { lines = s[1..].parse().unwrap_or(10); }
-            _ => files.push(args[i].clone()),
+use std::fs::File;
+use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::shell::Shell;
+use super::util::{NumberFormat, Opt, Options, Parsed, SizeUnits};
+
+pub const HEAD: Options = Options {
+    name: "head",
+    operands: "[FILE]...",
+    about: "Print the first lines of each file, or of standard input.",
+    opts: &[
+        Opt::new("n", "lines", "print N lines instead of 10 (-N for short); -n -N all but the last N").takes("N"),
+        Opt::new("c", "bytes", "print N bytes; -c -N all but the last N").takes("N"),
+    ],
+    numeric: Some("lines"),
+};
+
+pub const TAIL: Options = Options {
+    name: "tail",
+    operands: "[FILE]...",
+    about: "Print the last lines of each file, or of standard input.",
+    opts: &[
+        Opt::new("n", "lines", "print N lines instead of 10 (-N for short); -n +N from line N on").takes("N"),
+        Opt::new("c", "bytes", "print N bytes; -c +N from byte N on").takes("N"),
+        Opt::new("f", "follow", "keep printing what's added to the files, until Ctrl+C"),
+        Opt::new("F", "", "follow each name, reopening a file that's replaced or recreated"),
+    ],
+    numeric: Some("lines"),
+};
+
+pub const WC: Options = Options {
+    name: "wc",
+    operands: "[FILE]...",
+    about: "Count lines, words and bytes.",
+    opts: &[
+        Opt::new("l", "lines", "count lines"),
+        Opt::new("w", "words", "count words"),
+        Opt::new("m", "chars", "count characters"),
+        Opt::new("c", "bytes", "count bytes"),
+        Opt::new("L", "max-line-length", "show the width of the longest line"),
+        Opt::new("h", "human-readable", "show the byte count as a size in powers of 1024"),
+        Opt::new("", "si", "show it in powers of 1000"),
+    ],
+    numeric: None,
+};
+
+pub const SORT: Options = Options {
+    name: "sort",
+    operands: "[FILE]...",
+    about: "Print the lines of the files, sorted.\n\
+            A KEYDEF is F[.C][,F[.C]]: from field F (character C of it) to the\n\
+            end of the line or the second field, with any of b, f, h, n and r\n\
+            after it to sort that key its own way.",
+    opts: &[
+        Opt::new("r", "reverse", "sort in reverse"),
+        Opt::new("u", "unique", "drop repeated lines (or lines with the same keys)"),
+        Opt::new("n", "numeric-sort", "compare lines as numbers"),
+        Opt::new("h", "human-numeric-sort", "compare sizes such as 2K and 1.5G"),
+        Opt::new("f", "ignore-case", "sort upper and lower case together"),
+        Opt::new("b", "ignore-leading-blanks", "ignore blanks at the start of a -t field"),
+        Opt::new("k", "key", "sort by the key KEYDEF; more break ties").takes("KEYDEF"),
+        Opt::new("t", "field-separator", "fields are separated by SEP instead of blanks").takes("SEP"),
+        Opt::new("s", "stable", "keep lines whose keys are equal in their order"),
+    ],
+    numeric: None,
+};
+
+pub const UNIQ: Options = Options {
+    name: "uniq",
+    operands: "[FILE]",
+    about: "Collapse runs of repeated lines.",
+    opts: &[
+        Opt::new("c", "count", "prefix each line with how often it occurs"),
+        Opt::new("u", "unique", "only print lines that aren't repeated"),
+        Opt::new("d", "repeated", "only print lines that are"),
+    ],
+    numeric: None,
+};
+
+pub const CUT: Options = Options {
+    name: "cut",
+    operands: "[FILE]...",
+    about: "Print selected fields or characters of each line.",
+    opts: &[
+        Opt::new("f", "fields", "print these fields (like 1,3 or 2-4 or 5-)").takes("LIST"),
+        Opt::new("d", "delimiter", "fields are separated by DELIM instead of a tab").takes("DELIM"),
+        Opt::new("c", "characters", "print these characters").takes("LIST"),
+        Opt::new("s", "only-delimited", "leave out lines with no delimiter"),
+        Opt::new("", "complement", "print the fields or characters not selected"),
+    ],
+    numeric: None,
+};
+
+pub const TR: Options = Options {
+    name: "tr",
+    operands: "SET1 [SET2]",
+    about: "Translate, squeeze or delete characters from standard input.\n\
+            Sets take ranges (a-z), classes ([:upper:], [:digit:], [:space:] ...)\n\
+            and escapes (\\n, \\t, \\\\).",
+    opts: &[
+        Opt::new("d", "delete", "delete the characters in SET1"),
+        Opt::new("s", "squeeze-repeats", "squeeze runs of a character in the last set to one"),
+    ],
+    numeric: None,
+};
+
+pub const XARGS: Options = Options {
+    name: "xargs",
+    operands: "[CMD [ARG]...]",
+    about: "Run CMD (echo by default) with the lines of standard input added to\n\
+            its arguments, one argument per line: a line isn't split into words,\n\
+            so -n counts lines. Options go before CMD.",
+    opts: &[
+        Opt::new("I", "replace", "run CMD once per line, with REPL in its arguments replaced by the line").takes("REPL"),
+        Opt::new("n", "max-args", "pass at most N lines (so N arguments) to each run of CMD").takes("N"),
+        Opt::new("0", "null", "items are separated by NUL characters rather than newlines"),
+        Opt::new("P", "max-procs", "run up to N commands at a time (0 for no limit)").takes("N"),
+    ],
+    numeric: None,
+};
+
+/// How much head or tail prints: the -n count of lines or the -c count
+/// of bytes, whichever came last. `from_end` is a count given as `-N` to
+/// head (all but the last N) or without `+` to tail (the last N).
+struct Count {
+    bytes: bool,
+    n: u64,
+    from_end: bool,
+}
+
+impl Count {
+    /// The count for `name`, whose counts from the other end take `sign`;
+    /// None, after saying why, if it isn't a number.
+    fn parse(name: &str, p: &Parsed, sign: char) -> Option<Count> {
+        let last = p.given.iter().rev().find(|(key, _)| matches!(*key, "lines" | "bytes"));
+        let Some(&(key, Some(value))) = last else {
+            return Some(Count { bytes: false, n: 10, from_end: name == "tail" });
+        };
+        let bytes = key == "bytes";
+        let (signed, digits) = match value.strip_prefix(sign) {
+            Some(rest) => (true, rest),
+            None => (false, value.strip_prefix(if sign == '+' { '-' } else { '+' }).unwrap_or(value)),
+        };
+        let Ok(n) = digits.parse() else {
+            errln!("{}: invalid number of {}: '{}'", name, if bytes { "bytes" } else { "lines" }, value);
+            return None;
+        };
+        // tail counts from the end unless told otherwise, head the reverse
+        let from_end = if name == "tail" { !signed } else { signed };
+        Some(Count { bytes, n, from_end })
+    }
+}
+
+/// Where the last `n` lines of `data` start. A newline at the very end
+/// closes the last line rather than starting another.
+fn last_lines_start(data: &[u8], n: u64) -> usize {
+    if n == 0 { return data.len(); }
+    let body = data.strip_suffix(b"\n").unwrap_or(data);
+    body.iter().enumerate().rev().filter(|(_, b)| **b == b'\n').nth(n as usize - 1).map_or(0, |(i, _)| i + 1)
+}
+
+/// Copy `input` to stdout up to the point `count` says, leaving the rest unread
+/// when it can.
+fn head_of(input: &mut dyn BufRead, count: &Count) -> std::io::Result<()> {
+    let mut out = super::io::stdout();
+    match count {
+        Count { bytes: true, n, from_end: false } => { std::io::copy(&mut input.take(*n), &mut out)?; }
+        Count { bytes: false, n, from_end: false } => {
+            // Line by line, so `yes | head` finishes
+            let mut line = Vec::new();
+            for _ in 0..*n {
+                line.clear();
+                if input.read_until(b'\n', &mut line)? == 0 { break; }
+                out.write_all(&line)?;
+            }
+        }
+        Count { bytes, n, from_end: true } => {
+            let mut data = Vec::new();
+            input.read_to_end(&mut data)?;
+            let end = if *bytes { data.len().saturating_sub(*n as usize) } else { last_lines_start(&data, *n) };
+            out.write_all(&data[..end])?;
+        }
+    }
+    out.flush()
+}
+
+/// Copy the part of `input` that `count` picks out to stdout: the last N
+/// lines or bytes, or everything from the Nth on.
+fn tail_of(input: &mut dyn Read, count: &Count) -> std::io::Result<()> {
+    let mut out = super::io::stdout();
+    if !count.from_end {
+        // +N: skip what comes before the Nth line or byte
+        let skip = count.n.saturating_sub(1);
+        let mut input = std::io::BufReader::new(input);
+        if count.bytes {
+            std::io::copy(&mut (&mut input).take(skip), &mut std::io::sink())?;
+        } else {
+            for _ in 0..skip {
+                if input.skip_until(b'\n')? == 0 { break; }
+            }
         }
-        i += 1;
+        std::io::copy(&mut input, &mut out)?;
+    } else {
+        let mut data = Vec::new();
+        input.read_to_end(&mut data)?;
+        let start = if count.bytes { data.len().saturating_sub(count.n as usize) } else { last_lines_start(&data, count.n) };
+        out.write_all(&data[start..])?;
     }
+    out.flush()
+}
 
-    if files.is_empty() { eprintln!("usage: head [-n N] <file> [file2 ...]"); return 1; }
+/// `tail_of` for a regular file, which is read backwards from the end in
+/// blocks rather than from the start.
+fn tail_of_file(file: &mut File, count: &Count) -> std::io::Result<()> {
+    let meta = file.metadata()?;
+    if !count.from_end || !meta.is_file() { return tail_of(file, count); }
+    let len = meta.len();
+    let start = if count.bytes {
+        len.saturating_sub(count.n)
+    } else {
+        // Enough blocks from the end to hold more than N newlines
+        const BLOCK: u64 = 64 * 1024;
+        let mut block_start = len;
+        let mut data: Vec<u8> = Vec::new();
+        while block_start > 0 && data.iter().filter(|b| **b == b'\n').count() as u64 <= count.n {
+            let from = block_start.saturating_sub(BLOCK);
+            let mut block = vec![0; (block_start - from) as usize];
+            file.seek(SeekFrom::Start(from))?;
+            file.read_exact(&mut block)?;
+            block.extend_from_slice(&data);
+            data = block;
+            block_start = from;
+        }
+        block_start + last_lines_start(&data, count.n) as u64
+    };
+    file.seek(SeekFrom::Start(start))?;
+    std::io::copy(file, &mut super::io::stdout())?;
+    super::io::stdout().flush()
+}
+
+pub fn builtin_head(args: &[String]) -> i32 {
+    let p = match HEAD.parse(args) { Ok(p) => p, Err(code) => return code };
+    let Some(count) = Count::parse("head", &p, '-') else { return 2 };
+    let mut files = p.operands;
+    if files.is_empty() { files.push("-"); }
     let multiple = files.len() > 1;
     let mut code = 0;
     for file in &files {
-        let content = match std::fs::read_to_string(file) {
-            Ok(c) => c, Err(e) => { eprintln!("head: {}: {}", file, e); code = 1; continue; }
+        let mut input = match super::io::open_input(file) {
+            Ok(r) => r, Err(e) => { errln!("head: {}: {}", file, e); code = 1; continue; }
         };
-        if multiple { println!("==> {} <==", file); }
-        for line in content.lines().take(lines) { println!("{}", line); }
-        if multiple { println!(); }
+        if multiple { outln!("==> {} <==", file); }
+        if let Err(e) = head_of(&mut input, &count) {
+            // A reader that stopped listening isn't an error
+            if e.kind() == std::io::ErrorKind::BrokenPipe { return code; }
+            errln!("head: {}: {}", file, e);
+            code = 1;
+        }
+        if multiple { outln!(); }
     }
     code
 }
 
 pub fn builtin_tail(args: &[String]) -> i32 {
-    let mut lines = 10usize;
-    let mut files = Vec::new();
-
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-n" => { i += 1; if let Some(n) = args.get(i) { lines = n.parse().unwrap_or(10); } }
-            s if s.starts_with("-n") => { lines = s[2..].parse().unwrap_or(10); }
-            s if s.starts_with('-') && s[1..].chars().all(|c| c.is_ascii_digit()) => { lines = s[1..].parse().unwrap_or(10); }
-            _ => files.push(args[i].clone()),
-        }
-        i += 1;
-    }
-
-    if files.is_empty() { eprintln!("usage: tail [-n N] <file> [file2 ...]"); return 1; }
+    let p = match TAIL.parse(args) { Ok(p) => p, Err(code) => return code };
+    let Some(count) = Count::parse("tail", &p, '+') else { return 2 };
+    let (by_name, following) = (p.has("F"), p.has("follow") || p.has("F"));
+    let mut files = p.operands;
+    if files.is_empty() { files.push("-"); }
     let multiple = files.len() > 1;
+    let mut followed = Vec::new();
     let mut code = 0;
     for file in &files {
-        let content = match std::fs::read_to_string(file) {
-            Ok(c) => c, Err(e) => { eprintln!("tail: {}: {}", file, e); code = 1; continue; }
+        // Standard input has no end to wait past
+        if *file == "-" {
+            if multiple { outln!("==> standard input <=="); }
+            if let Err(e) = tail_of(&mut super::io::stdin(), &count) { errln!("tail: -: {}", e); code = 1; }
+            if multiple { outln!(); }
+            continue;
+        }
+        let mut opened = match File::open(file) {
+            Ok(f) => f,
+            Err(e) => {
+                errln!("tail: {}: {}", file, e);
+                code = 1;
+                // -F waits for it to turn up
+                if by_name { followed.push(Followed::missing(file)); }
+                continue;
+            }
         };
-        if multiple { println!("==> {} <==", file); }
-        let all_lines: Vec<&str> = content.lines().collect();
-        let start = all_lines.len().saturating_sub(lines);
-        for line in &all_lines[start..] { println!("{}", line); }
-        if multiple { println!(); }
+        if multiple { outln!("==> {} <==", file); }
+        if let Err(e) = tail_of_file(&mut opened, &count) { errln!("tail: {}: {}", file, e); code = 1; }
+        if multiple { outln!(); }
+        followed.push(Followed::open(file, opened));
+    }
+    if following && !followed.is_empty() {
+        return follow(followed, by_name, multiple);
     }
     code
 }
 
-pub fn builtin_wc(args: &[String]) -> i32 {
-    let mut count_lines = false;
-    let mut count_words = false;
-    let mut count_chars = false;
-    let mut files = Vec::new();
+/// How often tail -f looks for more.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Set by Ctrl+C while tail -f runs in the foreground.
+static STOP_FOLLOWING: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_: libc::c_int) {
+    STOP_FOLLOWING.store(true, Ordering::SeqCst);
+}
+
+/// A file tail -f is watching.
+struct Followed {
+    name: String,
+    /// None while a -F file is missing
+    file: Option<File>,
+    /// How far it has been printed
+    offset: u64,
+    /// (device, inode) of the open file, so -F notices a new one in its place
+    id: Option<(u64, u64)>,
+}
+
+impl Followed {
+    /// `file`, read as far as it has been printed.
+    fn open(name: &str, mut file: File) -> Followed {
+        let offset = file.stream_position().unwrap_or(0);
+        let id = file.metadata().ok().and_then(|m| file_id(&m));
+        Followed { name: name.to_string(), file: Some(file), offset, id }
+    }
+
+    fn missing(name: &str) -> Followed {
+        Followed { name: name.to_string(), file: None, offset: 0, id: None }
+    }
+
+    /// For -F: open whatever is under the name now, if that's a different
+    /// file from the one being read.
+    fn reopen(&mut self) {
+        match std::fs::metadata(&self.name) {
+            Ok(meta) if self.file.is_none() || file_id(&meta) != self.id => {
+                let Ok(file) = File::open(&self.name) else { return };
+                let what = if self.file.is_some() { "has been replaced" } else { "has appeared" };
+                errln!("tail: '{}' {}; following new file", self.name, what);
+                *self = Followed { name: self.name.clone(), file: Some(file), offset: 0, id: file_id(&meta) };
+            }
+            Ok(_) => {}
+            Err(e) => if self.file.take().is_some() {
+                errln!("tail: '{}' has become inaccessible: {}", self.name, e);
+            },
+        }
+    }
+
+    /// What has been added since the last look.
+    fn read_more(&mut self) -> Vec<u8> {
+        let mut added = Vec::new();
+        let Some(file) = &mut self.file else { return added };
+        let Ok(len) = file.metadata().map(|m| m.len()) else { return added };
+        if len < self.offset {
+            errln!("tail: {}: file truncated", self.name);
+            self.offset = 0;
+        }
+        if len > self.offset && file.seek(SeekFrom::Start(self.offset)).is_ok() {
+            if let Ok(n) = file.read_to_end(&mut added) { self.offset += n as u64; }
+        }
+        added
+    }
+}
+
+#[cfg(unix)]
+fn file_id(meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Print what's added to `files` until Ctrl+C, with a header whenever the
+/// output moves from one file to another.
+fn follow(mut files: Vec<Followed>, by_name: bool, headers: bool) -> i32 {
+    STOP_FOLLOWING.store(false, Ordering::SeqCst);
+    #[cfg(unix)]
+    let previous = unsafe { libc::signal(libc::SIGINT, on_sigint as *const () as libc::sighandler_t) };
+    // A background job ignores Ctrl+C, and should go on doing so
+    #[cfg(unix)]
+    if previous == libc::SIG_IGN {
+        unsafe { libc::signal(libc::SIGINT, libc::SIG_IGN); }
+    }
 
-    for arg in &args[1..] {
-        if arg.starts_with('-') {
-            for ch in arg.chars().skip(1) {
-                match ch { 'l' => count_lines = true, 'w' => count_words = true, 'c'|'m' => count_chars = true, _ => {} }
+    let mut out = super::io::stdout();
+    let mut last = files.len() - 1;
+    let mut written = false;
+    while !STOP_FOLLOWING.load(Ordering::SeqCst) {
+        for (i, file) in files.iter_mut().enumerate() {
+            if by_name { file.reopen(); }
+            let added = file.read_more();
+            if added.is_empty() { continue; }
+            if headers && i != last {
+                // The lines already shown end with a blank line
+                if written { outln!(); }
+                outln!("==> {} <==", file.name);
+                last = i;
+            }
+            written = true;
+            // A closed pipe ends it, as for any other command
+            if out.write_all(&added).and_then(|_| out.flush()).is_err() {
+                STOP_FOLLOWING.store(true, Ordering::SeqCst);
             }
-        } else { files.push(arg.clone()); }
+        }
+        std::thread::sleep(FOLLOW_INTERVAL);
     }
 
-    if !count_lines && !count_words && !count_chars {
-        count_lines = true; count_words = true; count_chars = true;
+    #[cfg(unix)]
+    unsafe { libc::signal(libc::SIGINT, previous); }
+    0
+}
+
+/// wc [-lwc] [-h|--si|--bytes] FILE... — counts are grouped with the
+/// configured thousands separator; -h / --si show the character count as
+/// a binary / SI size instead.
+pub fn builtin_wc(shell: &Shell, args: &[String]) -> i32 {
+    let p = match WC.parse(args) { Ok(p) => p, Err(code) => return code };
+    // In the order the columns are shown
+    let mut shown = ["lines", "words", "chars", "bytes", "max-line-length"].map(|key| p.has(key));
+    let mut files = p.operands;
+    let mut numbers = NumberFormat::from_config(shell);
+    // Only an explicit flag turns the count into a size; the last one wins
+    numbers.units = SizeUnits::Bytes;
+    for (key, _) in &p.given {
+        match *key {
+            "human-readable" => numbers.units = SizeUnits::Binary,
+            "si"             => numbers.units = SizeUnits::Si,
+            _ => {}
+        }
+    }
+
+    if !shown.contains(&true) {
+        shown = [true, true, false, true, false];
     }
 
-    if files.is_empty() { eprintln!("usage: wc [-lwc] <file> [file2 ...]"); return 1; }
+    if files.is_empty() { files.push("-"); }
 
-    let mut total_l = 0usize;
-    let mut total_w = 0usize;
-    let mut total_c = 0usize;
+    let mut total = Counts::default();
     let mut code = 0;
     let multiple = files.len() > 1;
 
     for file in &files {
-        let content = match std::fs::read_to_string(file) {
-            Ok(c) => c, Err(e) => { eprintln!("wc: {}: {}", file, e); code = 1; continue; }
+        let counted = super::io::open_input(file).and_then(|mut input| Counts::of(&mut input));
+        let counts = match counted {
+            Ok(c) => c, Err(e) => { errln!("wc: {}: {}", file, e); code = 1; continue; }
         };
-        let l = content.lines().count();
-        let w = content.split_whitespace().count();
-        let c = content.chars().count();
-        total_l += l; total_w += w; total_c += c;
-        print_wc(l, w, c, count_lines, count_words, count_chars, file);
+        total.add(&counts);
+        print_wc(&counts, shown, &numbers, file);
+    }
+    if multiple {
+        print_wc(&total, shown, &numbers, "total");
     }
-    if multiple { print_wc(total_l, total_w, total_c, count_lines, count_words, count_chars, "total"); }
     code
 }
 
-fn print_wc(l: usize, w: usize, c: usize, cl: bool, cw: bool, cc: bool, label: &str) {
-    let mut parts = Vec::new();
-    if cl { parts.push(format!("{:>8}", l)); }
-    if cw { parts.push(format!("{:>8}", w)); }
-    if cc { parts.push(format!("{:>8}", c)); }
-    println!("{} {}", parts.join(""), label);
+/// What wc counts in one input.
+#[derive(Default)]
+struct Counts {
+    /// Newlines, so a last line without one isn't counted
+    lines: u64,
+    words: u64,
+    chars: u64,
+    bytes: u64,
+    /// Columns taken by the widest line, with tabs every 8
+    longest: u64,
 }
 
-pub fn builtin_env(args: &[String]) -> i32 {
-    if args.len() == 1 {
-        let mut vars: Vec<(String, String)> = std::env::vars().collect();
-        vars.sort_by(|a, b| a.0.cmp(&b.0));
-        for (k, v) in vars { println!("{}={}", k, v); }
-        return 0;
-    }
-    let mut extra_vars: Vec<(String, String)> = Vec::new();
-    let mut cmd_start = 1;
-    for (i, arg) in args[1..].iter().enumerate() {
-        if let Some((k, v)) = arg.split_once('=') {
-            extra_vars.push((k.to_string(), v.to_string()));
-            cmd_start = i + 2;
-        } else { break; }
-    }
-    if cmd_start >= args.len() {
-        let mut vars: Vec<(String, String)> = std::env::vars().collect();
-        for (k, v) in &extra_vars { vars.retain(|(ek, _)| ek != k); vars.push((k.clone(), v.clone())); }
-        vars.sort_by(|a, b| a.0.cmp(&b.0));
-        for (k, v) in vars { println!("{}={}", k, v); }
-        return 0;
+impl Counts {
+    /// Read `input` a line at a time, as bytes: a file that isn't UTF-8
+    /// is still counted, each broken sequence as one character.
+    fn of(input: &mut dyn BufRead) -> std::io::Result<Counts> {
+        let mut counts = Counts::default();
+        let mut line = Vec::new();
+        let mut in_word = false;
+        loop {
+            line.clear();
+            if input.read_until(b'\n', &mut line)? == 0 { return Ok(counts); }
+            counts.bytes += line.len() as u64;
+            let text = String::from_utf8_lossy(&line);
+            let mut width = 0;
+            for c in text.chars() {
+                counts.chars += 1;
+                match c {
+                    '\n' => counts.lines += 1,
+                    '\t' => width = (width / 8 + 1) * 8,
+                    c => width += unicode_width::UnicodeWidthChar::width(c).unwrap_or(0) as u64,
+                }
+                // A word is a run of anything but white space
+                if c.is_whitespace() { in_word = false; } else if !in_word { in_word = true; counts.words += 1; }
+            }
+            counts.longest = counts.longest.max(width);
+        }
     }
-    let mut cmd = std::process::Command::new(&args[cmd_start]);
-    cmd.args(&args[cmd_start + 1..]);
-    for (k, v) in extra_vars { cmd.env(k, v); }
-    match cmd.status() {
-        Ok(status) => status.code().unwrap_or(0),
-        Err(e) => { eprintln!("env: {}: {}", args[cmd_start], e); 1 }
+
+    fn add(&mut self, other: &Counts) {
+        self.lines += other.lines;
+        self.words += other.words;
+        self.chars += other.chars;
+        self.bytes += other.bytes;
+        self.longest = self.longest.max(other.longest);
     }
 }
 
-pub fn builtin_sort(args: &[String]) -> i32 {
-    let mut reverse = false;
-    let mut unique = false;
-    let mut numeric = false;
-    let mut files = Vec::new();
-
-    for arg in &args[1..] {
-        if arg.starts_with('-') {
-            for ch in arg.chars().skip(1) {
-                match ch { 'r' => reverse = true, 'u' => unique = true, 'n' => numeric = true, _ => {} }
-            }
-        } else { files.push(arg.clone()); }
-    }
+fn print_wc(counts: &Counts, shown: [bool; 5], numbers: &NumberFormat, label: &str) {
+    let columns = [
+        numbers.count(counts.lines),
+        numbers.count(counts.words),
+        numbers.count(counts.chars),
+        numbers.size(counts.bytes),
+        numbers.count(counts.longest),
+    ];
+    let parts: Vec<String> = columns.iter().zip(shown).filter(|(_, shown)| *shown).map(|(n, _)| format!("{:>7}", n)).collect();
+    // Standard input has no name to show
+    if label == "-" { outln!("{}", parts.join(" ")); } else { outln!("{} {}", parts.join(" "), label); }
+}
 
-    if files.is_empty() { eprintln!("usage: sort [-rnu] <file> [file2 ...]"); return 1; }
+pub fn builtin_sort(args: &[String]) -> i32 {
+    let p = match SORT.parse(args) { Ok(p) => p, Err(code) => return code };
+    let order = Order {
+        numeric: p.has("numeric-sort"),
+        human: p.has("human-numeric-sort"),
+        fold: p.has("ignore-case"),
+        reverse: p.has("reverse"),
+        blanks: p.has("ignore-leading-blanks"),
+    };
+    let separator = match p.value("field-separator").map(|sep| (sep.chars().next(), sep.chars().count())) {
+        None => None,
+        Some((Some(c), 1)) => Some(c),
+        Some(_) => { errln!("sort: the separator must be a single character"); return 2; }
+    };
+    let keys: Result<Vec<SortKey>, String> = p.given.iter()
+        .filter(|(key, _)| *key == "key")
+        .map(|(_, def)| SortKey::parse(def.unwrap_or_default(), order))
+        .collect();
+    let keys = match keys {
+        Ok(keys) => keys,
+        Err(e) => { errln!("sort: {}", e); return 2; }
+    };
+    // -u keeps the first of the lines with equal keys, so they stay in order
+    let unique = p.has("unique");
+    let sorter = Sorter { keys, order, separator, stable: p.has("stable") || unique };
+    let mut files = p.operands;
+    if files.is_empty() { files.push("-"); }
 
     let mut all = String::new();
     for file in &files {
-        match std::fs::read_to_string(file) {
+        match super::io::read_to_string(file) {
             Ok(c) => all.push_str(&c),
-            Err(e) => { eprintln!("sort: {}: {}", file, e); return 1; }
+            Err(e) => { errln!("sort: {}: {}", file, e); return 1; }
         }
     }
 
     let mut lines: Vec<&str> = all.lines().collect();
-    if numeric {
-        lines.sort_by(|a, b| {
-            let an: f64 = a.trim().parse().unwrap_or(0.0);
-            let bn: f64 = b.trim().parse().unwrap_or(0.0);
-            an.partial_cmp(&bn).unwrap_or(std::cmp::Ordering::Equal)
-        });
-    } else { lines.sort(); }
-    if reverse { lines.reverse(); }
-    if unique { lines.dedup(); }
-    for line in lines { println!("{}", line); }
+    lines.sort_by(|a, b| sorter.compare(a, b));
+    if unique { lines.dedup_by(|a, b| sorter.compare_keys(a, b).is_eq()); }
+    for line in lines { outln!("{}", line); }
     0
 }
 
-pub fn builtin_uniq(args: &[String]) -> i32 {
-    let mut count = false;
-    let mut unique_only = false;
-    let mut repeated_only = false;
-    let mut files = Vec::new();
-
-    for arg in &args[1..] {
-        if arg.starts_with('-') {
-            for ch in arg.chars().skip(1) {
-                match ch { 'c' => count = true, 'u' => unique_only = true, 'd' => repeated_only = true, _ => {} }
+/// How two keys (or whole lines) are compared.
+#[derive(Clone, Copy, Default)]
+struct Order {
+    numeric: bool,
+    human: bool,
+    fold: bool,
+    reverse: bool,
+    blanks: bool,
+}
+
+impl Order {
+    fn compare(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        let (a, b) = if self.blanks { (a.trim_start(), b.trim_start()) } else { (a, b) };
+        let ordering = if self.human {
+            human_number(a).total_cmp(&human_number(b))
+        } else if self.numeric {
+            leading_number(a).0.total_cmp(&leading_number(b).0)
+        } else if self.fold {
+            a.chars().flat_map(char::to_lowercase).cmp(b.chars().flat_map(char::to_lowercase))
+        } else {
+            a.cmp(b)
+        };
+        if self.reverse { ordering.reverse() } else { ordering }
+    }
+}
+
+/// One -k: the part of the line from `start` to `end`, each a field and a
+/// character in it counting from 1 (a character of 0 ends at the end of
+/// the field).
+struct SortKey {
+    start: (usize, usize),
+    end: Option<(usize, usize)>,
+    order: Order,
+}
+
+impl SortKey {
+    /// A KEYDEF such as `2`, `3,3n` or `1.2,1.4r`. Without letters of its
+    /// own the key is sorted the way `global` says.
+    fn parse(def: &str, global: Order) -> Result<SortKey, String> {
+        let bad = || format!("invalid key '{}'", def);
+        let mut order = Order::default();
+        let mut own = false;
+        let mut position = |part: &str, default_char: usize| -> Result<(usize, usize), String> {
+            let digits = part.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+            for flag in part[digits.len()..].chars() {
+                match flag {
+                    'n' => order.numeric = true,
+                    'h' => order.human = true,
+                    'f' => order.fold = true,
+                    'r' => order.reverse = true,
+                    'b' => order.blanks = true,
+                    _ => return Err(format!("unknown ordering '{}' in key '{}'", flag, def)),
+                }
+                own = true;
+            }
+            let (field, char) = match digits.split_once('.') {
+                Some((field, char)) => (field, char.parse().map_err(|_| bad())?),
+                None => (digits, default_char),
+            };
+            let field: usize = field.parse().map_err(|_| bad())?;
+            if field == 0 { return Err(format!("fields are numbered from 1 in key '{}'", def)); }
+            Ok((field, char))
+        };
+        let (start, end) = match def.split_once(',') {
+            Some((start, end)) => (position(start, 1)?, Some(position(end, 0)?)),
+            None => (position(def, 1)?, None),
+        };
+        Ok(SortKey { start, end, order: if own { order } else { global } })
+    }
+
+    /// The key's text in `line`.
+    fn text<'a>(&self, line: &'a str, separator: Option<char>) -> &'a str {
+        let fields = field_spans(line, separator);
+        let at = |(field, char): (usize, usize), from_end: bool| -> usize {
+            let Some(&(start, end)) = fields.get(field - 1) else { return line.len() };
+            let text = &line[start..end];
+            // Blanks that separate fields aren't part of the count
+            let skipped = if separator.is_none() { text.len() - text.trim_start().len() } else { 0 };
+            if from_end && char == 0 { return end; }
+            let offset = text[skipped..].char_indices().nth(char.saturating_sub(1) + usize::from(from_end))
+                .map_or(text.len(), |(i, _)| skipped + i);
+            start + offset
+        };
+        let start = at(self.start, false);
+        let end = self.end.map_or(line.len(), |end| at(end, true));
+        line.get(start..end.max(start)).unwrap_or_default()
+    }
+}
+
+/// The byte ranges of the fields of `line`: split at `separator`, or else
+/// at runs of blanks, each field taking the blanks before it.
+fn field_spans(line: &str, separator: Option<char>) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    match separator {
+        Some(sep) => {
+            for (i, _) in line.match_indices(sep) {
+                spans.push((start, i));
+                start = i + sep.len_utf8();
             }
-        } else { files.push(arg.clone()); }
+            spans.push((start, line.len()));
+        }
+        None => {
+            let mut in_blanks = true;
+            for (i, c) in line.char_indices() {
+                let blank = c == ' ' || c == '\t';
+                if blank && !in_blanks && i > start {
+                    spans.push((start, i));
+                    start = i;
+                }
+                in_blanks = blank;
+            }
+            spans.push((start, line.len()));
+        }
+    }
+    spans
+}
+
+/// The number a line starts with (after blanks), and how it continues;
+/// 0 if there isn't one.
+fn leading_number(s: &str) -> (f64, &str) {
+    let s = s.trim_start();
+    let mut end = 0;
+    let mut seen_point = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '-' | '+' if i == 0 => {}
+            '0'..='9' => {}
+            '.' if !seen_point => seen_point = true,
+            _ => break,
+        }
+        end = i + c.len_utf8();
     }
+    (s[..end].parse().unwrap_or(0.0), &s[end..])
+}
+
+/// A size as du -h and ls -h show it: `1.5K` is 1536, `2G` is 2 << 30.
+fn human_number(s: &str) -> f64 {
+    let (n, rest) = leading_number(s);
+    let power = rest.chars().next().and_then(|c| "KMGTPE".find(c.to_ascii_uppercase())).map_or(0, |i| i + 1);
+    n * 1024f64.powi(power as i32)
+}
 
-    if files.is_empty() { eprintln!("usage: uniq [-cud] <file>"); return 1; }
+/// Everything that decides the order of two lines.
+struct Sorter {
+    keys: Vec<SortKey>,
+    order: Order,
+    separator: Option<char>,
+    stable: bool,
+}
+
+impl Sorter {
+    /// By the keys, or the whole line if there are none.
+    fn compare_keys(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        if self.keys.is_empty() { return self.order.compare(a, b); }
+        self.keys.iter()
+            .map(|key| key.order.compare(key.text(a, self.separator), key.text(b, self.separator)))
+            .find(|o| o.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    }
 
-    let content = match std::fs::read_to_string(&files[0]) {
-        Ok(c) => c, Err(e) => { eprintln!("uniq: {}: {}", files[0], e); return 1; }
+    /// By the keys, then (unless -s) the lines byte by byte, so that lines
+    /// the keys can't tell apart still come out in a fixed order.
+    fn compare(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        let ordering = self.compare_keys(a, b);
+        if ordering.is_ne() || self.stable { return ordering; }
+        if self.order.reverse { b.cmp(a) } else { a.cmp(b) }
+    }
+}
+
+pub fn builtin_uniq(args: &[String]) -> i32 {
+    let p = match UNIQ.parse(args) { Ok(p) => p, Err(code) => return code };
+    let (count, unique_only, repeated_only) = (p.has("count"), p.has("unique"), p.has("repeated"));
+    let mut files = p.operands;
+    if files.is_empty() { files.push("-"); }
+
+    let content = match super::io::read_to_string(files[0]) {
+        Ok(c) => c, Err(e) => { errln!("uniq: {}: {}", files[0], e); return 1; }
     };
 
     let lines: Vec<&str> = content.lines().collect();
@@ -212,71 +767,422 @@ pub fn builtin_uniq(args: &[String]) -> i32 {
     for (line, n) in groups {
         if unique_only && n > 1 { continue; }
         if repeated_only && n == 1 { continue; }
-        if count { println!("{:>7} {}", n, line); } else { println!("{}", line); }
+        if count { outln!("{:>7} {}", n, line); } else { outln!("{}", line); }
     }
     0
 }
 
-pub fn builtin_xargs(args: &[String]) -> i32 {
-    if args.len() < 2 {
-        eprintln!("usage: xargs <command> [args...]");
-        return 1;
+pub fn builtin_cut(args: &[String]) -> i32 {
+    let p = match CUT.parse(args) { Ok(p) => p, Err(code) => return code };
+    let (list, by_field) = match (p.value("fields"), p.value("characters")) {
+        (Some(list), None) => (list, true),
+        (None, Some(list)) => (list, false),
+        _ => { errln!("cut: give one of -f or -c"); return 2; }
+    };
+    let ranges = match cut_ranges(list) {
+        Ok(r) => r, Err(e) => { errln!("cut: {}", e); return 2; }
+    };
+    let delim = p.value("delimiter").unwrap_or("\t");
+    if delim.chars().count() != 1 { errln!("cut: the delimiter must be a single character"); return 2; }
+    if !by_field && (p.has("delimiter") || p.has("only-delimited")) {
+        errln!("cut: -d and -s only apply to fields");
+        return 2;
+    }
+    let only_delimited = p.has("only-delimited");
+    let complement = p.has("complement");
+
+    let mut files = p.operands;
+    if files.is_empty() { files.push("-"); }
+    let mut code = 0;
+    for file in &files {
+        // Line by line, so `cut` on an endless pipe prints as it goes
+        let input = match super::io::open_input(file) {
+            Ok(r) => r, Err(e) => { errln!("cut: {}: {}", file, e); code = 1; continue; }
+        };
+        for line in input.lines().map_while(Result::ok) {
+            if !by_field {
+                let chars: String = cut_pick(line.chars(), &ranges, complement).into_iter().collect();
+                outln!("{}", chars);
+            } else if line.contains(delim) {
+                outln!("{}", cut_pick(line.split(delim), &ranges, complement).join(delim));
+            } else if !only_delimited {
+                outln!("{}", line);
+            }
+        }
     }
+    code
+}
 
-    let tmp = std::env::temp_dir().join("rshell_pipe_in.tmp");
-    let input = match std::fs::read_to_string(&tmp) {
-        Ok(s) => s,
-        Err(_) => {
-            eprintln!("xargs: no input (must be used in a pipeline)");
-            return 1;
+/// A cut LIST — `N`, `N-M`, `N-` and `-M`, separated by commas — as
+/// inclusive ranges counted from 1.
+fn cut_ranges(list: &str) -> Result<Vec<(usize, usize)>, String> {
+    let number = |s: &str, default: usize| -> Result<usize, String> {
+        if s.is_empty() { return Ok(default); }
+        match s.parse::<usize>() {
+            Ok(0) => Err("fields and positions are numbered from 1".to_string()),
+            Ok(n) => Ok(n),
+            Err(_) => Err(format!("invalid field value '{}'", s)),
         }
     };
+    list.split(',').map(|part| {
+        let (from, to) = match part.split_once('-') {
+            Some(("", "")) => return Err("invalid range with no endpoint: -".to_string()),
+            Some((from, to)) => (number(from, 1)?, number(to, usize::MAX)?),
+            None => { let n = number(part, 0)?; (n, n) }
+        };
+        if from == 0 || from > to { return Err(format!("invalid range '{}'", part)); }
+        Ok((from, to))
+    }).collect()
+}
 
-    let file_args: Vec<String> = input
-        .lines()
-        .map(|l| l.trim().to_string())
-        .filter(|l| !l.is_empty())
-        .collect();
+/// The items at the positions in `ranges`, or with `complement` those at
+/// every other position, in their original order.
+fn cut_pick<T>(items: impl Iterator<Item = T>, ranges: &[(usize, usize)], complement: bool) -> Vec<T> {
+    items.enumerate()
+        .filter(|(i, _)| ranges.iter().any(|&(from, to)| (from..=to).contains(&(i + 1))) != complement)
+        .map(|(_, item)| item)
+        .collect()
+}
+
+pub fn builtin_tr(args: &[String]) -> i32 {
+    let p = match TR.parse(args) { Ok(p) => p, Err(code) => return code };
+    let (delete, squeeze) = (p.has("delete"), p.has("squeeze-repeats"));
+    // -d takes one set, or two with -s (the second to squeeze); -s alone
+    // one or two; translating two
+    let (fewest, most) = match (delete, squeeze) {
+        (true, false) => (1, 1),
+        (false, true) => (1, 2),
+        _ => (2, 2),
+    };
+    let count = p.operands.len();
+    if count < fewest || count > most {
+        match count < fewest {
+            true  => errln!("tr: missing operand"),
+            false => errln!("tr: extra operand '{}'", p.operands[most]),
+        }
+        errln!("Try 'tr --help' for more information.");
+        return 2;
+    }
+    let sets: Result<Vec<Vec<char>>, String> = p.operands.iter().map(|set| tr_set(set)).collect();
+    let sets = match sets {
+        Ok(sets) => sets, Err(e) => { errln!("tr: {}", e); return 2; }
+    };
+
+    if let Some(to) = sets.get(1).filter(|_| !delete) {
+        if to.is_empty() && !sets[0].is_empty() { errln!("tr: SET2 is empty"); return 2; }
+    }
+    let tr = Tr::new(sets, delete, squeeze);
+
+    let mut input = match super::io::open_input("-") {
+        Ok(r) => r, Err(e) => { errln!("tr: {}", e); return 1; }
+    };
+    // A line at a time, so `tr` in the middle of a pipe passes text on
+    // as it comes; the last character carries over for squeezing
+    let mut last: Option<char> = None;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match input.read_until(b'\n', &mut buf) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => { errln!("tr: {}", e); return 1; }
+        }
+        out!("{}", tr.apply(&String::from_utf8_lossy(&buf), &mut last));
+    }
+    0
+}
+
+/// What a tr command line does to each character.
+struct Tr {
+    from: Vec<char>,
+    /// What `from` becomes, when translating
+    to: Option<Vec<char>>,
+    delete: bool,
+    /// The characters whose runs are squeezed: those of the last set given
+    squeeze: Vec<char>,
+}
+
+impl Tr {
+    fn new(mut sets: Vec<Vec<char>>, delete: bool, squeeze: bool) -> Tr {
+        let squeeze = if squeeze { sets.last().cloned().unwrap_or_default() } else { Vec::new() };
+        let to = if sets.len() > 1 && !delete { sets.pop() } else { None };
+        Tr { from: sets.swap_remove(0), to, delete, squeeze }
+    }
+
+    /// `text` translated. `last` is the character before it, carried from
+    /// one call to the next so runs are squeezed across them.
+    fn apply(&self, text: &str, last: &mut Option<char>) -> String {
+        let mut out = String::new();
+        for c in text.chars() {
+            if self.delete && self.from.contains(&c) { continue; }
+            // A SET2 shorter than SET1 is padded with its last character
+            let c = match (&self.to, self.from.iter().rposition(|&f| f == c)) {
+                (Some(to), Some(i)) => *to.get(i).unwrap_or_else(|| to.last().unwrap()),
+                _ => c,
+            };
+            if *last == Some(c) && self.squeeze.contains(&c) { continue; }
+            *last = Some(c);
+            out.push(c);
+        }
+        out
+    }
+}
 
-    if file_args.is_empty() { return 0; }
-
-    let cmd_name = &args[1];
-    let initial_args = &args[2..];
-
-    let mut full_args: Vec<String> = vec![cmd_name.clone()];
-    full_args.extend(initial_args.iter().cloned());
-    full_args.extend(file_args);
-
-    // Try as builtin first, then fall back to external
-    // We need a shell reference for builtins that need it — for stateless
-    // builtins (wc, grep, sort etc) we can call them directly
-    match cmd_name.as_str() {
-        "wc"   => super::text::builtin_wc(&full_args),
-        "grep" => super::grep::builtin_grep(&full_args),
-        "sort" => super::text::builtin_sort(&full_args),
-        "head" => super::text::builtin_head(&full_args),
-        "tail" => super::text::builtin_tail(&full_args),
-        "cat"  => super::fs::builtin_cat(&full_args),
-        "rm"   => super::fs::builtin_rm(&full_args),
-        "chmod"=> super::fs::builtin_chmod(&full_args),
-        _ => {
-            // Fall back to external command
-            crossterm::terminal::disable_raw_mode().ok();
-            let mut cmd = std::process::Command::new(cmd_name);
-            cmd.args(&full_args[1..]);
-            let code = match cmd.status() {
-                Ok(status) => status.code().unwrap_or(0),
-                Err(e) => {
-                    if e.kind() == std::io::ErrorKind::NotFound {
-                        eprintln!("xargs: {}: command not found", cmd_name);
-                    } else {
-                        eprintln!("xargs: {}: {}", cmd_name, e);
+/// The characters of a tr SET, in order, with ranges, classes and escapes
+/// spelled out.
+fn tr_set(set: &str) -> Result<Vec<char>, String> {
+    let chars: Vec<char> = set.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    // One character, with a backslash escape read as the character it means
+    let next = |i: &mut usize| -> char {
+        let c = chars[*i];
+        *i += 1;
+        if c != '\\' || *i >= chars.len() { return c; }
+        let e = chars[*i];
+        *i += 1;
+        match e {
+            'n' => '\n', 't' => '\t', 'r' => '\r', '\\' => '\\',
+            'a' => '\x07', 'b' => '\x08', 'f' => '\x0c', 'v' => '\x0b',
+            '0'..='7' => {
+                let mut n = e.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.get(*i).and_then(|d| d.to_digit(8)) {
+                        Some(d) => { n = n * 8 + d; *i += 1; }
+                        None => break,
                     }
-                    1
                 }
+                char::from_u32(n).unwrap_or(e)
+            }
+            other => other,
+        }
+    };
+    while i < chars.len() {
+        if chars[i] == '[' && chars.get(i + 1) == Some(&':') {
+            let rest: String = chars[i + 2..].iter().collect();
+            if let Some(end) = rest.find(":]") {
+                let name = &rest[..end];
+                let class: fn(&char) -> bool = match name {
+                    "upper"  => char::is_ascii_uppercase,
+                    "lower"  => char::is_ascii_lowercase,
+                    "alpha"  => char::is_ascii_alphabetic,
+                    "digit"  => char::is_ascii_digit,
+                    "alnum"  => char::is_ascii_alphanumeric,
+                    "xdigit" => char::is_ascii_hexdigit,
+                    "punct"  => char::is_ascii_punctuation,
+                    "space"  => |c: &char| c.is_ascii_whitespace() || *c == '\x0b',
+                    "blank"  => |c: &char| *c == ' ' || *c == '\t',
+                    "cntrl"  => char::is_ascii_control,
+                    "print"  => |c: &char| (' '..='~').contains(c),
+                    "graph"  => char::is_ascii_graphic,
+                    _ => return Err(format!("invalid character class '{}'", name)),
+                };
+                out.extend((0u8..128).map(char::from).filter(class));
+                i += 2 + name.chars().count() + 2;
+                continue;
+            }
+        }
+        let c = next(&mut i);
+        if chars.get(i) == Some(&'-') && i + 1 < chars.len() {
+            i += 1;
+            let end = next(&mut i);
+            if end < c {
+                return Err(format!("range-endpoints of '{}-{}' are in reverse collating sequence order", c, end));
+            }
+            out.extend(c..=end);
+        } else {
+            out.push(c);
+        }
+    }
+    Ok(out)
+}
+
+pub fn builtin_xargs(shell: &mut Shell, args: &[String]) -> i32 {
+    // The options end at CMD, whose own options are left alone
+    let mut split = 1;
+    while let Some(word) = args.get(split) {
+        if word == "--" { split += 1; break; }
+        if !word.starts_with('-') || word == "-" { break; }
+        let takes_value = matches!(word.as_str(), "-I" | "-n" | "-P" | "--replace" | "--max-args" | "--max-procs");
+        split += if takes_value { 2 } else { 1 };
+    }
+    let split = split.min(args.len());
+    let p = match XARGS.parse(&args[..split]) { Ok(p) => p, Err(code) => return code };
+    let number = |name: &str| -> Result<Option<usize>, i32> {
+        let Some(n) = p.value(name) else { return Ok(None) };
+        match n.parse() {
+            Ok(n) => Ok(Some(n)),
+            Err(_) => { errln!("xargs: invalid number for --{}: '{}'", name, n); Err(2) }
+        }
+    };
+    let (max_args, procs) = match (number("max-args"), number("max-procs")) {
+        (Ok(max_args), Ok(procs)) => (max_args, procs.unwrap_or(1)),
+        (Err(code), _) | (_, Err(code)) => return code,
+    };
+    if max_args == Some(0) { errln!("xargs: --max-args must be at least 1"); return 2; }
+    let replace = p.value("replace");
+    let command: Vec<String> = match &args[split..] {
+        [] => vec!["echo".to_string()],
+        words => words.to_vec(),
+    };
+
+    let mut input = Vec::new();
+    if let Err(e) = super::io::stdin().read_to_end(&mut input) {
+        errln!("xargs: {}", e);
+        return 1;
+    }
+    let input = String::from_utf8_lossy(&input);
+    let items: Vec<&str> = if p.has("null") {
+        input.split('\0').filter(|item| !item.is_empty()).collect()
+    } else {
+        input.lines().map(str::trim).filter(|line| !line.is_empty()).collect()
+    };
+    if items.is_empty() { return 0; }
+
+    // One command per item with -I, else as many items as -n allows
+    let runs: Vec<Vec<String>> = match replace {
+        Some(replace) => items.iter().map(|item| {
+            command.iter().map(|word| word.replace(replace, item)).collect()
+        }).collect(),
+        None => items.chunks(max_args.unwrap_or(items.len())).map(|batch| {
+            command.iter().cloned().chain(batch.iter().map(|item| item.to_string())).collect()
+        }).collect(),
+    };
+
+    let mut code = 0;
+    let mut running: Vec<(String, std::process::Child)> = Vec::new();
+    for argv in &runs {
+        // Builtins run in the shell like any other, one at a time
+        if let Some(status) = super::run_builtin(shell, argv) {
+            if status != 0 { code = status; }
+            continue;
+        }
+        // Anything else is spawned the way the executor spawns commands,
+        // with the shell's environment
+        let mut cmd = match crate::executor::build_command(shell, argv, &[]) {
+            Ok(cmd) => cmd,
+            Err(e) => { errln!("xargs: {}", e); code = 1; continue; }
+        };
+        // Our stdin has been used up reading the arguments
+        cmd.env_clear().envs(shell.child_env()).stdin(std::process::Stdio::null());
+        match cmd.spawn() {
+            Ok(child) => running.push((argv[0].clone(), child)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                errln!("xargs: {}: command not found", argv[0]);
+                return 127;
+            }
+            Err(e) => { errln!("xargs: {}: {}", argv[0], e); return 126; }
+        }
+        while procs != 0 && running.len() >= procs {
+            if let Some(status) = wait_for_one(&mut running) { code = status; }
+        }
+    }
+    while !running.is_empty() {
+        if let Some(status) = wait_for_one(&mut running) { code = status; }
+    }
+    code
+}
+
+/// Wait until one of the `running` commands finishes and drop it; its
+/// exit code if that isn't 0.
+fn wait_for_one(running: &mut Vec<(String, std::process::Child)>) -> Option<i32> {
+    loop {
+        for i in 0..running.len() {
+            let status = match running[i].1.try_wait() {
+                Ok(Some(status)) => super::status::exit_status(&running[i].0, status),
+                Ok(None) => continue,
+                Err(e) => { errln!("xargs: {}: {}", running[i].0, e); 1 }
             };
-            crossterm::terminal::enable_raw_mode().ok();
-            code
+            running.remove(i);
+            return (status != 0).then_some(status);
         }
+        std::thread::sleep(Duration::from_millis(10));
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(lines: &[&'static str], keys: &[&str], order: Order, separator: Option<char>) -> Vec<&'static str> {
+        let keys = keys.iter().map(|k| SortKey::parse(k, order).unwrap()).collect();
+        let sorter = Sorter { keys, order, separator, stable: false };
+        let mut lines = lines.to_vec();
+        lines.sort_by(|a, b| sorter.compare(a, b));
+        lines
+    }
+
+    #[test]
+    fn sort_keys() {
+        let plain = Order::default();
+        let du = ["1.5K\tdocs", "12M\tvideos", "900\tREADME", "2G\tbackups"];
+        assert_eq!(sorted(&du, &[], Order { human: true, ..plain }, None), ["900\tREADME", "1.5K\tdocs", "12M\tvideos", "2G\tbackups"]);
+        let csv = ["b,10,x", "a,9,y", "c,10,a"];
+        assert_eq!(sorted(&csv, &["2n", "3"], plain, Some(',')), ["a,9,y", "c,10,a", "b,10,x"]);
+        assert_eq!(sorted(&csv, &["2,2nr"], plain, Some(',')), ["b,10,x", "c,10,a", "a,9,y"]);
+        let ls = ["-rw-r--r--  1 me  Zeta", "drwxr-xr-x  3 me  alpha"];
+        assert_eq!(sorted(&ls, &["4"], Order { fold: true, ..plain }, None), ["drwxr-xr-x  3 me  alpha", "-rw-r--r--  1 me  Zeta"]);
+        assert_eq!(SortKey::parse("2.2,2.3", plain).unwrap().text("ab  cdef gh", None), "de");
+        assert!(SortKey::parse("0", plain).is_err());
+        assert!(SortKey::parse("1x", plain).is_err());
+    }
+
+    #[test]
+    fn cut_lists() {
+        assert_eq!(cut_ranges("-3"), Ok(vec![(1, 3)]));
+        assert_eq!(cut_ranges("5-"), Ok(vec![(5, usize::MAX)]));
+        assert_eq!(cut_ranges("2,4-5"), Ok(vec![(2, 2), (4, 5)]));
+        assert!(cut_ranges("0").is_err());
+        assert!(cut_ranges("3-1").is_err());
+        assert!(cut_ranges("-").is_err());
+
+        let fields = "a:b:c:d:e:f";
+        let pick = |list: &str, complement: bool| {
+            cut_pick(fields.split(':'), &cut_ranges(list).unwrap(), complement).join(":")
+        };
+        assert_eq!(pick("-3", false), "a:b:c");
+        assert_eq!(pick("5-", false), "e:f");
+        // Overlapping ranges pick each field once, in line order
+        assert_eq!(pick("4-5,1-2,2-4", false), "a:b:c:d:e");
+        assert_eq!(pick("2,4-", true), "a:c");
+        assert_eq!(pick("1-", true), "");
+    }
+
+    #[test]
+    fn tr_sets() {
+        let set = |s: &str| tr_set(s).map(|chars| chars.into_iter().collect::<String>());
+        assert_eq!(set("a-e"), Ok("abcde".to_string()));
+        assert_eq!(set("x0-2-"), Ok("x012-".to_string()));
+        assert_eq!(set("[:digit:]"), Ok("0123456789".to_string()));
+        assert_eq!(set("[:upper:]").unwrap().len(), 26);
+        assert_eq!(set("[:blank:]_"), Ok("\t _".to_string()));
+        assert_eq!(set("\\n\\t\\101\\\\\\-"), Ok("\n\tA\\-".to_string()));
+        assert!(tr_set("z-a").is_err());
+        assert!(tr_set("[:nope:]").is_err());
+    }
+
+    #[test]
+    fn tr_apply() {
+        let tr = |sets: &[&str], delete: bool, squeeze: bool, text: &str| {
+            let sets = sets.iter().map(|s| tr_set(s).unwrap()).collect();
+            Tr::new(sets, delete, squeeze).apply(text, &mut None)
+        };
+        assert_eq!(tr(&["a-z", "A-Z"], false, false, "hi there"), "HI THERE");
+        // A shorter SET2 is padded with its last character
+        assert_eq!(tr(&["abcd", "xy"], false, false, "abcdab"), "xyyyxy");
+        assert_eq!(tr(&["[:digit:]"], true, false, "a1b22c"), "abc");
+        assert_eq!(tr(&[" "], false, true, "a   b  c"), "a b c");
+        // -d -s deletes SET1 and squeezes SET2, which may be shorter
+        assert_eq!(tr(&["0-9", "a"], true, true, "a1a2aab"), "ab");
+        // -s with two sets squeezes what the characters became
+        assert_eq!(tr(&["ab", "x"], false, true, "aabbc"), "xc");
+    }
+
+    #[test]
+    fn last_lines() {
+        assert_eq!(last_lines_start(b"a\nb\nc\n", 2), 2);
+        assert_eq!(last_lines_start(b"a\nb\nc", 2), 2);
+        assert_eq!(last_lines_start(b"a\nb\n", 5), 0);
+        assert_eq!(last_lines_start(b"a\n", 0), 2);
+    }
+}
@@ -0,0 +1,297 @@
+// src/executor/builtin/trash.rs
+// trash FILE... (and rm -t) — move files to the desktop trash instead of
+// deleting them, so a slip can be undone:
+//
+//   trash FILE...          move each FILE to the trash
+//   trash --list           what's in it, oldest first
+//   trash --restore NAME   put NAME (its name in the trash, or where it came
+//                          from) back; the latest one if there are several
+//   trash --empty          delete everything in the trash for good
+//
+// On Linux and other XDG desktops the trash is $XDG_DATA_HOME/Trash, with
+// where each file came from in info/NAME.trashinfo, so the file manager
+// can restore it too. macOS keeps trashed files in ~/.Trash; the records
+// go in ~/.rshell/trash, since Finder's own are private. On Windows files
+// go to the Recycle Bin, and are restored from Explorer.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::paths;
+use crate::shell::Shell;
+use super::util::{local_datetime, percent_decode, percent_encode, unix_secs, Opt, Options};
+
+pub const TRASH: Options = Options {
+    name: "trash",
+    operands: "[FILE]...",
+    about: "Move each FILE to the trash, or list, restore or empty the trash.",
+    opts: &[
+        Opt::new("l", "list", "list what's in the trash"),
+        Opt::new("", "restore", "put NAME back where it came from").takes("NAME"),
+        Opt::new("", "empty", "delete everything in the trash for good"),
+        Opt::new("f", "force", "ignore files that don't exist"),
+    ],
+    numeric: None,
+};
+
+pub fn builtin_trash(shell: &Shell, args: &[String]) -> i32 {
+    let p = match TRASH.parse(args) { Ok(p) => p, Err(code) => return code };
+    if p.has("empty") {
+        return match empty() {
+            Ok(()) => 0,
+            Err(e) => { errln!("trash: {}", e); 1 }
+        };
+    }
+    if p.has("list") { return list(); }
+    if let Some(name) = p.value("restore") { return restore(shell, name); }
+    if p.operands.is_empty() { errln!("usage: trash FILE... | --list | --restore NAME | --empty"); return 2; }
+
+    let mut code = 0;
+    for file in &p.operands {
+        let path = shell.cwd.join(file);
+        if path.symlink_metadata().is_err() {
+            if !p.has("force") { errln!("trash: {}: No such file or directory", file); code = 1; }
+            continue;
+        }
+        if let Err(e) = put(&path) { errln!("trash: {}: {}", file, e); code = 1; }
+    }
+    code
+}
+
+/// Move `path` (a symlink itself, never what it points to) to the trash.
+pub(super) fn put(path: &Path) -> std::io::Result<()> {
+    #[cfg(windows)]
+    { recycle(path) }
+    #[cfg(not(windows))]
+    {
+        let trash = Trash::home().ok_or_else(|| std::io::Error::other("no home directory for the trash"))?;
+        trash.put(path).map(|_| ())
+    }
+}
+
+/// The desktop trash as a directory: ~/.local/share/Trash on Linux,
+/// ~/.Trash on macOS, and None on Windows, whose Recycle Bin isn't one.
+pub(super) fn trash_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|h| h.join(".Trash"))
+    } else if cfg!(windows) {
+        None
+    } else {
+        dirs::data_local_dir().map(|d| d.join("Trash"))
+    }
+}
+
+/// Delete everything in the trash, keeping its directories.
+pub(super) fn empty() -> std::io::Result<()> {
+    #[cfg(windows)]
+    {
+        powershell("Clear-RecycleBin -Force -ErrorAction SilentlyContinue")
+    }
+    #[cfg(not(windows))]
+    {
+        let Some(trash) = Trash::home() else { return Ok(()) };
+        for dir in [&trash.files, &trash.info] {
+            let Ok(entries) = std::fs::read_dir(dir) else { continue };
+            for entry in entries {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    std::fs::remove_dir_all(entry.path())?;
+                } else {
+                    std::fs::remove_file(entry.path())?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn list() -> i32 {
+    let Some(trash) = Trash::home() else { return unsupported("--list") };
+    let entries = trash.entries();
+    if entries.is_empty() { outln!("The trash is empty."); return 0; }
+    for entry in &entries {
+        let original = paths::display(&entry.original);
+        // The name only needs showing when it isn't the original's
+        if entry.original.file_name().is_some_and(|n| n.to_string_lossy() == entry.name) {
+            outln!("{}  {}", entry.deleted, original);
+        } else {
+            outln!("{}  {}  (as {})", entry.deleted, original, entry.name);
+        }
+    }
+    0
+}
+
+fn restore(shell: &Shell, wanted: &str) -> i32 {
+    let Some(trash) = Trash::home() else { return unsupported("--restore") };
+    let full = absolute(&shell.cwd.join(wanted)).ok();
+    let entry = trash.entries().into_iter().rev().find(|e| {
+        e.name == wanted
+            || Some(&e.original) == full.as_ref()
+            || e.original.file_name().is_some_and(|n| n.to_string_lossy() == wanted)
+    });
+    let Some(entry) = entry else {
+        errln!("trash: {}: not in the trash (see trash --list)", wanted);
+        return 1;
+    };
+    let shown = paths::display(&entry.original);
+    if entry.original.symlink_metadata().is_ok() {
+        errln!("trash: {}: already exists; move it out of the way first", shown);
+        return 1;
+    }
+    let trashed = trash.files.join(&entry.name);
+    let result = entry.original.parent().map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::rename(&trashed, &entry.original).or_else(|e| match e.kind() {
+            // Trashed from another filesystem, so it goes back by copying
+            std::io::ErrorKind::CrossesDevices => super::fs::move_across(&trashed, &entry.original),
+            _ => Err(e),
+        }));
+    match result {
+        Ok(()) => {
+            std::fs::remove_file(trash.info.join(format!("{}.trashinfo", entry.name))).ok();
+            outln!("restored {}", shown);
+            0
+        }
+        Err(e) => { errln!("trash: {}: {}", shown, e); 1 }
+    }
+}
+
+fn unsupported(what: &str) -> i32 {
+    errln!("trash: {} isn't available here; use the Recycle Bin in Explorer", what);
+    1
+}
+
+/// A trash laid out the XDG way: the trashed files, and beside them a
+/// NAME.trashinfo record of where each came from and when.
+struct Trash {
+    files: PathBuf,
+    info: PathBuf,
+}
+
+/// One trashed file.
+struct Entry {
+    name: String,
+    original: PathBuf,
+    /// When it was trashed, as `YYYY-MM-DD HH:MM:SS`.
+    deleted: String,
+}
+
+impl Trash {
+    fn home() -> Option<Trash> {
+        if cfg!(windows) { return None; }
+        let top = trash_dir()?;
+        if cfg!(target_os = "macos") {
+            return Some(Trash { files: top, info: super::pkg::rshell_dir().join("trash") });
+        }
+        Some(Trash { files: top.join("files"), info: top.join("info") })
+    }
+
+    /// Move `path` in, under its own name or, if that's taken, `name.2`,
+    /// `name.3` ...; returns the name used.
+    #[cfg(not(windows))]
+    fn put(&self, path: &Path) -> std::io::Result<String> {
+        let original = absolute(path)?;
+        std::fs::create_dir_all(&self.files)?;
+        std::fs::create_dir_all(&self.info)?;
+        let base = original.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+
+        let mut n = 1;
+        loop {
+            let name = if n == 1 { base.clone() } else { format!("{}.{}", base, n) };
+            n += 1;
+            if self.files.join(&name).symlink_metadata().is_ok() { continue; }
+            // Creating the record claims the name
+            let record_path = self.info.join(format!("{}.trashinfo", name));
+            let mut record = match std::fs::OpenOptions::new().write(true).create_new(true).open(&record_path) {
+                Ok(f) => f,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+            let (year, month, day, hour, min, sec) = local_datetime(unix_secs(std::time::SystemTime::now()));
+            write!(record, "[Trash Info]\nPath={}\nDeletionDate={}-{:02}-{:02}T{:02}:{:02}:{:02}\n",
+                percent_encode(&paths::display(&original), "/"), year, month, day, hour, min, sec)?;
+
+            let target = self.files.join(&name);
+            let moved = std::fs::rename(path, &target).or_else(|e| match e.kind() {
+                std::io::ErrorKind::CrossesDevices => super::fs::move_across(path, &target),
+                _ => Err(e),
+            });
+            if let Err(e) = moved {
+                std::fs::remove_file(&record_path).ok();
+                return Err(e);
+            }
+            return Ok(name);
+        }
+    }
+
+    /// Everything in the trash with a record, oldest first.
+    fn entries(&self) -> Vec<Entry> {
+        let Ok(records) = std::fs::read_dir(&self.info) else { return Vec::new() };
+        let mut entries: Vec<Entry> = records.flatten().filter_map(|record| {
+            let file_name = record.file_name().to_string_lossy().into_owned();
+            let name = file_name.strip_suffix(".trashinfo")?.to_string();
+            self.files.join(&name).symlink_metadata().ok()?;
+            let text = std::fs::read_to_string(record.path()).ok()?;
+            let (original, deleted) = parse_record(&text)?;
+            Some(Entry { name, original, deleted })
+        }).collect();
+        entries.sort_by(|a, b| a.deleted.cmp(&b.deleted).then_with(|| a.name.cmp(&b.name)));
+        entries
+    }
+}
+
+/// The original path and deletion time in a .trashinfo record.
+fn parse_record(text: &str) -> Option<(PathBuf, String)> {
+    let mut original = None;
+    let mut deleted = String::new();
+    for line in text.lines() {
+        if let Some(path) = line.strip_prefix("Path=") {
+            original = Some(PathBuf::from(String::from_utf8(percent_decode(path)?).ok()?));
+        } else if let Some(date) = line.strip_prefix("DeletionDate=") {
+            deleted = date.replacen('T', " ", 1);
+        }
+    }
+    Some((original?, deleted))
+}
+
+/// `path` made absolute with its directory's symlinks and `..` resolved,
+/// but not the last part: a symlink is trashed as itself.
+fn absolute(path: &Path) -> std::io::Result<PathBuf> {
+    let full = std::path::absolute(path)?;
+    match (full.parent(), full.file_name()) {
+        (Some(parent), Some(name)) => Ok(parent.canonicalize().unwrap_or_else(|_| parent.to_path_buf()).join(name)),
+        _ => Err(std::io::Error::other("can't move that to the trash")),
+    }
+}
+
+#[cfg(windows)]
+fn recycle(path: &Path) -> std::io::Result<()> {
+    let full = absolute(path)?;
+    let method = if full.symlink_metadata()?.is_dir() { "DeleteDirectory" } else { "DeleteFile" };
+    powershell(&format!(
+        "Add-Type -AssemblyName Microsoft.VisualBasic; \
+         [Microsoft.VisualBasic.FileIO.FileSystem]::{}('{}', 'OnlyErrorDialogs', 'SendToRecycleBin')",
+        method, full.display().to_string().replace('\'', "''")))
+}
+
+#[cfg(windows)]
+fn powershell(script: &str) -> std::io::Result<()> {
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .output()?;
+    if output.status.success() { return Ok(()); }
+    Err(std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trashinfo_records() {
+        let text = "[Trash Info]\nPath=/home/me/my%20notes.txt\nDeletionDate=2026-10-16T14:03:12\n";
+        let (original, deleted) = parse_record(text).unwrap();
+        assert_eq!(original, PathBuf::from("/home/me/my notes.txt"));
+        assert_eq!(deleted, "2026-10-16 14:03:12");
+        assert!(parse_record("[Trash Info]\nDeletionDate=2026-10-16T14:03:12\n").is_none());
+    }
+}
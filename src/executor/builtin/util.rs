@@ -1,27 +1,666 @@
 // src/executor/builtin/util.rs
 
-pub fn strip_ansi_len(s: &str) -> usize {
-    let mut len = 0;
+// ── Option parsing ────────────────────────────────────────────────────────────
+//
+// Builtins declare the options they take as an `Options` table, and
+// `Options::parse` does the rest the same way for all of them: clustered
+// flags (`-rf`), values attached or not (`-n10`, `-n 10`, `--lines=10`),
+// options after operands, `--`, errors for anything unknown, and `--help`.
+// The same table drives completion of option names.
+
+/// An option a builtin accepts. `short` lists its letters (most have one;
+/// `rR` makes -r and -R the same option), `long` its --name, and `value`
+/// what its argument is called in help, for options that take one.
+#[derive(Debug, Clone, Copy)]
+pub struct Opt {
+    pub short: &'static str,
+    pub long: &'static str,
+    pub value: &'static str,
+    pub help: &'static str,
+}
+
+impl Opt {
+    pub const fn new(short: &'static str, long: &'static str, help: &'static str) -> Opt {
+        Opt { short, long, value: "", help }
+    }
+
+    /// The option takes an argument, called `value` in help.
+    pub const fn takes(self, value: &'static str) -> Opt {
+        Opt { value, ..self }
+    }
+
+    /// The name it's looked up by: --name if it has one, else its letter.
+    pub fn key(&self) -> &'static str {
+        if !self.long.is_empty() { self.long } else { &self.short[..1] }
+    }
+
+    fn is(&self, name: &str) -> bool {
+        name == self.long || (name.chars().count() == 1 && self.short.contains(name))
+    }
+
+    /// How it's written in help and completion: `-n, --lines N`.
+    pub fn label(&self) -> String {
+        let mut names: Vec<String> = self.short.chars().map(|c| format!("-{}", c)).collect();
+        if !self.long.is_empty() { names.push(format!("--{}", self.long)); }
+        let label = names.join(", ");
+        if self.value.is_empty() { label } else { format!("{} {}", label, self.value) }
+    }
+}
+
+/// The options of one builtin.
+pub struct Options {
+    pub name: &'static str,
+    /// What follows the options in the usage line, e.g. `[FILE]...`.
+    pub operands: &'static str,
+    pub about: &'static str,
+    pub opts: &'static [Opt],
+    /// An option that `-NUM` is short for, as `head -5` is `head -n 5`.
+    pub numeric: Option<&'static str>,
+}
+
+/// The options given to a builtin and its operands, from `Options::parse`.
+pub struct Parsed<'a> {
+    /// Each option given, in order, by key (see `Opt::key`), with its value.
+    pub given: Vec<(&'static str, Option<&'a str>)>,
+    pub operands: Vec<&'a str>,
+    options: &'static Options,
+}
+
+impl<'a> Parsed<'a> {
+    /// Whether the option named by its --name or any of its letters was given.
+    pub fn has(&self, name: &str) -> bool {
+        self.options.find(name).is_some_and(|opt| self.given.iter().any(|(k, _)| *k == opt.key()))
+    }
+
+    /// The last value given for an option.
+    pub fn value(&self, name: &str) -> Option<&'a str> {
+        let key = self.options.find(name)?.key();
+        self.given.iter().rev().find(|(k, _)| *k == key).and_then(|(_, v)| *v)
+    }
+}
+
+impl Options {
+    fn find(&self, name: &str) -> Option<&'static Opt> {
+        self.opts.iter().find(|opt| opt.is(name))
+    }
+
+    /// Parse a builtin's arguments. On a mistake the error and a pointer
+    /// to --help are printed, and on `--help` the help; either way the
+    /// builtin should return the Err status (2 or 0) straight away.
+    pub fn parse<'a>(&'static self, args: &'a [String]) -> Result<Parsed<'a>, i32> {
+        let mut parsed = Parsed { given: Vec::new(), operands: Vec::new(), options: self };
+        let mut words = self::args(args);
+
+        while let Some(arg) = words.next() {
+            let word = match arg {
+                Arg::Operand(word) => { parsed.operands.push(word); continue; }
+                Arg::Option(word) => word,
+            };
+
+            if let Some(long) = word.strip_prefix("--") {
+                let (name, attached) = match long.split_once('=') {
+                    Some((name, value)) => (name, Some(value)),
+                    None => (long, None),
+                };
+                let Some(opt) = self.find(name).filter(|_| name.len() > 1) else {
+                    if name == "help" { self.print_help(); return Err(0); }
+                    return Err(self.usage_error(&format!("unrecognized option '--{}'", name)));
+                };
+                let value = match (opt.value.is_empty(), attached) {
+                    (true, None) => None,
+                    (true, Some(_)) => {
+                        return Err(self.usage_error(&format!("option '--{}' doesn't allow an argument", name)));
+                    }
+                    (false, Some(value)) => Some(value),
+                    (false, None) => match words.value() {
+                        Some(value) => Some(value),
+                        None => return Err(self.usage_error(&format!("option '--{}' requires an argument", name))),
+                    },
+                };
+                parsed.given.push((opt.key(), value));
+                continue;
+            }
+
+            let letters = &word[1..];
+            if let Some(key) = self.numeric.filter(|_| letters.chars().all(|c| c.is_ascii_digit())) {
+                parsed.given.push((key, Some(letters)));
+                continue;
+            }
+            for (i, c) in letters.char_indices() {
+                let Some(opt) = self.opts.iter().find(|opt| opt.short.contains(c)) else {
+                    return Err(self.usage_error(&format!("invalid option -- '{}'", c)));
+                };
+                if opt.value.is_empty() {
+                    parsed.given.push((opt.key(), None));
+                    continue;
+                }
+                // The rest of the word is the value, or else the next word
+                let rest = &letters[i + c.len_utf8()..];
+                let value = if !rest.is_empty() { Some(rest) } else { words.value() };
+                if value.is_none() {
+                    return Err(self.usage_error(&format!("option requires an argument -- '{}'", c)));
+                }
+                parsed.given.push((opt.key(), value));
+                break;
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn usage_error(&self, message: &str) -> i32 {
+        errln!("{}: {}", self.name, message);
+        errln!("Try '{} --help' for more information.", self.name);
+        2
+    }
+
+    pub fn help(&self) -> String {
+        let mut text = format!("usage: {} [OPTION]... {}\n{}\n\n", self.name, self.operands, self.about);
+        let labels: Vec<String> = self.opts.iter().map(Opt::label).collect();
+        let width = labels.iter().map(String::len).max().unwrap_or(0).max(6);
+        for (opt, label) in self.opts.iter().zip(&labels) {
+            text.push_str(&format!("  {:<width$}  {}\n", label, opt.help));
+        }
+        text.push_str(&format!("  {:<width$}  {}\n", "--help", "show this help"));
+        text
+    }
+
+    fn print_help(&self) {
+        out!("{}", self.help());
+    }
+}
+
+/// One word of a builtin's arguments: an option such as `-rf` or `--si`,
+/// or an operand such as a file name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arg<'a> {
+    Option(&'a str),
+    Operand(&'a str),
+}
+
+/// Walks a builtin's arguments (after the command name), telling options
+/// from operands. `--` ends the options and is dropped, so `rm -- -rf`
+/// removes a file called `-rf`; a lone `-` (stdin) is always an operand.
+pub struct Args<'a> {
+    words: std::slice::Iter<'a, String>,
+    options_done: bool,
+}
+
+pub fn args(args: &[String]) -> Args<'_> {
+    Args { words: args.get(1..).unwrap_or_default().iter(), options_done: false }
+}
+
+/// The words after the command name of a builtin that takes no options,
+/// less a `--` before them, so `cd -- -dir` reaches `-dir`.
+pub fn operands(args: &[String]) -> &[String] {
+    match args.get(1) {
+        Some(word) if word == "--" => &args[2..],
+        _ => args.get(1..).unwrap_or_default(),
+    }
+}
+
+impl<'a> Args<'a> {
+    /// The next word, taken whole as the value of the option just seen
+    /// (`-n 5`), whatever it looks like.
+    pub fn value(&mut self) -> Option<&'a str> {
+        self.words.next().map(String::as_str)
+    }
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = Arg<'a>;
+
+    fn next(&mut self) -> Option<Arg<'a>> {
+        let word = self.words.next()?.as_str();
+        if self.options_done || word == "-" || !word.starts_with('-') {
+            return Some(Arg::Operand(word));
+        }
+        if word == "--" {
+            self.options_done = true;
+            return self.next();
+        }
+        Some(Arg::Option(word))
+    }
+}
+
+/// The columns `s` takes up on a terminal: colour codes take none, and
+/// wide characters (CJK, most emoji) two.
+pub fn display_width(s: &str) -> usize {
+    let mut width = 0;
     let mut in_escape = false;
     for ch in s.chars() {
         if ch == '\x1b' { in_escape = true; }
         else if in_escape && ch.is_ascii_alphabetic() { in_escape = false; }
-        else if !in_escape { len += 1; }
+        else if !in_escape { width += unicode_width::UnicodeWidthChar::width(ch).unwrap_or(0); }
+    }
+    width
+}
+
+/// How sizes are shown: powers of 1024 (`1.5K`), powers of 1000 (`1.5k`),
+/// or the exact byte count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeUnits { Binary, Si, Bytes }
+
+impl SizeUnits {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "binary" | "iec" => Some(SizeUnits::Binary),
+            "si"             => Some(SizeUnits::Si),
+            "bytes"          => Some(SizeUnits::Bytes),
+            _                => None,
+        }
+    }
+}
+
+pub fn format_size_as(size: u64, units: SizeUnits, separator: &str) -> String {
+    let (base, suffixes) = match units {
+        SizeUnits::Binary => (1024.0, ["K", "M", "G", "T"]),
+        SizeUnits::Si     => (1000.0, ["k", "M", "G", "T"]),
+        SizeUnits::Bytes  => return group_digits(size, separator),
+    };
+    let mut value  = size as f64;
+    let mut suffix = "B";
+    for s in suffixes {
+        if value < base { break; }
+        value /= base;
+        suffix = s;
+    }
+    if suffix == "B" { format!("{}B", size) } else { format!("{:.1}{}", value, suffix) }
+}
+
+/// `1234567` → `1,234,567` for separator ",". An empty separator leaves
+/// the number ungrouped.
+pub fn group_digits(n: u64, separator: &str) -> String {
+    let digits = n.to_string();
+    digits.as_bytes()
+        .rchunks(3)
+        .rev()
+        .map(|group| std::str::from_utf8(group).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Size and count formatting for one command: units from `--si`/`--bytes`
+/// style flags or `[format] size_units`, and `[format] thousands_separator`
+/// (only applied on a terminal, so piped numbers stay machine-readable).
+#[derive(Debug, Clone)]
+pub struct NumberFormat {
+    pub units: SizeUnits,
+    pub separator: String,
+}
+
+impl NumberFormat {
+    pub fn from_config(shell: &crate::shell::Shell) -> Self {
+        let cfg = &shell.config.format;
+        let units = SizeUnits::parse(&cfg.size_units).unwrap_or_else(|| {
+            errln!("myshell: unknown size_units '{}' in config", cfg.size_units);
+            SizeUnits::Binary
+        });
+        let separator = if super::io::stdout_is_terminal() {
+            cfg.thousands_separator.clone()
+        } else {
+            String::new()
+        };
+        NumberFormat { units, separator }
+    }
+
+    pub fn size(&self, n: u64) -> String {
+        format_size_as(n, self.units, &self.separator)
+    }
+
+    pub fn count(&self, n: u64) -> String {
+        group_digits(n, &self.separator)
+    }
+}
+
+/// Escape `s` for a URL: everything but letters, digits, `-_.~` and the
+/// characters in `keep` becomes %XX.
+pub fn percent_encode(s: &str, keep: &str) -> String {
+    s.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+        _ if b.is_ascii() && keep.contains(b as char) => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+/// Undo %XX escapes; None for a broken one.
+pub fn percent_decode(s: &str) -> Option<Vec<u8>> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(out)
+}
+
+/// A duration the way `stats` and the "took" line show it: 350ms, 4.2s, 3m12s.
+pub fn format_ms(ms: u64) -> String {
+    if ms >= 60_000     { format!("{}m{}s", ms / 60_000, ms % 60_000 / 1000) }
+    else if ms >= 1000  { format!("{:.1}s", ms as f64 / 1000.0) }
+    else                { format!("{}ms", ms) }
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun",
+    "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Format a timestamp the way `ls -l` does: "Oct 16 14:03" for recent
+/// files, "Oct 16  2024" for anything more than six months away.
+pub fn format_mtime(time: std::time::SystemTime) -> String {
+    let secs = unix_secs(time);
+    let now  = unix_secs(std::time::SystemTime::now());
+    let (year, month, day, hour, min, _) = local_datetime(secs);
+    let month = MONTHS[(month - 1) as usize];
+    if (now - secs).abs() > 182 * 86_400 {
+        format!("{} {:>2}  {}", month, day, year)
+    } else {
+        format!("{} {:>2} {:02}:{:02}", month, day, hour, min)
     }
-    len
 }
 
-pub fn format_size(size: u64) -> String {
-    if size >= 1_073_741_824      { format!("{:.1}G", size as f64 / 1_073_741_824.0) }
-    else if size >= 1_048_576     { format!("{:.1}M", size as f64 / 1_048_576.0) }
-    else if size >= 1024          { format!("{:.1}K", size as f64 / 1024.0) }
-    else                          { format!("{}B", size) }
+/// Seconds since the Unix epoch (negative for times before it).
+pub fn unix_secs(time: std::time::SystemTime) -> i64 {
+    match time.duration_since(std::time::UNIX_EPOCH) {
+        Ok(d)  => d.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+/// Break a Unix timestamp into (year, month, day, hour, minute, second) in
+/// local time. Falls back to UTC where the local offset isn't available.
+pub fn local_datetime(secs: i64) -> (i64, u32, u32, u32, u32, u32) {
+    #[cfg(unix)]
+    {
+        let t = secs as libc::time_t;
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        if !unsafe { libc::localtime_r(&t, &mut tm) }.is_null() {
+            return (
+                tm.tm_year as i64 + 1900, tm.tm_mon as u32 + 1, tm.tm_mday as u32,
+                tm.tm_hour as u32, tm.tm_min as u32, tm.tm_sec as u32,
+            );
+        }
+    }
+    utc_datetime(secs)
+}
+
+/// Civil-from-days conversion (proleptic Gregorian calendar, UTC).
+fn utc_datetime(secs: i64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = secs.div_euclid(86_400);
+    let rem  = secs.rem_euclid(86_400);
+    let z    = days + 719_468;
+    let era  = z.div_euclid(146_097);
+    let doe  = z.rem_euclid(146_097);
+    let yoe  = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy  = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp   = (5 * doy + 2) / 153;
+    let day  = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day, (rem / 3600) as u32, (rem % 3600 / 60) as u32, (rem % 60) as u32)
+}
+
+/// Look up a user name by uid, falling back to the numeric id.
+#[cfg(unix)]
+pub fn user_name(uid: u32) -> String {
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 1024];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let rc = unsafe {
+        libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result)
+    };
+    if rc == 0 && !result.is_null() {
+        let name = unsafe { std::ffi::CStr::from_ptr(pwd.pw_name) };
+        return name.to_string_lossy().to_string();
+    }
+    uid.to_string()
+}
+
+/// Look up a group name by gid, falling back to the numeric id.
+#[cfg(unix)]
+pub fn group_name(gid: u32) -> String {
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    let rc = unsafe {
+        libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result)
+    };
+    if rc == 0 && !result.is_null() {
+        let name = unsafe { std::ffi::CStr::from_ptr(grp.gr_name) };
+        return name.to_string_lossy().to_string();
+    }
+    gid.to_string()
+}
+
+/// The uid and login group of the user called `name`.
+#[cfg(unix)]
+pub fn user_id(name: &str) -> Option<(u32, u32)> {
+    let name = std::ffi::CString::new(name).ok()?;
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 1024];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let rc = unsafe {
+        libc::getpwnam_r(name.as_ptr(), &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result)
+    };
+    (rc == 0 && !result.is_null()).then_some((pwd.pw_uid, pwd.pw_gid))
+}
+
+/// The gid of the group called `name`.
+#[cfg(unix)]
+pub fn group_id(name: &str) -> Option<u32> {
+    let name = std::ffi::CString::new(name).ok()?;
+    let mut grp: libc::group = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut result: *mut libc::group = std::ptr::null_mut();
+    let rc = unsafe {
+        libc::getgrnam_r(name.as_ptr(), &mut grp, buf.as_mut_ptr(), buf.len(), &mut result)
+    };
+    (rc == 0 && !result.is_null()).then_some(grp.gr_gid)
+}
+
+/// Render mode bits as `drwxr-xr-x`, with setuid, setgid and sticky bits
+/// as s and t in the execute places. On Windows only the type and the
+/// read-only flag are meaningful.
+pub fn permission_string(meta: &std::fs::Metadata) -> String {
+    let kind = if meta.is_dir() { 'd' } else if meta.file_type().is_symlink() { 'l' } else { '-' };
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+        let t = meta.file_type();
+        let kind = if t.is_char_device() { 'c' } else if t.is_block_device() { 'b' }
+            else if t.is_fifo() { 'p' } else if t.is_socket() { 's' } else { kind };
+        let mode = meta.permissions().mode();
+        let mut s = String::with_capacity(10);
+        s.push(kind);
+        // Each class's special bit and the letter for it
+        for (shift, special, letter) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+            let bits = (mode >> shift) & 7;
+            s.push(if bits & 4 != 0 { 'r' } else { '-' });
+            s.push(if bits & 2 != 0 { 'w' } else { '-' });
+            s.push(match (bits & 1 != 0, mode & special != 0) {
+                (true, true)   => letter,
+                (false, true)  => letter.to_ascii_uppercase(),
+                (true, false)  => 'x',
+                (false, false) => '-',
+            });
+        }
+        s
+    }
+
+    #[cfg(windows)]
+    {
+        let write = if meta.permissions().readonly() { '-' } else { 'w' };
+        format!("{}r{}-r{}-r{}-", kind, write, write, write)
+    }
 }
 
 pub fn color_name(name: &str, is_dir: bool, path: &std::path::Path) -> String {
-    if is_dir { format!("\x1b[34m{}/\x1b[0m", name) }
-    else if is_executable(path) { format!("\x1b[32m{}\x1b[0m", name) }
-    else { name.to_string() }
+    paint_name(name, if is_dir { NameKind::Dir } else { NameKind::of(path) })
+}
+
+/// What a listed name is coloured by.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NameKind { Dir, Link, BrokenLink, Executable, Plain }
+
+impl NameKind {
+    pub fn of(path: &std::path::Path) -> NameKind {
+        match path.symlink_metadata() {
+            Ok(meta) => NameKind::from_meta(&meta, path),
+            Err(_) => NameKind::Plain,
+        }
+    }
+
+    /// From metadata already at hand, only looking further for a link.
+    pub fn from_meta(meta: &std::fs::Metadata, path: &std::path::Path) -> NameKind {
+        if meta.is_dir() { return NameKind::Dir; }
+        if meta.file_type().is_symlink() {
+            return if path.exists() { NameKind::Link } else { NameKind::BrokenLink };
+        }
+        #[cfg(unix)]
+        let executable = {
+            use std::os::unix::fs::PermissionsExt;
+            meta.permissions().mode() & 0o111 != 0
+        };
+        #[cfg(windows)]
+        let executable = is_executable(path);
+        if executable { NameKind::Executable } else { NameKind::Plain }
+    }
+}
+
+pub fn paint_name(name: &str, kind: NameKind) -> String {
+    match kind {
+        NameKind::Dir        => format!("\x1b[34m{}/\x1b[0m", name),
+        // Cyan, or red for a link whose target is missing
+        NameKind::Link       => format!("\x1b[36m{}\x1b[0m", name),
+        NameKind::BrokenLink => format!("\x1b[31m{}\x1b[0m", name),
+        NameKind::Executable => format!("\x1b[32m{}\x1b[0m", name),
+        NameKind::Plain      => name.to_string(),
+    }
+}
+
+/// Threads `stat_batch` spreads its work over. Stats on a network
+/// filesystem spend their time waiting on the server, not the CPU, so
+/// this is more than the core count would suggest.
+const STAT_WORKERS: usize = 16;
+
+/// `stat` applied to each item, in order. A handful run on this thread;
+/// more are spread over a pool of workers, so a directory on NFS or SMB
+/// waits for one round trip per worker's share rather than one per entry.
+pub fn stat_batch<T: Sync, R: Send>(items: &[T], stat: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    if items.len() <= STAT_WORKERS * 2 {
+        return items.iter().map(&stat).collect();
+    }
+    // Each worker takes the next item as it finishes one, so a slow
+    // entry holds up only its own worker
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let mut done: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..STAT_WORKERS).map(|_| scope.spawn(|| {
+            let mut results = Vec::new();
+            loop {
+                let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let Some(item) = items.get(i) else { break };
+                results.push((i, stat(item)));
+            }
+            results
+        })).collect();
+        workers.into_iter().flat_map(|w| w.join().unwrap_or_else(|e| std::panic::resume_unwind(e))).collect()
+    });
+    done.sort_by_key(|(i, _)| *i);
+    done.into_iter().map(|(_, r)| r).collect()
+}
+
+/// A new file `rshell-NAME-PID-N.EXT` in the temp directory, readable
+/// only by us. It must not exist already, so a link planted there isn't
+/// followed.
+pub fn new_temp_file(name: &str, ext: &str) -> std::io::Result<(std::path::PathBuf, std::fs::File)> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut n = 0;
+    loop {
+        let path = std::env::temp_dir().join(format!("rshell-{}-{}-{}.{}", name, std::process::id(), n, ext));
+        match options.open(&path) {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && n < 100 => n += 1,
+            result => return result.map(|file| (path, file)),
+        }
+    }
+}
+
+/// The disk space taken by `path` and everything under it: allocated
+/// blocks on Unix, file lengths elsewhere. Symlinks count as themselves and
+/// aren't followed; a file with several hard links is counted once.
+pub fn disk_usage(path: &std::path::Path) -> u64 {
+    disk_usage_by_dir(path, &mut |_, _, _| {})
+}
+
+/// `disk_usage`, also calling `each_dir` with every directory in the
+/// tree, how deep it is below `path` and its own total — innermost first,
+/// so a directory comes after everything in it, as `du` lists them.
+pub fn disk_usage_by_dir(path: &std::path::Path, each_dir: &mut dyn FnMut(&std::path::Path, usize, u64)) -> u64 {
+    let mut seen = std::collections::HashSet::new();
+    usage_of(path, 0, &mut seen, each_dir)
+}
+
+fn usage_of(
+    path: &std::path::Path,
+    depth: usize,
+    seen: &mut std::collections::HashSet<(u64, u64)>,
+    each_dir: &mut dyn FnMut(&std::path::Path, usize, u64),
+) -> u64 {
+    let Ok(meta) = path.symlink_metadata() else { return 0 };
+    #[cfg(unix)]
+    let size = {
+        use std::os::unix::fs::MetadataExt;
+        if meta.nlink() > 1 && !meta.is_dir() && !seen.insert((meta.dev(), meta.ino())) { return 0; }
+        meta.blocks() * 512
+    };
+    #[cfg(windows)]
+    let size = { let _ = &seen; meta.len() };
+
+    if !meta.is_dir() { return size; }
+    let children = std::fs::read_dir(path).into_iter().flatten().flatten();
+    let total = size + children.map(|entry| usage_of(&entry.path(), depth + 1, seen, each_dir)).sum::<u64>();
+    each_dir(path, depth, total);
+    total
+}
+
+pub fn is_symlink(path: &std::path::Path) -> bool {
+    path.symlink_metadata().map(|m| m.file_type().is_symlink()).unwrap_or(false)
+}
+
+/// The directories a tree walk is inside, so that following a symlink
+/// back into one of them is caught rather than recursing forever.
+#[derive(Default)]
+pub struct LoopGuard {
+    /// (canonical path, path as the walk reached it) of each open directory
+    open: Vec<(std::path::PathBuf, std::path::PathBuf)>,
+}
+
+impl LoopGuard {
+    /// Enter `dir`. If it is already open further up the walk, returns
+    /// the path it was first reached by instead.
+    pub fn enter(&mut self, dir: &std::path::Path) -> Result<(), std::path::PathBuf> {
+        let real = std::fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        if let Some((_, first)) = self.open.iter().find(|(open, _)| *open == real) {
+            return Err(first.clone());
+        }
+        self.open.push((real, dir.to_path_buf()));
+        Ok(())
+    }
+
+    /// Leave the directory entered last.
+    pub fn leave(&mut self) {
+        self.open.pop();
+    }
 }
 
 pub fn is_executable(path: &std::path::Path) -> bool {
@@ -34,35 +673,73 @@ pub fn is_executable(path: &std::path::Path) -> bool {
     }
 }
 
-pub fn command_not_found(cmd: &str) {
-    eprintln!("\x1b[31mmyshell: command not found: {}\x1b[0m", cmd);
-    if let Some(s) = find_closest_command(cmd) {
-        eprintln!("\x1b[33m  did you mean: {}\x1b[0m", s);
+/// Every match for `name` on $PATH, in PATH order. On Windows `name.exe`
+/// is also tried in each directory.
+pub fn find_in_path(name: &str) -> Vec<std::path::PathBuf> {
+    let sep = if cfg!(windows) { ';' } else { ':' };
+    let path_var = std::env::var("PATH").unwrap_or_default();
+    let mut found = Vec::new();
+
+    for dir in path_var.split(sep).filter(|d| !d.is_empty()) {
+        let base = std::path::Path::new(dir).join(name);
+        let candidates = if cfg!(windows) {
+            vec![base.clone(), std::path::Path::new(dir).join(format!("{}.exe", name))]
+        } else {
+            vec![base]
+        };
+        if let Some(hit) = candidates.into_iter().find(|c| c.is_file()) {
+            found.push(hit);
+        }
     }
+    found
 }
 
-fn find_closest_command(cmd: &str) -> Option<String> {
-    let path_var = std::env::var("PATH").unwrap_or_default();
-    let mut best: Option<(String, usize)> = None;
-    let builtins = vec![
-        "cd","pwd","echo","export","unset","alias","unalias","history",
-        "source","help","jobs","fg","bg","kill","clear","exit","ls",
-        "true","false","test","functions",
-    ];
-    let mut candidates: Vec<String> = builtins.iter().map(|s| s.to_string()).collect();
-    for dir in path_var.split(':') {
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                candidates.push(entry.file_name().to_string_lossy().to_string());
-            }
-        }
+pub fn command_not_found(shell: &mut crate::shell::Shell, cmd: &str) {
+    errln!("\x1b[31mmyshell: command not found: {}\x1b[0m", cmd);
+    if let Some(s) = find_closest_command(cmd, shell) {
+        errln!("\x1b[33m  did you mean: {}\x1b[0m", s);
     }
-    for candidate in &candidates {
+}
+
+/// What the user made of an offer to correct a command name.
+pub enum Correction {
+    /// Nothing close enough to offer, or no terminal to ask on.
+    None,
+    Accepted(String),
+    Declined,
+}
+
+/// `set -o autocorrect`: for a command that doesn't exist, ask whether to
+/// run the closest one instead, fish-style.
+pub fn offer_correction(shell: &mut crate::shell::Shell, args: &[String]) -> Correction {
+    use std::io::{BufRead, IsTerminal, Write};
+    if !std::io::stdin().is_terminal() { return Correction::None; }
+    let Some(fixed) = find_closest_command(&args[0], shell) else { return Correction::None };
+
+    let mut line = vec![fixed.as_str()];
+    line.extend(args[1..].iter().map(String::as_str));
+    crossterm::terminal::disable_raw_mode().ok();
+    err!("\x1b[33mmyshell: {}: command not found; run '{}' instead? [y/N]\x1b[0m ", args[0], line.join(" "));
+    std::io::stderr().flush().ok();
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer).ok();
+    if matches!(answer.trim(), "y" | "Y" | "yes") { Correction::Accepted(fixed) } else { Correction::Declined }
+}
+
+/// The closest name to `cmd` among those which would find: aliases,
+/// functions, builtins and commands on PATH.
+fn find_closest_command(cmd: &str, shell: &mut crate::shell::Shell) -> Option<String> {
+    let mut best: Option<(String, usize)> = None;
+    let defined: Vec<String> = shell.aliases.keys().chain(shell.functions.keys()).cloned().collect();
+    let candidates = defined.iter().map(String::as_str)
+        .chain(crate::completion::builtin_names().iter().copied())
+        .chain(shell.path_commands().iter().map(String::as_str));
+    for candidate in candidates {
         let dist = levenshtein(cmd, candidate);
         if dist <= 3 {
             match &best {
-                None => best = Some((candidate.clone(), dist)),
-                Some((_, d)) if dist < *d => best = Some((candidate.clone(), dist)),
+                None => best = Some((candidate.to_string(), dist)),
+                Some((_, d)) if dist < *d => best = Some((candidate.to_string(), dist)),
                 _ => {}
             }
         }
@@ -84,4 +761,71 @@ fn levenshtein(a: &str, b: &str) -> usize {
         }
     }
     dp[m][n]
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEMO: Options = Options {
+        name: "demo",
+        operands: "[FILE]...",
+        about: "A demonstration.",
+        opts: &[
+            Opt::new("rR", "recursive", "recurse"),
+            Opt::new("f", "", "force"),
+            Opt::new("n", "lines", "count").takes("N"),
+            Opt::new("", "si", "powers of 1000"),
+        ],
+        numeric: Some("lines"),
+    };
+
+    fn words(list: &[&str]) -> Vec<String> {
+        std::iter::once("demo").chain(list.iter().copied()).map(String::from).collect()
+    }
+
+    #[test]
+    fn test_options_parse() {
+        let args = words(&["-Rf", "a", "--si", "-n10", "--", "-b"]);
+        let p = DEMO.parse(&args).ok().unwrap();
+        assert!(p.has("r") && p.has("recursive") && p.has("f") && p.has("si"));
+        assert_eq!(p.value("n"), Some("10"));
+        assert_eq!(p.operands, ["a", "-b"]);
+
+        for list in [&["-n", "3"][..], &["--lines=3"], &["--lines", "3"], &["-3"], &["-fn3"]] {
+            let args = words(list);
+            assert_eq!(DEMO.parse(&args).ok().unwrap().value("lines"), Some("3"), "{:?}", list);
+        }
+
+        assert_eq!(DEMO.parse(&words(&["-x"])).err(), Some(2));
+        assert_eq!(DEMO.parse(&words(&["--bogus"])).err(), Some(2));
+        assert_eq!(DEMO.parse(&words(&["-n"])).err(), Some(2));
+        assert_eq!(DEMO.parse(&words(&["--si=1"])).err(), Some(2));
+        assert!(DEMO.help().contains("  -r, -R, --recursive  recurse"));
+    }
+
+    #[test]
+    fn test_args_end_of_options() {
+        let words: Vec<String> = ["rm", "-rf", "-", "--", "-n", "--", "x"].map(String::from).into();
+        assert_eq!(args(&words).collect::<Vec<_>>(), [
+            Arg::Option("-rf"), Arg::Operand("-"), Arg::Operand("-n"), Arg::Operand("--"), Arg::Operand("x"),
+        ]);
+
+        let words: Vec<String> = ["head", "-n", "-5", "f"].map(String::from).into();
+        let mut it = args(&words);
+        assert_eq!(it.next(), Some(Arg::Option("-n")));
+        assert_eq!(it.value(), Some("-5"));
+        assert_eq!(it.next(), Some(Arg::Operand("f")));
+    }
+
+    #[test]
+    fn test_size_units() {
+        assert_eq!(format_size_as(512, SizeUnits::Binary, ""), "512B");
+        assert_eq!(format_size_as(1536, SizeUnits::Binary, ""), "1.5K");
+        assert_eq!(format_size_as(1500, SizeUnits::Si, ""), "1.5k");
+        assert_eq!(format_size_as(2_500_000, SizeUnits::Si, ""), "2.5M");
+        assert_eq!(format_size_as(1234567, SizeUnits::Bytes, ","), "1,234,567");
+        assert_eq!(group_digits(999, ","), "999");
+        assert_eq!(group_digits(1000, "."), "1.000");
+        assert_eq!(group_digits(1000, ""), "1000");
+    }
+}
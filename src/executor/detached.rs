@@ -0,0 +1,137 @@
+// src/executor/detached.rs
+//
+// Background pipelines, blocks, builtins and functions. These run inside
+// the shell, so `cmd &` starts a new copy of it, `rshell --job FILE`, and
+// hands it what the command can see: variables, functions, aliases,
+// options and the directory, with the command itself as already parsed
+// (and, for a builtin or function, already expanded). Like a subshell,
+// the copy can't change the interactive shell.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::parser::ast::{Command, Redirect};
+use crate::shell::{Shell, ShellFunction};
+
+/// What a background copy of the shell runs.
+#[derive(Serialize, Deserialize)]
+pub enum Work {
+    /// A command still to be expanded, as `run` takes it
+    Run(Command),
+    /// An expanded builtin or function call, as `dispatch` takes it
+    Dispatch { args: Vec<String>, redirects: Vec<Redirect>, bypass: bool },
+}
+
+impl Work {
+    pub fn run(self, shell: &mut Shell) -> anyhow::Result<i32> {
+        match self {
+            Work::Run(cmd) => super::run(shell, cmd),
+            Work::Dispatch { args, redirects, bypass } => super::dispatch(shell, args, redirects, false, bypass),
+        }
+    }
+}
+
+/// The handover file: the shell's state and the work to do in it.
+#[derive(Serialize, Deserialize)]
+struct Job {
+    work: Work,
+    cwd: PathBuf,
+    env: HashMap<String, String>,
+    exported: HashSet<String>,
+    readonly: HashSet<String>,
+    aliases: HashMap<String, String>,
+    functions: HashMap<String, ShellFunction>,
+    named_dirs: HashMap<String, PathBuf>,
+    last_exit_code: i32,
+    exit_on_error: bool,
+    posix: bool,
+    pipefail: bool,
+}
+
+/// Start `work` in a new copy of the shell and record it as a job.
+pub fn spawn(shell: &mut Shell, text: String, work: Work) -> anyhow::Result<i32> {
+    use std::io::Write;
+    // Keep what's already been printed ahead of the job's output
+    super::builtin::io::stdout().flush().ok();
+    std::io::stdout().flush().ok();
+
+    let job = Job {
+        work,
+        cwd: shell.cwd.clone(),
+        env: shell.env.clone(),
+        exported: shell.exported.clone(),
+        readonly: shell.readonly.clone(),
+        aliases: shell.aliases.clone(),
+        functions: shell.functions.clone(),
+        named_dirs: shell.named_dirs.clone(),
+        last_exit_code: shell.last_exit_code,
+        exit_on_error: shell.exit_on_error,
+        posix: shell.posix,
+        pipefail: shell.pipefail,
+    };
+    let (path, file) = match super::builtin::new_temp_file("job", "json") {
+        Ok(created) => created,
+        Err(e) => { errln!("myshell: {}: {}", std::env::temp_dir().display(), e); return Ok(1); }
+    };
+    if let Err(e) = serde_json::to_writer(std::io::BufWriter::new(file), &job) {
+        errln!("myshell: {}: {}", path.display(), e);
+        std::fs::remove_file(&path).ok();
+        return Ok(1);
+    }
+
+    let exe = std::env::current_exe()?;
+    let mut cmd = std::process::Command::new(exe);
+    cmd.arg("--job").arg(&path).current_dir(&shell.cwd).env_clear().envs(shell.child_env());
+    super::jobctl::setup_child(&mut cmd, None, false, true);
+    // Like any background job, ignore Ctrl+C meant for the foreground
+    #[cfg(unix)]
+    unsafe {
+        use std::os::unix::process::CommandExt;
+        cmd.pre_exec(|| { libc::signal(libc::SIGINT, libc::SIG_IGN); Ok(()) });
+    }
+    match cmd.spawn() {
+        Ok(child) => {
+            let pid = child.id();
+            shell.add_job(pid, child, text);
+            shell.last_bg_pid = Some(pid);
+            println!("[bg] pid {}", pid);
+            Ok(0)
+        }
+        Err(e) => {
+            std::fs::remove_file(&path).ok();
+            errln!("myshell: {}: {}", text, e);
+            Ok(1)
+        }
+    }
+}
+
+/// `rshell --job FILE`: take over the job in FILE, run it and return its
+/// status.
+pub fn run(path: &Path) -> i32 {
+    let job = std::fs::read(path).map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_slice::<Job>(&data).map_err(|e| e.to_string()));
+    std::fs::remove_file(path).ok();
+    let job = match job {
+        Ok(job) => job,
+        Err(e) => { eprintln!("myshell: {}: {}", path.display(), e); return 1; }
+    };
+
+    let mut shell = Shell::new();
+    shell.cwd = job.cwd;
+    shell.env = job.env;
+    shell.exported = job.exported;
+    shell.readonly = job.readonly;
+    shell.aliases = job.aliases;
+    shell.functions = job.functions;
+    shell.named_dirs = job.named_dirs;
+    shell.last_exit_code = job.last_exit_code;
+    shell.exit_on_error = job.exit_on_error;
+    shell.posix = job.posix;
+    shell.pipefail = job.pipefail;
+
+    let code = job.work.run(&mut shell).unwrap_or_else(|e| { eprintln!("myshell: {e}"); 1 });
+    use std::io::Write;
+    super::builtin::io::stdout().flush().ok();
+    std::io::stdout().flush().ok();
+    code
+}
@@ -23,7 +23,7 @@ pub fn expand_arithmetic(shell: &Shell, s: &str) -> String {
             match eval_arithmetic(&expr) {
                 Ok(val)  => result.push_str(&val.to_string()),
                 Err(e)   => {
-                    eprintln!("myshell: arithmetic: {}", e);
+                    errln!("myshell: arithmetic: {}", e);
                     result.push_str("0");
                 }
             }
@@ -39,7 +39,7 @@ pub fn expand_arithmetic(shell: &Shell, s: &str) -> String {
     result
 }
 
-/// Expand all $VAR, ${VAR}, $?, $@, $#, $*, $$, and $(cmd) references in a string.
+/// Expand all $VAR, ${VAR}, $?, $@, $#, $*, $$, $!, and $(cmd) references in a string.
 pub fn expand_vars(shell: &Shell, s: &str) -> String {
     // First handle command substitution $(...) — must be done before char-by-char pass
     let s = expand_command_substitution(shell, s);
@@ -76,6 +76,12 @@ pub fn expand_vars(shell: &Shell, s: &str) -> String {
                 result.push_str(&std::process::id().to_string());
             }
 
+            // $! — pid of the last background job, empty before there is one
+            Some(&'!') => {
+                chars.next();
+                result.push_str(&lookup_var(shell, "!"));
+            }
+
             // $# — number of positional args
             Some(&'#') => {
                 chars.next();
@@ -116,6 +122,151 @@ pub fn expand_vars(shell: &Shell, s: &str) -> String {
     result
 }
 
+// ── POSIX word expansion ──────────────────────────────────────────────────────
+
+/// One field being built by `expand_words`.
+#[derive(Default)]
+struct Field {
+    text: String,
+    /// Contains an unquoted *, ? or [ and is subject to pathname expansion
+    glob: bool,
+    /// Non-empty or quoted, so it survives even if `text` is empty
+    keep: bool,
+}
+
+/// Expand raw words (as produced by the tokenizer in posix mode) in POSIX
+/// order: tilde, parameter/command/arithmetic expansion, field splitting
+/// of unquoted results on whitespace, pathname expansion, quote removal.
+pub fn expand_words(shell: &Shell, words: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    for word in words {
+        for field in expand_fields(shell, word, true) {
+            if field.glob {
+                out.extend(crate::glob::expand(&field.text));
+            } else {
+                out.push(field.text);
+            }
+        }
+    }
+    out
+}
+
+/// Expand the value of a `NAME=value` assignment: like a word, but with
+/// no field splitting or pathname expansion.
+pub fn expand_assignment(shell: &Shell, value: &str) -> String {
+    expand_fields(shell, value, false).into_iter().map(|f| f.text).collect()
+}
+
+fn expand_fields(shell: &Shell, word: &str, split: bool) -> Vec<Field> {
+    let mut fields = Vec::new();
+    let mut field  = Field::default();
+    let mut chars  = expand_tilde_prefix(shell, word).chars().collect::<Vec<_>>().into_iter().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                field.keep = true;
+                for ch in chars.by_ref() {
+                    if ch == '\'' { break; }
+                    field.text.push(ch);
+                }
+            }
+            '"' => {
+                field.keep = true;
+                while let Some(ch) = chars.next() {
+                    match ch {
+                        '"' => break,
+                        '\\' => match chars.peek() {
+                            Some(&n) if matches!(n, '$' | '`' | '"' | '\\') => {
+                                field.text.push(n);
+                                chars.next();
+                            }
+                            _ => field.text.push('\\'),
+                        },
+                        '$' => field.text.push_str(&expand_dollar(shell, &mut chars)),
+                        _ => field.text.push(ch),
+                    }
+                }
+            }
+            '\\' => {
+                if let Some(n) = chars.next() { field.text.push(n); field.keep = true; }
+            }
+            '$' => {
+                // Unquoted results are split into fields on whitespace
+                for ch in expand_dollar(shell, &mut chars).chars() {
+                    if split && ch.is_whitespace() {
+                        if field.keep || !field.text.is_empty() {
+                            fields.push(std::mem::take(&mut field));
+                        }
+                    } else {
+                        field.glob |= matches!(ch, '*' | '?' | '[');
+                        field.text.push(ch);
+                    }
+                }
+            }
+            _ => {
+                field.glob |= matches!(c, '*' | '?' | '[');
+                field.text.push(c);
+            }
+        }
+    }
+    if field.keep || !field.text.is_empty() { fields.push(field); }
+    fields
+}
+
+/// Replace a leading unquoted `~` or `~/` with $HOME, and `~name` with
+/// a named directory (`hash -d`).
+fn expand_tilde_prefix(shell: &Shell, word: &str) -> String {
+    if word == "~" || word.starts_with("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return format!("{}{}", home.display(), &word[1..]);
+        }
+    }
+    shell.expand_named_dir(word)
+}
+
+/// Expand the construct following a `$` (already consumed): $((expr)),
+/// $(cmd), ${VAR}, $VAR or a special parameter. Reuses the string-based
+/// expanders on just that piece so both modes agree on the results.
+fn expand_dollar<I: Iterator<Item = char>>(shell: &Shell, chars: &mut std::iter::Peekable<I>) -> String {
+    let mut piece = String::from("$");
+    match chars.peek() {
+        Some('(') => {
+            let mut depth = 0;
+            for ch in chars.by_ref() {
+                piece.push(ch);
+                match ch {
+                    '(' => depth += 1,
+                    ')' => { depth -= 1; if depth == 0 { break; } }
+                    _ => {}
+                }
+            }
+            if piece.starts_with("$((") {
+                return expand_arithmetic(shell, &piece);
+            }
+        }
+        Some('{') => {
+            for ch in chars.by_ref() {
+                piece.push(ch);
+                if ch == '}' { break; }
+            }
+        }
+        Some(&ch) if matches!(ch, '?' | '$' | '!' | '#' | '@' | '*') => {
+            piece.push(ch);
+            chars.next();
+        }
+        Some(&ch) if ch.is_alphanumeric() || ch == '_' => {
+            while let Some(&ch) = chars.peek() {
+                if !(ch.is_alphanumeric() || ch == '_') { break; }
+                piece.push(ch);
+                chars.next();
+            }
+        }
+        _ => return piece,
+    }
+    expand_vars(shell, &piece)
+}
+
 /// Expand $(command) substitutions by running the command and capturing output.
 fn expand_command_substitution(shell: &Shell, s: &str) -> String {
     let mut result = String::new();
@@ -162,7 +313,8 @@ fn expand_command_substitution(shell: &Shell, s: &str) -> String {
         // Run the command and capture stdout
         let output = std::process::Command::new(if cfg!(windows) { "cmd" } else { "sh" })
             .args(if cfg!(windows) { vec!["/C", cmd_str] } else { vec!["-c", cmd_str] })
-            .envs(&shell.env)
+            .env_clear()
+            .envs(shell.child_env())
             .output();
 
         match output {
@@ -181,11 +333,40 @@ fn expand_command_substitution(shell: &Shell, s: &str) -> String {
 // ── Private helpers ───────────────────────────────────────────────────────────
 
 fn lookup_var(shell: &Shell, name: &str) -> String {
+    if let Some(value) = lookup_pipe_status(shell, name) { return value; }
+    if name == "!" { return shell.last_bg_pid.map(|pid| pid.to_string()).unwrap_or_default(); }
+    if name == "CMD_DURATION" {
+        return shell.last_duration.map(|d| d.as_millis().to_string()).unwrap_or_default();
+    }
     shell.env.get(name).cloned()
         .or_else(|| std::env::var(name).ok())
         .unwrap_or_default()
 }
 
+/// PIPESTATUS, the shell's one array: `${PIPESTATUS[N]}` is a stage's
+/// status, `${PIPESTATUS[@]}` all of them, `${#PIPESTATUS[@]}` how many,
+/// and a bare `$PIPESTATUS` the first.
+fn lookup_pipe_status(shell: &Shell, name: &str) -> Option<String> {
+    let (count, name) = match name.strip_prefix('#') {
+        Some(rest) => (true, rest),
+        None => (false, name),
+    };
+    let (base, index) = match name.split_once('[') {
+        Some((base, rest)) => (base, Some(rest.strip_suffix(']')?)),
+        None => (name, None),
+    };
+    if base != "PIPESTATUS" { return None; }
+
+    let codes = &shell.pipe_status;
+    let code = |i: usize| codes.get(i).map(|c| c.to_string()).unwrap_or_default();
+    Some(match index {
+        Some("@" | "*") if count => codes.len().to_string(),
+        Some("@" | "*") => codes.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" "),
+        Some(i) => i.trim().parse().map(code).unwrap_or_default(),
+        None => code(0),
+    })
+}
+
 fn eval_arithmetic(expr: &str) -> Result<i64> {
     parse_additive(expr.trim()).map(|(v, _)| v)
 }
@@ -0,0 +1,171 @@
+// src/executor/jobctl.rs
+//
+// Job control. Each foreground job runs in a process group of its own,
+// which is handed the terminal while it runs, so Ctrl+C and Ctrl+Z reach
+// the job and never the shell. Afterwards the shell takes the terminal
+// back and restores its own terminal modes, in case the job left them
+// changed (an editor killed mid-session, say). Background jobs get a
+// group too, so `kill %N` and `fg` can signal every process in them.
+//
+// When the terminal hangs up the shell passes SIGHUP on to its jobs before
+// exiting, except those `disown` has taken out of its hands.
+//
+// Only an interactive shell on a terminal does this; with `-c`, or on
+// Windows, children share the shell's process group as they always have.
+
+use std::process::{Child, Command as Proc, ExitStatus};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The jobs to hang up when the terminal does, by pid (0 for an empty
+/// slot). Atomics in a fixed table, since the SIGHUP handler can neither
+/// lock nor allocate.
+static HANGUP: [AtomicI32; 64] = [const { AtomicI32::new(0) }; 64];
+
+/// The shell's terminal modes, restored whenever it takes the terminal back.
+#[cfg(unix)]
+static SHELL_MODES: std::sync::Mutex<Option<libc::termios>> = std::sync::Mutex::new(None);
+
+/// How a foreground job came back to the shell.
+pub enum Wait {
+    Exited(ExitStatus),
+    /// Stopped by Ctrl+Z; it's still there to be resumed.
+    Stopped,
+}
+
+/// Turn job control on, if stdin is a terminal: make the shell a process
+/// group of its own in the terminal's foreground, and save its modes.
+pub fn enable() {
+    #[cfg(unix)]
+    unsafe {
+        if libc::isatty(0) == 0 { return; }
+        // Fails harmlessly if the shell already leads a session
+        libc::setpgid(0, 0);
+        libc::tcsetpgrp(0, libc::getpgrp());
+        // Ctrl+Z is for jobs; the shell itself never stops
+        libc::signal(libc::SIGTSTP, libc::SIG_IGN);
+        libc::signal(libc::SIGHUP, on_hangup as *const () as libc::sighandler_t);
+        let mut modes: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(0, &mut modes) == 0 {
+            *SHELL_MODES.lock().unwrap_or_else(|e| e.into_inner()) = Some(modes);
+        }
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Set the jobs to pass a hangup on to; any beyond the table's size are
+/// left alone.
+pub fn set_hangup_jobs(pids: impl IntoIterator<Item = u32>) {
+    let mut pids = pids.into_iter();
+    for slot in &HANGUP {
+        slot.store(pids.next().map_or(0, |pid| pid as i32), Ordering::Relaxed);
+    }
+}
+
+/// The terminal is gone: hang up the jobs, waking any that are stopped so
+/// they see it, and exit as SIGHUP would have made the shell.
+#[cfg(unix)]
+extern "C" fn on_hangup(_: libc::c_int) {
+    for slot in &HANGUP {
+        let pid = slot.load(Ordering::Relaxed);
+        if pid <= 0 { continue; }
+        for sig in [libc::SIGHUP, libc::SIGCONT] {
+            unsafe {
+                if libc::kill(-pid, sig) != 0 { libc::kill(pid, sig); }
+            }
+        }
+    }
+    unsafe { libc::_exit(128 + libc::SIGHUP); }
+}
+
+/// Put a child in process group `pgid`, or a new group it leads. A
+/// foreground child also takes the terminal itself, so it never tries to
+/// read before the shell has handed it over. Unless `stoppable`, Ctrl+Z
+/// stays ignored, for jobs the shell couldn't take back once stopped.
+#[cfg_attr(windows, allow(unused_variables))]
+pub fn setup_child(cmd: &mut Proc, pgid: Option<u32>, foreground: bool, stoppable: bool) {
+    if !enabled() { return; }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(pgid.unwrap_or(0) as i32);
+        let signals: &[i32] = if stoppable {
+            &[libc::SIGINT, libc::SIGQUIT, libc::SIGTSTP, libc::SIGTTIN, libc::SIGTTOU]
+        } else {
+            &[libc::SIGINT, libc::SIGQUIT, libc::SIGTTIN, libc::SIGTTOU]
+        };
+        // Only async-signal-safe calls between fork and exec
+        unsafe {
+            cmd.pre_exec(move || {
+                if foreground { libc::tcsetpgrp(0, libc::getpgrp()); }
+                for &sig in signals { libc::signal(sig, libc::SIG_DFL); }
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Hand the terminal to the foreground job's process group.
+#[cfg_attr(windows, allow(unused_variables))]
+pub fn give_terminal(pgid: u32) {
+    if !enabled() { return; }
+    #[cfg(unix)]
+    unsafe { libc::tcsetpgrp(0, pgid as i32); }
+}
+
+/// Take the terminal back after a foreground job and restore the shell's
+/// terminal modes.
+pub fn take_terminal() {
+    if !enabled() { return; }
+    #[cfg(unix)]
+    unsafe {
+        libc::tcsetpgrp(0, libc::getpgrp());
+        if let Some(modes) = *SHELL_MODES.lock().unwrap_or_else(|e| e.into_inner()) {
+            libc::tcsetattr(0, libc::TCSADRAIN, &modes);
+        }
+    }
+}
+
+/// Send `sig` to a job: its whole process group under job control,
+/// otherwise just the process.
+#[cfg(unix)]
+pub fn signal_job(pid: u32, sig: i32) -> std::io::Result<()> {
+    let target = if enabled() { -(pid as i32) } else { pid as i32 };
+    unsafe {
+        if libc::kill(target, sig) == 0 { return Ok(()); }
+        // Started before job control, so not a group leader
+        if target < 0 && libc::kill(pid as i32, sig) == 0 { return Ok(()); }
+    }
+    Err(std::io::Error::last_os_error())
+}
+
+/// Wait for a foreground child, noticing if Ctrl+Z stops it.
+pub fn wait_foreground(child: &mut Child) -> std::io::Result<Wait> {
+    #[cfg(unix)]
+    {
+        wait_pid(child.id())
+    }
+    #[cfg(windows)]
+    {
+        child.wait().map(Wait::Exited)
+    }
+}
+
+/// `wait_foreground` for a job known only by its pid.
+#[cfg(unix)]
+pub fn wait_pid(pid: u32) -> std::io::Result<Wait> {
+    use std::os::unix::process::ExitStatusExt;
+    let mut raw = 0;
+    loop {
+        if unsafe { libc::waitpid(pid as i32, &mut raw, libc::WUNTRACED) } >= 0 { break; }
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted { return Err(err); }
+    }
+    if libc::WIFSTOPPED(raw) { return Ok(Wait::Stopped); }
+    Ok(Wait::Exited(ExitStatus::from_raw(raw)))
+}
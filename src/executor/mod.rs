@@ -3,21 +3,30 @@
 // Top-level executor — dispatches parsed AST nodes to the appropriate
 // handler. The heavy lifting lives in submodules:
 //
+//   detached.rs — background blocks, builtins and functions
 //   expand.rs   — variable and arithmetic expansion
+//   jobctl.rs   — process groups and the terminal for foreground jobs
 //   pipeline.rs — pipe-connected command sequences
+//   script.rs   — shebang / PATHEXT dispatch for scripts
 
+#[macro_use]
 pub mod builtin;
+pub mod detached;
 mod expand;
+pub mod jobctl;
 mod pipeline;
+mod script;
 
 use crate::parser::ast::{Command, Redirect};
 use crate::shell::Shell;
 use anyhow::Result;
-use std::fs::OpenOptions;
-use std::process::{Command as Proc, Stdio};
+use std::fs::{File, OpenOptions};
+use std::path::PathBuf;
+use std::process::Command as Proc;
 
 // Re-export the expand functions that other modules need
-pub use expand::{expand_arithmetic, expand_vars};
+pub use expand::{expand_arithmetic, expand_vars, expand_words};
+pub use pipeline::is_builtin_cmd;
 
 // ── Public API ────────────────────────────────────────────────────────────────
 
@@ -31,26 +40,35 @@ pub fn execute(shell: &mut Shell, cmd: Command) -> Result<()> {
 
 pub fn run(shell: &mut Shell, cmd: Command) -> Result<i32> {
     match cmd {
-        Command::Simple { args, redirects, background } => {
-            run_simple(shell, args, redirects, background)
+        Command::Simple { assigns, args, redirects } => {
+            let code = run_assigned(shell, assigns, args, redirects, false)?;
+            shell.pipe_status = vec![code];
+            Ok(code)
         }
 
         Command::Pipeline(cmds) => {
             pipeline::run_pipeline(shell, cmds)
         }
 
+        Command::Background(cmd) => {
+            run_background(shell, *cmd)
+        }
+
+        // $? on the right-hand side must see the left-hand side's status
         Command::And(left, right) => {
             let code = run(shell, *left)?;
+            shell.last_exit_code = code;
             if code == 0 { run(shell, *right) } else { Ok(code) }
         }
 
         Command::Or(left, right) => {
             let code = run(shell, *left)?;
+            shell.last_exit_code = code;
             if code != 0 { run(shell, *right) } else { Ok(code) }
         }
 
         Command::Sequence(left, right) => {
-            run(shell, *left)?;
+            shell.last_exit_code = run(shell, *left)?;
             run(shell, *right)
         }
 
@@ -68,16 +86,19 @@ pub fn run(shell: &mut Shell, cmd: Command) -> Result<i32> {
         Command::For { var, items, body } => {
             let mut last_code = 0;
             // Expand vars then globs in each item, flattening glob matches
-            let expanded_items: Vec<String> = items
-                .iter()
-                .flat_map(|item| {
-                    let item = expand_vars(shell, item);
-                    crate::glob::expand(&item)
-                })
-                .collect();
+            let expanded_items: Vec<String> = if shell.posix {
+                expand_words(shell, &items)
+            } else {
+                items
+                    .iter()
+                    .flat_map(|item| {
+                        let item = expand_vars(shell, item);
+                        crate::glob::expand(&item)
+                    })
+                    .collect()
+            };
             for item in expanded_items {
-                shell.env.insert(var.clone(), item.clone());
-                unsafe { std::env::set_var(&var, &item); }
+                if !shell.set_var(&var, &item) { return Ok(1); }
                 last_code = run_block(shell, body.clone())?;
             }
             Ok(last_code)
@@ -93,12 +114,9 @@ pub fn run(shell: &mut Shell, cmd: Command) -> Result<i32> {
             Ok(last_code)
         }
 
-        Command::FunctionDef { name, body } => {
-            shell.functions.insert(
-                name.clone(),
-                crate::shell::ShellFunction { body },
-            );
-            shell.save_functions();
+        Command::FunctionDef { name, body, source } => {
+            shell.functions.insert(name, crate::shell::ShellFunction { body, source });
+            if !shell.loading_rc { shell.save_functions(); }
             Ok(0)
         }
 
@@ -108,6 +126,52 @@ pub fn run(shell: &mut Shell, cmd: Command) -> Result<i32> {
     }
 }
 
+/// A simple command with its leading `NAME=value` assignments.
+fn run_assigned(
+    shell: &mut Shell,
+    assigns: Vec<(String, String)>,
+    args: Vec<String>,
+    redirects: Vec<Redirect>,
+    background: bool,
+) -> Result<i32> {
+    let assigns = expand_assigns(shell, assigns);
+
+    // `FOO=bar` on its own sets a shell variable; before a command
+    // it only applies to that command's environment
+    if args.is_empty() {
+        let ok = assigns.iter().all(|(k, v)| shell.set_var(k, v));
+        return Ok(if ok { 0 } else { 1 });
+    }
+    shell.with_temp_vars(&assigns, |shell| run_simple(shell, args, redirects, background))
+}
+
+/// Expand the values of `NAME=value` words.
+fn expand_assigns(shell: &mut Shell, assigns: Vec<(String, String)>) -> Vec<(String, String)> {
+    assigns.into_iter()
+        .map(|(k, v)| {
+            let v = if shell.posix {
+                expand::expand_assignment(shell, &v)
+            } else {
+                expand_vars(shell, &expand_arithmetic(shell, &v))
+            };
+            (k, v)
+        })
+        .collect()
+}
+
+// ── Background jobs ───────────────────────────────────────────────────────────
+
+/// Run `cmd &`. An external command is spawned directly; a pipeline,
+/// block, builtin or function runs in a new copy of the shell, so it sees
+/// the shell's variables and functions but can't change them.
+fn run_background(shell: &mut Shell, cmd: Command) -> Result<i32> {
+    if let Command::Simple { assigns, args, redirects } = cmd {
+        return run_assigned(shell, assigns, args, redirects, true);
+    }
+    let text = cmd.to_string();
+    detached::spawn(shell, text, detached::Work::Run(cmd))
+}
+
 // ── Block / function execution ────────────────────────────────────────────────
 
 fn run_block(shell: &mut Shell, cmds: Vec<Command>) -> Result<i32> {
@@ -122,29 +186,31 @@ fn run_block(shell: &mut Shell, cmds: Vec<Command>) -> Result<i32> {
     Ok(last_code)
 }
 
+/// Call the shell function `name` with `args`, as for a hook.
+pub fn call_function(shell: &mut Shell, name: &str, args: &[String]) -> Result<i32> {
+    run_function(shell, name, args)
+}
+
 fn run_function(shell: &mut Shell, name: &str, args: &[String]) -> Result<i32> {
     let func = match shell.functions.get(name).cloned() {
         Some(f) => f,
-        None    => { builtin::command_not_found(name); return Ok(127); }
+        None    => { builtin::command_not_found(shell, name); return Ok(127); }
     };
 
     // Save and set positional parameters $1..$9
     let saved_args = save_positional_args(shell);
     for (i, arg) in args.iter().enumerate() {
-        let key = (i + 1).to_string();
-        shell.env.insert(key.clone(), arg.clone());
-        unsafe { std::env::set_var(&key, arg); }
+        shell.set_var(&(i + 1).to_string(), arg);
     }
 
-    // Execute function body
+    // Execute function body, keeping $? current between its commands
     let mut last_code = 0;
-    for line in &func.body {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') { continue; }
-        match shell.eval(line) {
-            Ok(_)  => last_code = shell.last_exit_code,
-            Err(e) => { eprintln!("myshell: function {}: {}", name, e); last_code = 1; }
-        }
+    for cmd in func.body {
+        last_code = run(shell, cmd).unwrap_or_else(|e| {
+            errln!("myshell: function {}: {}", name, e);
+            1
+        });
+        shell.last_exit_code = last_code;
     }
 
     // Restore positional parameters
@@ -166,14 +232,8 @@ fn save_positional_args(shell: &Shell) -> Vec<(String, Option<String>)> {
 fn restore_positional_args(shell: &mut Shell, saved: Vec<(String, Option<String>)>) {
     for (key, old_val) in saved {
         match old_val {
-            Some(v) => {
-                shell.env.insert(key.clone(), v.clone());
-                unsafe { std::env::set_var(&key, v); }
-            }
-            None => {
-                shell.env.remove(&key);
-                unsafe { std::env::remove_var(&key); }
-            }
+            Some(v) => { shell.set_var(&key, &v); }
+            None    => { shell.unset_var(&key); }
         }
     }
 }
@@ -183,25 +243,45 @@ fn restore_positional_args(shell: &mut Shell, saved: Vec<(String, Option<String>
 fn run_simple(
     shell: &mut Shell,
     mut args: Vec<String>,
-    redirects: Vec<Redirect>,
+    mut redirects: Vec<Redirect>,
     background: bool,
 ) -> Result<i32> {
     if args.is_empty() { return Ok(0); }
 
-    // Expand variables and arithmetic in all arguments
-    for arg in &mut args {
-        *arg = expand_arithmetic(shell, arg);
-        *arg = expand_vars(shell, arg);
+    if shell.posix {
+        // Words arrive raw from the parser; expand them in POSIX order
+        args = expand_words(shell, &args);
+        redirects = expand_redirects(shell, redirects);
+        if args.is_empty() { return Ok(0); }
+    } else {
+        // Expand variables and arithmetic in all arguments. The line given
+        // to `explain` is only described, so its substitutions aren't run.
+        let describing = args[0] == "explain";
+        for arg in &mut args {
+            if describing && (arg.contains("$(") || arg.contains('`')) { continue; }
+            *arg = expand_arithmetic(shell, arg);
+            *arg = expand_vars(shell, arg);
+            *arg = shell.expand_named_dir(arg);
+        }
+        args = crate::glob::expand_args(args);
     }
-    args = crate::glob::expand_args(args);
 
-    // Special case: echo with redirects bypasses the normal builtin path
-    if args[0] == "echo" && !redirects.is_empty() {
-        return run_echo_redirect(&args, &redirects);
+    // `command NAME` and `builtin NAME` skip alias and function lookup.
+    // Their option forms (`command -v`) are ordinary builtins.
+    let bypass = args.len() > 1
+        && matches!(args[0].as_str(), "command" | "builtin")
+        && !args[1].starts_with('-');
+    if bypass && args.remove(0) == "builtin" {
+        if !shell.guard_allows(&args) { return Ok(1); }
+        if !pipeline::is_builtin_cmd(&args[0]) {
+            errln!("builtin: {}: not a shell builtin", args[0]);
+            return Ok(1);
+        }
+        return with_redirects(&redirects, || Ok(builtin::run_builtin(shell, &args).unwrap_or(0)));
     }
 
     // Expand alias if one exists (but don't recurse on the same name)
-    if let Some(alias_val) = shell.aliases.get(&args[0]).cloned() {
+    if let Some(alias_val) = shell.aliases.get(&args[0]).cloned().filter(|_| !bypass) {
         let alias_args: Vec<String> = alias_val
             .split_whitespace()
             .map(String::from)
@@ -213,127 +293,246 @@ fn run_simple(
         }
     }
 
+    // Guard rules see the command as it will actually run
+    if !shell.guard_allows(&args) { return Ok(1); }
+
+    // A wrapped command is taken out of the table while its hooks and the
+    // command itself run, so neither recurses into the wrapper
+    let wrapper = if bypass { None } else { shell.wrappers.remove(&args[0]) };
+    let Some(wrapper) = wrapper else {
+        return dispatch(shell, args, redirects, background, bypass);
+    };
+    let name = args[0].clone();
+    if let Some(before) = &wrapper.before { run_hook(shell, &name, before); }
+    let result = dispatch(shell, args, redirects, background, bypass);
+    if let (Some(after), Ok(code)) = (&wrapper.after, &result) {
+        shell.last_exit_code = *code;
+        run_hook(shell, &name, after);
+    }
+    shell.wrappers.insert(name, wrapper);
+    result
+}
+
+/// Run one of a `wrap` hook's commands, reporting (but not failing on) a
+/// parse error.
+fn run_hook(shell: &mut Shell, name: &str, cmd: &str) {
+    if let Err(e) = shell.eval(cmd) {
+        errln!("myshell: wrap {}: {}", name, e);
+    }
+}
+
+/// Run a fully expanded command as a function, builtin or external program.
+fn dispatch(
+    shell: &mut Shell,
+    args: Vec<String>,
+    redirects: Vec<Redirect>,
+    background: bool,
+    bypass: bool,
+) -> Result<i32> {
+    // Builtins and functions run inside the shell, so backgrounding one
+    // means running it in a copy of the shell
+    let internal = pipeline::is_builtin_cmd(&args[0])
+        || (!bypass && shell.functions.contains_key(&args[0]));
+    if background && internal {
+        let text = args.join(" ");
+        return detached::spawn(shell, text, detached::Work::Dispatch { args, redirects, bypass });
+    }
+
     // User-defined function
-    if shell.functions.contains_key(&args[0]) {
+    if !bypass && shell.functions.contains_key(&args[0]) {
         let name      = args[0].clone();
         let func_args = args[1..].to_vec();
-        return run_function(shell, &name, &func_args);
+        return with_redirects(&redirects, || run_function(shell, &name, &func_args));
+    }
+
+    // `exec`'s redirections are for the shell itself, not just the command
+    if args[0] == "exec" {
+        return Ok(builtin::exec::builtin_exec(shell, &args, &redirects));
+    }
+
+    // Shell builtin, with its streams redirected like an external command's
+    if pipeline::is_builtin_cmd(&args[0]) {
+        return with_redirects(&redirects, || Ok(builtin::run_builtin(shell, &args).unwrap_or(0)));
     }
 
-    // Shell builtin
-    if let Some(code) = builtin::run_builtin(shell, &args) {
-        return Ok(code);
+    // set -o autocorrect: a name that isn't found may be a typo for one that is
+    let missing = || !args[0].contains(['/', '\\'])
+        && !shell.command_hash.contains_key(&args[0])
+        && builtin::find_in_path(&args[0]).is_empty();
+    if shell.autocorrect && missing() {
+        match builtin::offer_correction(shell, &args) {
+            builtin::Correction::Accepted(name) => {
+                let mut args = args;
+                args[0] = name;
+                return dispatch(shell, args, redirects, background, bypass);
+            }
+            builtin::Correction::Declined => return Ok(127),
+            builtin::Correction::None => {}
+        }
     }
 
     // External command
     run_external(shell, &args, &redirects, background)
 }
 
-/// Handle `echo` when its output is being redirected (> or >>).
-fn run_echo_redirect(args: &[String], redirects: &[Redirect]) -> Result<i32> {
-    let mut start      = 1;
-    let mut no_newline = false;
+/// Expand the (raw, posix-mode) file names of redirections.
+pub fn expand_redirects(shell: &Shell, redirects: Vec<Redirect>) -> Vec<Redirect> {
+    let expand = |file: String| expand_words(shell, &[file]).join(" ");
+    redirects.into_iter().map(|r| match r {
+        Redirect::StdoutTo(f)     => Redirect::StdoutTo(expand(f)),
+        Redirect::StdoutAppend(f) => Redirect::StdoutAppend(expand(f)),
+        Redirect::StdinFrom(f)    => Redirect::StdinFrom(expand(f)),
+        Redirect::StderrTo(f)     => Redirect::StderrTo(expand(f)),
+        other                     => other,
+    }).collect()
+}
 
-    if args.get(1).map(|s| s.as_str()) == Some("-n") {
-        no_newline = true;
-        start = 2;
+/// Run a builtin or function with its streams taken from `redirects`.
+fn with_redirects(redirects: &[Redirect], f: impl FnOnce() -> Result<i32>) -> Result<i32> {
+    if redirects.is_empty() { return f(); }
+    match redirect_streams(redirects, None) {
+        Ok((stdin, stdout, stderr)) => builtin::io::with_streams(stdin, stdout, stderr, f),
+        Err(e) => { errln!("myshell: {}", e); Ok(1) }
     }
+}
 
-    let output = args[start..].join(" ")
-        .replace("\\n", "\n")
-        .replace("\\t", "\t");
-
+/// The streams a builtin should use: (stdin, stdout, stderr) after the
+/// files named by `redirects` are opened over `stdout`, the one it would
+/// otherwise write to (`None`: the thread's own). `None` leaves a stream as
+/// it is. They're applied in order, so `>f 2>&1` sends both to f.
+pub fn redirect_streams(redirects: &[Redirect], stdout: Option<File>) -> std::io::Result<Streams> {
+    let (mut stdin, mut stdout, mut stderr) = (None, stdout, None);
     for redirect in redirects {
         match redirect {
-            Redirect::StdoutTo(file) => {
-                let content = if no_newline { output.clone() } else { format!("{}\n", output) };
-                return Ok(std::fs::write(file, content).map(|_| 0).unwrap_or(1));
-            }
-            Redirect::StdoutAppend(file) => {
-                use std::io::Write;
-                let content = if no_newline { output.clone() } else { format!("{}\n", output) };
-                let mut f = OpenOptions::new().append(true).create(true).open(file)?;
-                f.write_all(content.as_bytes())?;
-                return Ok(0);
-            }
-            _ => {}
+            Redirect::StdinFrom(_) => stdin = open_redirect(redirect)?,
+            Redirect::StdoutTo(_) | Redirect::StdoutAppend(_) => stdout = open_redirect(redirect)?,
+            Redirect::StderrTo(_) => stderr = open_redirect(redirect)?,
+            Redirect::StderrToStdout => stderr = Some(match &stdout {
+                Some(file) => file.try_clone()?,
+                None => builtin::io::stdout_file()?,
+            }),
         }
     }
+    Ok((stdin, stdout, stderr))
+}
 
-    Ok(0)
+/// A builtin's (stdin, stdout, stderr), see `redirect_streams`.
+pub type Streams = (Option<File>, Option<File>, Option<File>);
+
+/// Open the file a redirection names, with the error naming the file.
+fn open_redirect(redirect: &Redirect) -> std::io::Result<Option<File>> {
+    let (file, opts) = match redirect {
+        Redirect::StdoutTo(f) | Redirect::StderrTo(f) =>
+            (f, OpenOptions::new().write(true).create(true).truncate(true).clone()),
+        Redirect::StdoutAppend(f) => (f, OpenOptions::new().write(true).create(true).append(true).clone()),
+        Redirect::StdinFrom(f)    => (f, OpenOptions::new().read(true).clone()),
+        Redirect::StderrToStdout  => return Ok(None),
+    };
+    opts.open(file)
+        .map(Some)
+        .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", file, e)))
 }
 
 // ── External command execution ────────────────────────────────────────────────
 
 fn run_external(
-    shell: &Shell,
+    shell: &mut Shell,
     args: &[String],
     redirects: &[Redirect],
     background: bool,
 ) -> Result<i32> {
-    crossterm::terminal::disable_raw_mode().ok();
+    let mut cmd = build_command(shell, args, redirects)?;
+    cmd.env_clear().envs(shell.child_env());
 
-    let mut cmd = build_command(args, redirects)?;
-    cmd.envs(&shell.env);
-
-    let result = if background {
-        spawn_background(cmd, &args[0])
+    if background {
+        spawn_background(shell, cmd, args)
     } else {
-        run_foreground(cmd, &args[0])
-    };
-
-    crossterm::terminal::enable_raw_mode().ok();
-    result
+        run_foreground(shell, cmd, args)
+    }
 }
 
-fn spawn_background(mut cmd: Proc, name: &str) -> Result<i32> {
+fn spawn_background(shell: &mut Shell, mut cmd: Proc, args: &[String]) -> Result<i32> {
+    jobctl::setup_child(&mut cmd, None, false, true);
     match cmd.spawn() {
-        Ok(child) => { println!("[bg] pid {}", child.id()); Ok(0) }
-        Err(e)    => { report_exec_error(name, &e); Ok(127) }
+        Ok(child) => {
+            let pid = child.id();
+            shell.add_job(pid, child, args.join(" "));
+            shell.last_bg_pid = Some(pid);
+            println!("[bg] pid {}", pid);
+            Ok(0)
+        }
+        Err(e)    => Ok(report_exec_error(shell, &args[0], &e)),
     }
 }
 
-fn run_foreground(mut cmd: Proc, name: &str) -> Result<i32> {
-    match cmd.status() {
-        Ok(status) => Ok(status.code().unwrap_or(0)),
-        Err(e)     => { report_exec_error(name, &e); Ok(127) }
+/// Run a command as the foreground job, holding the terminal until it
+/// exits or Ctrl+Z stops it; a stopped command becomes a job for `fg`.
+fn run_foreground(shell: &mut Shell, mut cmd: Proc, args: &[String]) -> Result<i32> {
+    jobctl::setup_child(&mut cmd, None, true, true);
+    let mut child = match cmd.spawn() {
+        Ok(c)  => c,
+        Err(e) => return Ok(report_exec_error(shell, &args[0], &e)),
+    };
+    jobctl::give_terminal(child.id());
+    let waited = jobctl::wait_foreground(&mut child);
+    jobctl::take_terminal();
+
+    match waited {
+        Ok(jobctl::Wait::Exited(status)) => Ok(builtin::status::exit_status(&args[0], status)),
+        Ok(jobctl::Wait::Stopped) => {
+            let id = shell.add_job(child.id(), child, args.join(" "));
+            Ok(shell.stop_job(id))
+        }
+        Err(e) => { errln!("myshell: {}: {}", args[0], e); Ok(1) }
     }
 }
 
-fn report_exec_error(name: &str, e: &std::io::Error) {
+/// Report a failed spawn and return the POSIX status: 127 if the command
+/// doesn't exist, 126 if it exists but can't be executed.
+fn report_exec_error(shell: &mut Shell, name: &str, e: &std::io::Error) -> i32 {
+    if let Some(msg) = script::bad_interpreter(name) {
+        errln!("myshell: {}", msg);
+        return 126;
+    }
     if e.kind() == std::io::ErrorKind::NotFound {
-        builtin::command_not_found(name);
+        builtin::command_not_found(shell, name);
+        127
     } else {
-        eprintln!("myshell: {}: {}", name, e);
+        errln!("myshell: {}: {}", name, e);
+        126
     }
 }
 
 // ── Command building ──────────────────────────────────────────────────────────
 
-pub fn build_command(args: &[String], redirects: &[Redirect]) -> Result<Proc> {
-    let mut cmd = platform_command(&args[0]);
+pub fn build_command(shell: &mut Shell, args: &[String], redirects: &[Redirect]) -> Result<Proc> {
+    let resolved = shell.lookup_command(&args[0]);
+    let mut cmd = platform_command(&args[0], resolved);
     cmd.args(&args[1..]);
 
+    // Inside a builtin's pipeline stage or a redirected function, children
+    // use the streams it has
+    if let Some(r) = builtin::io::current_stdin()  { cmd.stdin(r); }
+    if let Some(w) = builtin::io::current_stdout() { cmd.stdout(w); }
+    if let Some(w) = builtin::io::current_stderr() { cmd.stderr(w); }
+
+    // Where stdout goes so far, for `2>&1`; None is the thread's own
+    let mut stdout: Option<File> = None;
     for redirect in redirects {
+        let Some(file) = open_redirect(redirect)? else {
+            let target = match &stdout {
+                Some(file) => file.try_clone()?,
+                None => builtin::io::stdout_file()?,
+            };
+            cmd.stderr(target);
+            continue;
+        };
         match redirect {
-            Redirect::StdoutTo(file) => {
-                let f = OpenOptions::new().write(true).create(true).truncate(true).open(file)?;
-                cmd.stdout(Stdio::from(f));
-            }
-            Redirect::StdoutAppend(file) => {
-                let f = OpenOptions::new().write(true).create(true).append(true).open(file)?;
-                cmd.stdout(Stdio::from(f));
-            }
-            Redirect::StdinFrom(file) => {
-                let f = OpenOptions::new().read(true).open(file)?;
-                cmd.stdin(Stdio::from(f));
-            }
-            Redirect::StderrTo(file) => {
-                let f = OpenOptions::new().write(true).create(true).truncate(true).open(file)?;
-                cmd.stderr(Stdio::from(f));
-            }
-            Redirect::StderrToStdout => {
-                cmd.stderr(Stdio::inherit());
+            Redirect::StdinFrom(_) => { cmd.stdin(file); }
+            Redirect::StderrTo(_)  => { cmd.stderr(file); }
+            _ => {
+                stdout = Some(file.try_clone()?);
+                cmd.stdout(file);
             }
         }
     }
@@ -342,7 +541,13 @@ pub fn build_command(args: &[String], redirects: &[Redirect]) -> Result<Proc> {
 }
 
 /// On Windows, route known cmd.exe builtins through `cmd /C`.
-fn platform_command(program: &str) -> Proc {
+fn platform_command(program: &str, resolved: Option<PathBuf>) -> Proc {
+    if let Some(argv) = script::interpreter_for(program) {
+        let mut cmd = Proc::new(&argv[0]);
+        cmd.args(&argv[1..]);
+        return cmd;
+    }
+
     #[cfg(windows)]
     {
         const CMD_BUILTINS: &[&str] = &[
@@ -355,5 +560,14 @@ fn platform_command(program: &str) -> Proc {
             return cmd;
         }
     }
-    Proc::new(program)
+    match resolved {
+        Some(path) => {
+            let mut cmd = Proc::new(path);
+            // Programs still see the name they were invoked by as argv[0]
+            #[cfg(unix)]
+            std::os::unix::process::CommandExt::arg0(&mut cmd, program);
+            cmd
+        }
+        None => Proc::new(program),
+    }
 }
\ No newline at end of file
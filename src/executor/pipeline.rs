@@ -1,16 +1,20 @@
 // src/executor/pipeline.rs
 //
-// Pipeline execution — runs a sequence of commands connected by pipes,
-// threading data between stages via either OS pipes (external commands)
-// or in-memory buffers (builtins).
+// Pipeline execution — every stage starts at once, connected by OS pipes,
+// so data streams through without being buffered or written to disk.
+// External commands are child processes; builtins run on threads of their
+// own with the pipe ends installed as their streams (see builtin/io.rs).
+// The last stage, if it is a builtin, runs in the shell itself.
 
 use crate::parser::ast::{Command, Redirect};
 use crate::shell::Shell;
 use anyhow::Result;
-use std::process::Stdio;
+use std::fs::File;
+use std::process::Child;
+use std::thread::JoinHandle;
 
 use super::builtin;
-use super::expand::{expand_arithmetic, expand_vars};
+use super::expand::{expand_arithmetic, expand_vars, expand_words};
 
 // ── Public API ────────────────────────────────────────────────────────────────
 
@@ -23,251 +27,197 @@ pub fn run_pipeline(shell: &mut Shell, cmds: Vec<Command>) -> Result<i32> {
 
     let stages = collect_stages(shell, cmds);
     if stages.is_empty() { return Ok(0); }
-
-    let mut input_buf: Option<Vec<u8>> = None;
-    let mut last_code = 0;
-    let     n         = stages.len();
-
-    for (i, (args, redirects)) in stages.into_iter().enumerate() {
-        if args.is_empty() { continue; }
+    if !stages.iter().all(|(_, args, _)| args.is_empty() || shell.guard_allows(args)) { return Ok(1); }
+
+    let n = stages.len();
+    let mut running = Vec::with_capacity(n);
+    let mut input: Option<File> = None;
+    // External stages share one process group, led by the first of them,
+    // which holds the terminal. If the pipeline starts with a builtin the
+    // shell itself is part of the job, so its children stay in its group.
+    let job_control = !stages[0].1.first().is_some_and(|name| is_builtin_cmd(name));
+    let mut pgid: Option<u32> = None;
+
+    for (i, (assigns, args, redirects)) in stages.into_iter().enumerate() {
         let is_last = i == n - 1;
-
-        if is_builtin_cmd(&args[0]) {
-            // Write input to temp file for builtins like xargs that read it by path
-            if let Some(ref buf) = input_buf {
-                write_pipe_tmp(buf);
+        // Every stage but the last writes into a pipe read by the next one
+        let (next_input, output) = if is_last {
+            (None, None)
+        } else {
+            match builtin::io::pipe() {
+                Ok((r, w)) => (Some(r), Some(w)),
+                Err(e)     => { errln!("myshell: pipe: {}", e); (None, None) }
             }
+        };
 
-            if is_last {
-                last_code = match input_buf {
-                    Some(ref buf) => run_builtin_with_input(shell, &args, buf),
-                    None          => builtin::run_builtin(shell, &args).unwrap_or(0),
-                };
-            } else {
-                // Capture this builtin's output in memory for the next stage
-                input_buf = Some(capture_builtin_output(shell, &args, input_buf.as_deref()));
+        let stage_input = input.take();
+        // Assignments apply to their own stage only
+        running.push(shell.with_temp_vars(&assigns, |shell| match args.first() {
+            None => start_empty_stage(&redirects),
+            Some(name) if is_builtin_cmd(name) => {
+                start_builtin_stage(shell, args, &redirects, stage_input, output, is_last)
             }
-        } else {
-            input_buf = run_external_stage(
-                shell, &args, &redirects, input_buf, is_last, &mut last_code,
-            );
-        }
+            Some(_) => {
+                start_external_stage(shell, &args, &redirects, stage_input, output, job_control.then_some(&mut pgid))
+            }
+        }));
+        input = next_input;
     }
 
-    Ok(last_code)
+    // Wait for every stage; the pipeline's status is the last one's, or
+    // with pipefail the last failure's
+    let codes: Vec<i32> = running.into_iter().map(Stage::wait).collect();
+    let code = if shell.pipefail {
+        codes.iter().rev().find(|c| **c != 0).copied().unwrap_or(0)
+    } else {
+        codes.last().copied().unwrap_or(0)
+    };
+    shell.pipe_status = codes;
+
+    if pgid.is_some() { super::jobctl::take_terminal(); }
+    Ok(code)
 }
 
 // ── Stage collection ──────────────────────────────────────────────────────────
 
+type Assigns = Vec<(String, String)>;
+
 fn collect_stages(
     shell: &mut Shell,
     cmds: Vec<Command>,
-) -> Vec<(Vec<String>, Vec<Redirect>)> {
+) -> Vec<(Assigns, Vec<String>, Vec<Redirect>)> {
     let mut stages = Vec::new();
     for cmd in cmds {
-        if let Command::Simple { args, redirects, .. } = cmd {
+        if let Command::Simple { assigns, args, redirects } = cmd {
+            let assigns = super::expand_assigns(shell, assigns);
             let mut expanded = args;
-            for arg in &mut expanded {
-                *arg = expand_arithmetic(shell, arg);
-                *arg = expand_vars(shell, arg);
+            let mut redirects = redirects;
+            if shell.posix {
+                expanded  = expand_words(shell, &expanded);
+                redirects = super::expand_redirects(shell, redirects);
+            } else {
+                for arg in &mut expanded {
+                    *arg = expand_arithmetic(shell, arg);
+                    *arg = expand_vars(shell, arg);
+                }
+                expanded = crate::glob::expand_args(expanded);
+            }
+            // Stages never see aliases or functions, so these are no-ops
+            if expanded.len() > 1 && matches!(expanded[0].as_str(), "command" | "builtin") {
+                expanded.remove(0);
             }
-            expanded = crate::glob::expand_args(expanded);
-            stages.push((expanded, redirects));
+            stages.push((assigns, expanded, redirects));
         }
     }
     stages
 }
 
-// ── Builtin stage execution ───────────────────────────────────────────────────
+// ── Stages ────────────────────────────────────────────────────────────────────
 
-/// Capture a builtin's stdout into an in-memory Vec<u8>.
-/// Uses OS pipes so no temp files are written for the capture itself.
-fn capture_builtin_output(shell: &mut Shell, args: &[String], input: Option<&[u8]>) -> Vec<u8> {
-    // cat with no file args is a pure pass-through — no need to run anything
-    if args[0] == "cat" && args.len() == 1 {
-        return input.unwrap_or_default().to_vec();
-    }
+/// A pipeline stage that has been started.
+enum Stage {
+    Child(Child, String),
+    Thread(JoinHandle<i32>),
+    Done(i32),
+}
 
-    // For builtins that take file arguments, write input to temp file and
-    // append the path as an argument (e.g. sort, grep, wc, uniq, head, tail)
-    let mut new_args = args.to_vec();
-    if let Some(data) = input {
-        let tmp = pipe_in_tmp();
-        let _ = std::fs::write(&tmp, data);
-        new_args.push(tmp.to_string_lossy().to_string());
+impl Stage {
+    fn wait(self) -> i32 {
+        match self {
+            Stage::Child(mut child, name) => match child.wait() {
+                Ok(status) => builtin::status::exit_status(&name, status),
+                Err(_)     => 1,
+            },
+            Stage::Thread(handle)   => handle.join().unwrap_or(1),
+            Stage::Done(code)       => code,
+        }
     }
-
-    // Capture stdout using an OS pipe — stays entirely in memory
-    capture_stdout_pipe(shell, &new_args)
 }
 
-/// Run the final builtin in a pipeline, feeding input via temp file.
-fn run_builtin_with_input(shell: &mut Shell, args: &[String], input: &[u8]) -> i32 {
-    if args[0] == "cat" && args.len() == 1 {
-        use std::io::Write;
-        std::io::stdout().write_all(input).ok();
-        return 0;
+/// A stage with no command, such as `FOO=bar`, or `$NOPE` in posix mode
+/// once the empty expansion is gone: its assignments would only reach its
+/// own subshell, so all it does is open its redirections.
+fn start_empty_stage(redirects: &[Redirect]) -> Stage {
+    match super::redirect_streams(redirects, None) {
+        Ok(_)  => Stage::Done(0),
+        Err(e) => { errln!("myshell: {}", e); Stage::Done(1) }
     }
-
-    let tmp = pipe_in_tmp();
-    let _ = std::fs::write(&tmp, input);
-
-    let mut new_args = args.to_vec();
-    new_args.push(tmp.to_string_lossy().to_string());
-
-    builtin::run_builtin(shell, &new_args).unwrap_or(0)
 }
 
-/// Capture a builtin's stdout using an OS pipe (in-memory, no disk I/O).
-fn capture_stdout_pipe(shell: &mut Shell, args: &[String]) -> Vec<u8> {
-    #[cfg(unix)]
-    {
-        use std::os::unix::io::FromRawFd;
-
-        unsafe {
-            // Create a pipe: read_fd → write_fd
-            let mut fds = [0i32; 2];
-            if libc::pipe(fds.as_mut_ptr()) != 0 {
-                return Vec::new();
-            }
-            let (read_fd, write_fd) = (fds[0], fds[1]);
-
-            // Save stdout, replace it with the write end of the pipe
-            let old_stdout = libc::dup(1);
-            libc::dup2(write_fd, 1);
-            libc::close(write_fd);
-
-            // Run the builtin — its output goes into the pipe
-            builtin::run_builtin(shell, args);
-
-            // Flush and restore stdout
-            libc::dup2(old_stdout, 1);
-            libc::close(old_stdout);
-
-            // Read everything from the pipe into a Vec<u8>
-            let mut file = std::fs::File::from_raw_fd(read_fd);
-            let mut buf  = Vec::new();
-            use std::io::Read;
-            file.read_to_end(&mut buf).ok();
-            buf
-        }
+fn start_builtin_stage(
+    shell: &mut Shell,
+    args: Vec<String>,
+    redirects: &[Redirect],
+    input: Option<File>,
+    output: Option<File>,
+    is_last: bool,
+) -> Stage {
+    // Explicit redirections win over the pipe, as for external stages
+    let (input, output, errors) = match super::redirect_streams(redirects, output) {
+        Ok((r, w, e)) => (r.or(input), w, e),
+        Err(e)     => { errln!("myshell: {}", e); return Stage::Done(1); }
+    };
+    if is_last {
+        let code = builtin::io::with_streams(input, output, errors, || {
+            builtin::run_builtin(shell, &args).unwrap_or(0)
+        });
+        return Stage::Done(code);
     }
 
-    #[cfg(windows)]
-    {
-        use std::os::windows::io::FromRawHandle;
-        use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
-        use windows_sys::Win32::Security::SECURITY_ATTRIBUTES;
-        use windows_sys::Win32::System::Console::{
-            GetStdHandle, SetStdHandle, STD_OUTPUT_HANDLE,
-        };
-        use windows_sys::Win32::System::Pipes::CreatePipe;
-
-        unsafe {
-            let mut sa = SECURITY_ATTRIBUTES {
-                nLength:              std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
-                lpSecurityDescriptor: std::ptr::null_mut(),
-                bInheritHandle:       1,
-            };
-
-            let mut read_handle  = INVALID_HANDLE_VALUE;
-            let mut write_handle = INVALID_HANDLE_VALUE;
-
-            if CreatePipe(&mut read_handle, &mut write_handle, &mut sa, 0) == 0 {
-                return Vec::new();
-            }
-
-            // Save stdout and redirect it to the write end of the pipe
-            let old_stdout = GetStdHandle(STD_OUTPUT_HANDLE);
-            SetStdHandle(STD_OUTPUT_HANDLE, write_handle);
-
-            // Run the builtin
-            builtin::run_builtin(shell, args);
-
-            // Restore stdout and close the write end so reads don't block
-            SetStdHandle(STD_OUTPUT_HANDLE, old_stdout);
-            windows_sys::Win32::Foundation::CloseHandle(write_handle);
-
-            // Read from the pipe into a Vec<u8>
-            let mut file = std::fs::File::from_raw_handle(read_handle as _);
-            let mut buf  = Vec::new();
-            use std::io::Read;
-            file.read_to_end(&mut buf).ok();
-            buf
-        }
-    }
+    // Earlier stages behave like subshells: they get a copy of the shell's
+    // state, and anything they change is discarded with it
+    let mut subshell = shell.clone();
+    let input  = input.or_else(builtin::io::current_stdin);
+    let output = output.or_else(builtin::io::current_stdout);
+    let errors = errors.or_else(builtin::io::current_stderr);
+    Stage::Thread(std::thread::spawn(move || {
+        builtin::io::with_streams(input, output, errors, || {
+            builtin::run_builtin(&mut subshell, &args).unwrap_or(0)
+        })
+    }))
 }
 
-// ── External stage execution ──────────────────────────────────────────────────
-
-fn run_external_stage(
-    shell: &Shell,
+fn start_external_stage(
+    shell: &mut Shell,
     args: &[String],
     redirects: &[Redirect],
-    input_buf: Option<Vec<u8>>,
-    is_last: bool,
-    last_code: &mut i32,
-) -> Option<Vec<u8>> {
-    crossterm::terminal::disable_raw_mode().ok();
-
-    let mut cmd = match super::build_command(args, redirects) {
+    input: Option<File>,
+    output: Option<File>,
+    pgid: Option<&mut Option<u32>>,
+) -> Stage {
+    let mut cmd = match super::build_command(shell, args, redirects) {
         Ok(c)  => c,
-        Err(e) => { eprintln!("myshell: {e}"); return None; }
+        Err(e) => { errln!("myshell: {e}"); return Stage::Done(1); }
     };
-    cmd.envs(&shell.env);
+    cmd.env_clear().envs(shell.child_env());
 
-    let result = match input_buf {
-        Some(buf) => run_external_with_input(cmd, buf, is_last, last_code),
-        None      => run_external_no_input(cmd, is_last, last_code),
-    };
-
-    crossterm::terminal::enable_raw_mode().ok();
-    result
-}
-
-fn run_external_with_input(
-    mut cmd: std::process::Command,
-    buf: Vec<u8>,
-    is_last: bool,
-    last_code: &mut i32,
-) -> Option<Vec<u8>> {
-    cmd.stdin(Stdio::piped());
-    if !is_last { cmd.stdout(Stdio::piped()); }
-
-    match cmd.spawn() {
-        Ok(mut child) => {
-            if let Some(mut stdin) = child.stdin.take() {
-                use std::io::Write;
-                let _ = stdin.write_all(&buf);
-            }
-            if !is_last {
-                child.wait_with_output().ok().map(|o| o.stdout)
-            } else {
-                *last_code = child.wait().map(|s| s.code().unwrap_or(0)).unwrap_or(0);
-                None
-            }
-        }
-        Err(e) => { report_spawn_error(&e); None }
+    // Explicit redirections win over the pipe
+    if let Some(r) = input.filter(|_| !redirects.iter().any(|r| matches!(r, Redirect::StdinFrom(_)))) {
+        cmd.stdin(r);
+    }
+    let redirected = redirects.iter().any(|r| matches!(r, Redirect::StdoutTo(_) | Redirect::StdoutAppend(_)));
+    if let Some(w) = output.filter(|_| !redirected) {
+        // `2>&1` before any `>` sends errors down the pipe as well
+        let joined = redirects.iter()
+            .take_while(|r| !matches!(r, Redirect::StdoutTo(_) | Redirect::StdoutAppend(_)))
+            .any(|r| matches!(r, Redirect::StderrToStdout));
+        if joined { if let Ok(e) = w.try_clone() { cmd.stderr(e); } }
+        cmd.stdout(w);
     }
-}
 
-fn run_external_no_input(
-    mut cmd: std::process::Command,
-    is_last: bool,
-    last_code: &mut i32,
-) -> Option<Vec<u8>> {
-    if !is_last { cmd.stdout(Stdio::piped()); }
+    // Pipelines aren't stopped with Ctrl+Z: the shell couldn't resume the
+    // builtin stages running on its own threads
+    if let Some(pgid) = &pgid { super::jobctl::setup_child(&mut cmd, **pgid, true, false); }
 
     match cmd.spawn() {
-        Ok(mut child) => {
-            if !is_last {
-                child.wait_with_output().ok().map(|o| o.stdout)
-            } else {
-                *last_code = child.wait().map(|s| s.code().unwrap_or(0)).unwrap_or(0);
-                None
+        Ok(child) => {
+            if let Some(pgid) = pgid.filter(|p| p.is_none()) {
+                *pgid = Some(child.id());
+                super::jobctl::give_terminal(child.id());
             }
+            Stage::Child(child, args[0].clone())
         }
-        Err(e) => { report_spawn_error(&e); None }
+        Err(e)    => { report_spawn_error(&e); Stage::Done(127) }
     }
 }
 
@@ -277,29 +227,69 @@ fn run_external_no_input(
 pub fn is_builtin_cmd(name: &str) -> bool {
     matches!(name,
         "cd"  | "pwd"   | "echo"  | "export" | "unset"  | "alias"  |
-        "unalias" | "history" | "source" | "clear" | "cls"   | "sleep"  |
+        "unalias" | "history" | "fc" | "source" | "." | "clear" | "cls"   | "sleep"  |
         "functions" | "help" | "which" | "pushd" | "popd"  | "dirs"   |
         "ls"  | "mkdir" | "rm"   | "cp"    | "mv"    | "cat"    |
-        "touch" | "chmod" | "ln" | "grep"  | "find"  | "head"   |
+        "touch" | "chmod" | "chown" | "ln" | "grep"  | "find"  | "head"   |
         "tail"  | "wc"   | "env" | "sort"  | "uniq"  | "xargs"  |
         "jobs"  | "fg"   | "bg"  | "kill"  | "test"  | "["      |
-        "true"  | "false"| "exit"| "quit"
+        "true"  | "false"| "exit"| "quit"  | "stats" | "set"   |
+        "printf"| "type" | "command" | "builtin" | "pkg" | "install" |
+        "uninstall" | "shift" | "getopts" | "quote" |
+        "umask" | "ulimit" | "hash" | "complete" | "declare" | "readonly" | "schedule" |
+        "guard" | "wrap" | "explain" | "disown" | "exec" | "cleanup" | "hook" | "sandbox" |
+        "sed"   | "cut"  | "tr"  | "du"    | "basename" | "dirname" | "realpath" |
+        "stat"  | "file" | "yes" | "expr" | "sha256sum" | "md5sum" |
+        "fetch" | "serve" | "ping" | "port" |
+        "whoami" | "hostname" | "uname" | "uptime" | "open" | "rename" | "trash"
     )
 }
 
-/// Write data to the pipe input temp file (used by xargs and similar).
-fn write_pipe_tmp(data: &[u8]) {
-    let _ = std::fs::write(pipe_in_tmp(), data);
-}
-
-fn pipe_in_tmp() -> std::path::PathBuf {
-    std::env::temp_dir().join(format!("rshell_pipe_{}.tmp", std::process::id()))
-}
-
 fn report_spawn_error(e: &std::io::Error) {
     if e.kind() == std::io::ErrorKind::NotFound {
-        eprintln!("myshell: command not found");
+        errln!("myshell: command not found");
     } else {
-        eprintln!("myshell: {}", e);
+        errln!("myshell: {}", e);
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    /// Run `line` and return what it printed.
+    fn output(shell: &mut Shell, line: &str) -> String {
+        let (mut r, w) = builtin::io::pipe().unwrap();
+        builtin::io::with_streams(None, Some(w), None, || shell.eval(line).unwrap());
+        let mut out = String::new();
+        r.read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn assignment_only_stages_are_no_ops() {
+        let mut shell = Shell::new();
+        assert_eq!(output(&mut shell, "FOO=bar | cat"), "");
+        assert_eq!(shell.last_exit_code, 0);
+        assert_eq!(output(&mut shell, "echo a | FOO=bar"), "");
+        assert_eq!(shell.last_exit_code, 0);
+        assert!(!shell.env.contains_key("FOO"));
+    }
+
+    #[test]
+    fn assignments_reach_their_stage() {
+        let mut shell = Shell::new();
+        assert_eq!(output(&mut shell, "FOO=3 env | grep FOO="), "FOO=3\n");
+        assert!(!shell.env.contains_key("FOO"));
+    }
+
+    #[test]
+    fn emptied_posix_stages_are_no_ops() {
+        let mut shell = Shell::new();
+        shell.posix = true;
+        assert_eq!(output(&mut shell, "$NOPE | cat"), "");
+        assert_eq!(shell.last_exit_code, 0);
+        assert_eq!(output(&mut shell, "echo a | $NOPE"), "");
+        assert_eq!(shell.last_exit_code, 0);
+    }
+}
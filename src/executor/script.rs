@@ -0,0 +1,180 @@
+// src/executor/script.rs
+//
+// Script dispatch — decides how to launch a file the OS won't run
+// directly.
+//
+//   Unix:    the kernel honours `#!` itself, so only scripts without one
+//            need help; like other POSIX shells we run those with `sh`.
+//   Windows: shebangs are ignored by CreateProcess, so the `#!` line is
+//            read here and mapped onto an interpreter found on PATH
+//            (`/usr/bin/env python3` → python3 → python → py). Files
+//            without a shebang whose extension is listed in PATHEXT fall
+//            back to their file association via `cmd /C`.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::builtin::{find_in_path, is_executable};
+
+/// The full argv (interpreter, its arguments, then the script path) to
+/// launch `program` with, or None to execute it directly.
+pub fn interpreter_for(program: &str) -> Option<Vec<String>> {
+    let path = locate(program)?;
+    let head = read_head(&path)?;
+
+    let mut argv = match shebang_line(&head) {
+        // Unix kernels run shebang scripts themselves
+        Some(_) if !cfg!(windows) => return None,
+        Some(line) => {
+            let mut words = shebang_words(&line);
+            let interp = words.first()?.clone();
+            words[0] = windows_interpreter(&interp)?;
+            words
+        }
+        None if cfg!(windows) && has_pathext_extension(&path) => {
+            vec!["cmd".to_string(), "/C".to_string()]
+        }
+        // Executable text file with no shebang: the kernel refuses it (ENOEXEC)
+        None if !cfg!(windows) && is_executable(&path) && !is_binary(&head) => {
+            vec!["sh".to_string()]
+        }
+        None => return None,
+    };
+
+    let script = path.display().to_string();
+    // cmd.exe and most Windows interpreters want native separators
+    argv.push(if cfg!(windows) { script.replace('/', "\\") } else { script });
+    Some(argv)
+}
+
+/// Explain a failed launch caused by a shebang naming an interpreter that
+/// doesn't exist, which would otherwise look like "command not found".
+pub fn bad_interpreter(program: &str) -> Option<String> {
+    let path = locate(program)?;
+    let line = shebang_line(&read_head(&path)?)?;
+    let interp = line.split_whitespace().next()?.to_string();
+
+    let found = if cfg!(windows) {
+        windows_interpreter(&interp).is_some()
+    } else {
+        Path::new(&interp).is_file()
+    };
+    if found { return None; }
+    Some(format!("{}: bad interpreter: {}: No such file or directory", program, interp))
+}
+
+// ── Shebang parsing ───────────────────────────────────────────────────────────
+
+fn shebang_line(head: &[u8]) -> Option<String> {
+    let rest = head.strip_prefix(b"#!")?;
+    let line = rest.split(|&b| b == b'\n').next().unwrap_or(b"");
+    Some(String::from_utf8_lossy(line).trim().to_string())
+}
+
+/// Split a shebang line into interpreter + arguments, resolving `env`
+/// indirection (including `env -S`).
+fn shebang_words(line: &str) -> Vec<String> {
+    let mut words: Vec<String> = line.split_whitespace().map(String::from).collect();
+    if words.first().map(|w| file_stem(w) == "env").unwrap_or(false) {
+        words.remove(0);
+        if words.first().map(|w| w == "-S").unwrap_or(false) { words.remove(0); }
+    }
+    words
+}
+
+/// Map a Unix interpreter path onto something runnable on Windows.
+fn windows_interpreter(interp: &str) -> Option<String> {
+    if Path::new(interp).is_file() { return Some(interp.to_string()); }
+
+    let name = file_stem(interp);
+    // A script written for rshell runs in this same binary
+    if name == "rshell" {
+        return std::env::current_exe().ok().map(|p| p.display().to_string());
+    }
+
+    let mut candidates = vec![name.clone()];
+    if let Some(base) = name.strip_suffix('3') {
+        // python3 is usually just python (or the py launcher) on Windows
+        candidates.push(base.to_string());
+        if base == "python" { candidates.push("py".to_string()); }
+    }
+    candidates.into_iter()
+        .find_map(|c| find_in_path(&c).into_iter().next())
+        .map(|p| p.display().to_string())
+}
+
+// ── PATHEXT ───────────────────────────────────────────────────────────────────
+
+fn pathext() -> Vec<String> {
+    std::env::var("PATHEXT").unwrap_or_default()
+        .split(';')
+        .filter(|e| !e.is_empty())
+        .map(|e| e.to_lowercase())
+        .collect()
+}
+
+/// Scripts whose extension is in PATHEXT (but which aren't executables
+/// CreateProcess can start itself) are opened through their association.
+fn has_pathext_extension(path: &Path) -> bool {
+    let Some(ext) = path.extension() else { return false; };
+    let ext = format!(".{}", ext.to_string_lossy().to_lowercase());
+    !matches!(ext.as_str(), ".exe" | ".com") && pathext().contains(&ext)
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+/// Only explicit paths (`./x`, `dir/x`, `C:\x`) are considered scripts;
+/// bare names go through the normal PATH search of the OS. On Windows a
+/// bare name may also match a PATHEXT extension, e.g. `tool` → tool.py.
+fn locate(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if program.contains('/') || program.contains('\\') {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    if !cfg!(windows) || path.extension().is_some() { return None; }
+
+    let path_var = std::env::var("PATH").unwrap_or_default();
+    for dir in std::iter::once(".").chain(path_var.split(';')) {
+        for ext in pathext() {
+            // Executables are found by CreateProcess without our help
+            if ext == ".exe" || ext == ".com" { continue; }
+            let candidate = Path::new(dir).join(format!("{}{}", program, ext));
+            if candidate.is_file() { return Some(candidate); }
+        }
+    }
+    None
+}
+
+fn is_binary(head: &[u8]) -> bool {
+    head.contains(&0) || head.starts_with(b"\x7fELF")
+}
+
+fn read_head(path: &Path) -> Option<Vec<u8>> {
+    let mut buf = vec![0u8; 256];
+    let n = std::fs::File::open(path).ok()?.read(&mut buf).ok()?;
+    buf.truncate(n);
+    Some(buf)
+}
+
+fn file_stem(path: &str) -> String {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    name.strip_suffix(".exe").unwrap_or(name).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shebang_words() {
+        assert_eq!(shebang_words("/usr/bin/python3 -u"), vec!["/usr/bin/python3", "-u"]);
+        assert_eq!(shebang_words("/usr/bin/env python3"), vec!["python3"]);
+        assert_eq!(shebang_words("/usr/bin/env -S node --harmony"), vec!["node", "--harmony"]);
+    }
+
+    #[test]
+    fn test_shebang_line() {
+        assert_eq!(shebang_line(b"#! /bin/sh\r\necho hi\n").as_deref(), Some("/bin/sh"));
+        assert_eq!(shebang_line(b"echo hi\n"), None);
+    }
+}
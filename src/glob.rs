@@ -9,7 +9,7 @@ use std::path::Path;
 /// Returns a sorted list of matches, or the original string if no matches.
 pub fn expand(pattern: &str) -> Vec<String> {
     // Normalise separators first so Windows paths work cleanly
-    let pattern = normalise_path(pattern);
+    let pattern = crate::paths::normalise(pattern);
 
     // Expand ~ at the start
     let expanded = expand_tilde(&pattern);
@@ -30,9 +30,18 @@ pub fn expand(pattern: &str) -> Vec<String> {
 
 /// Expand a full argument list, replacing any glob patterns with their matches
 pub fn expand_args(args: Vec<String>) -> Vec<String> {
+    // printf's backslashes are escapes, not separators, so its other words
+    // are kept as typed
+    let printf = args.iter()
+        .find(|a| !matches!(a.as_str(), "command" | "builtin"))
+        .is_some_and(|a| a == "printf");
     let mut result = Vec::new();
     for arg in args {
-        result.extend(expand(&arg));
+        if printf && !has_glob_chars(&arg) {
+            result.push(expand_tilde(&arg));
+        } else {
+            result.extend(expand(&arg));
+        }
     }
     result
 }
@@ -41,22 +50,16 @@ fn has_glob_chars(s: &str) -> bool {
     s.contains('*') || s.contains('?') || s.contains('[')
 }
 
-/// Normalise a path — strip \\?\ long path prefix, unify separators to /
-fn normalise_path(s: &str) -> String {
-    let s = s.trim_start_matches("\\\\?\\");
-    s.replace('\\', "/")
-}
-
 /// Expand ~ to home directory
 fn expand_tilde(s: &str) -> String {
     if s == "~" {
         return dirs::home_dir()
-            .map(|h| normalise_path(&h.display().to_string()))
+            .map(|h| crate::paths::display(&h))
             .unwrap_or_else(|| "~".to_string());
     }
     if s.starts_with("~/") {
         let home = dirs::home_dir()
-            .map(|h| normalise_path(&h.display().to_string()))
+            .map(|h| crate::paths::display(&h))
             .unwrap_or_else(|| "~".to_string());
         return format!("{}/{}", home, &s[2..]);
     }
@@ -73,7 +76,7 @@ fn glob_expand(pattern: &str) -> Vec<String> {
 
     let (dir, file_pat) = match path.parent() {
         Some(parent) if parent != Path::new("") => {
-            let parent_str = normalise_path(&parent.display().to_string());
+            let parent_str = crate::paths::normalise(&parent.display().to_string());
             let file = path.file_name()
                 .map(|f| f.to_string_lossy().to_string())
                 .unwrap_or_default();
@@ -224,8 +227,10 @@ mod tests {
     }
 
     #[test]
-    fn test_normalise() {
-        assert_eq!(normalise_path("\\\\?\\C:\\Users\\foo"), "C:/Users/foo");
-        assert_eq!(normalise_path("C:\\Users\\foo"), "C:/Users/foo");
+    fn printf_keeps_its_escapes() {
+        let args = |words: &[&str]| expand_args(words.iter().map(|w| w.to_string()).collect());
+        assert_eq!(args(&["printf", "a\\tb\\n"]), ["printf", "a\\tb\\n"]);
+        assert_eq!(args(&["command", "printf", "\\n"]), ["command", "printf", "\\n"]);
+        assert_eq!(args(&["echo", "a\\b"]), ["echo", "a/b"]);
     }
-}
\ No newline at end of file
+}
@@ -7,6 +7,9 @@ use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
+mod ansi;
+use ansi::strip_ansi;
+
 /// Shared terminal output buffer
 type OutputBuffer = Arc<Mutex<String>>;
 
@@ -301,29 +304,3 @@ impl eframe::App for TerminalApp {
             });
     }
 }
-
-/// Very basic ANSI escape code stripper
-/// A real terminal would parse and render these as colors
-fn strip_ansi(s: &str) -> String {
-    let mut result = String::new();
-    let mut chars = s.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == '\x1b' {
-            // Skip until end of escape sequence
-            if chars.peek() == Some(&'[') {
-                chars.next();
-                // Skip until we hit a letter (end of CSI sequence)
-                while let Some(&ch) = chars.peek() {
-                    chars.next();
-                    if ch.is_ascii_alphabetic() { break; }
-                }
-            } else {
-                // Skip other escape types
-                chars.next();
-            }
-        } else {
-            result.push(c);
-        }
-    }
-    result
-}
\ No newline at end of file
@@ -0,0 +1,279 @@
+// src/lsp.rs
+// Language server for rshell scripts: `rshell lsp`
+//
+// Speaks LSP (JSON-RPC with Content-Length framing) over stdin/stdout, so
+// editors get feedback while editing ~/.myshellrc and scripts:
+//
+//   diagnostics — syntax errors from parser::check, on open and each edit
+//   completion  — builtins, keywords and functions; variables after `$`
+//   hover       — the help entries for a builtin
+//
+// Documents are synced whole (TextDocumentSyncKind::Full), which keeps the
+// server stateless apart from the latest text of each open file.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::executor::builtin::help_entries;
+use crate::parser::{self, ParseOptions};
+
+const KEYWORDS: &[&str] = &[
+    "if", "then", "else", "fi", "for", "in", "do", "done", "while", "function",
+];
+
+// LSP CompletionItemKind values
+const KIND_FUNCTION: u32 = 3;
+const KIND_VARIABLE: u32 = 6;
+const KIND_KEYWORD:  u32 = 14;
+
+pub fn run() -> i32 {
+    let stdin  = io::stdin();
+    let mut input  = stdin.lock();
+    let mut output = io::stdout().lock();
+    let mut server = Server::default();
+
+    while let Some(msg) = read_message(&mut input) {
+        if let Some(code) = server.handle(&msg, &mut output) {
+            return code;
+        }
+    }
+    // The client went away without `exit`
+    1
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<String, String>,
+    shutting_down: bool,
+}
+
+impl Server {
+    /// Handle one message, returning the exit status once told to exit.
+    fn handle(&mut self, msg: &Value, out: &mut impl Write) -> Option<i32> {
+        let method = msg["method"].as_str().unwrap_or("");
+        let params = &msg["params"];
+        let id     = msg.get("id").cloned();
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "completionProvider": { "triggerCharacters": ["$"] },
+                    "hoverProvider": true,
+                },
+                "serverInfo": { "name": "rshell" },
+            }),
+            "shutdown" => { self.shutting_down = true; Value::Null }
+            "exit"     => return Some(if self.shutting_down { 0 } else { 1 }),
+
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                self.update(doc["uri"].as_str()?, doc["text"].as_str()?, out);
+                return None;
+            }
+            "textDocument/didChange" => {
+                let text = params["contentChanges"].as_array()?.last()?["text"].as_str()?;
+                self.update(params["textDocument"]["uri"].as_str()?, text, out);
+                return None;
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str()?;
+                self.documents.remove(uri);
+                publish(out, uri, Vec::new());
+                return None;
+            }
+
+            "textDocument/completion" => self.completion(params),
+            "textDocument/hover"      => self.hover(params),
+
+            // Other notifications need no answer
+            _ if id.is_none() => return None,
+            _ => {
+                send(out, &json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32601, "message": format!("unsupported method: {}", method) },
+                }));
+                return None;
+            }
+        };
+
+        if id.is_some() {
+            send(out, &json!({ "jsonrpc": "2.0", "id": id, "result": result }));
+        }
+        None
+    }
+
+    /// Store a document's new text and publish its diagnostics.
+    fn update(&mut self, uri: &str, text: &str, out: &mut impl Write) {
+        let lines: Vec<&str> = text.lines().collect();
+        let diagnostics = parser::check(text, ParseOptions::default())
+            .into_iter()
+            .map(|e| {
+                let end = lines.get(e.line).map_or(0, |l| l.chars().count());
+                json!({
+                    "range": {
+                        "start": { "line": e.line, "character": 0 },
+                        "end":   { "line": e.line, "character": end },
+                    },
+                    "severity": 1,
+                    "source": "rshell",
+                    "message": e.message,
+                })
+            })
+            .collect();
+        publish(out, uri, diagnostics);
+        self.documents.insert(uri.to_string(), text.to_string());
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let Some((text, line, col)) = self.position(params) else { return json!([]) };
+        let before: Vec<char> = line_text(text, line).chars().take(col).collect();
+        let start = word_start(&before);
+        let word: String = before[start..].iter().collect();
+
+        // Replace just the word being typed, whatever the client counts as one
+        let range = json!({
+            "start": { "line": line, "character": col - word.trim_start_matches(['$', '{']).chars().count() },
+            "end":   { "line": line, "character": col },
+        });
+        let item = |label: &str, kind: u32, detail: Option<String>| json!({
+            "label": label,
+            "kind": kind,
+            "detail": detail,
+            "textEdit": { "range": range, "newText": label },
+        });
+
+        if word.starts_with('$') {
+            let vars = variables(text);
+            return vars.iter().map(|v| item(v, KIND_VARIABLE, None)).collect();
+        }
+
+        let mut items: Vec<Value> = crate::completion::builtin_names()
+            .iter()
+            .map(|name| item(name, KIND_FUNCTION, help_entries(name).into_iter().next()))
+            .collect();
+        items.extend(functions(text).iter().map(|f| item(f, KIND_FUNCTION, Some("function".to_string()))));
+        items.extend(KEYWORDS.iter().map(|k| item(k, KIND_KEYWORD, None)));
+        Value::Array(items)
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((text, line, col)) = self.position(params) else { return Value::Null };
+        let chars: Vec<char> = line_text(text, line).chars().collect();
+        let col   = col.min(chars.len());
+        let start = word_start(&chars[..col]);
+        let end   = col + chars[col..].iter().take_while(|c| is_word_char(**c)).count();
+        let word: String = chars[start..end].iter().collect();
+
+        let entries = help_entries(&word);
+        let contents = if !entries.is_empty() {
+            format!("```\n{}\n```", entries.join("\n"))
+        } else if functions(text).contains(&word) {
+            format!("`{}` — function defined in this file", word)
+        } else {
+            return Value::Null;
+        };
+        json!({ "contents": { "kind": "markdown", "value": contents } })
+    }
+
+    /// The document text and zero-based (line, character) a request is about.
+    fn position<'a>(&'a self, params: &Value) -> Option<(&'a str, usize, usize)> {
+        let text = self.documents.get(params["textDocument"]["uri"].as_str()?)?;
+        let pos  = &params["position"];
+        Some((text, pos["line"].as_u64()? as usize, pos["character"].as_u64()? as usize))
+    }
+}
+
+// ── Document scanning ─────────────────────────────────────────────────────────
+
+fn line_text(text: &str, line: usize) -> &str {
+    text.lines().nth(line).unwrap_or("")
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+/// Where the word ending at the end of `chars` starts, including a `$`
+/// or `${` in front of it.
+fn word_start(chars: &[char]) -> usize {
+    let mut start = chars.len();
+    while start > 0 && is_word_char(chars[start - 1]) { start -= 1; }
+    if start > 0 && chars[start - 1] == '{' { start -= 1; }
+    if start > 0 && chars[start - 1] == '$' { start -= 1; }
+    start
+}
+
+/// Variables worth offering after `$`: the environment, special
+/// parameters, and anything the document assigns or loops over.
+fn variables(text: &str) -> BTreeSet<String> {
+    let mut vars: BTreeSet<String> = std::env::vars().map(|(k, _)| k).collect();
+    vars.extend(["?", "#", "@", "*", "$", "!", "0", "1", "2", "3", "PIPESTATUS", "CMD_DURATION"].map(String::from));
+    for line in text.lines() {
+        let line = line.trim_start();
+        let line = ["export ", "readonly ", "declare "].iter()
+            .find_map(|kw| line.strip_prefix(kw))
+            .unwrap_or(line);
+        if let Some((name, _)) = line.split_once('=') {
+            if crate::shell::is_valid_name(name) { vars.insert(name.to_string()); }
+        }
+        if let Some(rest) = line.strip_prefix("for ") {
+            if let Some(name) = rest.split_whitespace().next() { vars.insert(name.to_string()); }
+        }
+    }
+    vars
+}
+
+/// Names of the functions the document defines.
+fn functions(text: &str) -> BTreeSet<String> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let name = line.strip_prefix("function ").unwrap_or(line);
+            let name = name.split(|c: char| c == '(' || c == '{' || c.is_whitespace()).next()?;
+            let defines = line.starts_with("function ") || line[name.len()..].trim_start().starts_with("()");
+            (defines && crate::shell::is_valid_name(name)).then(|| name.to_string())
+        })
+        .collect()
+}
+
+// ── JSON-RPC framing ──────────────────────────────────────────────────────────
+
+/// Read one message: `Content-Length` and other headers, a blank line,
+/// then the JSON body. None at end of input.
+fn read_message(input: &mut impl BufRead) -> Option<Value> {
+    loop {
+        let mut length = None;
+        loop {
+            let mut header = String::new();
+            if input.read_line(&mut header).ok()? == 0 { return None; }
+            let header = header.trim_end();
+            if header.is_empty() { break; }
+            if let Some(value) = header.strip_prefix("Content-Length:") {
+                length = value.trim().parse().ok();
+            }
+        }
+        // A message without a length can't be skipped past; wait for the next
+        let Some(length) = length else { continue };
+        let mut body = vec![0; length];
+        input.read_exact(&mut body).ok()?;
+        if let Ok(msg) = serde_json::from_slice(&body) { return Some(msg); }
+    }
+}
+
+fn send(out: &mut impl Write, msg: &Value) {
+    let body = msg.to_string();
+    write!(out, "Content-Length: {}\r\n\r\n{}", body.len(), body).ok();
+    out.flush().ok();
+}
+
+fn publish(out: &mut impl Write, uri: &str, diagnostics: Vec<Value>) {
+    send(out, &json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    }));
+}
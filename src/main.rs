@@ -1,44 +1,96 @@
 // src/main.rs
+mod ansi;
 mod shell;
 mod parser;
 mod executor;
 mod readline;
 mod completion;
 mod glob;
+mod paths;
+mod messages;
+mod lsp;
+#[cfg(feature = "selftest")]
+mod selftest;
 
 use shell::Shell;
 use readline::{ShellReadline, ReadlineError};
 
 fn main() {
-    println!(
-        "\x1b[36m
-    ██████╗ ███████╗██╗  ██╗███████╗██╗     ██╗     
-    ██╔══██╗██╔════╝██║  ██║██╔════╝██║     ██║     
-    ██████╔╝███████╗███████║█████╗  ██║     ██║     
-    ██╔══██╗╚════██║██╔══██║██╔══╝  ██║     ██║     
-    ██║  ██║███████║██║  ██║███████╗███████╗███████╗
-    ╚═╝  ╚═╝╚══════╝╚═╝  ╚═╝╚══════╝╚══════╝╚══════╝
-\x1b[0m  \x1b[90mCtrl+C cancel  Ctrl+D exit  Ctrl+Z suspend  Ctrl+L clear\x1b[0m
-"
-    );
+    if std::env::args().nth(1).as_deref() == Some("--selftest") {
+        #[cfg(feature = "selftest")]
+        std::process::exit(selftest::run());
+        #[cfg(not(feature = "selftest"))]
+        {
+            eprintln!("myshell: --selftest requires a build with `--features selftest`");
+            std::process::exit(2);
+        }
+    }
+
+    // rshell --job FILE — a background block or builtin (see detached.rs)
+    if std::env::args().nth(1).as_deref() == Some("--job") {
+        let Some(file) = std::env::args().nth(2) else { std::process::exit(2) };
+        std::process::exit(executor::detached::run(std::path::Path::new(&file)));
+    }
+
+    // rshell -c CMD — run one command line and exit (used by `schedule`)
+    if std::env::args().nth(1).as_deref() == Some("-c") {
+        let Some(cmd) = std::env::args().nth(2) else {
+            eprintln!("myshell: -c requires a command");
+            std::process::exit(2);
+        };
+        std::process::exit(run_command_line(&cmd));
+    }
+
+    // rshell -n [--posix] [FILE] — check a script's syntax without running it
+    if std::env::args().nth(1).as_deref() == Some("-n") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        std::process::exit(run_syntax_check(&args));
+    }
+
+    // rshell lsp — language server for editors, over stdin/stdout
+    if std::env::args().nth(1).as_deref() == Some("lsp") {
+        std::process::exit(lsp::run());
+    }
+
+    // rshell schedule — run `schedule` jobs without an interactive shell
+    if std::env::args().nth(1).as_deref() == Some("schedule") {
+        std::process::exit(executor::builtin::run_scheduler());
+    }
+
+    // rshell parse --dump [--json] [--posix] [INPUT] — print the parsed AST
+    if std::env::args().nth(1).as_deref() == Some("parse") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        std::process::exit(run_parse_dump(&args));
+    }
 
     // Set up Ctrl+Z signal handler on Unix
     #[cfg(unix)]
     setup_signals();
+    // Run each foreground job in its own process group on the terminal
+    executor::jobctl::enable();
 
     let mut shell = Shell::new();
+    print_banner(&shell.config.ui);
     shell.load_history();
 
     if let Err(e) = shell.load_rc() {
         eprintln!("myshell: warning: failed to load .myshellrc: {e}");
     }
 
+    // Runs jobs added with `schedule add` while the shell is open
+    executor::builtin::start_scheduler();
+
     let mut readline = ShellReadline::new();
 
     loop {
+        // The prompt and line editor stay on the terminal through `exec >`
+        executor::builtin::exec::to_terminal();
+
         // Check and report any completed background jobs
         check_background_jobs(&mut shell);
 
+        shell.run_hooks("precmd", &[]);
+        readline.sync_shell(&shell);
         let prompt = shell.build_prompt();
         let mut input = String::new();
 
@@ -54,7 +106,7 @@ fn main() {
                     let line: String = line.trim_end().to_string();
                     if !input.is_empty() { input.push('\n'); }
                     input.push_str(&line);
-                    if is_incomplete(&input) { continue; }
+                    if parser::is_incomplete(&input) { continue; }
                     else { break; }
                 }
                 Err(ReadlineError::Interrupted) => {
@@ -77,30 +129,165 @@ fn main() {
         let input = shell.expand_history(&input);
         if input.is_empty() { continue; }
 
-        shell.history.push(input.clone());
-        shell.save_history_line(&input);
+        // Decided before running so `set +o nohistory` itself isn't recorded
+        let mut record = shell.should_record(&input);
+        if record {
+            shell.history.push(input.clone());
+            shell.save_history_line(&input);
+        }
+        shell.recorded_line = record.then(|| input.clone());
 
+        let started_at = std::time::SystemTime::now();
+        let started_in = shell.cwd.clone();
+        let timer = std::time::Instant::now();
+        shell.run_hooks("preexec", std::slice::from_ref(&input));
+        executor::builtin::exec::to_session();
         if let Err(e) = shell.eval(&input) {
             eprintln!("\x1b[31mmyshell: {e}\x1b[0m");
             shell.last_exit_code = 1;
         }
+        let elapsed = timer.elapsed();
+        shell.last_duration = Some(elapsed);
+        shell.recorded_line = None;
+        // `history --forget PAT` has already taken its own line back out
+        if std::mem::take(&mut shell.forgot_history) {
+            record = false;
+        }
+        if record {
+            shell.record_history_entry(&input, &started_in, started_at, elapsed);
+        }
+        report_duration(&shell, elapsed);
+    }
+}
+
+/// The banner and key hints, as [ui] in config.toml asks. Input that isn't
+/// a terminal, such as a script piped in, gets neither.
+fn print_banner(ui: &shell::config::UiConfig) {
+    use std::io::IsTerminal;
+    if !std::io::stdin().is_terminal() { return; }
+    let text = messages::get();
+    match ui.banner.as_str() {
+        "none"    => {}
+        "default" => print!("\x1b[36m{}\x1b[0m", text.banner),
+        custom    => println!("{}", custom),
+    }
+    if ui.hints {
+        println!("  \x1b[90m{}\x1b[0m", text.hints);
     }
+    if ui.banner != "none" || ui.hints { println!(); }
+}
+
+/// "took 12.5s" after a command line slower than [ui] report_duration.
+/// Always on the terminal, even under `exec >`.
+fn report_duration(shell: &Shell, elapsed: std::time::Duration) {
+    let threshold = shell.config.ui.report_duration;
+    if threshold <= 0.0 || elapsed.as_secs_f64() < threshold { return; }
+    executor::builtin::exec::to_terminal();
+    eprintln!("\x1b[90mtook {}\x1b[0m", executor::builtin::format_ms(elapsed.as_millis() as u64));
+}
+
+/// Non-interactive mode: evaluate `cmd` with the user's rc loaded and
+/// return its exit status.
+fn run_command_line(cmd: &str) -> i32 {
+    let mut shell = Shell::new();
+    if let Err(e) = shell.load_rc() {
+        eprintln!("myshell: warning: failed to load .myshellrc: {e}");
+    }
+    if let Err(e) = shell.eval(cmd) {
+        eprintln!("myshell: {e}");
+        shell.last_exit_code = 1;
+    }
+    use std::io::Write;
+    std::io::stdout().flush().ok();
+    shell.last_exit_code
+}
+
+/// `rshell parse --dump`: print the AST for INPUT, or for stdin if none is
+/// given, without running anything.
+fn run_parse_dump(args: &[String]) -> i32 {
+    let mut json  = false;
+    let mut posix = false;
+    let mut input = None;
+    for arg in args {
+        match arg.as_str() {
+            "--dump"  => {}
+            "--json"  => json = true,
+            "--posix" => posix = true,
+            s if s.starts_with('-') && s != "-" => {
+                eprintln!("usage: rshell parse --dump [--json] [--posix] [INPUT]");
+                return 2;
+            }
+            s => input = Some(s.to_string()),
+        }
+    }
+
+    let input = match input.filter(|s| s != "-") {
+        Some(s) => s,
+        None => {
+            let mut s = String::new();
+            if let Err(e) = std::io::Read::read_to_string(&mut std::io::stdin(), &mut s) {
+                eprintln!("myshell: parse: {e}");
+                return 1;
+            }
+            s
+        }
+    };
+
+    match parser::dump(&input, parser::ParseOptions { posix }, json) {
+        Ok(out) => { println!("{out}"); 0 }
+        Err(e)  => { eprintln!("myshell: parse: {e}"); 1 }
+    }
+}
+
+/// `rshell -n`: report every syntax error in FILE, or stdin, as
+/// `FILE:LINE: message`, without running anything. Like `sh -n`, the status
+/// is 2 if there were errors.
+fn run_syntax_check(args: &[String]) -> i32 {
+    let mut posix = false;
+    let mut file = None;
+    for arg in args {
+        match arg.as_str() {
+            "--posix" => posix = true,
+            s if s.starts_with('-') && s != "-" => {
+                eprintln!("usage: rshell -n [--posix] [FILE]");
+                return 2;
+            }
+            s => file = Some(s.to_string()),
+        }
+    }
+
+    let (name, text) = match file.filter(|f| f != "-") {
+        Some(f) => match std::fs::read_to_string(&f) {
+            Ok(text) => (f, text),
+            Err(e) => { eprintln!("myshell: {f}: {e}"); return 1; }
+        },
+        None => {
+            let mut s = String::new();
+            if let Err(e) = std::io::Read::read_to_string(&mut std::io::stdin(), &mut s) {
+                eprintln!("myshell: -n: {e}");
+                return 1;
+            }
+            ("<stdin>".to_string(), s)
+        }
+    };
+
+    let errors = parser::check(&text, parser::ParseOptions { posix });
+    for error in &errors {
+        eprintln!("{}:{}: {}", name, error.line + 1, error.message);
+    }
+    if errors.is_empty() { 0 } else { 2 }
 }
 
 /// Check for completed background jobs and notify user
 fn check_background_jobs(shell: &mut Shell) {
-    shell.reap_jobs();
-    let done: Vec<_> = shell.jobs.iter()
-        .filter(|(_, j)| j.status == shell::JobStatus::Done)
-        .map(|(id, j)| (*id, j.command.clone()))
-        .collect();
-
-    for (id, cmd) in done {
-        println!("[{}] Done  {}", id, cmd);
-        shell.jobs.remove(&id);
+    for job in shell.take_finished_jobs() {
+        println!("[{}] {}  {}", job.id, job.status, job.command);
     }
 }
 
+#[cfg(unix)]
+extern "C" fn on_sigint(_: libc::c_int) {}
+
 /// Set up Unix signal handlers
 #[cfg(unix)]
 fn setup_signals() {
@@ -108,26 +295,10 @@ fn setup_signals() {
         // Ignore SIGTTOU so we can write to terminal from background
         libc::signal(libc::SIGTTOU, libc::SIG_IGN);
         libc::signal(libc::SIGTTIN, libc::SIG_IGN);
+        // Under job control Ctrl+C goes to the foreground job's process
+        // group only; without it (no terminal) it reaches the shell too.
+        // Catch it with a no-op handler rather than SIG_IGN so children
+        // still get the default action after exec.
+        libc::signal(libc::SIGINT, on_sigint as *const () as libc::sighandler_t);
     }
-}
-
-fn is_incomplete(input: &str) -> bool {
-    let trimmed = input.trim_end();
-    if trimmed.ends_with('|')
-        || trimmed.ends_with("&&")
-        || trimmed.ends_with("||")
-        || trimmed.ends_with('\\')
-    {
-        return true;
-    }
-    let mut in_single = false;
-    let mut in_double = false;
-    for ch in trimmed.chars() {
-        match ch {
-            '\'' if !in_double => in_single = !in_single,
-            '"'  if !in_single => in_double = !in_double,
-            _ => {}
-        }
-    }
-    in_single || in_double
 }
\ No newline at end of file
@@ -0,0 +1,370 @@
+// src/messages.rs
+// The shell's fixed user-visible text — the startup banner, the hint line
+// under it, and the `help` pages — in one table per language, so that a
+// translation can be contributed by adding a table rather than by editing
+// the code that shows the text.
+//
+// The language is [ui] language in ~/.rshell/config.toml, or else the
+// first of LC_ALL, LC_MESSAGES and LANG that is set. `pt_BR.UTF-8` picks
+// a pt_BR table, then a pt one, then English.
+//
+// To add a language, write a `const` like ENGLISH with its own
+// `language` code, taking whatever isn't translated yet from English with
+// `..ENGLISH`, and list it in TABLES. Help topics a table leaves out are
+// shown in English.
+
+use std::sync::OnceLock;
+
+pub struct Messages {
+    /// Language code, as in LANG: `de`, `pt_BR`
+    pub language: &'static str,
+    pub banner: &'static str,
+    /// Shown under the banner: the keys worth knowing about
+    pub hints: &'static str,
+    /// `help` pages by topic
+    pub help: &'static [(&'static str, &'static str)],
+}
+
+impl Messages {
+    /// The `help` page for `topic`, in English if this table has none.
+    pub fn help(&self, topic: &str) -> Option<&'static str> {
+        let find = |table: &Messages| table.help.iter().find(|(name, _)| *name == topic).map(|(_, text)| *text);
+        find(self).or_else(|| find(&ENGLISH))
+    }
+}
+
+const TABLES: &[&Messages] = &[&ENGLISH];
+
+static CURRENT: OnceLock<&'static Messages> = OnceLock::new();
+
+/// Settle the language, from `configured` ([ui] language) if it isn't
+/// empty. Until this is called the locale alone decides.
+pub fn init(configured: &str) {
+    CURRENT.get_or_init(|| pick(configured));
+}
+
+pub fn get() -> &'static Messages {
+    CURRENT.get_or_init(|| pick(""))
+}
+
+fn pick(configured: &str) -> &'static Messages {
+    let wanted = if configured.is_empty() {
+        ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+    } else {
+        configured.to_string()
+    };
+    table_for(&wanted)
+}
+
+fn table_for(locale: &str) -> &'static Messages {
+    // Drop the encoding and modifier: `de_DE.UTF-8@euro` is `de_DE`
+    let locale = locale.split(['.', '@']).next().unwrap_or("");
+    let language = locale.split(['_', '-']).next().unwrap_or("");
+    TABLES.iter().find(|t| t.language == locale)
+        .or_else(|| TABLES.iter().find(|t| t.language == language))
+        .copied()
+        .unwrap_or(&ENGLISH)
+}
+
+// ── English ───────────────────────────────────────────────────────────────────
+
+const ENGLISH: Messages = Messages {
+    language: "en",
+    banner: "
+    ██████╗ ███████╗██╗  ██╗███████╗██╗     ██╗     
+    ██╔══██╗██╔════╝██║  ██║██╔════╝██║     ██║     
+    ██████╔╝███████╗███████║█████╗  ██║     ██║     
+    ██╔══██╗╚════██║██╔══██║██╔══╝  ██║     ██║     
+    ██║  ██║███████║██║  ██║███████╗███████╗███████╗
+    ╚═╝  ╚═╝╚══════╝╚═╝  ╚═╝╚══════╝╚══════╝╚══════╝
+",
+    hints: "Ctrl+C cancel  Ctrl+D exit  Ctrl+Z suspend  Ctrl+L clear",
+    help: &[
+        ("overview", r#"
+╔══════════════════════════════════════════════╗
+║          rshell  —  Built-in Commands        ║
+╚══════════════════════════════════════════════╝
+
+  Type 'help <topic>' for details on a topic, or 'CMD --help' for the
+  options of a file or text command such as ls, grep or head.
+
+  Topics:
+    help nav          Navigation  (cd, pushd, popd ...)
+    help files        Files       (ls, cp, mv, rm ...)
+    help search       Search      (grep, find)
+    help text         Text        (head, tail, wc, sort ...)
+    help shell        Shell       (alias, export, history ...)
+    help editor       Editor      (mini)
+    help jobs         Job Control (jobs, fg, bg ...)
+    help pkg          Packages    (install, uninstall ...)
+    help scripting    Scripting   (if, for, $VAR, globs ...)
+    help all          Show everything at once
+"#),
+        ("nav", r#"
+  Navigation:
+    cd [dir]           Change directory (- for previous, ~ for home)
+    pwd                Print working directory
+    pushd [dir]        Push directory onto stack and cd
+    pushd +N           Rotate the stack so entry N (from dirs -v) is on top
+    popd [+N]          Pop directory stack and cd back (+N: drop entry N)
+    dirs [-v]          Show directory stack (-v numbered, -c clear)
+"#),
+        ("files", r#"
+  Files:
+    ls [-la] [dir]     List directory contents (-t newest first,
+                       -S largest first, -r reversed, -R recursive)
+                       (--git: long format with git status)
+                       (-l columns: [ls] in ~/.rshell/config.toml;
+                        sizes: -h binary, --si, --bytes, or [format])
+    mkdir [-p] DIR     Create directory
+    rm [-rf] FILE      Remove file or directory (-t: to the trash)
+    trash FILE...      Move files to the trash; --list, --restore NAME
+                       puts one back, --empty deletes them for good
+    cp [-r] SRC DEST   Copy file or directory (-i ask before overwriting,
+                       -n never overwrite, -p keep mode and times, -v)
+    mv SRC DEST        Move or rename file (-i, -n and -v as for cp)
+    rename FROM TO [FILE...]  Rename in bulk: %.jpeg %.jpg, or a regex
+                       and replacement ('s/ /_/g' FILE...); -n to preview
+    cat [FILE]         Print file contents (stdin if no FILE or -);
+                       -n number lines, -A show tabs, ends and control
+                       bytes, -x hex dump (binary shows as hex anyway)
+    open FILE|URL      Open with the default app (-a APP to choose one)
+    touch FILE         Create or update file timestamp
+    chmod [-R] MODE FILE  Change file permissions (-R: and all inside)
+    chown [-R] USER[:GROUP] FILE  Change file owner and group
+    ln [-s] SRC DEST   Create hard or symbolic link
+    du [-hs] [-d N] [PATH]  Disk space used by each directory
+                       (-s total only, -d N levels deep)
+    stat [-L] [-c FMT] FILE  Type, size, permissions, owner and times
+                       (-c FMT: %n %s %a %A %U %G %y %Y %F ...)
+    file [-b] FILE     Guess what a file holds from its first bytes
+                       (ELF/PE, images, archives, text encoding)
+    sha256sum [FILE]   SHA-256 of each file (md5sum: MD5); -c LIST checks
+                       files against HASH  NAME lines
+    basename NAME [SUFFIX]  Last part of a path (less SUFFIX)
+    dirname NAME       Path without its last part
+    realpath [-s] PATH Absolute path with symlinks and .. resolved
+                       (-s: without resolving symlinks)
+    fetch URL [-o FILE|-O]  Download URL to stdout or FILE; -H 'K: V',
+                       -X METHOD and -d BODY (or @FILE) for APIs
+    serve [DIR] [-p PORT]  Share DIR over HTTP with directory listings
+                       (port 8000; -b 127.0.0.1 for this machine only)
+    ping [-c N] HOST   Check HOST answers (ICMP, or TCP connects
+                       to -p PORT where ICMP isn't allowed)
+    port HOST:PORT     Check a TCP port is open, closed or filtered
+    cleanup [-n|-y] [DIR]  Show build output and caches that can be
+                       rebuilt (target/, node_modules/ ...) and remove
+                       them after asking (-t: empty the trash too)
+"#),
+        ("search", r#"
+  Search:
+    grep [-rnivc] PAT [FILE]   Search for pattern in files
+      -r  recursive   -n  line numbers   -i  ignore case
+      -v  invert      -c  count matches  -w  whole words
+      -E  extended regex  -F  plain text -o  only the matches
+      -A/-B/-C N  lines of context after/before/around matches
+      -l/-L  only names of files with/without a match
+      -H/-h  always/never show file names   -m N  stop after N
+      --include/--exclude GLOB, --exclude-dir GLOB  pick files for -r
+      --gitignore  skip .git and ignored files   -I  skip binary files
+    find [DIR] [-name PATTERN] Search for files by name
+      -type f/d/l   -maxdepth/-mindepth N   -empty   -newer FILE
+      -size [+-]N[ckMG]  bigger/smaller   -mtime [+-]N  days since changed
+      -not/!  -o (or)  ( ... )   -print   -delete (after its tests)
+"#),
+        ("text", r#"
+  Text Processing (each reads stdin when no FILE is given):
+    head [-n N|-c N] [FILE]  Show first N lines (default 10) or bytes
+      -n -N / -c -N  everything but the last N lines or bytes
+    tail [-n N|-c N] [-f|-F] [FILE]  Show last N lines (default 10) or bytes
+      -n +N / -c +N  everything from line or byte N on
+      -f  keep printing lines added to FILE, until Ctrl+C
+      -F  the same, reopening FILE when it's rotated or recreated
+    wc [-lwmcL] [-h|--si] [FILE]  Count lines (-l), words (-w), chars (-m),
+                       bytes (-c), or the longest line's width (-L)
+    sort [-rnhfu] [FILE]  Sort lines (-r reverse, -n numeric, -h sizes, -f any case)
+      -k F[,F]  by fields F to F (2n, 1.3 ...)   -t SEP  fields split at SEP
+    uniq [-c] [FILE]   Remove duplicate lines (-c count)
+    cut -f LIST [-d DELIM] [-s] [FILE]  Print fields (1,3 or 2-4 or 5-)
+    cut -c LIST [FILE] Print characters at those positions
+    tr [-ds] SET1 [SET2]  Translate characters of stdin (a-z, [:upper:]);
+                       -d delete SET1, -s squeeze repeats
+    xargs [CMD]        Run CMD with each line of stdin as one argument,
+                       spaces and all (not split into words)
+      -I {}  once per line, {} replaced by it   -n N  N lines per run
+      -0  NUL-separated items   -P N  run N commands at a time
+    sed [-n] [-E] [-i[SUF]] SCRIPT [FILE]
+                       Edit lines: s/PAT/REPL/[gip], d, p, with
+                       addresses N, $, /PAT/ and ranges A,B
+    env                Show exported environment variables
+    env [-i] [-u VAR] [VAR=val] CMD  Run CMD with a clean (-i) or changed
+                       environment; without CMD, show it
+    env snapshot [NAME]  Remember the exported variables as they are now
+    env diff [NAME]    Show variables added (+), removed (-) or changed (~)
+                       since the snapshot
+"#),
+        ("shell", r#"
+  Shell:
+    echo [-n] [args]   Print text (-n no newline)
+    printf FMT [args]  Formatted output (%s %d %x %f %q, width/precision)
+    yes [STRING]       Print STRING (default y) until the reader stops
+    expr EXPR          Evaluate: expr 1 + 2, expr \( 3 \* 4 \), expr S : RE,
+                       length S, substr S POS LEN, index S CHARS
+    quote [args]       Print arguments as shell-safe quoted words
+    whoami, hostname [-s]  The current user and this machine's name
+    uname [-a|-srnvmo] System name, kernel release, hardware ...
+    uptime [-p|-s]     Time since boot and load (-p in words, -s boot time)
+    VAR=VAL            Set a shell variable (not passed to commands)
+    VAR=VAL cmd        Run cmd with VAR in its environment only
+    export [-n] [VAR[=VAL]]  Pass variables to commands, or list them
+    declare [-x|+x] [-r] [VAR[=VAL]]  Set variables and attributes
+    readonly [VAR[=VAL]]  Make variables unchangeable
+    unset VAR          Remove a variable
+    set                List all variables
+    alias [k=v]        Set or show aliases (--search PAT to filter)
+    unalias NAME       Remove alias
+    wrap NAME [--before CMD] [--after CMD]  Run commands around NAME
+    wrap -r NAME       Remove a wrapper (no args: list wrappers)
+    hook add EVENT FUNC  Run a function before each command (preexec, with
+                       the line as $1), before each prompt (precmd) or
+                       after the directory changes (chpwd)
+    hook rm EVENT FUNC Remove a hook (no args: list hooks)
+    history [N|PAT]    Show command history (the last N, or lines with PAT)
+      -t  with times   -c  clear   -d N  delete entry N (-1 the last)
+    history --forget PAT   Remove matching entries from history and stats
+    fc [-e EDITOR] [FIRST [LAST]]  Edit past commands, then run them
+    fc -s [OLD=NEW] [CMD]  Run the last command (or CMD...) again, OLD -> NEW
+    set -o nohistory   Stop recording history (set +o to resume)
+    set -o posix       POSIX sh mode: then/fi blocks only, POSIX expansion
+    set -o pipefail    A pipeline fails if any stage does, not just the last
+    set -o autocorrect Offer to run the closest command when one isn't found
+    set -o fuzzycomplete  Tab-complete by letters in order (gcm: git-commit-msg)
+    stats [-n N] [--json]  Top commands, directories, durations, failures
+    schedule add "M H D MON WD" CMD   Run CMD on a cron schedule
+    schedule [list] | rm ID   Show or remove jobs (rshell schedule runs
+                       them without an interactive shell)
+    source FILE        Execute commands from a file
+    exec CMD [args]    Replace the shell with CMD
+    exec > FILE 2>&1   Send the rest of the session's output to FILE
+                       (any of < > >> 2> 2>&1; exec -r to undo)
+    sandbox run CMD    Run CMD with a temporary HOME and . read-only, then
+                       list the files it wrote (-w: . writable, -n: no
+                       network, -k: keep the HOME)
+    functions          List defined shell functions (--search PAT to filter)
+    shift [n]          Drop the first n positional parameters ($1, $2, ...)
+    getopts OPTS NAME  Parse flags into NAME, with OPTARG and OPTIND
+    umask [-S] [MODE]  Show or set the file creation mask
+    ulimit [-a] [-n N] Show or set resource limits (-c -d -f -n -s -t -u -v)
+    which [-a] CMD     Show what CMD runs (-a: every match on PATH too)
+    type [-a|-t] NAME  Show whether NAME is an alias, function, builtin or file
+    command CMD [args] Run CMD skipping aliases and functions (-v: locate)
+    hash [-r] [NAME]   Show, fill (NAME) or reset (-r) the command path cache
+    hash -d NAME=DIR   Name a directory so ~NAME expands to it
+    complete -W WORDS CMD  Tab-complete CMD's arguments from WORDS
+                       (-G GLOB: matching files, -F FUNC: what FUNC prints;
+                       -p: show, -r: remove)
+    guard [list]       Show rules that confirm or block commands before they run
+    guard add [--block] REGEX [MSG]   Add a rule (confirm by default); rm N, test CMD
+    explain [STATUS]   Describe an exit status (default: $?)
+    explain 'CMD LINE' Show how a line parses: pipelines, redirects and
+                       the expansions left for each word
+    builtin CMD [args] Run a shell builtin even if a function shadows it
+    clear / cls        Clear the screen
+    sleep SECS         Wait for N seconds
+    help [topic]       Show this help (--search PAT searches every topic)
+    exit               Exit rshell
+"#),
+        ("editor", r#"
+  Editor:
+    mini FILE          Open file in built-in text editor
+
+    Keybindings:
+      Ctrl+S           Save
+      Ctrl+Z           Undo
+      Ctrl+Q           Quit
+      Arrow keys       Move cursor
+      Home / End       Start / end of line
+
+    Syntax highlighting is applied automatically based on
+    file extension: .rs  .py  .js  .ts  .sh
+"#),
+        ("jobs", r#"
+  Job Control:
+    jobs               List background jobs
+    fg [%id]           Bring job to foreground
+    bg [%id]           Resume stopped job in background
+    %N %+ %- %str %?str  Job specs: number, current, previous, command
+                       starting with / containing str
+    disown [-h] [%id]  Forget a job so closing the terminal won't hang
+                       it up (-h: keep it listed, -a: all jobs)
+    kill [%id|pid]     Kill a job or process (SIGTERM)
+    kill -SIG %id|pid  Send a signal: -9, -KILL, -s TERM
+    kill -l [STATUS]   List signals, or name the one behind a status
+    CMD &              Run a command, pipeline or block in background
+    Ctrl+Z             Suspend foreground job
+"#),
+        ("pkg", r#"
+  Package Manager:
+    pkg list           List installed packages
+    install PKG        Download and install a package
+    uninstall PKG      Remove an installed package
+"#),
+        ("scripting", r#"
+  Scripting:
+    Control flow:
+      if CMD; then ... fi
+      if CMD; then ... else ... fi
+      for VAR in ITEMS; do ... done
+      while CMD; do ... done
+      function name() { ... }
+
+    Expansion:
+      $VAR / ${VAR}      variable
+      $((2 + 2))         arithmetic
+      $(command)         command substitution
+      $#                 number of arguments
+      $@  $*             all arguments
+      $?                 last exit code
+      ${PIPESTATUS[@]}   exit code of each stage of the last pipeline
+                         (${PIPESTATUS[N]} for one)
+      $$                 current process id
+      $!                 process id of the last background job
+      $CMD_DURATION      milliseconds the last command line took
+                         ([ui] report_duration: say "took 4.2s" past it)
+
+    Globs:
+      *                  match any characters
+      ?                  match one character
+      [abc]              match character class
+      [a-z]              match character range
+
+  Operators:
+    |   pipe      &&  and    ||  or    ;  sequence   &  background
+    >   stdout    >>  append  <  stdin  2>  stderr
+"#),
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locales_fall_back_to_english() {
+        assert_eq!(table_for("en_GB.UTF-8").language, "en");
+        assert_eq!(table_for("xx_YY.UTF-8@euro").language, "en");
+        assert_eq!(table_for("C").language, "en");
+        assert_eq!(table_for("").language, "en");
+    }
+
+    #[test]
+    fn every_table_has_a_language() {
+        for table in TABLES {
+            assert!(!table.language.is_empty());
+            assert!(table.help("overview").is_some());
+        }
+    }
+}
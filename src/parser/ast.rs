@@ -4,14 +4,18 @@
 // so it thinks they are unused. But they are needed for the parser to construct the AST,
 // so we need to keep them around, hense the #[allow(dead_code)].
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum Command {
     Simple {
+        /// Leading `NAME=value` words. With no args they set shell
+        /// variables; otherwise they only apply to this command.
+        assigns: Vec<(String, String)>,
         args: Vec<String>,
         redirects: Vec<Redirect>,
-        background: bool,
     },
     Pipeline(Vec<Command>),
+    /// `cmd &` — any command, pipeline or block run detached as a job.
+    Background(Box<Command>),
     And(Box<Command>, Box<Command>),
     Or(Box<Command>, Box<Command>),
     Sequence(Box<Command>, Box<Command>),
@@ -34,18 +38,76 @@ pub enum Command {
         name: String,
         args: Vec<String>,
     },
-    /// Function definition
+    /// Function definition: `name() { ... }` or `function name { ... }`
     FunctionDef {
         name: String,
-        body: Vec<String>,
+        body: Vec<Command>,
+        /// The body's commands as written, for listing and saving.
+        source: Vec<String>,
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum Redirect {
     StdoutTo(String),
     StdoutAppend(String),
     StdinFrom(String),
     StderrTo(String),
     StderrToStdout,
-}
\ No newline at end of file
+}
+// ── Display ───────────────────────────────────────────────────────────────────
+
+/// Roughly the source a command was parsed from, for job listings.
+impl std::fmt::Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Command::Simple { assigns, args, redirects } => {
+                let words: Vec<String> = assigns.iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .chain(args.iter().cloned())
+                    .chain(redirects.iter().map(|r| r.to_string()))
+                    .collect();
+                write!(f, "{}", words.join(" "))
+            }
+            Command::Pipeline(cmds) => {
+                let stages: Vec<String> = cmds.iter().map(|c| c.to_string()).collect();
+                write!(f, "{}", stages.join(" | "))
+            }
+            Command::Background(cmd)   => write!(f, "{} &", cmd),
+            Command::And(a, b)         => write!(f, "{} && {}", a, b),
+            Command::Or(a, b)          => write!(f, "{} || {}", a, b),
+            Command::Sequence(a, b)    => write!(f, "{}; {}", a, b),
+            Command::If { condition, body, else_body } => {
+                write!(f, "if {}; then {}", condition, join_body(body))?;
+                if let Some(else_body) = else_body { write!(f, " else {}", join_body(else_body))?; }
+                write!(f, " fi")
+            }
+            Command::For { var, items, body } =>
+                write!(f, "for {} in {}; do {} done", var, items.join(" "), join_body(body)),
+            Command::While { condition, body } =>
+                write!(f, "while {}; do {} done", condition, join_body(body)),
+            Command::FunctionCall { name, args } => write!(f, "{} {}", name, args.join(" ")),
+            Command::FunctionDef { name, source, .. } => {
+                let body: Vec<String> = source.iter().map(|c| format!("{};", c)).collect();
+                write!(f, "{}() {{ {} }}", name, body.join(" "))
+            }
+        }
+    }
+}
+
+/// Block bodies as `cmd; cmd;`.
+fn join_body(body: &[Command]) -> String {
+    body.iter().map(|c| format!("{};", c)).collect::<Vec<_>>().join(" ")
+}
+
+impl std::fmt::Display for Redirect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Redirect::StdoutTo(file)     => write!(f, "> {}", file),
+            Redirect::StdoutAppend(file) => write!(f, ">> {}", file),
+            Redirect::StdinFrom(file)    => write!(f, "< {}", file),
+            Redirect::StderrTo(file)     => write!(f, "2> {}", file),
+            Redirect::StderrToStdout     => write!(f, "2>&1"),
+        }
+    }
+}
@@ -1,21 +1,25 @@
 // src/parser/block.rs
 //
-// Parsers for block-level control flow: if, for, while.
-// Also contains the shared helpers for extracting block bodies
+// Parsers for block-level control flow: if, for, while, and function
+// definitions. Also contains the shared helpers for extracting block bodies
 // (brace style { } and keyword style then...fi / do...done).
 
 use anyhow::{Result, bail};
 use super::ast::Command;
+use super::tokenizer::{tokenize, Token};
+use super::ParseOptions;
 
 // ── Control flow parsers ──────────────────────────────────────────────────────
 
 /// Parse: if <condition>; then <body> [else <else_body>] fi
 /// Or:    if <condition> { <body> } [else { <else_body> }]
-pub fn parse_if(input: &str) -> Result<Command> {
+pub fn parse_if(input: &str, opts: ParseOptions) -> Result<Command> {
     let rest = input[2..].trim();
 
     let (cond_str, remainder) = if let Some(then_pos) = find_keyword(rest, "then") {
         (rest[..then_pos].trim(), rest[then_pos + 4..].trim())
+    } else if opts.posix {
+        bail!("if: expected 'then' (brace blocks are disabled in posix mode)");
     } else if rest.contains('{') {
         let brace = rest.find('{').unwrap();
         (rest[..brace].trim(), rest[brace..].trim())
@@ -23,9 +27,9 @@ pub fn parse_if(input: &str) -> Result<Command> {
         bail!("if: expected 'then' or '{{'");
     };
 
-    let condition = super::parse(cond_str)?;
-    let (body_str, else_str) = extract_block(remainder)?;
-    let body = parse_block_lines(&body_str)?;
+    let condition = super::parse(cond_str, opts)?;
+    let (body_str, else_str, after) = extract_block(remainder)?;
+    let body = parse_block_lines(&body_str, opts)?;
 
     let else_body = if let Some(else_content) = else_str {
         let else_content = else_content.trim();
@@ -35,21 +39,22 @@ pub fn parse_if(input: &str) -> Result<Command> {
         } else {
             else_content.to_string()
         };
-        Some(parse_block_lines(&else_content)?)
+        Some(parse_block_lines(&else_content, opts)?)
     } else {
         None
     };
 
-    Ok(Command::If {
+    let block = Command::If {
         condition: Box::new(condition),
         body,
         else_body,
-    })
+    };
+    followed_by(block, after, opts)
 }
 
 /// Parse: for <var> in <items...>; do <body> done
 /// Or:    for <var> in <items...> { <body> }
-pub fn parse_for(input: &str) -> Result<Command> {
+pub fn parse_for(input: &str, opts: ParseOptions) -> Result<Command> {
     let rest = input[3..].trim();
 
     let var_end = rest.find(|c: char| c.is_whitespace())
@@ -62,41 +67,46 @@ pub fn parse_for(input: &str) -> Result<Command> {
     }
     let rest = rest[2..].trim();
 
-    let (items_str, body_str) = if let Some(do_pos) = find_keyword(rest, "do") {
-        let items = rest[..do_pos].trim();
+    let (items_str, body_str, after) = if let Some(do_pos) = find_keyword(rest, "do") {
+        let items = rest[..do_pos].trim().trim_end_matches(';');
         let body_and_done = rest[do_pos + 2..].trim();
-        let (body, _) = extract_block_done(body_and_done)?;
-        (items.to_string(), body)
+        let (body, after) = extract_block_done(body_and_done)?;
+        (items.to_string(), body, after.to_string())
+    } else if opts.posix {
+        bail!("for: expected 'do' (brace blocks are disabled in posix mode)");
     } else if let Some(brace_pos) = rest.find('{') {
         let items = rest[..brace_pos].trim();
-        let (body, _) = extract_block(&rest[brace_pos..])?;
-        (items.to_string(), body)
+        let (body, _, after) = extract_block(&rest[brace_pos..])?;
+        (items.to_string(), body, after)
     } else {
         bail!("for: expected 'do' or '{{'");
     };
 
-    let items: Vec<String> = items_str
-        .split_whitespace()
-        .flat_map(|item| {
-            if item.contains('*') || item.contains('?') {
-                expand_glob(item)
-            } else {
-                vec![item.to_string()]
-            }
-        })
-        .collect();
+    // Items are expanded and globbed when the loop runs, in the current
+    // directory. POSIX keeps the raw words for its expansion pass; otherwise
+    // they are just split on whitespace.
+    let items: Vec<String> = if opts.posix {
+        tokenize(&items_str, opts)?
+            .into_iter()
+            .filter_map(|t| match t { Token::Word(w) => Some(w), _ => None })
+            .collect()
+    } else {
+        items_str.split_whitespace().map(String::from).collect()
+    };
 
-    let body = parse_block_lines(&body_str)?;
+    let body = parse_block_lines(&body_str, opts)?;
 
-    Ok(Command::For { var, items, body })
+    followed_by(Command::For { var, items, body }, after, opts)
 }
 
 /// Parse: while <condition>; do <body> done
-pub fn parse_while(input: &str) -> Result<Command> {
+pub fn parse_while(input: &str, opts: ParseOptions) -> Result<Command> {
     let rest = input[5..].trim();
 
     let (cond_str, remainder) = if let Some(do_pos) = find_keyword(rest, "do") {
         (rest[..do_pos].trim(), rest[do_pos + 2..].trim())
+    } else if opts.posix {
+        bail!("while: expected 'do' (brace blocks are disabled in posix mode)");
     } else if rest.contains('{') {
         let brace = rest.find('{').unwrap();
         (rest[..brace].trim(), rest[brace..].trim())
@@ -104,27 +114,83 @@ pub fn parse_while(input: &str) -> Result<Command> {
         bail!("while: expected 'do' or '{{'");
     };
 
-    let condition = super::parse(cond_str)?;
-    let (body_str, _) = extract_block(remainder)?;
-    let body = parse_block_lines(&body_str)?;
+    let condition = super::parse(cond_str, opts)?;
+    let (body_str, after) = if remainder.starts_with('{') {
+        let (body, _, after) = extract_block(remainder)?;
+        (body, after)
+    } else {
+        let (body, after) = extract_block_done(remainder)?;
+        (body, after.to_string())
+    };
+    let body = parse_block_lines(&body_str, opts)?;
 
-    Ok(Command::While {
+    let block = Command::While {
         condition: Box::new(condition),
         body,
-    })
+    };
+    followed_by(block, after, opts)
+}
+
+/// Parse: name() { <body> }
+/// Or:    function name [()] { <body> }
+pub fn parse_function(input: &str, name: String, opts: ParseOptions) -> Result<Command> {
+    let Some(brace) = input.find('{') else { bail!("{}: expected '{{'", name) };
+    let (body_str, _, after) = extract_block(&input[brace..])?;
+    let source = super::split_commands(&body_str);
+    let body = source.iter()
+        .map(|cmd| super::parse(cmd, opts))
+        .collect::<Result<Vec<_>>>()?;
+    followed_by(Command::FunctionDef { name, body, source }, after, opts)
+}
+
+/// If `input` starts a function definition, the function's name.
+pub fn function_name(input: &str) -> Option<String> {
+    if let Some(rest) = input.strip_prefix("function ") {
+        let name = rest
+            .split(|c: char| c == '(' || c == '{' || c.is_whitespace())
+            .next()?
+            .trim()
+            .to_string();
+        if !name.is_empty() { return Some(name); }
+    }
+
+    if let Some(paren) = input.find("()") {
+        let name = input[..paren].trim().to_string();
+        if !name.is_empty()
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            && input[paren + 2..].trim().starts_with('{')
+        {
+            return Some(name);
+        }
+    }
+
+    None
+}
+
+/// Commands may follow a block on the same line: `while ...; done; echo`.
+/// A `&` after the block runs the whole block in the background.
+fn followed_by(block: Command, after: String, opts: ParseOptions) -> Result<Command> {
+    let after = after.trim();
+    if let Some(rest) = after.strip_prefix('&').filter(|r| !r.starts_with('&')) {
+        return followed_by(Command::Background(Box::new(block)), rest.to_string(), opts);
+    }
+    let after = after.trim_start_matches(';').trim();
+    if after.is_empty() { return Ok(block); }
+    Ok(Command::Sequence(Box::new(block), Box::new(super::parse(after, opts)?)))
 }
 
 // ── Block extraction helpers ──────────────────────────────────────────────────
 
-/// Extract content between { } or then...fi, returning (body, optional_else).
-pub fn extract_block(s: &str) -> Result<(String, Option<String>)> {
+/// Extract content between { } or then...fi, returning (body, optional_else,
+/// whatever follows the closing `fi`).
+pub fn extract_block(s: &str) -> Result<(String, Option<String>, String)> {
     let s = s.trim();
 
     if s.starts_with('{') {
         // Brace style: count depth to find matching }
         let mut depth = 0;
         let mut end = 0;
-        for (i, ch) in s.chars().enumerate() {
+        for (i, ch) in s.char_indices() {
             match ch {
                 '{' => depth += 1,
                 '}' => {
@@ -134,27 +200,28 @@ pub fn extract_block(s: &str) -> Result<(String, Option<String>)> {
                 _ => {}
             }
         }
+        if end == 0 { bail!("expected '}}'"); }
         let body = s[1..end].trim().to_string();
         let after = s[end + 1..].trim();
-        let else_part = if after.starts_with("else") {
-            Some(after[4..].trim().to_string())
+        if let Some(else_part) = after.strip_prefix("else") {
+            Ok((body, Some(else_part.trim().to_string()), String::new()))
         } else {
-            None
-        };
-        Ok((body, else_part))
+            Ok((body, None, after.to_string()))
+        }
     } else {
         // Keyword style: then...fi
         if let Some(fi_pos) = find_keyword(s, "fi") {
             let content = s[..fi_pos].trim();
+            let after = s[fi_pos + 2..].to_string();
             if let Some(else_pos) = find_keyword(content, "else") {
                 let body = content[..else_pos].trim().to_string();
                 let else_body = content[else_pos + 4..].trim().to_string();
-                Ok((body, Some(else_body)))
+                Ok((body, Some(else_body), after))
             } else {
-                Ok((content.to_string(), None))
+                Ok((content.to_string(), None, after))
             }
         } else {
-            Ok((s.to_string(), None))
+            Ok((s.to_string(), None, String::new()))
         }
     }
 }
@@ -169,19 +236,13 @@ pub fn extract_block_done(s: &str) -> Result<(String, &str)> {
     }
 }
 
-/// Parse multiple semicolon/newline separated lines into a Vec of Commands.
-pub fn parse_block_lines(block: &str) -> Result<Vec<Command>> {
-    let mut cmds = Vec::new();
-    for line in block.lines() {
-        let line = line.trim();
-        if line.is_empty() || line.starts_with('#') { continue; }
-        for part in line.split(';') {
-            let part = part.trim();
-            if part.is_empty() { continue; }
-            cmds.push(super::parse(part)?);
-        }
-    }
-    Ok(cmds)
+/// Parse a block body into its commands, split at newlines and `;` the
+/// same way as top-level input (see lines.rs).
+pub fn parse_block_lines(block: &str, opts: ParseOptions) -> Result<Vec<Command>> {
+    super::split_commands(block)
+        .iter()
+        .map(|cmd| super::parse(cmd, opts))
+        .collect()
 }
 
 // ── Shared utility ────────────────────────────────────────────────────────────
@@ -191,9 +252,11 @@ pub fn find_keyword(s: &str, keyword: &str) -> Option<usize> {
     let mut pos = 0;
     while pos + keyword.len() <= s.len() {
         if s[pos..].starts_with(keyword) {
-            let before_ok = pos == 0 || s.as_bytes()[pos - 1].is_ascii_whitespace();
+            // `;` separates keywords just like whitespace: `...; done; echo`
+            let boundary  = |b: u8| b.is_ascii_whitespace() || b == b';';
+            let before_ok = pos == 0 || boundary(s.as_bytes()[pos - 1]);
             let after_ok  = pos + keyword.len() == s.len()
-                || s.as_bytes()[pos + keyword.len()].is_ascii_whitespace();
+                || boundary(s.as_bytes()[pos + keyword.len()]);
             if before_ok && after_ok {
                 return Some(pos);
             }
@@ -202,16 +265,3 @@ pub fn find_keyword(s: &str, keyword: &str) -> Option<usize> {
     }
     None
 }
-
-fn expand_glob(pattern: &str) -> Vec<String> {
-    match glob::glob(pattern) {
-        Ok(paths) => {
-            let results: Vec<String> = paths
-                .filter_map(|p| p.ok())
-                .map(|p| p.display().to_string())
-                .collect();
-            if results.is_empty() { vec![pattern.to_string()] } else { results }
-        }
-        Err(_) => vec![pattern.to_string()],
-    }
-}
\ No newline at end of file
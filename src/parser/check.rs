@@ -0,0 +1,61 @@
+// src/parser/check.rs
+//
+// Syntax checking without running anything. Each complete command in a
+// file is parsed on its own, so one mistake doesn't hide the rest, and
+// errors are reported at the line the command starts on.
+
+use super::{is_incomplete, logical_lines, parse, ParseOptions};
+
+/// A syntax error, at a zero-based line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Every syntax error in `text`.
+pub fn check(text: &str, opts: ParseOptions) -> Vec<SyntaxError> {
+    let mut errors  = Vec::new();
+    let mut command = String::new();
+    let mut start   = 0;
+
+    for (n, line) in text.lines().enumerate() {
+        if command.is_empty() { start = n; } else { command.push('\n'); }
+        command.push_str(line);
+        if is_incomplete(&command) { continue; }
+
+        for cmd in logical_lines(&command) {
+            if let Err(e) = parse(&cmd, opts) {
+                errors.push(SyntaxError { line: start, message: e.to_string() });
+            }
+        }
+        command.clear();
+    }
+
+    if !command.trim().is_empty() {
+        errors.push(SyntaxError {
+            line: start,
+            message: "unexpected end of file (unclosed quote or block, or a trailing | && \\)".to_string(),
+        });
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_errors_by_line() {
+        let text = "echo ok\n# comment\nls >\nfor i in 1 2; do\n  echo $i\ndone\nif true {";
+        let errors = check(text, ParseOptions::default());
+        assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), [2, 6]);
+        assert_eq!(errors[0].message, "expected filename after >");
+    }
+
+    #[test]
+    fn accepts_valid_scripts() {
+        let text = "greet() {\n  echo hi $1\n}\ngreet you | cat &&\n  echo done";
+        assert!(check(text, ParseOptions::default()).is_empty());
+    }
+}
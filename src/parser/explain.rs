@@ -0,0 +1,192 @@
+// src/parser/explain.rs
+//
+// `explain 'CMD LINE'`: the parsed AST drawn as a tree, with a note on
+// each part — what an operator does, where a redirect sends a stream, and
+// which expansions the executor will still apply to a word. For finding
+// out why the parser reads a line the way it does.
+
+use super::ast::{Command, Redirect};
+
+/// A line of the tree and the lines under it.
+struct Node {
+    label: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(label: impl Into<String>, children: Vec<Node>) -> Node {
+        Node { label: label.into(), children }
+    }
+
+    fn leaf(label: impl Into<String>) -> Node {
+        Node::new(label, Vec::new())
+    }
+}
+
+/// The tree for `cmd`. `describe` says what a command name refers to
+/// (builtin, function, a file on PATH ...).
+pub fn explain(cmd: &Command, describe: &dyn Fn(&str) -> String) -> String {
+    let mut out = String::new();
+    let root = build(cmd, describe);
+    out.push_str(&root.label);
+    out.push('\n');
+    render(&root.children, "", &mut out);
+    out
+}
+
+fn render(nodes: &[Node], prefix: &str, out: &mut String) {
+    for (i, node) in nodes.iter().enumerate() {
+        let last = i + 1 == nodes.len();
+        out.push_str(prefix);
+        out.push_str(if last { "└── " } else { "├── " });
+        out.push_str(&node.label);
+        out.push('\n');
+        let deeper = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        render(&node.children, &deeper, out);
+    }
+}
+
+fn build(cmd: &Command, describe: &dyn Fn(&str) -> String) -> Node {
+    let list = |label: &str, cmds: Vec<&Command>| {
+        Node::new(label, cmds.into_iter().map(|c| build(c, describe)).collect())
+    };
+    let body = |label: &str, cmds: &[Command]| {
+        Node::new(label, cmds.iter().map(|c| build(c, describe)).collect())
+    };
+
+    match cmd {
+        Command::Simple { assigns, args, redirects } => simple(assigns, args, redirects, describe),
+        Command::Pipeline(stages) =>
+            list("pipeline — each stage's output is the next one's input", stages.iter().collect()),
+        Command::Background(inner) =>
+            list("background — runs as a job; the shell doesn't wait for it", vec![inner]),
+        Command::Sequence(..) => list("sequence — one after another", chain(cmd)),
+        Command::And(..) => list("and — each runs only if the one before succeeded", chain(cmd)),
+        Command::Or(..)  => list("or — each runs only if the one before failed", chain(cmd)),
+        Command::If { condition, body: then, else_body } => {
+            let mut parts = vec![
+                Node::new("condition", vec![build(condition, describe)]),
+                body("then", then),
+            ];
+            if let Some(else_body) = else_body { parts.push(body("else", else_body)); }
+            Node::new("if", parts)
+        }
+        Command::For { var, items, body: cmds } => {
+            let items = items.iter().map(|item| word_node("item", item)).collect();
+            Node::new(format!("for — sets ${} to each item in turn", var), vec![
+                Node::new("items", items),
+                body("do", cmds),
+            ])
+        }
+        Command::While { condition, body: cmds } => Node::new("while — repeats while the condition succeeds", vec![
+            Node::new("condition", vec![build(condition, describe)]),
+            body("do", cmds),
+        ]),
+        Command::FunctionCall { name, args } => {
+            let args = args.iter().enumerate().map(|(i, a)| word_node(&format!("${}", i + 1), a)).collect();
+            Node::new(format!("call function {}", name), args)
+        }
+        Command::FunctionDef { name, body: cmds, .. } => body(&format!("define function {}", name), cmds),
+    }
+}
+
+/// `a && b && c` parses as `(a && b) && c`; shown as one list of three.
+fn chain(cmd: &Command) -> Vec<&Command> {
+    let same = |other: &Command| std::mem::discriminant(other) == std::mem::discriminant(cmd);
+    let mut out = Vec::new();
+    let mut stack = vec![cmd];
+    while let Some(c) = stack.pop() {
+        match c {
+            Command::Sequence(a, b) | Command::And(a, b) | Command::Or(a, b) if same(c) => {
+                stack.push(b);
+                stack.push(a);
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn simple(assigns: &[(String, String)], args: &[String], redirects: &[Redirect], describe: &dyn Fn(&str) -> String) -> Node {
+    let mut parts = Vec::new();
+    for (name, value) in assigns {
+        let scope = if args.is_empty() { "sets a shell variable" } else { "for this command only" };
+        parts.push(Node::leaf(format!("assign {}={} — {}{}", name, value, scope, expansions(value))));
+    }
+    if let Some((name, rest)) = args.split_first() {
+        parts.push(Node::leaf(format!("name {} — {}{}", name, describe(name), expansions(name))));
+        for (i, arg) in rest.iter().enumerate() {
+            parts.push(word_node(&format!("arg {}", i + 1), arg));
+        }
+    }
+    for redirect in redirects {
+        parts.push(Node::leaf(format!("{} — {}", redirect, redirect_meaning(redirect))));
+    }
+    Node::new("command", parts)
+}
+
+fn word_node(label: &str, word: &str) -> Node {
+    Node::leaf(format!("{} {}{}", label, word, expansions(word)))
+}
+
+fn redirect_meaning(redirect: &Redirect) -> String {
+    match redirect {
+        Redirect::StdoutTo(f)     => format!("stdout to {}, replacing it", f),
+        Redirect::StdoutAppend(f) => format!("stdout added to the end of {}", f),
+        Redirect::StdinFrom(f)    => format!("stdin read from {}", f),
+        Redirect::StderrTo(f)     => format!("stderr to {}, replacing it", f),
+        Redirect::StderrToStdout  => "stderr to wherever stdout goes".to_string(),
+    }
+}
+
+/// The expansions the executor still applies to `word` when the command
+/// runs, as a note to put after it.
+fn expansions(word: &str) -> String {
+    let mut found = Vec::new();
+    if word.contains("$((") {
+        found.push("arithmetic");
+    }
+    if word.replace("$((", "").contains("$(") || word.contains('`') {
+        found.push("command substitution");
+    }
+    let chars: Vec<char> = word.chars().collect();
+    let variable = chars.windows(2).any(|w| {
+        w[0] == '$' && (w[1].is_alphanumeric() || matches!(w[1], '_' | '{' | '?' | '#' | '@' | '*' | '$' | '!'))
+    });
+    if variable {
+        found.push("variables");
+    }
+    if word.contains(['*', '?', '[']) && !word.contains("$(") {
+        found.push("glob");
+    }
+    if word.starts_with('~') {
+        found.push("named directory");
+    }
+    if found.is_empty() { String::new() } else { format!("  ({})", found.join(", ")) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse, ParseOptions};
+
+    fn tree(line: &str) -> String {
+        explain(&parse(line, ParseOptions::default()).unwrap(), &|_| "builtin".to_string())
+    }
+
+    #[test]
+    fn chains_are_flattened() {
+        let out = tree("a && b && c");
+        assert!(out.starts_with("and"));
+        assert_eq!(out.lines().filter(|l| l.contains("── command")).count(), 3);
+    }
+
+    #[test]
+    fn notes_redirects_and_expansions() {
+        let out = tree("ls *.rs $HOME 2>&1 | wc -l > n.txt");
+        assert!(out.contains("arg 1 *.rs  (glob)"));
+        assert!(out.contains("(variables)"));
+        assert!(out.contains("2>&1 — stderr to wherever stdout goes"));
+        assert!(out.contains("> n.txt — stdout to n.txt, replacing it"));
+    }
+}
@@ -0,0 +1,196 @@
+// src/parser/lines.rs
+//
+// Splitting source text into complete commands. The same rules decide when
+// the interactive prompt needs another line, how rc files and sourced
+// scripts are read, and how block bodies are divided:
+//
+//   - a line ending in `|`, `&&`, `||` or `\` continues on the next one
+//   - newlines and `;` inside quotes don't end a command
+//   - an `if`/`for`/`while` or `{` runs until its `fi`/`done`/`}`
+//   - `#` at the start of a word starts a comment
+
+/// Scanner state over a piece of source text.
+#[derive(Default)]
+struct Scanner {
+    /// Commands completed so far.
+    done: Vec<String>,
+    /// The command being built.
+    current: String,
+    /// The word being built, for keyword detection.
+    word: String,
+    in_single: bool,
+    in_double: bool,
+    /// Open blocks: keyword blocks and braces.
+    depth: usize,
+    /// Whether the next word is in command position, where keywords count.
+    command_pos: bool,
+}
+
+impl Scanner {
+    fn new() -> Self {
+        Scanner { command_pos: true, ..Default::default() }
+    }
+
+    /// Feed `text`, ending commands at newlines and, if `semicolons`, at `;`.
+    fn scan(mut self, text: &str, semicolons: bool) -> Self {
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if self.in_single {
+                self.current.push(c);
+                if c == '\'' { self.in_single = false; }
+                continue;
+            }
+            if self.in_double {
+                self.current.push(c);
+                match c {
+                    '\\' => { if let Some(next) = chars.next() { self.current.push(next); } }
+                    '"'  => self.in_double = false,
+                    _    => {}
+                }
+                continue;
+            }
+
+            match c {
+                '\\' => match chars.next() {
+                    // Backslash-newline joins the lines
+                    Some('\n') => {}
+                    Some(next) => { self.current.push('\\'); self.current.push(next); self.word.push(next); }
+                    None       => self.current.push('\\'),
+                },
+                '\'' => { self.in_single = true; self.current.push(c); self.word.push(c); }
+                '"'  => { self.in_double = true; self.current.push(c); self.word.push(c); }
+                '#' if self.word.is_empty() => {
+                    // Comment: skip to the end of the line
+                    while chars.peek().is_some_and(|&n| n != '\n') { chars.next(); }
+                }
+                '\n' => {
+                    self.end_word();
+                    if self.continues() {
+                        self.current.push('\n');
+                        self.command_pos = true;
+                    } else {
+                        self.finish();
+                    }
+                }
+                ';' if semicolons => {
+                    self.end_word();
+                    if self.depth > 0 { self.current.push(';'); } else { self.finish(); }
+                    self.command_pos = true;
+                }
+                ' ' | '\t' | '\r' => { self.end_word(); self.current.push(c); }
+                // `g(){` is `g`, `(`, `)` and an opening brace
+                ';' | '|' | '&' | '(' | ')' => {
+                    self.end_word();
+                    self.current.push(c);
+                    self.command_pos = true;
+                }
+                _ => { self.current.push(c); self.word.push(c); }
+            }
+        }
+        self.end_word();
+        self
+    }
+
+    /// Classify the word just read: block keywords and braces change the
+    /// depth, and decide whether the next word is a command.
+    fn end_word(&mut self) {
+        if self.word.is_empty() { return; }
+        let word = std::mem::take(&mut self.word);
+        match word.as_str() {
+            "{" => { self.depth += 1; self.command_pos = true; }
+            "}" => { self.depth = self.depth.saturating_sub(1); self.command_pos = false; }
+            "if" | "while" | "until" if self.command_pos => { self.depth += 1; }
+            "for" if self.command_pos => { self.depth += 1; self.command_pos = false; }
+            "fi" | "done" if self.command_pos => { self.depth = self.depth.saturating_sub(1); self.command_pos = false; }
+            "then" | "do" | "else" | "elif" if self.command_pos => {}
+            _ => self.command_pos = false,
+        }
+    }
+
+    /// Whether the command so far needs more input to be complete.
+    fn continues(&self) -> bool {
+        let trimmed = self.current.trim_end();
+        self.in_single || self.in_double || self.depth > 0
+            || trimmed.ends_with('|') || trimmed.ends_with("&&")
+    }
+
+    fn finish(&mut self) {
+        let cmd = std::mem::take(&mut self.current);
+        let cmd = cmd.trim();
+        if !cmd.is_empty() { self.done.push(cmd.to_string()); }
+        self.command_pos = true;
+    }
+}
+
+/// Whether `input` stops partway through a command: an open quote or
+/// block, or a trailing `|`, `&&`, `||` or `\`.
+pub fn is_incomplete(input: &str) -> bool {
+    if input.trim_end().ends_with('\\') { return true; }
+    Scanner::new().scan(input, false).continues()
+}
+
+/// Split a file into complete commands, one per logical line: a command
+/// continued over several lines, or a whole multi-line block, is one item.
+pub fn logical_lines(text: &str) -> Vec<String> {
+    let mut scanner = Scanner::new().scan(text, false);
+    scanner.finish();
+    scanner.done
+}
+
+/// Split a block body into its commands, at newlines and `;` outside
+/// quotes and nested blocks.
+pub fn split_commands(text: &str) -> Vec<String> {
+    let mut scanner = Scanner::new().scan(text, true);
+    scanner.finish();
+    scanner.done
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_continued_lines() {
+        assert_eq!(logical_lines("ls |\n  grep x\necho done"), ["ls |\n  grep x", "echo done"]);
+        assert_eq!(logical_lines("echo a \\\nb"), ["echo a b"]);
+        assert_eq!(logical_lines("true &&\necho yes # comment"), ["true &&\necho yes"]);
+    }
+
+    #[test]
+    fn keeps_blocks_whole() {
+        let text = "for i in 1 2; do\n  echo $i\ndone\necho after";
+        assert_eq!(logical_lines(text), ["for i in 1 2; do\n  echo $i\ndone", "echo after"]);
+        assert_eq!(split_commands("if true; then echo a; fi; echo 'x;y'"),
+            ["if true; then echo a; fi", "echo 'x;y'"]);
+        assert_eq!(split_commands("echo done; echo fi"), ["echo done", "echo fi"]);
+    }
+
+    #[test]
+    fn keeps_multi_line_functions_whole() {
+        let text = "g(){
+  echo a
+  echo b
+}
+g";
+        assert_eq!(logical_lines(text), ["g(){
+  echo a
+  echo b
+}", "g"]);
+        assert_eq!(logical_lines("h() {
+  echo $(pwd)
+}"), ["h() {
+  echo $(pwd)
+}"]);
+    }
+
+    #[test]
+    fn detects_incomplete_input() {
+        assert!(is_incomplete("echo 'open"));
+        assert!(is_incomplete("ls |"));
+        assert!(is_incomplete("while true; do"));
+        assert!(is_incomplete("f() {"));
+        assert!(is_incomplete("g(){"));
+        assert!(!is_incomplete("for i in 1; do echo $i; done"));
+        assert!(!is_incomplete("echo if"));
+    }
+}
@@ -4,46 +4,96 @@
 //
 //   ast.rs        — Command and Redirect enums
 //   tokenizer.rs  — raw text → Token list
-//   block.rs      — if / for / while parsers + block extraction helpers
+//   block.rs      — if / for / while / function parsers + block extraction
+//                   helpers
+//   lines.rs      — splitting text into complete commands (continuations,
+//                   multi-line blocks)
+//   check.rs      — syntax checking a whole file without running it
+//   explain.rs    — the AST drawn as an annotated tree, for `explain`
 
 pub mod ast;
 mod block;
+mod check;
+mod explain;
+mod lines;
 mod tokenizer;
 
+pub use check::check;
+pub use explain::explain;
+pub use lines::{is_incomplete, logical_lines, split_commands};
+
 use ast::{Command, Redirect};
 use anyhow::{Result, bail};
 use tokenizer::Token;
 
+/// Syntax switches that change how input is parsed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// `set -o posix`: reject rshell-only syntax (brace blocks) and leave
+    /// words unexpanded for the executor's POSIX expansion pass.
+    pub posix: bool,
+}
+
 /// Parse a complete input string into a Command AST.
-pub fn parse(input: &str) -> Result<Command> {
+pub fn parse(input: &str, opts: ParseOptions) -> Result<Command> {
     let input = input.trim();
     if input.is_empty() {
         bail!("empty input");
     }
 
-    // Block-level keywords are handled before tokenising
+    // Commands separated by `;` or newlines are parsed one at a time, so a
+    // block or function definition can come anywhere in a line
+    let cmds = split_commands(input);
+    if cmds.len() > 1 {
+        let mut parsed = cmds.iter().map(|cmd| parse(cmd, opts)).collect::<Result<Vec<_>>>()?;
+        let last = parsed.pop().unwrap();
+        return Ok(parsed.into_iter().rev().fold(last, |right, left| {
+            Command::Sequence(Box::new(left), Box::new(right))
+        }));
+    }
+
+    // Function definitions and block-level keywords are handled before
+    // tokenising
+    if let Some(name) = block::function_name(input) {
+        return block::parse_function(input, name, opts);
+    }
     if input.starts_with("if ") || input == "if" {
-        return block::parse_if(input);
+        return block::parse_if(input, opts);
     }
     if input.starts_with("for ") || input == "for" {
-        return block::parse_for(input);
+        return block::parse_for(input, opts);
     }
     if input.starts_with("while ") || input == "while" {
-        return block::parse_while(input);
+        return block::parse_while(input, opts);
     }
 
-    let tokens = tokenizer::tokenize(input)?;
+    let tokens = tokenizer::tokenize(input, opts)?;
     if tokens.is_empty() {
         bail!("empty input");
     }
     parse_sequence(&tokens)
 }
 
+/// The AST for `input` as `rshell parse --dump` prints it: Rust debug
+/// output, or JSON for editors and linters building on the shell.
+pub fn dump(input: &str, opts: ParseOptions, json: bool) -> Result<String> {
+    let ast = parse(input, opts)?;
+    if json {
+        Ok(serde_json::to_string_pretty(&ast)?)
+    } else {
+        Ok(format!("{:#?}", ast))
+    }
+}
+
 // ── Recursive descent parser ──────────────────────────────────────────────────
 
 fn parse_sequence(tokens: &[Token]) -> Result<Command> {
-    let (left, rest) = parse_and_or(tokens)?;
-    if rest.first() == Some(&Token::Semicolon) {
+    let (mut left, rest) = parse_and_or(tokens)?;
+    // `&` ends a list like `;` does, and backgrounds the whole of it
+    if rest.first() == Some(&Token::Ampersand) {
+        left = Command::Background(Box::new(left));
+    }
+    if matches!(rest.first(), Some(Token::Semicolon | Token::Ampersand)) {
         let rest = &rest[1..];
         if rest.is_empty() { return Ok(left); }
         let right = parse_sequence(rest)?;
@@ -87,14 +137,20 @@ fn parse_pipeline(tokens: &[Token]) -> Result<(Command, &[Token])> {
 }
 
 fn parse_simple(tokens: &[Token]) -> Result<(Command, &[Token])> {
+    let mut assigns   = Vec::new();
     let mut args      = Vec::new();
     let mut redirects = Vec::new();
-    let mut background = false;
     let mut i = 0;
 
     while i < tokens.len() {
         match &tokens[i] {
-            Token::Word(w) => { args.push(w.clone()); i += 1; }
+            Token::Word(w) => {
+                match assignment(w).filter(|_| args.is_empty()) {
+                    Some(pair) => assigns.push(pair),
+                    None       => args.push(w.clone()),
+                }
+                i += 1;
+            }
             Token::RedirectOut => {
                 if let Some(Token::Word(file)) = tokens.get(i + 1) {
                     redirects.push(Redirect::StdoutTo(file.clone()));
@@ -120,12 +176,17 @@ fn parse_simple(tokens: &[Token]) -> Result<(Command, &[Token])> {
                 } else { bail!("expected filename after 2>"); }
             }
             Token::RedirectErrOut => { redirects.push(Redirect::StderrToStdout); i += 1; }
-            Token::Ampersand     => { background = true; i += 1; break; }
             _ => break,
         }
     }
 
-    if args.is_empty() { bail!("expected command"); }
+    if args.is_empty() && assigns.is_empty() { bail!("expected command"); }
+
+    Ok((Command::Simple { assigns, args, redirects }, &tokens[i..]))
+}
 
-    Ok((Command::Simple { args, redirects, background }, &tokens[i..]))
+/// Split a `NAME=value` word into its parts.
+fn assignment(word: &str) -> Option<(String, String)> {
+    let (name, value) = word.split_once('=')?;
+    crate::shell::is_valid_name(name).then(|| (name.to_string(), value.to_string()))
 }
\ No newline at end of file
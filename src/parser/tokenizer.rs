@@ -1,9 +1,16 @@
 // src/parser/tokenizer.rs
 //
 // Converts a raw input string into a flat list of tokens.
-// Handles quoting, escapes, redirects, operators, tilde expansion, and globs.
+// Handles quoting, escapes, redirects, operators and tilde expansion. Globs
+// and `$((...))` / `$(...)` are left in the words for the executor, which
+// expands them when the command runs: a function or loop body is parsed
+// once but may run many times, in different directories.
+//
+// In POSIX mode words are returned raw — quotes and backslashes intact,
+// nothing expanded — so the executor can expand them in POSIX order.
 
-use anyhow::Result;
+use anyhow::{Result, bail};
+use super::ParseOptions;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -20,7 +27,7 @@ pub enum Token {
     RedirectErrOut,
 }
 
-pub fn tokenize(input: &str) -> Result<Vec<Token>> {
+pub fn tokenize(input: &str, opts: ParseOptions) -> Result<Vec<Token>> {
     let mut tokens = Vec::new();
     let mut chars = input.chars().peekable();
 
@@ -28,33 +35,13 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>> {
         match c {
             ' ' | '\t' | '\n' | '\r' => { chars.next(); }
 
-            '\'' => {
-                chars.next();
-                let mut word = String::new();
-                while let Some(&ch) = chars.peek() {
-                    chars.next();
-                    if ch == '\'' { break; }
-                    word.push(ch);
-                }
-                tokens.push(Token::Word(word));
+            '\'' | '"' | '\\' if opts.posix => {
+                tokens.push(Token::Word(read_raw_word(&mut chars)?));
             }
 
-            '"' => {
-                chars.next();
-                let mut word = String::new();
-                while let Some(&ch) = chars.peek() {
-                    chars.next();
-                    if ch == '"' { break; }
-                    if ch == '\\' {
-                        if let Some(&next) = chars.peek() {
-                            chars.next();
-                            word.push(next);
-                        }
-                    } else {
-                        word.push(ch);
-                    }
-                }
-                tokens.push(Token::Word(word));
+            '\'' | '"' => {
+                // Quoted words are never tilde- or glob-expanded
+                tokens.push(Token::Word(read_word(&mut chars)));
             }
 
             '|' => {
@@ -99,9 +86,10 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>> {
                 } else if s.starts_with("2>") {
                     chars.next(); chars.next();
                     tokens.push(Token::RedirectErr);
+                } else if opts.posix {
+                    tokens.push(Token::Word(read_raw_word(&mut chars)?));
                 } else {
-                    let word = read_word(&mut chars);
-                    tokens.push(Token::Word(word));
+                    push_plain_word(&mut tokens, &mut chars);
                 }
             }
 
@@ -115,63 +103,219 @@ pub fn tokenize(input: &str) -> Result<Vec<Token>> {
                 }
             }
 
-            _ => {
-                let word = read_word(&mut chars);
+            _ if opts.posix => {
+                tokens.push(Token::Word(read_raw_word(&mut chars)?));
+            }
 
-                // Expand leading tilde
-                let word = if word.starts_with('~') {
-                    let home = dirs::home_dir()
-                        .map(|p| p.display().to_string())
-                        .unwrap_or_else(|| "~".to_string());
-                    word.replacen('~', &home, 1)
-                } else {
-                    word
-                };
+            _ => push_plain_word(&mut tokens, &mut chars),
+        }
+    }
 
-                // Expand globs inline
-                if word.contains('*') || word.contains('?') {
-                    for w in expand_glob(&word) {
-                        tokens.push(Token::Word(w));
+    Ok(tokens)
+}
+
+/// Read a word, stopping at shell metacharacters. Quoted and unquoted
+/// pieces written back to back form one word (`FOO="a b"` → `FOO=a b`).
+pub fn read_word(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    read_word_parts(chars).0
+}
+
+/// Read a word and report whether any part of it was quoted, in which
+/// case it must not be tilde- or glob-expanded.
+fn read_word_parts(chars: &mut std::iter::Peekable<std::str::Chars>) -> (String, bool) {
+    let mut word   = String::new();
+    let mut quoted = false;
+    while let Some(&c) = chars.peek() {
+        if matches!(c, ' ' | '\t' | '\n' | '\r' | '|' | '&' | ';' | '>' | '<') {
+            break;
+        }
+        chars.next();
+        match c {
+            // `$(cmd args)` and `$(( a + b ))` are one word, spaces and all
+            '$' if chars.peek() == Some(&'(') => {
+                word.push('$');
+                let mut depth = 0;
+                for ch in chars.by_ref() {
+                    word.push(ch);
+                    match ch {
+                        '(' => depth += 1,
+                        ')' => { depth -= 1; if depth == 0 { break; } }
+                        _ => {}
+                    }
+                }
+            }
+            '\'' => {
+                quoted = true;
+                for ch in chars.by_ref() {
+                    if ch == '\'' { break; }
+                    word.push(ch);
+                }
+            }
+            '"' => {
+                quoted = true;
+                while let Some(ch) = chars.next() {
+                    if ch == '"' { break; }
+                    if ch == '\\' {
+                        // Inside double quotes a backslash only escapes
+                        // $ ` " \ and newline; otherwise it is kept, so
+                        // "a\n" reaches printf/echo intact.
+                        match chars.peek() {
+                            Some(&next) if matches!(next, '$' | '`' | '"' | '\\' | '\n') => {
+                                chars.next();
+                                word.push(next);
+                            }
+                            _ => word.push('\\'),
+                        }
+                    } else {
+                        word.push(ch);
                     }
-                } else {
-                    tokens.push(Token::Word(word));
                 }
             }
+            '\\' => {
+                if let Some(next) = chars.next() { word.push(next); }
+            }
+            c => word.push(c),
         }
     }
+    (word, quoted)
+}
 
-    Ok(tokens)
+/// Read an unquoted-start word, applying tilde expansion unless part of
+/// it was quoted.
+fn push_plain_word(tokens: &mut Vec<Token>, chars: &mut std::iter::Peekable<std::str::Chars>) {
+    let (word, quoted) = read_word_parts(chars);
+    if quoted {
+        tokens.push(Token::Word(word));
+        return;
+    }
+
+    // Expand a leading `~` or `~/`; `~name` is left for the executor's
+    // named directories
+    let word = if word == "~" || word.starts_with("~/") {
+        let home = dirs::home_dir()
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|| "~".to_string());
+        word.replacen('~', &home, 1)
+    } else {
+        word
+    };
+
+    tokens.push(Token::Word(word));
 }
 
-/// Read a plain (unquoted) word, stopping at shell metacharacters.
-pub fn read_word(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+/// Read a whole word without interpreting it: quoted sections (which may
+/// contain spaces and metacharacters), `$(...)` and `${...}`, and backslash
+/// escapes are copied verbatim, and the word ends at the first unquoted
+/// separator.
+fn read_raw_word(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String> {
     let mut word = String::new();
     while let Some(&c) = chars.peek() {
-        if matches!(c, ' ' | '\t' | '\n' | '\r' | '|' | '&' | ';' | '>' | '<' | '"' | '\'') {
-            break;
+        match c {
+            ' ' | '\t' | '\n' | '\r' | '|' | '&' | ';' | '>' | '<' => break,
+            '\'' => {
+                word.push(chars.next().unwrap_or(c));
+                loop {
+                    match chars.next() {
+                        Some(ch) => { word.push(ch); if ch == '\'' { break; } }
+                        None => bail!("unterminated single quote"),
+                    }
+                }
+            }
+            '"' => read_double_quoted(chars, &mut word)?,
+            '$' if opens_substitution(chars) => read_substitution(chars, &mut word)?,
+            '\\' => {
+                chars.next();
+                match chars.next() {
+                    Some('\n') => {} // line continuation
+                    Some(next)  => { word.push('\\'); word.push(next); }
+                    None        => word.push('\\'),
+                }
+            }
+            _ => { word.push(c); chars.next(); }
         }
-        chars.next();
-        if c == '\\' {
-            if let Some(&next) = chars.peek() {
+    }
+    Ok(word)
+}
+
+/// Copy a double-quoted section, quotes included, onto `word`.
+fn read_double_quoted(chars: &mut std::iter::Peekable<std::str::Chars>, word: &mut String) -> Result<()> {
+    word.push(chars.next().unwrap_or('"'));
+    loop {
+        match chars.peek().copied() {
+            Some('\\') => {
                 chars.next();
-                word.push(next);
+                word.push('\\');
+                if let Some(next) = chars.next() { word.push(next); }
             }
-        } else {
-            word.push(c);
+            Some('$') if opens_substitution(chars) => read_substitution(chars, word)?,
+            Some(ch) => { chars.next(); word.push(ch); if ch == '"' { return Ok(()); } }
+            None => bail!("unterminated double quote"),
         }
     }
-    word
 }
 
-fn expand_glob(pattern: &str) -> Vec<String> {
-    match glob::glob(pattern) {
-        Ok(paths) => {
-            let results: Vec<String> = paths
-                .filter_map(|p| p.ok())
-                .map(|p| p.display().to_string())
-                .collect();
-            if results.is_empty() { vec![pattern.to_string()] } else { results }
+/// Whether the `$` next in `chars` starts a `$(` or `${`.
+fn opens_substitution(chars: &std::iter::Peekable<std::str::Chars>) -> bool {
+    let mut ahead = chars.clone();
+    ahead.next();
+    matches!(ahead.peek(), Some('(') | Some('{'))
+}
+
+/// Copy a `$(...)` or `${...}` onto `word` whole, through nested brackets
+/// and quotes, so the spaces inside don't split the word.
+fn read_substitution(chars: &mut std::iter::Peekable<std::str::Chars>, word: &mut String) -> Result<()> {
+    word.push(chars.next().unwrap_or('$'));
+    let open = chars.next().unwrap_or('(');
+    let close = if open == '(' { ')' } else { '}' };
+    word.push(open);
+    let mut depth = 1;
+    while depth > 0 {
+        match chars.peek().copied() {
+            Some('\'') => {
+                word.push(chars.next().unwrap_or('\''));
+                loop {
+                    match chars.next() {
+                        Some(ch) => { word.push(ch); if ch == '\'' { break; } }
+                        None => bail!("unterminated single quote"),
+                    }
+                }
+            }
+            Some('"') => read_double_quoted(chars, word)?,
+            Some('$') if opens_substitution(chars) => read_substitution(chars, word)?,
+            Some('\\') => {
+                chars.next();
+                word.push('\\');
+                if let Some(next) = chars.next() { word.push(next); }
+            }
+            Some(ch) => {
+                chars.next();
+                word.push(ch);
+                if ch == open { depth += 1; }
+                if ch == close { depth -= 1; }
+            }
+            None => bail!("unterminated ${}", open),
         }
-        Err(_) => vec![pattern.to_string()],
     }
-}
\ No newline at end of file
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posix_words(input: &str) -> Vec<String> {
+        tokenize(input, ParseOptions { posix: true }).unwrap().into_iter()
+            .filter_map(|t| if let Token::Word(w) = t { Some(w) } else { None })
+            .collect()
+    }
+
+    #[test]
+    fn substitutions_stay_one_word() {
+        assert_eq!(posix_words("for i in $(echo x y)"), ["for", "i", "in", "$(echo x y)"]);
+        assert_eq!(posix_words("echo a$(echo \"b c\" | tr b d)e ${x:-1 2}"),
+            ["echo", "a$(echo \"b c\" | tr b d)e", "${x:-1 2}"]);
+        assert_eq!(posix_words("echo \"$(echo ')' \"q r\")\" $((1 + (2)))"),
+            ["echo", "\"$(echo ')' \"q r\")\"", "$((1 + (2)))"]);
+        assert!(tokenize("echo $(echo x", ParseOptions { posix: true }).is_err());
+    }
+}
@@ -0,0 +1,69 @@
+// src/paths.rs
+// How paths are shown to the user.
+//
+// Windows APIs such as canonicalize hand back paths in verbatim form,
+// `\\?\C:\Users\me`, or `\\?\UNC\server\share\dir` for a network share.
+// The shell shows these as `C:/Users/me` and `//server/share/dir`, the same
+// way in every builtin. Nothing is lost by stripping the prefix: std adds
+// it back itself whenever a path too long for the old 260-character limit
+// is opened.
+
+use std::path::Path;
+
+/// Drop a verbatim prefix: `\\?\C:\x` becomes `C:\x`, and
+/// `\\?\UNC\server\share` the plain UNC path `\\server\share`.
+pub fn strip_verbatim(s: &str) -> String {
+    if let Some(share) = s.strip_prefix("\\\\?\\UNC\\") {
+        return format!("\\\\{}", share);
+    }
+    s.strip_prefix("\\\\?\\").unwrap_or(s).to_string()
+}
+
+/// A Windows path string with its verbatim prefix dropped and forward
+/// slashes throughout.
+pub fn normalise(s: &str) -> String {
+    strip_verbatim(s).replace('\\', "/")
+}
+
+/// A path as the shell displays it. On Unix a backslash can be part of a
+/// file name, so paths are shown exactly as they are.
+pub fn display(path: &Path) -> String {
+    let s = path.display().to_string();
+    if cfg!(windows) { normalise(&s) } else { s }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drive_paths() {
+        assert_eq!(normalise("\\\\?\\C:\\Users\\foo"), "C:/Users/foo");
+        assert_eq!(normalise("C:\\Users\\foo"), "C:/Users/foo");
+        assert_eq!(normalise("C:/Users/foo"), "C:/Users/foo");
+    }
+
+    #[test]
+    fn unc_shares() {
+        assert_eq!(strip_verbatim("\\\\?\\UNC\\server\\share\\dir"), "\\\\server\\share\\dir");
+        assert_eq!(normalise("\\\\?\\UNC\\server\\share\\dir"), "//server/share/dir");
+        assert_eq!(normalise("\\\\server\\share"), "//server/share");
+    }
+
+    #[test]
+    fn long_paths() {
+        let tail = vec!["directory"; 40].join("\\");
+        let long = format!("\\\\?\\C:\\{}", tail);
+        assert!(long.len() > 260);
+        assert_eq!(normalise(&long), format!("C:/{}", tail.replace('\\', "/")));
+
+        let share = format!("\\\\?\\UNC\\server\\share\\{}", tail);
+        assert_eq!(normalise(&share), format!("//server/share/{}", tail.replace('\\', "/")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_names_keep_backslashes() {
+        assert_eq!(display(Path::new("/tmp/a\\b")), "/tmp/a\\b");
+    }
+}
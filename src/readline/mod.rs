@@ -9,7 +9,9 @@ use reedline::{
     ReedlineMenu, ColumnarMenu, MenuBuilder,
 };
 use std::borrow::Cow;
-use crate::completion;
+use std::sync::{Arc, Mutex};
+use crate::completion::{self, Candidate, Kind, Matching};
+use crate::shell::Shell;
 
 // ── Prompt ───────────────────────────────────────────────────────────────────
 
@@ -47,7 +49,11 @@ impl Prompt for MyPrompt {
 
 // ── Tab Completer ─────────────────────────────────────────────────────────────
 
-pub struct ShellCompleter;
+pub struct ShellCompleter {
+    /// A copy of the shell as of the last prompt, for its settings and
+    /// `complete` specs.
+    shell: Arc<Mutex<Option<Shell>>>,
+}
 
 impl Completer for ShellCompleter {
     fn complete(&mut self, line: &str, pos: usize) -> Vec<Suggestion> {
@@ -62,44 +68,105 @@ impl Completer for ShellCompleter {
         let is_first_word = !before_cursor[..word_start]
             .trim()
             .contains(|c: char| !matches!(c, '|' | ';' | '&'));
+        let segment_start = before_cursor[..word_start]
+            .rfind(['|', ';', '&'])
+            .map_or(0, |i| i + 1);
+        let words: Vec<&str> = before_cursor[segment_start..word_start].split_whitespace().collect();
+        let command = words.first().copied().unwrap_or("");
+        let shell = self.shell.lock().unwrap_or_else(|e| e.into_inner());
+        let matching = shell.as_ref().map_or(Matching::Prefix, Shell::matching);
 
-        // Get completions from our engine
-        let mut suggestions: Vec<Suggestion> = completion::complete(partial, is_first_word)
-            .into_iter()
-            .map(|s| Suggestion {
-                value: s,
-                description: None,
-                style: None,
-                extra: None,
-                span: Span::new(word_start, pos),
-                append_whitespace: false,
-            })
-            .collect();
-
-        // Also complete builtin names if first word
-        if is_first_word {
-            for builtin in completion::builtin_names() {
-                if builtin.starts_with(partial) {
-                    suggestions.push(Suggestion {
-                        value: builtin.to_string(),
-                        description: Some("builtin".to_string()),
-                        style: None,
-                        extra: None,
-                        span: Span::new(word_start, pos),
-                        append_whitespace: true,
-                    });
+        let span = Span::new(word_start, pos);
+
+        // A variable being typed, `$HO` or `${HO`
+        if let (Some(shell), Some(at)) = (shell.as_ref(), completion::variable_start(partial)) {
+            let mut found = completion::complete_variables(shell, &partial[at..], matching);
+            rank(matching, &partial[at..], &mut found);
+            let span = Span::new(word_start + at, pos);
+            return found.into_iter().map(|c| suggestion(c, span)).collect();
+        }
+
+        // Arguments of a command given a spec with `complete`
+        if let Some(shell) = shell.as_ref().filter(|_| !is_first_word) {
+            if let Some(spec) = shell.completions.get(command) {
+                let previous = words.last().copied().unwrap_or("");
+                let mut found = completion::complete_with(shell, spec, command, partial, previous);
+                if !found.is_empty() {
+                    rank(matching, partial, &mut found);
+                    return found.into_iter().map(|c| suggestion(c, span)).collect();
                 }
             }
         }
 
-        suggestions
+        // Arguments of a command that only takes one kind of thing
+        if let Some(context) = completion::context(command).filter(|_| !is_first_word && !partial.starts_with('-')) {
+            let mut found = completion::complete_in_context(shell.as_ref(), context, &words[1..], partial, matching);
+            rank(matching, partial, &mut found);
+            return found.into_iter().map(|c| suggestion(c, span)).collect();
+        }
+
+        let mut candidates = Vec::new();
+
+        // Options of the command being typed, if it has them
+        if !is_first_word && partial.starts_with('-') {
+            candidates.extend(completion::complete_options(command, partial, matching).into_iter()
+                .map(|(name, help)| Candidate::new(name, Kind::Flag, Some(help))));
+        }
+
+        // The shell's own names first for a command, as they're what runs
+        if is_first_word && !partial.is_empty() {
+            candidates.extend(completion::complete_names(shell.as_ref(), partial, matching));
+        }
+
+        // Get completions from our engine
+        candidates.extend(completion::complete(partial, is_first_word, matching));
+        let mut seen = std::collections::HashSet::new();
+        candidates.retain(|c| seen.insert(c.value.clone()));
+
+        rank(matching, partial, &mut candidates);
+        candidates.into_iter().map(|c| suggestion(c, span)).collect()
+    }
+}
+
+/// A candidate for the menu, coloured by what it is, with its description
+/// beside it.
+fn suggestion(candidate: Candidate, span: Span) -> Suggestion {
+    use nu_ansi_term::{Color, Style};
+    let style = match candidate.kind {
+        Kind::Directory  => Some(Style::new().fg(Color::Blue).bold()),
+        Kind::Executable | Kind::Command => Some(Style::new().fg(Color::Green)),
+        Kind::Builtin    => Some(Style::new().fg(Color::Yellow)),
+        Kind::Alias      => Some(Style::new().fg(Color::Cyan)),
+        Kind::Function   => Some(Style::new().fg(Color::Magenta)),
+        Kind::File | Kind::Flag | Kind::Variable | Kind::Word => None,
+    };
+    Suggestion {
+        // A directory or `$VAR` is as likely to go on as to end there
+        append_whitespace: !matches!(candidate.kind, Kind::Directory | Kind::Variable),
+        value: candidate.value,
+        description: candidate.description,
+        style,
+        extra: None,
+        span,
     }
 }
 
+/// Put the best fuzzy matches first, judging each by its last path part
+/// against the last part typed. Prefix matches keep the engine's order.
+fn rank(matching: Matching, partial: &str, candidates: &mut [Candidate]) {
+    let word = partial.rsplit('/').next().unwrap_or(partial);
+    if matching == Matching::Prefix || word.is_empty() { return; }
+    candidates.sort_by_cached_key(|c| {
+        let name = c.value.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+        std::cmp::Reverse(matching.score(word, name))
+    });
+}
+
 // ── Main readline struct ──────────────────────────────────────────────────────
 
 pub struct ShellReadline {
     editor: Reedline,
+    shell: Arc<Mutex<Option<Shell>>>,
 }
 
 impl ShellReadline {
@@ -115,9 +182,12 @@ impl ShellReadline {
                 }),
         );
 
-        // Tab completion menu (shows list of options when multiple matches)
+        // Tab completion menu (shows list of options when multiple matches),
+        // with what each one is alongside it
         let completion_menu = Box::new(
-            ColumnarMenu::default().with_name("completion_menu")
+            ColumnarMenu::default()
+                .with_name("completion_menu")
+                .with_description_text_style(nu_ansi_term::Style::new().fg(nu_ansi_term::Color::DarkGray))
         );
 
         // Custom keybindings
@@ -140,9 +210,10 @@ impl ShellReadline {
             ]),
         );
 
+        let shell = Arc::new(Mutex::new(None));
         let editor = Reedline::create()
             .with_history(history)
-            .with_completer(Box::new(ShellCompleter))
+            .with_completer(Box::new(ShellCompleter { shell: Arc::clone(&shell) }))
             .with_menu(ReedlineMenu::EngineCompleter(completion_menu))
             .with_edit_mode(Box::new(Emacs::new(keybindings)))
             .with_hinter(Box::new(
@@ -153,7 +224,14 @@ impl ShellReadline {
                 ),
             ));
 
-        ShellReadline { editor }
+        ShellReadline { editor, shell }
+    }
+
+    /// Let completion see the shell as it is now, and have PATH read
+    /// ahead of the first Tab.
+    pub fn sync_shell(&mut self, shell: &Shell) {
+        completion::prewarm_commands();
+        *self.shell.lock().unwrap_or_else(|e| e.into_inner()) = Some(shell.clone());
     }
 
     pub fn readline(&mut self, prompt_text: &str) -> Result<String, ReadlineError> {
@@ -0,0 +1,248 @@
+// src/selftest.rs
+// Interactive integration tests: `rshell --selftest`
+//
+// Only built with `cargo build --features selftest`. Each scenario spawns
+// this same binary inside a fresh PTY with an isolated $HOME, types
+// keystrokes at it and waits for expected text to appear in the (ANSI
+// stripped) output. This covers what unit tests can't reach: the line
+// editor, completion, prompts, Ctrl+C and job control.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use portable_pty::{native_pty_system, Child, CommandBuilder, PtySize};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An external command that runs for a while. `sleep` itself is a builtin,
+/// which can't be backgrounded or interrupted like a child process.
+#[cfg(unix)]
+const LONG_RUNNING: &str = "sh -c 'sleep 30'";
+#[cfg(windows)]
+const LONG_RUNNING: &str = "ping -n 30 127.0.0.1";
+
+type Scenario = fn(&mut Session) -> Result<()>;
+
+pub fn run() -> i32 {
+    let scenarios: &[(&str, Scenario)] = &[
+        ("prompt",             prompt),
+        ("echo",               echo),
+        ("exit status",        exit_status),
+        ("file completion",    file_completion),
+        ("command completion", command_completion),
+        ("ctrl-c",             ctrl_c),
+        ("background job",     background_job),
+        ("pipeline",           pipeline),
+        ("history forget",     history_forget),
+    ];
+
+    let mut failed = 0;
+    for (name, scenario) in scenarios {
+        let result = Session::spawn().and_then(|mut s| {
+            s.expect("❯")?;
+            scenario(&mut s)
+        });
+        match result {
+            Ok(()) => println!("  \x1b[32mok\x1b[0m    {}", name),
+            Err(e) => {
+                println!("  \x1b[31mFAIL\x1b[0m  {}: {}", name, e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n{} passed, {} failed", scenarios.len() - failed, failed);
+    if failed == 0 { 0 } else { 1 }
+}
+
+// ── Scenarios ─────────────────────────────────────────────────────────────────
+
+fn prompt(s: &mut Session) -> Result<()> {
+    s.send("cd sub\r")?;
+    s.expect("~/sub")?;
+    s.expect("❯")
+}
+
+fn echo(s: &mut Session) -> Result<()> {
+    // The expected text never appears in the typed line itself
+    s.send("echo $((6*7))\r")?;
+    s.expect("42")
+}
+
+fn exit_status(s: &mut Session) -> Result<()> {
+    s.send("false\r")?;
+    s.expect("❯")?;
+    s.send("echo status=$?\r")?;
+    s.expect("status=1")
+}
+
+fn file_completion(s: &mut Session) -> Result<()> {
+    s.send("cat selftest_f\t")?;
+    s.expect("selftest_file.txt")?;
+    // The first Enter accepts the menu entry, the second runs the line
+    s.send("\r")?;
+    s.send("\r")?;
+    s.expect("hello from selftest")
+}
+
+fn command_completion(s: &mut Session) -> Result<()> {
+    s.send("histo\t")?;
+    s.expect("history")
+}
+
+fn ctrl_c(s: &mut Session) -> Result<()> {
+    s.send(&format!("{}\r", LONG_RUNNING))?;
+    std::thread::sleep(Duration::from_millis(300));
+    s.send("\x03")?;
+    s.expect("❯")?;
+    // Well inside the sleep, so this only prints if Ctrl+C interrupted it
+    s.send("echo after-$((1+2))\r")?;
+    s.expect("after-3")
+}
+
+fn background_job(s: &mut Session) -> Result<()> {
+    s.send(&format!("{} &\r", LONG_RUNNING))?;
+    s.expect("[bg] pid")?;
+    s.send("echo still-$((2+2))\r")?;
+    s.expect("still-4")
+}
+
+fn pipeline(s: &mut Session) -> Result<()> {
+    s.send("echo $((20+3)) | grep 2\r")?;
+    s.expect("23")
+}
+
+fn history_forget(s: &mut Session) -> Result<()> {
+    s.send("echo zebra\r")?;
+    s.expect("❯")?;
+    // The --forget line matches too, but isn't one of the entries forgotten
+    s.send("history --forget zebra\r")?;
+    s.expect("forgot 1 entry")
+}
+
+// ── PTY session ───────────────────────────────────────────────────────────────
+
+struct Session {
+    child:  Box<dyn Child + Send + Sync>,
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    output: Receiver<Vec<u8>>,
+    /// Every byte seen so far; a character can be split across reads
+    raw:    Vec<u8>,
+    /// `raw` decoded and ANSI-stripped
+    screen: String,
+    /// Position in `screen` up to which earlier expectations matched
+    cursor: usize,
+    home:   PathBuf,
+}
+
+impl Session {
+    fn spawn() -> Result<Session> {
+        let home = make_home()?;
+
+        let pty = native_pty_system().openpty(PtySize {
+            rows: 24, cols: 100, pixel_width: 0, pixel_height: 0,
+        })?;
+
+        let mut cmd = CommandBuilder::new(std::env::current_exe()?);
+        cmd.cwd(&home);
+        cmd.env("HOME", &home);
+        cmd.env("USERPROFILE", &home);
+        cmd.env("TERM", "xterm-256color");
+        let child = pty.slave.spawn_command(cmd)?;
+        drop(pty.slave);
+
+        let writer = Arc::new(Mutex::new(pty.master.take_writer()?));
+        let mut reader = pty.master.try_clone_reader()?;
+        let (tx, rx) = channel();
+
+        // The line editor asks for the cursor position (ESC[6n) and blocks
+        // until it gets an answer, so reply from the reader thread.
+        let replies = Arc::clone(&writer);
+        std::thread::spawn(move || {
+            let _master = pty.master;
+            let mut buf = [0u8; 4096];
+            let mut tail: Vec<u8> = Vec::new();
+            loop {
+                let n = match reader.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => n,
+                };
+                tail.extend_from_slice(&buf[..n]);
+                let queries = tail.windows(4).filter(|w| w == b"\x1b[6n").count();
+                if queries > 0 {
+                    if let Ok(mut w) = replies.lock() {
+                        for _ in 0..queries { w.write_all(b"\x1b[1;1R").ok(); }
+                        w.flush().ok();
+                    }
+                }
+                // Keep a few bytes so a query split across reads is still seen
+                let keep = tail.len().saturating_sub(3);
+                tail.drain(..keep);
+                if tx.send(buf[..n].to_vec()).is_err() { break; }
+            }
+        });
+
+        Ok(Session { child, writer, output: rx, raw: Vec::new(), screen: String::new(), cursor: 0, home })
+    }
+
+    fn send(&mut self, keys: &str) -> Result<()> {
+        let mut w = self.writer.lock().map_err(|_| anyhow!("pty writer poisoned"))?;
+        w.write_all(keys.as_bytes())?;
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Wait until `needle` appears in output that arrived after the last
+    /// successful expectation.
+    fn expect(&mut self, needle: &str) -> Result<()> {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            if let Some(pos) = self.screen[self.cursor..].find(needle) {
+                self.cursor += pos + needle.len();
+                return Ok(());
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.output.recv_timeout(left) {
+                Ok(bytes) => {
+                    self.raw.extend_from_slice(&bytes);
+                    // Carriage returns go too, leaving lines as typed
+                    self.screen = crate::ansi::strip_ansi(&String::from_utf8_lossy(&self.raw)).replace('\r', "");
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let seen: String = self.screen[self.cursor..].chars().rev().take(200)
+                        .collect::<Vec<_>>().into_iter().rev().collect();
+                    return Err(anyhow!("timed out waiting for {:?}; last output: {:?}", needle, seen));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(anyhow!("shell exited while waiting for {:?}", needle));
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+        std::fs::remove_dir_all(&self.home).ok();
+    }
+}
+
+/// A throwaway $HOME with a subdirectory and a known file to complete.
+fn make_home() -> Result<PathBuf> {
+    static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+    let n = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let home = std::env::temp_dir().join(format!("rshell-selftest-{}-{}", std::process::id(), n));
+    std::fs::create_dir_all(home.join("sub"))?;
+    write_file(&home.join("selftest_file.txt"), "hello from selftest\n")?;
+    Ok(home)
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    std::fs::write(path, content).map_err(|e| anyhow!("{}: {}", path.display(), e))
+}
@@ -0,0 +1,158 @@
+// src/shell/config.rs
+//
+// User configuration loaded from ~/.rshell/config.toml on startup.
+// Every section and key is optional — anything missing falls back to
+// the defaults below, so an empty or absent file is perfectly valid.
+//
+//   [ls]
+//   columns         = ["perm", "links", "owner", "group", "size", "mtime", "git"]
+//   git_max_entries = 200
+//   stat_max_entries = 10000
+//
+//   [history]
+//   exclude = ["password=", "token=", "*secret*"]
+//
+//   [format]
+//   size_units          = "binary"   # or "si", "bytes"
+//   thousands_separator = ","
+//
+//   [ui]
+//   banner          = "default"   # "none", or text to show instead
+//   hints           = true        # the Ctrl+C / Ctrl+D line under the banner
+//   language        = "de"        # for messages; by default from LANG
+//   report_duration = 10          # "took 12.5s" after slower commands; 0: never
+
+use serde::Deserialize;
+use std::path::PathBuf;
+
+// ── Types ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub ls: LsConfig,
+    pub history: HistoryConfig,
+    pub format: FormatConfig,
+    pub ui: UiConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LsConfig {
+    /// Columns shown by `ls -l`, in display order. The name always comes last.
+    pub columns: Vec<String>,
+    /// Skip the git column for directories with more entries than this.
+    pub git_max_entries: usize,
+    /// Colour names by what the directory listing says alone, without a
+    /// stat per entry, for directories with more entries than this.
+    pub stat_max_entries: usize,
+}
+
+impl Default for LsConfig {
+    fn default() -> Self {
+        LsConfig {
+            columns: vec![
+                "perm".to_string(),
+                "links".to_string(),
+                "owner".to_string(),
+                "group".to_string(),
+                "size".to_string(),
+                "mtime".to_string(),
+            ],
+            git_max_entries: 200,
+            stat_max_entries: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// Lines matching any of these (substring, or glob if it contains
+    /// `*`, `?` or `[`; case-insensitive) are never written to history.
+    pub exclude: Vec<String>,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        HistoryConfig {
+            exclude: vec![
+                "password=".to_string(),
+                "passwd=".to_string(),
+                "secret=".to_string(),
+                "token=".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+    /// Units for human-readable sizes (ls -l, wc -h): "binary" (1.5K =
+    /// 1536 bytes), "si" (1.5k = 1500 bytes) or "bytes" for exact counts.
+    pub size_units: String,
+    /// Inserted between groups of three digits in counts and exact sizes
+    /// shown on a terminal. Empty for none.
+    pub thousands_separator: String,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        FormatConfig {
+            size_units: "binary".to_string(),
+            thousands_separator: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// The startup banner: "default" for the rshell logo, "none" to start
+    /// straight at the prompt, or any other text to show in its place.
+    pub banner: String,
+    /// Show the line of key hints under the banner.
+    pub hints: bool,
+    /// Language for the banner and help (see src/messages.rs). Empty to
+    /// follow LC_ALL / LC_MESSAGES / LANG.
+    pub language: String,
+    /// Print how long a command line took when it ran for longer than
+    /// this many seconds. 0 turns the report off.
+    pub report_duration: f64,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        UiConfig {
+            banner: "default".to_string(),
+            hints: true,
+            language: String::new(),
+            report_duration: 10.0,
+        }
+    }
+}
+
+// ── Loading ───────────────────────────────────────────────────────────────────
+
+pub fn config_path() -> PathBuf {
+    crate::executor::builtin::pkg::rshell_dir().join("config.toml")
+}
+
+impl Config {
+    /// Read ~/.rshell/config.toml, warning (not failing) on a malformed file.
+    pub fn load() -> Self {
+        let path = config_path();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(_) => return Config::default(),
+        };
+        match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("myshell: warning: {}: {}", path.display(), e);
+                Config::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+// src/shell/guard.rs
+//
+// Guard rules: regular expressions checked against every command line
+// after expansion, just before it runs. A matching rule either asks for
+// confirmation or blocks the command outright. Rules are kept in
+// ~/.rshell/guard.toml and managed with the `guard` builtin:
+//
+//   [[rule]]
+//   pattern = '^git push .*(--force|-f\b).*\b(main|master)\b'
+//   action  = "confirm"
+//   message = "force-pushing to a main branch"
+
+use std::io::{BufRead, IsTerminal, Write};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::Shell;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardAction {
+    Confirm,
+    Block,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardRule {
+    pub pattern: String,
+    pub action: GuardAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip)]
+    regex: Option<Regex>,
+}
+
+impl GuardRule {
+    pub fn new(pattern: &str, action: GuardAction, message: Option<String>) -> Result<Self, regex::Error> {
+        let regex = Regex::new(pattern)?;
+        Ok(GuardRule { pattern: pattern.to_string(), action, message, regex: Some(regex) })
+    }
+
+    pub fn matches(&self, line: &str) -> bool {
+        self.regex.as_ref().is_some_and(|r| r.is_match(line))
+    }
+
+    /// What to show when the rule fires: its message, or failing that the
+    /// pattern itself.
+    pub fn describe(&self) -> &str {
+        self.message.as_deref().unwrap_or(&self.pattern)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct GuardFile {
+    #[serde(default)]
+    rule: Vec<GuardRule>,
+}
+
+/// Used until the user saves rules of their own.
+fn default_rules() -> Vec<GuardRule> {
+    [
+        (r"^rm\s+(-\S+\s+)*/\*?$", GuardAction::Block, "removing the root directory"),
+        (r"^git\s+push\s.*(--force|-f\b).*\b(main|master)\b", GuardAction::Confirm,
+            "force-pushing to a main branch"),
+    ]
+    .into_iter()
+    .filter_map(|(p, a, m)| GuardRule::new(p, a, Some(m.to_string())).ok())
+    .collect()
+}
+
+pub fn guard_path() -> std::path::PathBuf {
+    crate::executor::builtin::pkg::rshell_dir().join("guard.toml")
+}
+
+/// Read ~/.rshell/guard.toml, skipping (with a warning) rules whose
+/// pattern doesn't compile.
+pub fn load_guard_rules() -> Vec<GuardRule> {
+    let path = guard_path();
+    let Ok(content) = std::fs::read_to_string(&path) else { return default_rules() };
+    let file: GuardFile = match toml::from_str(&content) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("myshell: warning: {}: {}", path.display(), e);
+            return default_rules();
+        }
+    };
+    file.rule.into_iter()
+        .filter_map(|r| match GuardRule::new(&r.pattern, r.action, r.message) {
+            Ok(rule) => Some(rule),
+            Err(e) => { eprintln!("myshell: warning: guard pattern {}: {}", r.pattern, e); None }
+        })
+        .collect()
+}
+
+impl Shell {
+    /// Write the current guard rules to ~/.rshell/guard.toml.
+    pub fn save_guard_rules(&self) {
+        let file = GuardFile { rule: self.guard_rules.clone() };
+        let result = toml::to_string(&file)
+            .map_err(std::io::Error::other)
+            .and_then(|content| {
+                std::fs::create_dir_all(crate::executor::builtin::pkg::rshell_dir())?;
+                std::fs::write(guard_path(), content)
+            });
+        if let Err(e) = result {
+            eprintln!("myshell: warning: could not save guard rules: {}", e);
+        }
+    }
+
+    /// Check an expanded command line against the guard rules. Returns
+    /// false if it must not run: a block rule matched, or a confirm rule
+    /// matched and the user didn't answer yes.
+    pub fn guard_allows(&self, args: &[String]) -> bool {
+        let line = args.join(" ");
+        let Some(rule) = self.guard_rules.iter().find(|r| r.matches(&line)) else { return true };
+
+        if rule.action == GuardAction::Block {
+            eprintln!("myshell: guard: blocked ({}): {}", rule.describe(), line);
+            return false;
+        }
+        // Without a terminal to ask on, the answer is no
+        if !std::io::stdin().is_terminal() {
+            eprintln!("myshell: guard: not confirmed ({}): {}", rule.describe(), line);
+            return false;
+        }
+
+        crossterm::terminal::disable_raw_mode().ok();
+        eprint!("myshell: guard: {}\n  {}\nRun it? [y/N] ", rule.describe(), line);
+        std::io::stderr().flush().ok();
+        let mut answer = String::new();
+        std::io::stdin().lock().read_line(&mut answer).ok();
+        matches!(answer.trim(), "y" | "Y" | "yes")
+    }
+}
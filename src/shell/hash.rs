@@ -0,0 +1,85 @@
+// src/shell/hash.rs
+//
+// Command-location cache and named directories, both managed by `hash`.
+//
+//   command_hash — name → resolved path, filled on first lookup so PATH is
+//                  searched once per command rather than once per run.
+//                  Dropped whenever $PATH changes.
+//   path_names   — every executable name on PATH, scanned lazily for
+//                  "did you mean" suggestions.
+//   named_dirs   — `hash -d proj=~/code/proj` makes `~proj` expand to it.
+
+use std::path::PathBuf;
+
+use super::Shell;
+use crate::executor::builtin::find_in_path;
+
+/// A cached command location and how often it has been used.
+#[derive(Debug, Clone)]
+pub struct HashedCommand {
+    pub path: PathBuf,
+    pub hits: usize,
+}
+
+impl Shell {
+    /// Resolve a bare command name through the cache, searching PATH on a
+    /// miss. Names containing a path separator are never hashed.
+    pub fn lookup_command(&mut self, name: &str) -> Option<PathBuf> {
+        if name.contains('/') || name.contains('\\') { return None; }
+        self.sync_path();
+
+        if let Some(entry) = self.command_hash.get_mut(name) {
+            // A hashed binary that has since been removed is looked up again
+            if entry.path.is_file() {
+                entry.hits += 1;
+                return Some(entry.path.clone());
+            }
+        }
+
+        let path = find_in_path(name).into_iter().next()?;
+        self.command_hash.insert(name.to_string(), HashedCommand { path: path.clone(), hits: 1 });
+        Some(path)
+    }
+
+    /// Every file name in every PATH directory, scanned once per $PATH.
+    pub fn path_commands(&mut self) -> &[String] {
+        self.sync_path();
+        let sep = if cfg!(windows) { ';' } else { ':' };
+        let path_var = self.hashed_path.clone();
+
+        self.path_names.get_or_insert_with(|| {
+            // Kept in PATH order so earlier directories win ties
+            path_var.split(sep)
+                .filter_map(|dir| std::fs::read_dir(dir).ok())
+                .flat_map(|entries| entries.flatten())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+    }
+
+    /// Forget every cached command location (`hash -r`).
+    pub fn clear_command_hash(&mut self) {
+        self.command_hash.clear();
+        self.path_names = None;
+        crate::completion::forget_commands();
+    }
+
+    /// Expand a leading `~name` or `~name/...` using the named directories.
+    pub fn expand_named_dir(&self, word: &str) -> String {
+        let Some(rest) = word.strip_prefix('~') else { return word.to_string() };
+        let end = rest.find(['/', '\\']).unwrap_or(rest.len());
+        match self.named_dirs.get(&rest[..end]) {
+            Some(dir) if end > 0 => format!("{}{}", dir.display(), &rest[end..]),
+            _ => word.to_string(),
+        }
+    }
+
+    /// Drop cached lookups if $PATH has changed since they were made.
+    fn sync_path(&mut self) {
+        let path = self.env.get("PATH").cloned().unwrap_or_default();
+        if path != self.hashed_path {
+            self.clear_command_hash();
+            self.hashed_path = path;
+        }
+    }
+}
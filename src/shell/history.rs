@@ -2,10 +2,54 @@
 //
 // History loading, saving, and expansion (!!, !n).
 // History is persisted to ~/.myshell_history across sessions.
+//
+// Alongside the plain line history, every command is also recorded in an
+// extended store (~/.rshell/history.jsonl) with its directory, exit code
+// and duration — the data behind the `stats` builtin. `history -c` and
+// `history -d` edit the line history only; `history --forget` removes
+// commands from both.
 
 use super::Shell;
+use serde::{Deserialize, Serialize};
 
 const MAX_HISTORY: usize = 1000;
+const MAX_EXTENDED_HISTORY: usize = 10_000;
+
+/// One record in the extended history store.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub command:     String,
+    pub cwd:         String,
+    /// Start time, seconds since the Unix epoch.
+    pub started:     i64,
+    pub duration_ms: u64,
+    pub exit_code:   i32,
+}
+
+pub fn extended_history_path() -> std::path::PathBuf {
+    crate::executor::builtin::pkg::rshell_dir().join("history.jsonl")
+}
+
+/// Case-insensitive match of a history line against a forget/exclude
+/// pattern: a glob when it contains glob characters, otherwise a substring.
+pub fn history_matches(line: &str, pattern: &str) -> bool {
+    let line    = line.to_lowercase();
+    let pattern = pattern.to_lowercase();
+    if pattern.contains(['*', '?', '[']) {
+        crate::glob::matches_pattern(&line, &pattern)
+    } else {
+        line.contains(&pattern)
+    }
+}
+
+/// Read every entry from the extended store, skipping malformed lines.
+pub fn load_extended_history() -> Vec<HistoryEntry> {
+    std::fs::read_to_string(extended_history_path())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
 
 impl Shell {
     /// Load history from ~/.myshell_history into memory on startup.
@@ -21,6 +65,135 @@ impl Shell {
                 .map(|l| l.to_string())
                 .collect();
         }
+
+        // Trim the extended store once per session rather than on every write
+        let ext_path = extended_history_path();
+        if let Ok(content) = std::fs::read_to_string(&ext_path) {
+            let lines: Vec<&str> = content.lines().collect();
+            if lines.len() > MAX_EXTENDED_HISTORY {
+                let keep = &lines[lines.len() - MAX_EXTENDED_HISTORY..];
+                let _ = std::fs::write(&ext_path, keep.join("\n") + "\n");
+            }
+        }
+    }
+
+    /// Whether a line should be recorded at all: not while `set -o nohistory`
+    /// is on, and never for lines matching a configured exclusion pattern.
+    pub fn should_record(&self, line: &str) -> bool {
+        !self.no_history
+            && !self.config.history.exclude.iter().any(|p| history_matches(line, p))
+    }
+
+    /// Remove every history entry matching `pattern` from memory, the line
+    /// history file and the extended store. Returns how many were removed.
+    ///
+    /// The line running this goes too, as it would otherwise leave PAT
+    /// there itself, but isn't counted.
+    pub fn forget_history(&mut self, pattern: &str) -> usize {
+        self.forgot_history = true;
+        let current = self.recorded_line.clone();
+        let own = current.is_some() && self.history.last() == current.as_ref();
+        let removed = self.remove_history(|line| {
+            history_matches(line, pattern) || current.as_deref() == Some(line)
+        });
+        removed - usize::from(own)
+    }
+
+    fn remove_history(&mut self, matches: impl Fn(&str) -> bool) -> usize {
+        let before = self.history.len();
+        self.history.retain(|l| !matches(l));
+        let removed = before - self.history.len();
+
+        let path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(".myshell_history");
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            let kept: Vec<&str> = content.lines()
+                .filter(|l| !matches(l))
+                .collect();
+            let _ = std::fs::write(&path, kept.join("\n") + "\n");
+        }
+
+        let ext_path = extended_history_path();
+        if let Ok(content) = std::fs::read_to_string(&ext_path) {
+            let kept: Vec<&str> = content.lines()
+                .filter(|l| {
+                    serde_json::from_str::<HistoryEntry>(l)
+                        .map(|e| !matches(&e.command))
+                        .unwrap_or(true)
+                })
+                .collect();
+            let _ = std::fs::write(&ext_path, kept.join("\n") + "\n");
+        }
+
+        removed
+    }
+
+    /// Append a finished command to the extended history store. `cwd` is
+    /// where it started, which a `cd` in it will have changed since.
+    pub fn record_history_entry(&self, command: &str, cwd: &std::path::Path, started: std::time::SystemTime, duration: std::time::Duration) {
+        let entry = HistoryEntry {
+            command:     command.to_string(),
+            cwd:         cwd.display().to_string(),
+            started:     crate::executor::builtin::unix_secs(started),
+            duration_ms: duration.as_millis() as u64,
+            exit_code:   self.last_exit_code,
+        };
+        let Ok(line) = serde_json::to_string(&entry) else { return };
+
+        let path = extended_history_path();
+        if let Some(dir) = path.parent() { let _ = std::fs::create_dir_all(dir); }
+
+        use std::io::Write;
+        if let Ok(mut file) = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+        {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+
+    /// Empty the line history, in memory and in ~/.myshell_history.
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.write_history_file();
+    }
+
+    /// Remove entry `n` (counting from 1) from the line history, in memory
+    /// and in ~/.myshell_history. Returns the entry, or None if there's no
+    /// such entry.
+    pub fn delete_history(&mut self, n: usize) -> Option<String> {
+        if n == 0 || n > self.history.len() { return None; }
+        let removed = self.history.remove(n - 1);
+        self.write_history_file();
+        Some(removed)
+    }
+
+    /// When each line history entry was run, from the extended store. The
+    /// two are matched up from the most recent back, by command; an entry
+    /// the store has no record of (run with the store missing, or trimmed
+    /// from it since) has no time.
+    pub fn history_times(&self) -> Vec<Option<i64>> {
+        let records = load_extended_history();
+        let mut left = records.len();
+        let mut times = vec![None; self.history.len()];
+        for (i, line) in self.history.iter().enumerate().rev() {
+            if let Some(at) = records[..left].iter().rposition(|r| r.command == *line) {
+                times[i] = Some(records[at].started);
+                left = at;
+            }
+        }
+        times
+    }
+
+    fn write_history_file(&self) {
+        let path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(".myshell_history");
+        let mut content = self.history.join("\n");
+        if !content.is_empty() { content.push('\n'); }
+        let _ = std::fs::write(&path, content);
     }
 
     /// Append a single command to ~/.myshell_history.
@@ -0,0 +1,72 @@
+// src/shell/hooks.rs
+//
+// Functions the shell runs at fixed points, registered with `hook add`:
+//
+//   preexec — after a command line is read, before it runs ($1 is the line)
+//   precmd  — before each prompt is drawn
+//   chpwd   — after the working directory changes
+//
+// Hooks run in the shell itself, so they can set variables (a preexec
+// hook recording a start time for a precmd hook to report on, say).
+// `$?` is kept as it was, so the prompt still shows the command's status.
+
+use super::Shell;
+
+/// The events hooks can be added to.
+pub const HOOK_EVENTS: &[&str] = &["preexec", "precmd", "chpwd"];
+
+#[derive(Debug, Clone, Default)]
+pub struct Hooks {
+    pub preexec: Vec<String>,
+    pub precmd: Vec<String>,
+    pub chpwd: Vec<String>,
+    /// Set while hooks run, so a hook that runs `cd` doesn't set off
+    /// chpwd hooks in turn.
+    running: bool,
+}
+
+impl Hooks {
+    /// The functions hooked to `event`, or None if there's no such event.
+    pub fn get_mut(&mut self, event: &str) -> Option<&mut Vec<String>> {
+        match event {
+            "preexec" => Some(&mut self.preexec),
+            "precmd"  => Some(&mut self.precmd),
+            "chpwd"   => Some(&mut self.chpwd),
+            _ => None,
+        }
+    }
+
+    /// The `hook add` command lines that recreate these hooks.
+    pub fn command_lines(&self) -> Vec<String> {
+        [("preexec", &self.preexec), ("precmd", &self.precmd), ("chpwd", &self.chpwd)]
+            .into_iter()
+            .flat_map(|(event, names)| names.iter().map(move |name| format!("hook add {} {}", event, name)))
+            .collect()
+    }
+}
+
+impl Shell {
+    /// Run the functions hooked to `event` with `args` as their positional
+    /// parameters, leaving `$?` as it was.
+    pub fn run_hooks(&mut self, event: &str, args: &[String]) {
+        if self.hooks.running { return; }
+        let names = match self.hooks.get_mut(event) {
+            Some(names) if !names.is_empty() => names.clone(),
+            _ => return,
+        };
+
+        self.hooks.running = true;
+        let status = self.last_exit_code;
+        for name in names {
+            if !self.functions.contains_key(&name) {
+                eprintln!("myshell: {} hook: {}: no such function", event, name);
+                continue;
+            }
+            if let Err(e) = crate::executor::call_function(self, &name, args) {
+                eprintln!("myshell: {} hook: {}: {}", event, name, e);
+            }
+        }
+        self.last_exit_code = status;
+        self.hooks.running = false;
+    }
+}
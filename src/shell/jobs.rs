@@ -0,0 +1,138 @@
+// src/shell/jobs.rs
+//
+// The background job table. A job is added when `cmd &` starts, or when
+// Ctrl+Z stops a foreground command, and marked Done, with its exit
+// status, once the process has been reaped. Reaping
+// waits on the job's own child handle only, so a PID that has been reused
+// by something else is never mistaken for the job.
+//
+// When the terminal hangs up, jobctl passes the hangup on to every job in
+// the table except those marked with `disown -h`.
+
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+
+use super::Shell;
+use crate::executor::builtin::status;
+use crate::executor::jobctl;
+
+/// `$?` after Ctrl+Z stops the foreground job.
+#[cfg(unix)]
+const STOPPED: i32 = 128 + libc::SIGTSTP;
+#[cfg(windows)]
+const STOPPED: i32 = 148;
+
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: usize,
+    pub pid: u32,
+    pub command: String,
+    pub status: JobStatus,
+    /// The job's process. Shared so that the copy of the shell a
+    /// pipeline stage gets doesn't take it.
+    pub child: Arc<Mutex<Child>>,
+    /// Marked by `disown -h`: left running when the terminal hangs up.
+    pub nohup: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobStatus {
+    Running,
+    /// Stopped by Ctrl+Z, waiting for `fg` or `bg`.
+    Stopped,
+    /// Finished, with its exit status.
+    Done(i32),
+}
+
+impl std::fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobStatus::Running => write!(f, "Running"),
+            JobStatus::Stopped => write!(f, "Stopped"),
+            JobStatus::Done(0) => write!(f, "Done"),
+            JobStatus::Done(n) => write!(f, "Exit {}", n),
+        }
+    }
+}
+
+impl Job {
+    /// The job's exit status if it has finished, without blocking.
+    fn try_wait(&self) -> Option<i32> {
+        let status = self.child.lock().ok()?.try_wait().ok()??;
+        Some(status::status_code(status))
+    }
+
+    /// Wait for the job in the foreground (`fg`), reporting a signal death.
+    /// None if it was stopped again.
+    pub fn wait(&self) -> Option<i32> {
+        #[cfg(unix)]
+        {
+            // The child handle, if any, hasn't been waited on yet, so the
+            // pid is still this job's
+            match jobctl::wait_pid(self.pid) {
+                Ok(jobctl::Wait::Exited(s)) => Some(status::exit_status(&self.command, s)),
+                Ok(jobctl::Wait::Stopped)   => None,
+                Err(_) => Some(0),
+            }
+        }
+        #[cfg(windows)]
+        {
+            let Ok(mut child) = self.child.lock() else { return Some(1) };
+            Some(match child.wait() {
+                Ok(s)  => status::exit_status(&self.command, s),
+                Err(_) => 1,
+            })
+        }
+    }
+}
+
+impl Shell {
+    /// Record a background process as a job, returning its job number.
+    pub fn add_job(&mut self, pid: u32, child: Child, command: String) -> usize {
+        let id = self.jobs.keys().max().map_or(1, |n| n + 1);
+        let child = Arc::new(Mutex::new(child));
+        self.jobs.insert(id, Job { id, pid, command, status: JobStatus::Running, child, nohup: false });
+        self.update_hangup();
+        id
+    }
+
+    /// Mark a job stopped by Ctrl+Z and say so, returning the `$?` for it.
+    pub fn stop_job(&mut self, id: usize) -> i32 {
+        if let Some(job) = self.jobs.get_mut(&id) {
+            job.status = JobStatus::Stopped;
+            println!("\n[{}]+ Stopped  {}", id, job.command);
+        }
+        STOPPED
+    }
+
+    /// Reap finished background jobs, marking them Done with their status.
+    pub fn reap_jobs(&mut self) {
+        for job in self.jobs.values_mut() {
+            if matches!(job.status, JobStatus::Done(_)) { continue; }
+            if let Some(code) = job.try_wait() {
+                job.status = JobStatus::Done(code);
+            }
+        }
+    }
+
+    /// Tell jobctl which jobs to hang up if the terminal does: those not
+    /// finished or marked with `disown -h`. Called whenever the table changes.
+    pub fn update_hangup(&self) {
+        jobctl::set_hangup_jobs(self.jobs.values()
+            .filter(|j| !j.nohup && !matches!(j.status, JobStatus::Done(_)))
+            .map(|j| j.pid));
+    }
+
+    /// Remove finished jobs from the table, returning them by job number.
+    pub fn take_finished_jobs(&mut self) -> Vec<Job> {
+        self.reap_jobs();
+        let mut done: Vec<usize> = self.jobs.iter()
+            .filter(|(_, j)| matches!(j.status, JobStatus::Done(_)))
+            .map(|(id, _)| *id)
+            .collect();
+        done.sort();
+        let finished = done.into_iter().filter_map(|id| self.jobs.remove(&id)).collect();
+        self.update_hangup();
+        finished
+    }
+}
@@ -4,86 +4,188 @@
 //
 //   prompt.rs   — build_prompt(), shorten_path(), get_git_branch()
 //   history.rs  — load_history(), save_history_line(), expand_history()
-//   persist.rs  — save_aliases(), save_functions()
-
-mod history;
+//   persist.rs  — save_aliases(), save_functions(), save_named_dirs(),
+//                 save_wrappers(), save_hooks()
+//   config.rs   — Config loaded from ~/.rshell/config.toml
+//   hash.rs     — lookup_command(), path_commands(), expand_named_dir()
+//   vars.rs     — set_var(), unset_var(), set_exported(), child_env()
+//   guard.rs    — guard_allows(), rules from ~/.rshell/guard.toml
+//   jobs.rs     — add_job(), reap_jobs(), the background job table
+//   hooks.rs    — run_hooks(), the preexec/precmd/chpwd functions
+
+pub mod config;
+mod guard;
+mod hash;
+mod hooks;
+pub mod history;
+mod jobs;
 mod persist;
 mod prompt;
+mod vars;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use anyhow::Result;
 
+use crate::parser::ast::Command;
+
+pub use guard::{GuardAction, GuardRule};
+pub use hash::HashedCommand;
+pub use hooks::HOOK_EVENTS;
+pub use jobs::{Job, JobStatus};
+pub use vars::is_valid_name;
+
 // ── Types ─────────────────────────────────────────────────────────────────────
 
-pub struct Job {
-    pub id: usize,
-    pub pid: u32,
-    pub command: String,
-    pub status: JobStatus,
+/// A user-defined shell function.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ShellFunction {
+    pub body: Vec<Command>,
+    /// The body's commands as written, for `functions`, `type` and saving.
+    pub source: Vec<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum JobStatus {
-    Running,
-    Done,
+/// Commands run before and after every use of a wrapped command (`wrap`).
+#[derive(Debug, Clone, Default)]
+pub struct CommandWrapper {
+    pub before: Option<String>,
+    pub after: Option<String>,
 }
 
-impl std::fmt::Display for JobStatus {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            JobStatus::Running => write!(f, "Running"),
-            JobStatus::Done    => write!(f, "Done"),
-        }
+impl CommandWrapper {
+    /// The `wrap` command line that recreates this wrapper for `name`.
+    pub fn command_line(&self, name: &str) -> String {
+        use crate::executor::builtin::shell_quote;
+        let mut line = format!("wrap {}", shell_quote(name));
+        if let Some(cmd) = &self.before { line.push_str(&format!(" --before {}", shell_quote(cmd))); }
+        if let Some(cmd) = &self.after  { line.push_str(&format!(" --after {}", shell_quote(cmd))); }
+        line
     }
 }
 
-/// A user-defined shell function.
-#[derive(Debug, Clone)]
-pub struct ShellFunction {
-    pub body: Vec<String>,
-}
-
 // ── Shell struct ──────────────────────────────────────────────────────────────
 
+/// Cloned to give pipeline stages their own copy of the shell's state.
+#[derive(Clone)]
 pub struct Shell {
+    /// All shell variables, exported or not.
     pub env: HashMap<String, String>,
+    /// Names of variables passed to child processes.
+    pub exported: HashSet<String>,
+    /// Names of variables that can't be changed or unset.
+    pub readonly: HashSet<String>,
     pub cwd: PathBuf,
     pub prev_dir: Option<PathBuf>,
     pub history: Vec<String>,
     pub aliases: HashMap<String, String>,
+    /// `wrap NAME --before CMD --after CMD` hooks, keyed by command name.
+    pub wrappers: HashMap<String, CommandWrapper>,
+    /// `hook add EVENT FUNC` — functions run before commands, prompts
+    /// and after cd.
+    pub hooks: hooks::Hooks,
     pub functions: HashMap<String, ShellFunction>,
     pub last_exit_code: i32,
     pub jobs: HashMap<usize, Job>,
     pub dir_stack: Vec<PathBuf>,
     pub exit_on_error: bool,
+    /// `set -o nohistory` — stop recording commands until turned off.
+    pub no_history: bool,
+    /// Set by `history --forget`, so the line that ran it isn't kept.
+    pub forgot_history: bool,
+    /// The line now running, if it has been put in the history.
+    pub recorded_line: Option<String>,
+    /// `set -o posix` — POSIX sh syntax and expansion, standard exit codes.
+    pub posix: bool,
+    /// `set -o pipefail` — a pipeline's status is that of the last stage
+    /// to fail, rather than of the last stage.
+    pub pipefail: bool,
+    /// `set -o autocorrect` — offer to run the closest command when one
+    /// isn't found.
+    pub autocorrect: bool,
+    /// `set -o fuzzycomplete` — complete names holding the typed letters in
+    /// order, not just those starting with them.
+    pub fuzzy_complete: bool,
+    /// Each stage's status from the last pipeline, for `${PIPESTATUS[@]}`.
+    /// A lone command counts as a pipeline of one.
+    pub pipe_status: Vec<i32>,
+    /// `$!` — the pid of the most recent background job.
+    pub last_bg_pid: Option<u32>,
+    /// `$CMD_DURATION` — how long the last command line took, wall clock.
+    pub last_duration: Option<std::time::Duration>,
+    /// `env snapshot [NAME]` — exported variables as they were, by name.
+    pub env_snapshots: HashMap<String, HashMap<String, String>>,
+    /// getopts progress through a clustered flag like `-abc`: the OPTIND
+    /// it applies to, that argument, and the character offset within it.
+    pub getopts_pos: (usize, String, usize),
+    /// Resolved locations of external commands, see hash.rs.
+    pub command_hash: HashMap<String, HashedCommand>,
+    /// `hash -d name=dir` — directories reachable as `~name`.
+    pub named_dirs: HashMap<String, PathBuf>,
+    /// `complete` — how each command's arguments are tab-completed.
+    pub completions: HashMap<String, crate::completion::CompletionSpec>,
+    /// The $PATH that `command_hash` and `path_names` were built from.
+    hashed_path: String,
+    path_names: Option<Vec<String>>,
+    pub config: config::Config,
+    /// Confirm/block rules checked before each command runs.
+    pub guard_rules: Vec<GuardRule>,
+    /// Set while ~/.myshellrc runs, so the functions it defines aren't
+    /// written straight back to it.
+    pub loading_rc: bool,
 }
 
 impl Shell {
     pub fn new() -> Self {
         let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
         let env: HashMap<String, String> = std::env::vars().collect();
+        // Everything inherited from the parent stays exported
+        let exported: HashSet<String> = env.keys().cloned().collect();
 
         let mut shell = Shell {
             env,
+            exported,
+            readonly: HashSet::new(),
             cwd,
             prev_dir: None,
             history: Vec::new(),
             aliases: HashMap::new(),
+            wrappers: HashMap::new(),
+            hooks: hooks::Hooks::default(),
             functions: HashMap::new(),
             last_exit_code: 0,
             jobs: HashMap::new(),
             dir_stack: Vec::new(),
             exit_on_error: false,
+            no_history: false,
+            forgot_history: false,
+            recorded_line: None,
+            posix: false,
+            pipefail: false,
+            autocorrect: false,
+            fuzzy_complete: false,
+            pipe_status: Vec::new(),
+            last_bg_pid: None,
+            last_duration: None,
+            env_snapshots: HashMap::new(),
+            getopts_pos: (0, String::new(), 0),
+            command_hash: HashMap::new(),
+            named_dirs: HashMap::new(),
+            completions: HashMap::new(),
+            hashed_path: String::new(),
+            path_names: None,
+            config: config::Config::load(),
+            guard_rules: guard::load_guard_rules(),
+            loading_rc: false,
         };
 
+        crate::messages::init(&shell.config.ui.language);
+
         // Set $0 to the shell executable name
         let exe = std::env::current_exe()
             .ok()
             .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
             .unwrap_or_else(|| "rshell".to_string());
-        shell.env.insert("0".to_string(), exe.clone());
-        unsafe { std::env::set_var("0", &exe); }
+        shell.env.insert("0".to_string(), exe);
 
         // Default aliases
         shell.aliases.insert("ll".to_string(),  "ls -la".to_string());
@@ -109,32 +211,14 @@ impl Shell {
         shell
     }
 
-    /// Check for finished background jobs and mark them Done.
-    pub fn reap_jobs(&mut self) {
-        let mut done = Vec::new();
-        for (id, job) in &self.jobs {
-            #[cfg(unix)]
-            {
-                let result = unsafe {
-                    libc::waitpid(job.pid as i32, std::ptr::null_mut(), libc::WNOHANG)
-                };
-                if result > 0 { done.push(*id); }
-            }
-            #[cfg(windows)]
-            {
-                let alive = std::process::Command::new("tasklist")
-                    .args(["/FI", &format!("PID eq {}", job.pid)])
-                    .output()
-                    .map(|o| String::from_utf8_lossy(&o.stdout).contains(&job.pid.to_string()))
-                    .unwrap_or(false);
-                if !alive { done.push(*id); }
-            }
-        }
-        for id in done {
-            if let Some(job) = self.jobs.get_mut(&id) {
-                job.status = JobStatus::Done;
-            }
-        }
+    /// Positional parameters $1..$9 that are currently set.
+    pub fn positional_args(&self) -> Vec<String> {
+        (1..=9).map_while(|i| self.env.get(&i.to_string()).cloned()).collect()
+    }
+
+    /// How tab completion matches the word typed (`set -o fuzzycomplete`).
+    pub fn matching(&self) -> crate::completion::Matching {
+        if self.fuzzy_complete { crate::completion::Matching::Fuzzy } else { crate::completion::Matching::Prefix }
     }
 
     /// Load and execute ~/.myshellrc on startup.
@@ -145,34 +229,15 @@ impl Shell {
 
         if rc_path.exists() {
             let content = std::fs::read_to_string(&rc_path)?;
-            let mut func_buffer: Option<(String, Vec<String>)> = None;
-
-            for line in content.lines() {
-                let trimmed = line.trim();
-
-                if let Some((ref name, ref mut body)) = func_buffer {
-                    if trimmed == "}" {
-                        let name = name.clone();
-                        let body = body.clone();
-                        self.functions.insert(name, ShellFunction { body });
-                        func_buffer = None;
-                    } else {
-                        body.push(trimmed.to_string());
-                    }
-                    continue;
-                }
 
-                if trimmed.is_empty() || trimmed.starts_with('#') { continue; }
-
-                if let Some(func_name) = parse_function_start(trimmed) {
-                    func_buffer = Some((func_name, Vec::new()));
-                    continue;
-                }
-
-                if let Err(e) = self.eval(trimmed) {
+            // Multi-line functions and blocks arrive as one command each
+            self.loading_rc = true;
+            for cmd in crate::parser::logical_lines(&content) {
+                if let Err(e) = self.eval(&cmd) {
                     eprintln!("myshell: rc error: {e}");
                 }
             }
+            self.loading_rc = false;
         }
         Ok(())
     }
@@ -184,64 +249,8 @@ impl Shell {
             return Ok(());
         }
 
-        if let Some(func_name) = parse_function_start(input) {
-            return self.parse_inline_function(input, func_name);
-        }
-
-        let input = crate::executor::expand_arithmetic(self, input);
-        let input = input.trim().to_string();
-
-        let ast = crate::parser::parse(&input)?;
+        let opts = crate::parser::ParseOptions { posix: self.posix };
+        let ast = crate::parser::parse(input, opts)?;
         crate::executor::execute(self, ast)
     }
-
-    /// Parse a function defined on a single line: `name() { cmd; cmd }`.
-    fn parse_inline_function(&mut self, input: &str, name: String) -> Result<()> {
-        let open = match input.find('{') {
-            Some(i) => i,
-            None => {
-                self.functions.insert(name, ShellFunction { body: vec![] });
-                self.save_functions();
-                return Ok(());
-            }
-        };
-        let close = input.rfind('}').unwrap_or(input.len());
-        let body_str = &input[open + 1..close];
-        let body: Vec<String> = body_str
-            .split(';')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
-        self.functions.insert(name, ShellFunction { body });
-        self.save_functions();
-        Ok(())
-    }
 }
-
-// ── Free functions ────────────────────────────────────────────────────────────
-
-/// Detect if a line starts a function definition, returning the function name.
-pub fn parse_function_start(line: &str) -> Option<String> {
-    let line = line.trim();
-
-    if let Some(rest) = line.strip_prefix("function ") {
-        let name = rest
-            .split(|c: char| c == '(' || c == '{' || c.is_whitespace())
-            .next()?
-            .trim()
-            .to_string();
-        if !name.is_empty() { return Some(name); }
-    }
-
-    if let Some(paren) = line.find("()") {
-        let name = line[..paren].trim().to_string();
-        if !name.is_empty()
-            && name.chars().all(|c| c.is_alphanumeric() || c == '_')
-            && line[paren + 2..].trim().starts_with('{')
-        {
-            return Some(name);
-        }
-    }
-
-    None
-}
\ No newline at end of file
@@ -1,7 +1,7 @@
 // src/shell/persist.rs
 //
-// Persists aliases and user-defined functions to ~/.myshellrc
-// so they survive across shell sessions.
+// Persists aliases, command wrappers, hooks, user-defined functions and
+// named directories to ~/.myshellrc so they survive across shell sessions.
 
 use super::Shell;
 
@@ -37,6 +37,55 @@ impl Shell {
         }
     }
 
+    /// Write all command wrappers (`wrap`) back to ~/.myshellrc.
+    pub fn save_wrappers(&self) {
+        let rc_path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(".myshellrc");
+
+        let existing = std::fs::read_to_string(&rc_path).unwrap_or_default();
+
+        let mut lines: Vec<String> = existing
+            .lines()
+            .filter(|l| !l.trim_start().starts_with("wrap "))
+            .map(|l| l.to_string())
+            .collect();
+
+        if !self.wrappers.is_empty() {
+            let mut sorted: Vec<_> = self.wrappers.iter().collect();
+            sorted.sort_by_key(|(k, _)| k.as_str());
+            for (name, wrapper) in sorted {
+                lines.push(wrapper.command_line(name));
+            }
+        }
+
+        let content = lines.join("\n") + "\n";
+        if let Err(e) = std::fs::write(&rc_path, content) {
+            eprintln!("myshell: warning: could not save wrappers: {}", e);
+        }
+    }
+
+    /// Write all hooks (`hook add`) back to ~/.myshellrc.
+    pub fn save_hooks(&self) {
+        let rc_path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(".myshellrc");
+
+        let existing = std::fs::read_to_string(&rc_path).unwrap_or_default();
+
+        let mut lines: Vec<String> = existing
+            .lines()
+            .filter(|l| !l.trim_start().starts_with("hook add "))
+            .map(|l| l.to_string())
+            .collect();
+        lines.extend(self.hooks.command_lines());
+
+        let content = lines.join("\n") + "\n";
+        if let Err(e) = std::fs::write(&rc_path, content) {
+            eprintln!("myshell: warning: could not save hooks: {}", e);
+        }
+    }
+
     /// Write all current user-defined functions back to ~/.myshellrc.
     pub fn save_functions(&self) {
         let rc_path = dirs::home_dir()
@@ -66,7 +115,7 @@ impl Shell {
             sorted.sort_by_key(|(k, _)| k.as_str());
             for (name, func) in sorted {
                 lines.push(format!("function {}() {{", name));
-                for line in &func.body {
+                for line in &func.source {
                     lines.push(format!("    {}", line));
                 }
                 lines.push("}".to_string());
@@ -79,4 +128,32 @@ impl Shell {
             eprintln!("myshell: warning: could not save functions: {}", e);
         }
     }
-}
\ No newline at end of file
+
+    /// Write all named directories (`hash -d`) back to ~/.myshellrc.
+    pub fn save_named_dirs(&self) {
+        let rc_path = dirs::home_dir()
+            .unwrap_or_default()
+            .join(".myshellrc");
+
+        let existing = std::fs::read_to_string(&rc_path).unwrap_or_default();
+
+        let mut lines: Vec<String> = existing
+            .lines()
+            .filter(|l| !l.trim_start().starts_with("hash -d "))
+            .map(|l| l.to_string())
+            .collect();
+
+        if !self.named_dirs.is_empty() {
+            let mut sorted: Vec<_> = self.named_dirs.iter().collect();
+            sorted.sort_by_key(|(k, _)| k.as_str());
+            for (name, dir) in sorted {
+                lines.push(format!("hash -d '{}={}'", name, dir.display()));
+            }
+        }
+
+        let content = lines.join("\n") + "\n";
+        if let Err(e) = std::fs::write(&rc_path, content) {
+            eprintln!("myshell: warning: could not save named directories: {}", e);
+        }
+    }
+}
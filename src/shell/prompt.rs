@@ -9,11 +9,10 @@ impl Shell {
     /// Build the prompt string for the current shell state.
     pub fn build_prompt(&self) -> String {
         let home = dirs::home_dir()
-            .map(|h| h.display().to_string())
+            .map(|h| crate::paths::display(&h))
             .unwrap_or_default();
 
-        let cwd = self.cwd.display().to_string();
-        let cwd = cwd.trim_start_matches("\\\\?\\").to_string();
+        let cwd = crate::paths::display(&self.cwd);
 
         let cwd = if cwd.starts_with(&home) {
             cwd.replacen(&home, "~", 1)
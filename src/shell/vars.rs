@@ -0,0 +1,90 @@
+// src/shell/vars.rs
+//
+// Shell variables and their attributes.
+//
+// Every variable lives in `Shell::env`; only names in `exported` are
+// passed to child processes (and mirrored into the process environment,
+// which library calls such as PATH lookup read). Plain `FOO=bar` creates
+// an unexported shell variable, `export FOO` promotes it, and `readonly`
+// variables refuse to change.
+
+use super::Shell;
+
+impl Shell {
+    /// Set a variable, keeping its export attribute. Prints an error and
+    /// returns false if it is readonly.
+    pub fn set_var(&mut self, key: &str, value: &str) -> bool {
+        if self.readonly.contains(key) {
+            eprintln!("myshell: {}: readonly variable", key);
+            return false;
+        }
+        self.env.insert(key.to_string(), value.to_string());
+        if self.exported.contains(key) {
+            unsafe { std::env::set_var(key, value); }
+        }
+        true
+    }
+
+    /// Remove a variable and its export attribute. Readonly variables
+    /// can't be unset.
+    pub fn unset_var(&mut self, key: &str) -> bool {
+        if self.readonly.contains(key) {
+            eprintln!("myshell: {}: readonly variable", key);
+            return false;
+        }
+        self.env.remove(key);
+        if self.exported.remove(key) {
+            unsafe { std::env::remove_var(key); }
+        }
+        true
+    }
+
+    /// Mark a variable for export (`export NAME`), or with `on == false`
+    /// keep it as a shell-only variable (`export -n NAME`).
+    pub fn set_exported(&mut self, key: &str, on: bool) {
+        if on {
+            self.exported.insert(key.to_string());
+            if let Some(value) = self.env.get(key) {
+                unsafe { std::env::set_var(key, value); }
+            }
+        } else if self.exported.remove(key) {
+            unsafe { std::env::remove_var(key); }
+        }
+    }
+
+    /// The environment handed to child processes: exported variables only.
+    pub fn child_env(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.env.iter().filter(|(k, _)| self.exported.contains(*k))
+    }
+
+    /// Run `f` with `assigns` temporarily set and exported, as for
+    /// `FOO=bar cmd`, then restore the previous values.
+    pub fn with_temp_vars<T>(&mut self, assigns: &[(String, String)], f: impl FnOnce(&mut Shell) -> T) -> T {
+        let saved: Vec<(String, Option<String>, bool)> = assigns.iter()
+            .map(|(k, _)| (k.clone(), self.env.get(k).cloned(), self.exported.contains(k)))
+            .collect();
+        for (k, v) in assigns {
+            if self.set_var(k, v) { self.set_exported(k, true); }
+        }
+
+        let result = f(self);
+
+        for (k, old, was_exported) in saved {
+            if self.readonly.contains(&k) { continue; }
+            self.set_exported(&k, was_exported);
+            match old {
+                Some(v) => { self.set_var(&k, &v); }
+                None    => { self.unset_var(&k); }
+            }
+        }
+        result
+    }
+}
+
+/// Whether `name` is a valid variable name: a letter or underscore, then
+/// letters, digits and underscores.
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}